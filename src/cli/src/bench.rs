@@ -0,0 +1,100 @@
+//! Greeting throughput benchmark
+
+use crate::output::CliBenchReport;
+use helloworld_client::{find_greeting_address, say_hello_with_message_ix, HelloWorldClient};
+use solana_sdk::signature::{Keypair, Signer};
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::time::{interval, MissedTickBehavior};
+
+/// Greet the greeting account of `greeter` `tps` times per second for
+/// `duration`, each greeting being sent without waiting for the previous
+/// ones, and report the latencies of their confirmations
+///
+/// The greetings carry distinct messages so that those signed against the
+/// same blockhash remain distinct transactions.
+pub async fn bench(
+    client: HelloWorldClient,
+    greeter: Keypair,
+    tps: u32,
+    duration: Duration,
+) -> CliBenchReport {
+    let greeter = Arc::new(greeter);
+    let greeting_address = find_greeting_address(&greeter.pubkey()).0;
+    let mut ticks = interval(Duration::from_secs(1) / tps);
+    ticks.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    let start = Instant::now();
+    let mut greetings = vec![];
+    while start.elapsed() < duration {
+        ticks.tick().await;
+        let instruction = say_hello_with_message_ix(
+            &greeting_address,
+            &greeter.pubkey(),
+            format!("bench {}", greetings.len()),
+        );
+        let (client, greeter) = (client.clone(), greeter.clone());
+        greetings.push(tokio::spawn(async move {
+            let sent_at = Instant::now();
+            client
+                .send_and_confirm(&[instruction], &greeter)
+                .await
+                .map(|_| sent_at.elapsed())
+        }));
+    }
+
+    let sent = greetings.len();
+    let mut latencies = vec![];
+    let mut errors = vec![];
+    for greeting in greetings {
+        match greeting.await.expect("greetings do not panic") {
+            Ok(latency) => latencies.push(latency),
+            Err(error) => errors.push(error.to_string()),
+        }
+    }
+    let elapsed = start.elapsed();
+    latencies.sort_unstable();
+    errors.sort_unstable();
+    errors.dedup();
+
+    CliBenchReport {
+        sent,
+        confirmed: latencies.len(),
+        failed: sent - latencies.len(),
+        elapsed_ms: elapsed.as_millis() as u64,
+        confirmed_tps: latencies.len() as f64 / elapsed.as_secs_f64(),
+        latency_p50_ms: percentile(&latencies, 50).as_millis() as u64,
+        latency_p90_ms: percentile(&latencies, 90).as_millis() as u64,
+        latency_p99_ms: percentile(&latencies, 99).as_millis() as u64,
+        latency_max_ms: latencies.last().copied().unwrap_or_default().as_millis() as u64,
+        errors,
+    }
+}
+
+/// Nearest-rank `percent`th percentile of the sorted `latencies`, zero if
+/// there are none
+fn percentile(latencies: &[Duration], percent: usize) -> Duration {
+    if latencies.is_empty() {
+        return Duration::default();
+    }
+    let rank = (latencies.len() * percent).div_ceil(100);
+    latencies[rank.max(1) - 1]
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_percentile() {
+        let latencies: Vec<Duration> = (1..=10).map(Duration::from_millis).collect();
+        assert_eq!(percentile(&latencies, 50), Duration::from_millis(5));
+        assert_eq!(percentile(&latencies, 90), Duration::from_millis(9));
+        assert_eq!(percentile(&latencies, 99), Duration::from_millis(10));
+        assert_eq!(percentile(&latencies, 0), Duration::from_millis(1));
+        assert_eq!(percentile(&latencies[..1], 99), Duration::from_millis(1));
+        assert_eq!(percentile(&[], 50), Duration::default());
+    }
+}
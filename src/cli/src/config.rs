@@ -0,0 +1,188 @@
+//! Settings of the commands, from the flags, the environment and the
+//! configuration file
+
+use clap::ArgMatches;
+use helloworld_client::{id, Wallet};
+use serde::Deserialize;
+use solana_sdk::{
+    commitment_config::{CommitmentConfig, CommitmentLevel},
+    pubkey::Pubkey,
+};
+use std::{error::Error, fs, path::PathBuf, str::FromStr};
+
+/// Configuration file read unless another one is given
+pub const DEFAULT_CONFIG_FILE: &str = "hello-cli.toml";
+
+/// Contents of a configuration file, every setting being optional
+#[derive(Debug, Default, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct ConfigFile {
+    pub url: Option<String>,
+    pub keypair: Option<PathBuf>,
+    pub program_id: Option<String>,
+    pub commitment: Option<String>,
+}
+
+/// Settings of the commands
+pub struct Settings {
+    /// JSON RPC URL of the cluster
+    pub url: String,
+    /// Wallet paying for and signing the transactions
+    pub wallet: Wallet,
+    /// Address of the program
+    pub program_id: Pubkey,
+    /// Commitment of the reads and confirmations
+    pub commitment: CommitmentConfig,
+}
+
+impl Settings {
+    /// Settings given by the flags of `matches`, else by the environment
+    /// variables read through `var`, else by the configuration file, else by
+    /// default
+    ///
+    /// The configuration file is the one given by `--config` or
+    /// `HELLO_CLI_CONFIG`, if any, or else `hello-cli.toml` if it exists.
+    pub fn load(
+        matches: &ArgMatches,
+        var: impl Fn(&str) -> Option<String>,
+    ) -> Result<Self, Box<dyn Error>> {
+        let file = match matches
+            .value_of("config")
+            .map(str::to_string)
+            .or_else(|| var("HELLO_CLI_CONFIG"))
+        {
+            Some(path) => read_config_file(&path)?,
+            None if fs::metadata(DEFAULT_CONFIG_FILE).is_ok() => {
+                read_config_file(DEFAULT_CONFIG_FILE)?
+            }
+            None => ConfigFile::default(),
+        };
+        let setting = |name: &str, variable: &str, file: Option<String>| {
+            matches
+                .value_of(name)
+                .map(str::to_string)
+                .or_else(|| var(variable))
+                .or(file)
+        };
+
+        let url =
+            setting("url", "HELLO_CLI_URL", file.url).unwrap_or_else(|| "localhost".to_string());
+        let wallet = match setting(
+            "keypair",
+            "HELLO_CLI_KEYPAIR",
+            file.keypair.map(|path| path.to_string_lossy().into_owned()),
+        ) {
+            Some(path) => Wallet::File(path.into()),
+            None => Wallet::default(),
+        };
+        let program_id = match setting("program-id", "HELLO_CLI_PROGRAM_ID", file.program_id) {
+            Some(program_id) => Pubkey::from_str(&program_id)
+                .map_err(|error| format!("Invalid program id {}: {}", program_id, error))?,
+            None => id(),
+        };
+        // The client builds its instructions for the declared program id
+        if program_id != id() {
+            return Err(format!(
+                "The program id {} differs from the id {} declared by the program",
+                program_id,
+                id()
+            )
+            .into());
+        }
+        let commitment = match setting("commitment", "HELLO_CLI_COMMITMENT", file.commitment) {
+            Some(commitment) => CommitmentConfig {
+                commitment: CommitmentLevel::from_str(&commitment)
+                    .map_err(|error| format!("Invalid commitment {}: {}", commitment, error))?,
+            },
+            None => CommitmentConfig::confirmed(),
+        };
+
+        Ok(Self {
+            url: rpc_url(&url),
+            wallet,
+            program_id,
+            commitment,
+        })
+    }
+}
+
+/// Configuration file at `path`
+fn read_config_file(path: &str) -> Result<ConfigFile, Box<dyn Error>> {
+    let contents =
+        fs::read_to_string(path).map_err(|error| format!("Cannot read {}: {}", path, error))?;
+    toml::from_str(&contents).map_err(|error| format!("Invalid {}: {}", path, error).into())
+}
+
+/// JSON RPC URL of `url`, either a URL or the moniker of a cluster
+fn rpc_url(url: &str) -> String {
+    match url {
+        "localhost" => "http://localhost:8899",
+        "devnet" => "https://api.devnet.solana.com",
+        "testnet" => "https://api.testnet.solana.com",
+        "mainnet-beta" => "https://api.mainnet-beta.solana.com",
+        url => url,
+    }
+    .to_string()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::app;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_settings() {
+        let path = std::env::temp_dir().join(format!("hello-cli-{}.toml", Pubkey::new_unique()));
+        fs::write(
+            &path,
+            "url = \"devnet\"\nkeypair = \"file.json\"\ncommitment = \"finalized\"\n",
+        )
+        .unwrap();
+        let config = path.to_str().unwrap();
+        let load = |args: Vec<&str>, vars: HashMap<&str, &str>| {
+            let matches = app()
+                .get_matches_from_safe([vec!["hello-cli"], args, vec!["say-hello"]].concat())
+                .unwrap();
+            Settings::load(&matches, |name| {
+                vars.get(name).map(|value| value.to_string())
+            })
+        };
+
+        // The configuration file
+        let settings = load(vec!["--config", config], HashMap::new()).unwrap();
+        assert_eq!(settings.url, "https://api.devnet.solana.com");
+        assert!(settings.wallet == Wallet::File("file.json".into()));
+        assert_eq!(settings.program_id, id());
+        assert_eq!(settings.commitment, CommitmentConfig::finalized());
+
+        // Environment variables override it, and flags override both
+        let vars: HashMap<&str, &str> = [
+            ("HELLO_CLI_CONFIG", config),
+            ("HELLO_CLI_URL", "testnet"),
+            ("HELLO_CLI_COMMITMENT", "processed"),
+        ]
+        .iter()
+        .copied()
+        .collect();
+        let settings = load(vec!["-u", "http://127.0.0.1:8899"], vars).unwrap();
+        assert_eq!(settings.url, "http://127.0.0.1:8899");
+        assert_eq!(settings.commitment, CommitmentConfig::processed());
+
+        // Defaults
+        let settings = load(vec![], HashMap::new()).unwrap();
+        assert_eq!(settings.url, "http://localhost:8899");
+        assert!(settings.wallet == Wallet::default());
+        assert_eq!(settings.commitment, CommitmentConfig::confirmed());
+
+        // Invalid settings
+        let program_id = Pubkey::new_unique().to_string();
+        assert!(load(vec!["--program-id", &program_id], HashMap::new()).is_err());
+        let vars: HashMap<&str, &str> =
+            [("HELLO_CLI_COMMITMENT", "soon")].iter().copied().collect();
+        assert!(load(vec![], vars).is_err());
+        fs::write(&path, "rpc = \"devnet\"\n").unwrap();
+        assert!(load(vec!["--config", config], HashMap::new()).is_err());
+        fs::remove_file(&path).unwrap();
+    }
+}
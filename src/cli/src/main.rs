@@ -0,0 +1,348 @@
+//! Command line interface for the hello world program
+
+mod bench;
+mod config;
+mod output;
+
+use crate::{
+    config::Settings,
+    output::{
+        CliBootstrap, CliDeployment, CliError, CliGreetingAccount, CliInitialization, CliSignature,
+        CliTransactionEvents, CliUpgrade, OutputFormat,
+    },
+};
+use clap::{
+    crate_description, crate_name, crate_version, App, AppSettings, Arg, ArgMatches, SubCommand,
+};
+use helloworld_client::{
+    blocking::HelloWorldClient, find_config_address, find_greeting_address, ClientConfig,
+    Error as ClientError,
+};
+use solana_sdk::{
+    native_token::sol_to_lamports,
+    pubkey::Pubkey,
+    signature::{read_keypair_file, Signer},
+};
+use std::{env, error::Error, fs, process::exit, str::FromStr};
+use tokio::runtime::Builder;
+
+fn app() -> App<'static, 'static> {
+    App::new(crate_name!())
+        .about(crate_description!())
+        .version(crate_version!())
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .arg(
+            Arg::with_name("config")
+                .long("config")
+                .short("C")
+                .global(true)
+                .takes_value(true)
+                .value_name("PATH")
+                .help("Configuration file [default: hello-cli.toml, if it exists]"),
+        )
+        .arg(
+            Arg::with_name("url")
+                .long("url")
+                .short("u")
+                .global(true)
+                .takes_value(true)
+                .value_name("URL_OR_MONIKER")
+                .help(
+                    "JSON RPC URL of the cluster, or one of the monikers localhost, devnet, \
+                     testnet and mainnet-beta [default: localhost]",
+                ),
+        )
+        .arg(
+            Arg::with_name("keypair")
+                .long("keypair")
+                .short("k")
+                .global(true)
+                .takes_value(true)
+                .value_name("PATH")
+                .help("Keypair paying for and signing the transactions [default: ~/.config/solana/id.json]"),
+        )
+        .arg(
+            Arg::with_name("program-id")
+                .long("program-id")
+                .global(true)
+                .takes_value(true)
+                .value_name("PUBKEY")
+                .help("Address of the program [default: the id declared by the program]"),
+        )
+        .arg(
+            Arg::with_name("commitment")
+                .long("commitment")
+                .global(true)
+                .takes_value(true)
+                .possible_values(&["processed", "confirmed", "finalized"])
+                .help("Commitment of the reads and confirmations [default: confirmed]"),
+        )
+        .arg(
+            Arg::with_name("output")
+                .long("output")
+                .global(true)
+                .takes_value(true)
+                .value_name("FORMAT")
+                .possible_values(&["json", "json-compact"])
+                .help("Print the results and the errors as JSON"),
+        )
+        .subcommand(
+            SubCommand::with_name("deploy")
+                .about("Deploy the program, upgradeable by the keypair")
+                .arg(
+                    Arg::with_name("program")
+                        .takes_value(true)
+                        .value_name("PROGRAM_PATH")
+                        .default_value("dist/program/helloworld.so")
+                        .help("Program ELF to deploy"),
+                )
+                .arg(
+                    Arg::with_name("program-keypair")
+                        .long("program-keypair")
+                        .takes_value(true)
+                        .value_name("PATH")
+                        .default_value("dist/program/helloworld-keypair.json")
+                        .help("Keypair of the program address"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("upgrade")
+                .about("Upgrade the program, whose upgrade authority is the keypair")
+                .arg(
+                    Arg::with_name("program")
+                        .takes_value(true)
+                        .value_name("PROGRAM_PATH")
+                        .required(true)
+                        .help("Program ELF to upgrade to"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("bootstrap")
+                .about(
+                    "Airdrop to the keypair, then create the config account, administered by \
+                     the keypair, and the greeting account of the keypair",
+                )
+                .arg(
+                    Arg::with_name("amount")
+                        .long("amount")
+                        .takes_value(true)
+                        .value_name("SOL")
+                        .default_value("1")
+                        .validator(|amount| {
+                            amount
+                                .parse::<f64>()
+                                .map(drop)
+                                .map_err(|error| error.to_string())
+                        })
+                        .help("Amount to airdrop, on clusters with a faucet"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("bench")
+                .about(
+                    "Greet the greeting account of the keypair at a steady rate and report \
+                     the latencies of the confirmations",
+                )
+                .arg(
+                    Arg::with_name("tps")
+                        .long("tps")
+                        .takes_value(true)
+                        .value_name("N")
+                        .default_value("100")
+                        .validator(|tps| match tps.parse::<u32>() {
+                            Ok(0) => Err("must be positive".to_string()),
+                            Ok(_) => Ok(()),
+                            Err(error) => Err(error.to_string()),
+                        })
+                        .help("Greetings sent per second"),
+                )
+                .arg(
+                    Arg::with_name("duration")
+                        .long("duration")
+                        .takes_value(true)
+                        .value_name("DURATION")
+                        .default_value("60s")
+                        .validator(|duration| {
+                            humantime::parse_duration(&duration)
+                                .map(drop)
+                                .map_err(|error| error.to_string())
+                        })
+                        .help("How long to send greetings for, such as 60s or 5m"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("monitor")
+                .about("Print the events of the program as its transactions succeed"),
+        )
+        .subcommand(
+            SubCommand::with_name("init")
+                .about("Create the greeting account of the keypair, unless it exists"),
+        )
+        .subcommand(
+            SubCommand::with_name("say-hello")
+                .about("Greet the greeting account of the keypair, creating it if needed"),
+        )
+        .subcommand(
+            SubCommand::with_name("get-count")
+                .about("Print how many times a greeting account was greeted")
+                .arg(
+                    Arg::with_name("user")
+                        .takes_value(true)
+                        .value_name("PUBKEY")
+                        .help("Owner of the greeting account [default: the keypair]"),
+                ),
+        )
+}
+
+fn main() {
+    let matches = app().get_matches();
+    let output = OutputFormat::from_name(matches.value_of("output"));
+    match run(&matches, output) {
+        // Streaming commands print their results as they come
+        Ok(result) if result.is_empty() => {}
+        Ok(result) => println!("{}", result),
+        Err(error) => {
+            let error = output.formatted_string(&CliError::new(error.as_ref()));
+            // Scripts parse the JSON errors from the standard output
+            match output {
+                OutputFormat::Display => eprintln!("{}", error),
+                _ => println!("{}", error),
+            }
+            exit(1);
+        }
+    }
+}
+
+/// Run the command of `matches`, returning its result in `output` format
+fn run(matches: &ArgMatches, output: OutputFormat) -> Result<String, Box<dyn Error>> {
+    let settings = Settings::load(matches, |name| env::var(name).ok())?;
+    let config = ClientConfig::default().commitment(settings.commitment);
+    let client = HelloWorldClient::new(settings.url.clone()).with_config(config);
+    let wallet = settings.wallet;
+
+    let result = match matches.subcommand() {
+        ("deploy", Some(matches)) => {
+            let program_data = fs::read(matches.value_of("program").unwrap())?;
+            let program = read_keypair_file(matches.value_of("program-keypair").unwrap())?;
+            if program.pubkey() != settings.program_id {
+                return Err(format!(
+                    "The program keypair is for {}, while the program id is {}",
+                    program.pubkey(),
+                    settings.program_id
+                )
+                .into());
+            }
+            let signature = client.deploy(&wallet.load()?, &program, &program_data)?;
+            output.formatted_string(&CliDeployment {
+                program_id: program.pubkey().to_string(),
+                signature: signature.to_string(),
+            })
+        }
+        ("upgrade", Some(matches)) => {
+            let program_data = fs::read(matches.value_of("program").unwrap())?;
+            let signature = client.upgrade(&wallet.load()?, &settings.program_id, &program_data)?;
+            output.formatted_string(&CliUpgrade {
+                program_id: settings.program_id.to_string(),
+                program_data_slot: client.get_program_data_slot(&settings.program_id)?,
+                signature: signature.to_string(),
+            })
+        }
+        ("bootstrap", Some(matches)) => {
+            let admin = wallet.load()?;
+            let amount = matches.value_of("amount").unwrap().parse().unwrap();
+            let airdrop_signature =
+                client.request_airdrop(&admin.pubkey(), sol_to_lamports(amount))?;
+            // Bootstrapping again keeps the existing config
+            let config_signature = match client.get_config() {
+                Ok(_) => None,
+                Err(ClientError::AccountNotFound(_)) => Some(client.initialize_config(&admin)?),
+                Err(error) => return Err(error.into()),
+            };
+            let greeting_signature = client.initialize(&admin)?;
+            output.formatted_string(&CliBootstrap {
+                wallet: admin.pubkey().to_string(),
+                airdrop_signature: airdrop_signature.to_string(),
+                config: find_config_address().0.to_string(),
+                config_signature: config_signature.map(|signature| signature.to_string()),
+                greeting_account: find_greeting_address(&admin.pubkey()).0.to_string(),
+                greeting_signature: greeting_signature.to_string(),
+            })
+        }
+        ("bench", Some(matches)) => {
+            let tps = matches.value_of("tps").unwrap().parse().unwrap();
+            let duration = humantime::parse_duration(matches.value_of("duration").unwrap())?;
+            // The greetings are sent concurrently, which takes the
+            // asynchronous client
+            let client = helloworld_client::HelloWorldClient::new(settings.url).with_config(config);
+            let runtime = Builder::new_current_thread().enable_all().build()?;
+            let report = runtime.block_on(bench::bench(client, wallet.load()?, tps, duration));
+            output.formatted_string(&report)
+        }
+        ("monitor", _) => {
+            for events in client.subscribe_transaction_events() {
+                println!(
+                    "{}",
+                    output.formatted_string(&CliTransactionEvents::new(&events))
+                );
+            }
+            String::new()
+        }
+        ("init", _) => {
+            let user = wallet.load()?;
+            let signature = client.initialize(&user)?;
+            output.formatted_string(&CliInitialization {
+                greeting_account: find_greeting_address(&user.pubkey()).0.to_string(),
+                signature: signature.to_string(),
+            })
+        }
+        ("say-hello", _) => {
+            let signature = client.say_hello(&wallet.load()?)?;
+            output.formatted_string(&CliSignature::new(&signature))
+        }
+        ("get-count", Some(matches)) => {
+            let user = match matches.value_of("user") {
+                Some(user) => Pubkey::from_str(user)?,
+                None => wallet.load()?.pubkey(),
+            };
+            let address = find_greeting_address(&user).0;
+            let greeting = client.get_greeting_account(&address)?;
+            output.formatted_string(&CliGreetingAccount::new(&address, &greeting))
+        }
+        _ => unreachable!(),
+    };
+    Ok(result)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_app() {
+        let matches = app()
+            .get_matches_from_safe(vec!["hello-cli", "get-count", "-u", "devnet"])
+            .unwrap();
+        assert_eq!(matches.value_of("url"), Some("devnet"));
+        assert_eq!(matches.subcommand_name(), Some("get-count"));
+
+        assert!(app()
+            .get_matches_from_safe(vec!["hello-cli", "--commitment", "soon", "init"])
+            .is_err());
+        assert!(app()
+            .get_matches_from_safe(vec!["hello-cli", "upgrade"])
+            .is_err());
+        assert!(app()
+            .get_matches_from_safe(vec!["hello-cli", "bench", "--duration", "5m"])
+            .is_ok());
+        assert!(app()
+            .get_matches_from_safe(vec!["hello-cli", "bench", "--tps", "0"])
+            .is_err());
+        assert!(app()
+            .get_matches_from_safe(vec!["hello-cli", "bootstrap", "--amount", "2.5"])
+            .is_ok());
+        assert!(app()
+            .get_matches_from_safe(vec!["hello-cli", "bootstrap", "--amount", "lots"])
+            .is_err());
+        assert!(app().get_matches_from_safe(vec!["hello-cli"]).is_err());
+    }
+}
@@ -0,0 +1,357 @@
+//! Results of the commands, printed for humans or as JSON
+
+use helloworld_client::{
+    Error as ClientError, GreetingAccount, HelloWorldError, TransactionEvents,
+};
+use serde::Serialize;
+use solana_sdk::{pubkey::Pubkey, signature::Signature};
+use std::{
+    error::Error,
+    fmt::{self, Display, Formatter},
+    time::SystemTime,
+};
+
+/// Format of the printed results
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OutputFormat {
+    Display,
+    Json,
+    JsonCompact,
+}
+
+impl OutputFormat {
+    /// Format given by `--output`, if any
+    pub fn from_name(name: Option<&str>) -> Self {
+        match name {
+            Some("json") => Self::Json,
+            Some("json-compact") => Self::JsonCompact,
+            _ => Self::Display,
+        }
+    }
+
+    /// `item` in this format
+    pub fn formatted_string<T: Serialize + Display>(self, item: &T) -> String {
+        match self {
+            Self::Display => item.to_string(),
+            Self::Json => serde_json::to_string_pretty(item).unwrap(),
+            Self::JsonCompact => serde_json::to_string(item).unwrap(),
+        }
+    }
+}
+
+/// Signature of a sent transaction
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CliSignature {
+    pub signature: String,
+}
+
+impl CliSignature {
+    pub fn new(signature: &Signature) -> Self {
+        Self {
+            signature: signature.to_string(),
+        }
+    }
+}
+
+impl Display for CliSignature {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "Signature: {}", self.signature)
+    }
+}
+
+/// Deployed program
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CliDeployment {
+    pub program_id: String,
+    pub signature: String,
+}
+
+impl Display for CliDeployment {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        writeln!(f, "Program id: {}", self.program_id)?;
+        write!(f, "Signature: {}", self.signature)
+    }
+}
+
+/// Upgraded program
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CliUpgrade {
+    pub program_id: String,
+    pub program_data_slot: u64,
+    pub signature: String,
+}
+
+impl Display for CliUpgrade {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        writeln!(f, "Program id: {}", self.program_id)?;
+        writeln!(f, "Program data slot: {}", self.program_data_slot)?;
+        write!(f, "Signature: {}", self.signature)
+    }
+}
+
+/// Accounts created by `bootstrap`
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CliBootstrap {
+    pub wallet: String,
+    pub airdrop_signature: String,
+    pub config: String,
+    /// `None` if the config account already existed
+    pub config_signature: Option<String>,
+    pub greeting_account: String,
+    pub greeting_signature: String,
+}
+
+impl Display for CliBootstrap {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        writeln!(f, "Wallet: {}", self.wallet)?;
+        writeln!(f, "Airdrop signature: {}", self.airdrop_signature)?;
+        match &self.config_signature {
+            Some(signature) => {
+                writeln!(f, "Config: {}", self.config)?;
+                writeln!(f, "Config signature: {}", signature)?;
+            }
+            None => writeln!(f, "Config: {} (already initialized)", self.config)?,
+        }
+        writeln!(f, "Greeting account: {}", self.greeting_account)?;
+        write!(f, "Greeting signature: {}", self.greeting_signature)
+    }
+}
+
+/// Greeting account created for a user
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CliInitialization {
+    pub greeting_account: String,
+    pub signature: String,
+}
+
+impl Display for CliInitialization {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        writeln!(f, "Greeting account: {}", self.greeting_account)?;
+        write!(f, "Signature: {}", self.signature)
+    }
+}
+
+/// Decoded greeting account, displayed as its counter alone
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CliGreetingAccount {
+    pub address: String,
+    pub version: u8,
+    pub counter: u32,
+    pub expires_at: i64,
+    pub has_mirror: bool,
+    pub last_greeter: String,
+    pub last_slot: u64,
+    pub bump_seed: u8,
+}
+
+impl CliGreetingAccount {
+    pub fn new(address: &Pubkey, greeting: &GreetingAccount) -> Self {
+        Self {
+            address: address.to_string(),
+            version: greeting.version,
+            counter: greeting.counter,
+            expires_at: greeting.expires_at,
+            has_mirror: greeting.has_mirror,
+            last_greeter: greeting.last_greeter.to_string(),
+            last_slot: greeting.last_slot,
+            bump_seed: greeting.bump_seed,
+        }
+    }
+}
+
+impl Display for CliGreetingAccount {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}", self.counter)
+    }
+}
+
+/// Summary of a benchmark, with the latencies of the confirmed greetings
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CliBenchReport {
+    pub sent: usize,
+    pub confirmed: usize,
+    pub failed: usize,
+    pub elapsed_ms: u64,
+    pub confirmed_tps: f64,
+    pub latency_p50_ms: u64,
+    pub latency_p90_ms: u64,
+    pub latency_p99_ms: u64,
+    pub latency_max_ms: u64,
+    /// Distinct errors of the failed greetings
+    pub errors: Vec<String>,
+}
+
+impl Display for CliBenchReport {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "Sent: {}, confirmed: {}, failed: {}",
+            self.sent, self.confirmed, self.failed
+        )?;
+        writeln!(
+            f,
+            "Elapsed: {} ms, confirmed TPS: {:.1}",
+            self.elapsed_ms, self.confirmed_tps
+        )?;
+        write!(
+            f,
+            "Latency: p50 {} ms, p90 {} ms, p99 {} ms, max {} ms",
+            self.latency_p50_ms, self.latency_p90_ms, self.latency_p99_ms, self.latency_max_ms
+        )?;
+        for error in &self.errors {
+            write!(f, "\nError: {}", error)?;
+        }
+        Ok(())
+    }
+}
+
+/// Events of a transaction of the program, timestamped on receipt
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CliTransactionEvents {
+    pub timestamp: String,
+    pub slot: u64,
+    pub signature: String,
+    pub events: Vec<String>,
+}
+
+impl CliTransactionEvents {
+    pub fn new(events: &TransactionEvents) -> Self {
+        Self {
+            timestamp: humantime::format_rfc3339_seconds(SystemTime::now()).to_string(),
+            slot: events.slot,
+            signature: events.signature.to_string(),
+            events: events
+                .events
+                .iter()
+                .map(|event| format!("{:?}", event))
+                .collect(),
+        }
+    }
+}
+
+impl Display for CliTransactionEvents {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} slot {} {}: {}",
+            self.timestamp,
+            self.slot,
+            self.signature,
+            self.events.join(", ")
+        )
+    }
+}
+
+/// Error of the program, with its code
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CliProgramError {
+    pub code: u32,
+    pub name: String,
+    pub message: String,
+}
+
+impl CliProgramError {
+    fn new(error: HelloWorldError) -> Self {
+        Self {
+            code: error.code(),
+            name: format!("{:?}", error),
+            message: error.to_string(),
+        }
+    }
+}
+
+/// Failed command, decoding the errors of the program and the logs of failed
+/// simulations
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CliError {
+    pub error: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub program_error: Option<CliProgramError>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub logs: Vec<String>,
+}
+
+impl CliError {
+    pub fn new(error: &(dyn Error + 'static)) -> Self {
+        let (program_error, logs) = match error.downcast_ref::<ClientError>() {
+            Some(ClientError::Program(program_error)) => (Some(*program_error), vec![]),
+            Some(ClientError::Simulation {
+                program_error,
+                logs,
+                ..
+            }) => (*program_error, logs.clone()),
+            _ => (None, vec![]),
+        };
+        Self {
+            error: error.to_string(),
+            program_error: program_error.map(CliProgramError::new),
+            logs,
+        }
+    }
+}
+
+impl Display for CliError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "Error: {}", self.error)?;
+        for log in &self.logs {
+            write!(f, "\n  {}", log)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use solana_sdk::{instruction::InstructionError, transaction::TransactionError};
+
+    #[test]
+    fn test_output() {
+        let signature = CliSignature::new(&Signature::default());
+        assert_eq!(
+            OutputFormat::Display.formatted_string(&signature),
+            format!("Signature: {}", Signature::default())
+        );
+        assert_eq!(
+            OutputFormat::JsonCompact.formatted_string(&signature),
+            format!("{{\"signature\":\"{}\"}}", Signature::default())
+        );
+
+        let error: Box<dyn Error> = Box::new(ClientError::Simulation {
+            error: TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(HelloWorldError::ProgramPaused.code()),
+            ),
+            program_error: Some(HelloWorldError::ProgramPaused),
+            logs: vec!["Program log: paused".to_string()],
+        });
+        let error = CliError::new(error.as_ref());
+        assert_eq!(
+            OutputFormat::Display.formatted_string(&error),
+            "Error: Simulation failed: Error processing Instruction 0: custom program error: \
+             0x7\n  Program log: paused"
+        );
+        assert_eq!(
+            OutputFormat::JsonCompact.formatted_string(&error),
+            "{\"error\":\"Simulation failed: Error processing Instruction 0: custom program \
+             error: 0x7\",\"programError\":{\"code\":7,\"name\":\"ProgramPaused\",\"message\":\
+             \"Program is paused\"},\"logs\":[\"Program log: paused\"]}"
+        );
+
+        let error: Box<dyn Error> = "Invalid pubkey".into();
+        assert_eq!(
+            OutputFormat::JsonCompact.formatted_string(&CliError::new(error.as_ref())),
+            "{\"error\":\"Invalid pubkey\"}"
+        );
+    }
+}
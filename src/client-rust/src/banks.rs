@@ -0,0 +1,173 @@
+//! High level client driving a `BanksClient`, mostly for program tests
+
+use crate::{
+    decode_account, find_config_address, find_greeter_address, find_greeting_address,
+    initialize_ix, say_hello_ix, Config, Error, GreeterAccount, GreetingAccount,
+};
+use helloworld::state::AccountState;
+use solana_banks_client::BanksClient;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::{
+    instruction::Instruction,
+    signature::{Keypair, Signature, Signer},
+    transaction::Transaction,
+};
+
+/// Counterpart of `HelloWorldClient` sending its requests through a
+/// `BanksClient`, like the one of a `ProgramTestContext`
+pub struct HelloWorldBanksClient {
+    banks_client: BanksClient,
+}
+
+impl HelloWorldBanksClient {
+    /// Client sending its requests through `banks_client`
+    pub fn new(banks_client: BanksClient) -> Self {
+        Self { banks_client }
+    }
+
+    /// Greet the greeting address of `greeter`, creating it on the first
+    /// greeting
+    pub async fn say_hello(&mut self, greeter: &Keypair) -> Result<Signature, Error> {
+        let greeting_address = find_greeting_address(&greeter.pubkey()).0;
+        self.process(say_hello_ix(&greeting_address, &greeter.pubkey()), greeter)
+            .await
+    }
+
+    /// Create the greeting address of `user` unless it already exists
+    pub async fn initialize(&mut self, user: &Keypair) -> Result<Signature, Error> {
+        self.process(initialize_ix(&user.pubkey()), user).await
+    }
+
+    /// Greeting account at `pubkey`, `None` if there is no account
+    pub async fn get_greeting(
+        &mut self,
+        pubkey: &Pubkey,
+    ) -> Result<Option<GreetingAccount>, Error> {
+        decode_account(self.banks_client.get_account(*pubkey).await?)
+    }
+
+    /// State of type `T` stored in the account at `pubkey`, checking that the
+    /// program owns the account and that it holds a `T`
+    pub async fn get_state<T: AccountState>(&mut self, pubkey: &Pubkey) -> Result<T, Error> {
+        decode_account(self.banks_client.get_account(*pubkey).await?)?
+            .ok_or(Error::AccountNotFound(*pubkey))
+    }
+
+    /// Greeting account at `pubkey`
+    pub async fn get_greeting_account(
+        &mut self,
+        pubkey: &Pubkey,
+    ) -> Result<GreetingAccount, Error> {
+        self.get_state(pubkey).await
+    }
+
+    /// Config of the program
+    pub async fn get_config(&mut self) -> Result<Config, Error> {
+        self.get_state(&find_config_address().0).await
+    }
+
+    /// Greeter account of `greeter`
+    pub async fn get_greeter_account(&mut self, greeter: &Pubkey) -> Result<GreeterAccount, Error> {
+        self.get_state(&find_greeter_address(greeter).0).await
+    }
+
+    /// Sign `instruction` with `signer`, also paying for the transaction, and
+    /// process it
+    async fn process(
+        &mut self,
+        instruction: Instruction,
+        signer: &Keypair,
+    ) -> Result<Signature, Error> {
+        let recent_blockhash = self.banks_client.get_latest_blockhash().await?;
+        let transaction = Transaction::new_signed_with_payer(
+            &[instruction],
+            Some(&signer.pubkey()),
+            &[signer],
+            recent_blockhash,
+        );
+        let signature = transaction.signatures[0];
+        self.banks_client.process_transaction(transaction).await?;
+        Ok(signature)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::id;
+    use helloworld::{process_instruction, state::AccountState};
+    use solana_program::program_error::ProgramError;
+    use solana_program_test::{processor, ProgramTest};
+    use solana_sdk::{account::Account, rent::Rent};
+
+    #[tokio::test]
+    async fn test_banks_client() {
+        let user = Keypair::new();
+        let (greeting_address, bump_seed) = find_greeting_address(&user.pubkey());
+
+        let mut program_test =
+            ProgramTest::new("helloworld", id(), processor!(process_instruction));
+        // Native CPI cannot allocate the greeting address, so start from a
+        // created one
+        let mut data = vec![0; GreetingAccount::LEN];
+        GreetingAccount {
+            version: GreetingAccount::VERSION,
+            bump_seed,
+            ..GreetingAccount::default()
+        }
+        .store(&mut data)
+        .unwrap();
+        program_test.add_account(
+            greeting_address,
+            Account {
+                lamports: Rent::default().minimum_balance(GreetingAccount::LEN),
+                data,
+                owner: id(),
+                ..Account::default()
+            },
+        );
+        program_test.add_account(
+            user.pubkey(),
+            Account {
+                lamports: 1_000_000_000,
+                ..Account::default()
+            },
+        );
+        let mut context = program_test.start_with_context().await;
+        let mut client = HelloWorldBanksClient::new(context.banks_client.clone());
+
+        client.initialize(&user).await.unwrap();
+        for counter in 1..=2 {
+            client.say_hello(&user).await.unwrap();
+            let greeting = client.get_greeting(&greeting_address).await.unwrap();
+            assert_eq!(greeting.unwrap().counter, counter);
+            // Move on to a new blockhash, the next greeting would otherwise be
+            // the same transaction
+            let slot = context.banks_client.get_root_slot().await.unwrap();
+            context.warp_to_slot(slot + 2).unwrap();
+        }
+        assert_eq!(
+            client.get_greeting(&Pubkey::new_unique()).await.unwrap(),
+            None
+        );
+
+        assert_eq!(
+            client
+                .get_greeting_account(&greeting_address)
+                .await
+                .unwrap()
+                .counter,
+            2
+        );
+        let missing = Pubkey::new_unique();
+        assert!(matches!(
+            client.get_greeting_account(&missing).await,
+            Err(Error::AccountNotFound(pubkey)) if pubkey == missing
+        ));
+        // Greeting accounts do not hold other states
+        assert!(matches!(
+            client.get_state::<Config>(&greeting_address).await,
+            Err(Error::InvalidAccount(ProgramError::InvalidAccountData))
+        ));
+    }
+}
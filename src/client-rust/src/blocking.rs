@@ -0,0 +1,328 @@
+//! Synchronous client, for scripts and tools running without a Tokio runtime
+
+use crate::{
+    ClientConfig, Config, DurableNonce, Error, GreeterAccount, GreetingAccount, GreetingEvent,
+    GuardianSet, LookupTable, Observer, TransactionEvents,
+};
+use futures::StreamExt;
+use helloworld::state::AccountState;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    instruction::Instruction,
+    signature::{Keypair, Signature},
+    transaction::{Transaction, VersionedTransaction},
+};
+use std::sync::Arc;
+use tokio::runtime::{Builder, Runtime};
+
+/// Blocking equivalent of `crate::HelloWorldClient`, running each call to
+/// completion on a runtime of its own
+#[derive(Clone)]
+pub struct HelloWorldClient {
+    client: crate::HelloWorldClient,
+    runtime: Arc<Runtime>,
+}
+
+impl HelloWorldClient {
+    /// Client for the cluster serving JSON RPC at `rpc_url`
+    pub fn new(rpc_url: impl Into<String>) -> Self {
+        Self::with_rpc_client(RpcClient::new(rpc_url.into()))
+    }
+
+    /// Client sending its requests through `rpc_client`
+    pub fn with_rpc_client(rpc_client: RpcClient) -> Self {
+        let runtime = Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .expect("failed to build the runtime of the blocking client");
+        Self {
+            client: crate::HelloWorldClient::with_rpc_client(rpc_client),
+            runtime: Arc::new(runtime),
+        }
+    }
+
+    /// See `crate::HelloWorldClient::with_websocket_url`
+    pub fn with_websocket_url(mut self, websocket_url: impl Into<String>) -> Self {
+        self.client = self.client.with_websocket_url(websocket_url);
+        self
+    }
+
+    /// See `crate::HelloWorldClient::with_observer`
+    pub fn with_observer(mut self, observer: impl Observer + 'static) -> Self {
+        self.client = self.client.with_observer(observer);
+        self
+    }
+
+    /// See `crate::HelloWorldClient::with_config`
+    pub fn with_config(mut self, config: ClientConfig) -> Self {
+        self.client = self.client.with_config(config);
+        self
+    }
+
+    /// See `crate::HelloWorldClient::at_commitment`
+    pub fn at_commitment(&self, commitment: CommitmentConfig) -> Self {
+        Self {
+            client: self.client.at_commitment(commitment),
+            runtime: self.runtime.clone(),
+        }
+    }
+
+    /// Iterator over the updates of greeting accounts, blocking until the
+    /// next one
+    ///
+    /// The subscription only makes progress while the iterator is waited on.
+    pub fn subscribe_greetings(&self) -> impl Iterator<Item = GreetingEvent> + '_ {
+        let _guard = self.runtime.enter();
+        let mut events = Box::pin(self.client.subscribe_greetings());
+        std::iter::from_fn(move || self.runtime.block_on(events.next()))
+    }
+
+    /// See `crate::HelloWorldClient::subscribe_transaction_events`
+    pub fn subscribe_transaction_events(&self) -> impl Iterator<Item = TransactionEvents> + '_ {
+        let _guard = self.runtime.enter();
+        let mut events = Box::pin(self.client.subscribe_transaction_events());
+        std::iter::from_fn(move || self.runtime.block_on(events.next()))
+    }
+
+    /// See `crate::HelloWorldClient::say_hello`
+    pub fn say_hello(&self, greeter: &Keypair) -> Result<Signature, Error> {
+        self.runtime.block_on(self.client.say_hello(greeter))
+    }
+
+    /// See `crate::HelloWorldClient::send_greetings_batch`
+    pub fn send_greetings_batch(
+        &self,
+        keys: Vec<Keypair>,
+    ) -> Vec<(Pubkey, Result<Signature, Error>)> {
+        self.runtime
+            .block_on(self.client.send_greetings_batch(keys))
+    }
+
+    /// See `crate::HelloWorldClient::say_hello_to_all`
+    pub fn say_hello_to_all(
+        &self,
+        greeter: &Keypair,
+        greeted: &[Pubkey],
+        lookup_tables: &[LookupTable],
+    ) -> Result<Signature, Error> {
+        self.runtime.block_on(
+            self.client
+                .say_hello_to_all(greeter, greeted, lookup_tables),
+        )
+    }
+
+    /// See `crate::HelloWorldClient::create_lookup_table`
+    pub fn create_lookup_table(&self, authority: &Keypair) -> Result<Pubkey, Error> {
+        self.runtime
+            .block_on(self.client.create_lookup_table(authority))
+    }
+
+    /// See `crate::HelloWorldClient::extend_lookup_table`
+    pub fn extend_lookup_table(
+        &self,
+        authority: &Keypair,
+        table_pubkey: &Pubkey,
+        addresses: Vec<Pubkey>,
+    ) -> Result<Signature, Error> {
+        self.runtime.block_on(
+            self.client
+                .extend_lookup_table(authority, table_pubkey, addresses),
+        )
+    }
+
+    /// See `crate::HelloWorldClient::get_lookup_table`
+    pub fn get_lookup_table(&self, table_pubkey: &Pubkey) -> Result<LookupTable, Error> {
+        self.runtime
+            .block_on(self.client.get_lookup_table(table_pubkey))
+    }
+
+    /// See `crate::HelloWorldClient::initialize`
+    pub fn initialize(&self, user: &Keypair) -> Result<Signature, Error> {
+        self.runtime.block_on(self.client.initialize(user))
+    }
+
+    /// See `crate::HelloWorldClient::initialize_config`
+    pub fn initialize_config(&self, admin: &Keypair) -> Result<Signature, Error> {
+        self.runtime.block_on(self.client.initialize_config(admin))
+    }
+
+    /// See `crate::HelloWorldClient::request_airdrop`
+    pub fn request_airdrop(&self, pubkey: &Pubkey, lamports: u64) -> Result<Signature, Error> {
+        self.runtime
+            .block_on(self.client.request_airdrop(pubkey, lamports))
+    }
+
+    /// See `crate::HelloWorldClient::get_greeting`
+    pub fn get_greeting(&self, pubkey: &Pubkey) -> Result<Option<GreetingAccount>, Error> {
+        self.runtime.block_on(self.client.get_greeting(pubkey))
+    }
+
+    /// See `crate::HelloWorldClient::get_state`
+    pub fn get_state<T: AccountState>(&self, pubkey: &Pubkey) -> Result<T, Error> {
+        self.runtime.block_on(self.client.get_state(pubkey))
+    }
+
+    /// See `crate::HelloWorldClient::get_greeting_account`
+    pub fn get_greeting_account(&self, pubkey: &Pubkey) -> Result<GreetingAccount, Error> {
+        self.runtime
+            .block_on(self.client.get_greeting_account(pubkey))
+    }
+
+    /// See `crate::HelloWorldClient::get_config`
+    pub fn get_config(&self) -> Result<Config, Error> {
+        self.runtime.block_on(self.client.get_config())
+    }
+
+    /// See `crate::HelloWorldClient::get_greeter_account`
+    pub fn get_greeter_account(&self, greeter: &Pubkey) -> Result<GreeterAccount, Error> {
+        self.runtime
+            .block_on(self.client.get_greeter_account(greeter))
+    }
+
+    /// See `crate::HelloWorldClient::list_greetings`
+    pub fn list_greetings(&self) -> Result<Vec<(Pubkey, GreetingAccount)>, Error> {
+        self.runtime.block_on(self.client.list_greetings())
+    }
+
+    /// See `crate::HelloWorldClient::list_by_authority`
+    pub fn list_by_authority(
+        &self,
+        authority: &Pubkey,
+    ) -> Result<Vec<(Pubkey, GuardianSet)>, Error> {
+        self.runtime
+            .block_on(self.client.list_by_authority(authority))
+    }
+
+    /// See `crate::HelloWorldClient::deploy`
+    pub fn deploy(
+        &self,
+        payer: &Keypair,
+        program: &Keypair,
+        program_data: &[u8],
+    ) -> Result<Signature, Error> {
+        self.runtime
+            .block_on(self.client.deploy(payer, program, program_data))
+    }
+
+    /// See `crate::HelloWorldClient::upgrade`
+    pub fn upgrade(
+        &self,
+        authority: &Keypair,
+        program_id: &Pubkey,
+        program_data: &[u8],
+    ) -> Result<Signature, Error> {
+        self.runtime
+            .block_on(self.client.upgrade(authority, program_id, program_data))
+    }
+
+    /// See `crate::HelloWorldClient::get_program_data_slot`
+    pub fn get_program_data_slot(&self, program_id: &Pubkey) -> Result<u64, Error> {
+        self.runtime
+            .block_on(self.client.get_program_data_slot(program_id))
+    }
+
+    /// See `crate::HelloWorldClient::write_buffer`
+    pub fn write_buffer(&self, payer: &Keypair, program_data: &[u8]) -> Result<Pubkey, Error> {
+        self.runtime
+            .block_on(self.client.write_buffer(payer, program_data))
+    }
+
+    /// See `crate::HelloWorldClient::get_durable_nonce`
+    pub fn get_durable_nonce(&self, nonce_pubkey: &Pubkey) -> Result<DurableNonce, Error> {
+        self.runtime
+            .block_on(self.client.get_durable_nonce(nonce_pubkey))
+    }
+
+    /// See `crate::HelloWorldClient::send_transaction`
+    pub fn send_transaction(&self, transaction: &Transaction) -> Result<Signature, Error> {
+        self.runtime
+            .block_on(self.client.send_transaction(transaction))
+    }
+
+    /// See `crate::HelloWorldClient::send_versioned_transaction`
+    pub fn send_versioned_transaction(
+        &self,
+        transaction: &VersionedTransaction,
+    ) -> Result<Signature, Error> {
+        self.runtime
+            .block_on(self.client.send_versioned_transaction(transaction))
+    }
+
+    /// See `crate::HelloWorldClient::send_and_confirm`
+    pub fn send_and_confirm(
+        &self,
+        instructions: &[Instruction],
+        signer: &Keypair,
+    ) -> Result<Signature, Error> {
+        self.runtime
+            .block_on(self.client.send_and_confirm(instructions, signer))
+    }
+
+    /// See `crate::HelloWorldClient::send_and_confirm_with_signers`
+    pub fn send_and_confirm_with_signers(
+        &self,
+        instructions: &[Instruction],
+        signers: &[&Keypair],
+    ) -> Result<Signature, Error> {
+        self.runtime.block_on(
+            self.client
+                .send_and_confirm_with_signers(instructions, signers),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{find_greeting_address, id};
+    use serde_json::json;
+    use solana_client::rpc_request::RpcRequest;
+    use solana_sdk::signature::Signer;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_blocking_client() {
+        let user = Keypair::new();
+        let greeting_address = find_greeting_address(&user.pubkey()).0;
+
+        let client = HelloWorldClient::with_rpc_client(RpcClient::new_mock("succeeds".to_string()));
+        client.initialize(&user).unwrap();
+        client.initialize_config(&user).unwrap();
+        client.request_airdrop(&user.pubkey(), 1).unwrap();
+        client.say_hello(&user).unwrap();
+        assert_eq!(client.get_greeting(&greeting_address).unwrap(), None);
+
+        let greeting = GreetingAccount {
+            version: GreetingAccount::VERSION,
+            counter: 3,
+            ..GreetingAccount::default()
+        };
+        let mut data = vec![0; GreetingAccount::LEN];
+        greeting.store(&mut data).unwrap();
+        let mut mocks = HashMap::new();
+        mocks.insert(
+            RpcRequest::GetAccountInfo,
+            json!({
+                "context": { "slot": 1 },
+                "value": {
+                    "data": [base64::encode(&data), "base64"],
+                    "executable": false,
+                    "lamports": 1,
+                    "owner": id().to_string(),
+                    "rentEpoch": 0,
+                },
+            }),
+        );
+        let client = HelloWorldClient::with_rpc_client(RpcClient::new_mock_with_mocks(
+            "succeeds".to_string(),
+            mocks,
+        ))
+        .at_commitment(CommitmentConfig::finalized());
+        assert_eq!(
+            client.get_greeting_account(&greeting_address).unwrap(),
+            greeting
+        );
+    }
+}
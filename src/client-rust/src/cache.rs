@@ -0,0 +1,93 @@
+//! Cache of the accounts read by the client
+
+use solana_program::pubkey::Pubkey;
+use solana_sdk::account::Account;
+use std::{collections::HashMap, sync::Mutex};
+
+/// Accounts read by the client, along with the slot at which they were read
+///
+/// An account is served from the cache until the latest slot the client has
+/// observed, in any response, is more than the configured number of slots
+/// past the slot of the read, or until a transaction of the client that may
+/// write it is confirmed.
+#[derive(Debug, Default)]
+pub(crate) struct AccountCache {
+    state: Mutex<CacheState>,
+}
+
+#[derive(Debug, Default)]
+struct CacheState {
+    /// Latest slot observed by the client
+    slot: u64,
+    /// Slot of the read of each account, and the account, `None` if there was
+    /// none
+    accounts: HashMap<Pubkey, (u64, Option<Account>)>,
+}
+
+impl AccountCache {
+    /// Account at `pubkey`, `None` unless it was read at most `max_age` slots
+    /// before the latest observed slot
+    pub(crate) fn get(&self, pubkey: &Pubkey, max_age: u64) -> Option<Option<Account>> {
+        let mut state = self.state.lock().unwrap();
+        let (slot, account) = state.accounts.get(pubkey)?;
+        if state.slot.saturating_sub(*slot) <= max_age {
+            return Some(account.clone());
+        }
+        state.accounts.remove(pubkey);
+        None
+    }
+
+    /// Cache `account`, read at `pubkey` at `slot`
+    pub(crate) fn insert(&self, pubkey: Pubkey, slot: u64, account: Option<Account>) {
+        let mut state = self.state.lock().unwrap();
+        state.slot = state.slot.max(slot);
+        state.accounts.insert(pubkey, (slot, account));
+    }
+
+    /// Record that the cluster reached `slot`, ageing the cached accounts
+    pub(crate) fn observe(&self, slot: u64) {
+        let mut state = self.state.lock().unwrap();
+        state.slot = state.slot.max(slot);
+    }
+
+    /// Evict the accounts at `pubkeys`, which may have changed
+    pub(crate) fn invalidate<'a>(&self, pubkeys: impl IntoIterator<Item = &'a Pubkey>) {
+        let mut state = self.state.lock().unwrap();
+        for pubkey in pubkeys {
+            state.accounts.remove(pubkey);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_account_cache() {
+        let cache = AccountCache::default();
+        let (pubkey, missing) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let account = Account {
+            lamports: 1,
+            ..Account::default()
+        };
+        assert_eq!(cache.get(&pubkey, 10), None);
+
+        cache.insert(pubkey, 100, Some(account.clone()));
+        cache.insert(missing, 100, None);
+        assert_eq!(cache.get(&pubkey, 10), Some(Some(account.clone())));
+        assert_eq!(cache.get(&missing, 10), Some(None));
+
+        // Accounts expire once the cluster moves past their age
+        cache.observe(110);
+        assert_eq!(cache.get(&pubkey, 10), Some(Some(account.clone())));
+        cache.observe(105);
+        cache.insert(missing, 111, None);
+        assert_eq!(cache.get(&pubkey, 10), None);
+        assert_eq!(cache.get(&pubkey, 20), None);
+        assert_eq!(cache.get(&missing, 10), Some(None));
+
+        cache.invalidate(&[missing]);
+        assert_eq!(cache.get(&missing, 10), None);
+    }
+}
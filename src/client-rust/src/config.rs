@@ -0,0 +1,125 @@
+//! Settings of the transactions built by the client
+
+use crate::{PriorityFee, RetryPolicy};
+use solana_program::pubkey::Pubkey;
+use solana_sdk::commitment_config::CommitmentConfig;
+
+/// Most compute units a transaction can request
+pub(crate) const MAX_COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+
+/// Margin, in percent of the units consumed in the simulation, added to the
+/// compute unit limit set by `ClientConfig::auto_compute_budget`
+pub(crate) const AUTO_COMPUTE_UNIT_MARGIN: u64 = 20;
+
+/// Transactions of a batch in flight at once, unless configured otherwise
+pub(crate) const DEFAULT_BATCH_CONCURRENCY: usize = 16;
+
+/// How the client learns that a sent transaction is confirmed
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Confirmation {
+    /// Poll the status of the transaction over JSON RPC
+    #[default]
+    Polling,
+    /// Subscribe to the signature of the transaction over the websocket
+    /// endpoint, which notifies the confirmation as soon as it happens
+    Subscription,
+}
+
+/// Settings of the transactions built by `HelloWorldClient`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ClientConfig {
+    pub(crate) commitment: Option<CommitmentConfig>,
+    pub(crate) confirmation: Confirmation,
+    pub(crate) priority_fee: PriorityFee,
+    pub(crate) compute_unit_limit: Option<u32>,
+    pub(crate) auto_compute_budget: bool,
+    pub(crate) nonce_pubkey: Option<Pubkey>,
+    pub(crate) retry_policy: RetryPolicy,
+    pub(crate) batch_concurrency: usize,
+    pub(crate) account_cache_slots: Option<u64>,
+}
+
+impl Default for ClientConfig {
+    fn default() -> Self {
+        Self {
+            commitment: None,
+            confirmation: Confirmation::default(),
+            priority_fee: PriorityFee::default(),
+            compute_unit_limit: None,
+            auto_compute_budget: false,
+            nonce_pubkey: None,
+            retry_policy: RetryPolicy::default(),
+            batch_concurrency: DEFAULT_BATCH_CONCURRENCY,
+            account_cache_slots: None,
+        }
+    }
+}
+
+impl ClientConfig {
+    /// Read accounts and confirm transactions at `commitment`, rather than
+    /// the commitment of the RPC client
+    pub fn commitment(mut self, commitment: CommitmentConfig) -> Self {
+        self.commitment = Some(commitment);
+        self
+    }
+
+    /// Learn of the confirmation of the transactions through `confirmation`
+    pub fn confirmation(mut self, confirmation: Confirmation) -> Self {
+        self.confirmation = confirmation;
+        self
+    }
+
+    /// Price the compute units of the transactions with `priority_fee`
+    pub fn priority_fee(mut self, priority_fee: PriorityFee) -> Self {
+        self.priority_fee = priority_fee;
+        self
+    }
+
+    /// Cap the compute units of the transactions at `units`, rather than the
+    /// runtime default
+    pub fn compute_unit_limit(mut self, units: u32) -> Self {
+        self.compute_unit_limit = Some(units);
+        self
+    }
+
+    /// Simulate each transaction before sending it, capping its compute units
+    /// at the units consumed in the simulation plus a safety margin
+    ///
+    /// An explicit `compute_unit_limit` takes precedence.
+    pub fn auto_compute_budget(mut self, enabled: bool) -> Self {
+        self.auto_compute_budget = enabled;
+        self
+    }
+
+    /// Build the transactions against the durable nonce stored in
+    /// `nonce_pubkey`, whose authority must be the signer of each transaction
+    pub fn durable_nonce(mut self, nonce_pubkey: Pubkey) -> Self {
+        self.nonce_pubkey = Some(nonce_pubkey);
+        self
+    }
+
+    /// Retry sending the transactions following `retry_policy`
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Keep at most `transactions` transactions of a batch in flight at once,
+    /// at least one
+    pub fn batch_concurrency(mut self, transactions: usize) -> Self {
+        self.batch_concurrency = transactions.max(1);
+        self
+    }
+
+    /// Serve the accounts read by the client from a cache for up to `slots`
+    /// slots past their read, rather than fetching them on every call
+    ///
+    /// The client ages the cache with the slots of the responses it gets, and
+    /// evicts the accounts written by its transactions once they are
+    /// confirmed. Writes by other clients go unnoticed until the cached
+    /// accounts expire.
+    pub fn account_cache(mut self, slots: u64) -> Self {
+        self.account_cache_slots = Some(slots);
+        self
+    }
+}
@@ -0,0 +1,214 @@
+//! Errors of the high level clients
+
+use crate::{id, HelloWorldError, SendFailure};
+use solana_banks_client::BanksClientError;
+use solana_client::{
+    client_error::{ClientError, ClientErrorKind},
+    nonblocking::pubsub_client::PubsubClientError,
+    nonce_utils,
+    rpc_request::{RpcError, RpcResponseErrorData},
+    tpu_client::TpuSenderError,
+};
+use solana_program::{
+    instruction::InstructionError, message::VersionedMessage, program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use solana_sdk::{signer::SignerError, transaction::TransactionError};
+use thiserror::Error;
+
+/// Errors of `HelloWorldClient` and `HelloWorldBanksClient`
+#[derive(Debug, Error)]
+pub enum Error {
+    /// The RPC request or the transaction failed, boxed as it is much
+    /// larger than the other errors
+    #[error(transparent)]
+    Rpc(Box<ClientError>),
+    /// The simulation of the transaction failed, before it was sent or in its
+    /// preflight checks
+    #[error("Simulation failed: {error}")]
+    Simulation {
+        error: TransactionError,
+        /// Error of the program, if it failed the transaction
+        program_error: Option<HelloWorldError>,
+        logs: Vec<String>,
+    },
+    /// The program failed the transaction with one of its errors
+    #[error("Program error: {0}")]
+    Program(HelloWorldError),
+    /// The websocket subscription failed, boxed as the RPC errors
+    #[error(transparent)]
+    Pubsub(Box<PubsubClientError>),
+    /// The TPU sender could not track the leaders, boxed as the RPC errors
+    #[error(transparent)]
+    Tpu(Box<TpuSenderError>),
+    /// The banks request or the transaction failed
+    #[error(transparent)]
+    Banks(#[from] BanksClientError),
+    /// There is no account at the given address
+    #[error("Account {0} not found")]
+    AccountNotFound(Pubkey),
+    /// An account does not hold the expected state
+    #[error("Invalid account data: {0}")]
+    InvalidAccount(#[from] ProgramError),
+    /// An account does not hold an initialized durable nonce
+    #[error("Invalid nonce account: {0}")]
+    Nonce(#[from] nonce_utils::Error),
+    /// Sending the transaction failed for good
+    #[error("Sending failed after {attempts} attempts: {reason}")]
+    SendFailed { attempts: u32, reason: SendFailure },
+    /// An instruction could not be built
+    #[error("Invalid instruction: {0}")]
+    Instruction(#[from] InstructionError),
+    /// The keypair of a wallet could not be loaded
+    #[error("Could not load the keypair: {0}")]
+    Keypair(String),
+    /// The signers do not match the signers required by the message
+    #[error(transparent)]
+    Signer(#[from] SignerError),
+}
+
+impl From<ClientError> for Error {
+    fn from(error: ClientError) -> Self {
+        Self::Rpc(Box::new(error))
+    }
+}
+
+impl From<TpuSenderError> for Error {
+    fn from(error: TpuSenderError) -> Self {
+        Self::Tpu(Box::new(error))
+    }
+}
+
+impl From<PubsubClientError> for Error {
+    fn from(error: PubsubClientError) -> Self {
+        Self::Pubsub(Box::new(error))
+    }
+}
+
+impl Error {
+    /// The error, with the failure of the transaction of `message` it holds
+    /// decoded into a simulation failure or an error of the program
+    pub(crate) fn decode(self, message: &VersionedMessage) -> Self {
+        let error = match self {
+            Self::Rpc(error) => error,
+            error => return error,
+        };
+        if let ClientErrorKind::RpcError(RpcError::RpcResponseError {
+            data: RpcResponseErrorData::SendTransactionPreflightFailure(result),
+            ..
+        }) = error.kind()
+        {
+            if let Some(transaction_error) = &result.err {
+                return Self::Simulation {
+                    error: transaction_error.clone(),
+                    program_error: program_error(transaction_error, message),
+                    logs: result.logs.clone().unwrap_or_default(),
+                };
+            }
+        }
+        let transaction_error = error.get_transaction_error();
+        match transaction_error
+            .and_then(|transaction_error| program_error(&transaction_error, message))
+        {
+            Some(program_error) => Self::Program(program_error),
+            None => Self::Rpc(error),
+        }
+    }
+}
+
+/// Error of the program failing the transaction of `message` with `error`,
+/// if the failing instruction is one of the program
+pub(crate) fn program_error(
+    error: &TransactionError,
+    message: &VersionedMessage,
+) -> Option<HelloWorldError> {
+    match error {
+        TransactionError::InstructionError(index, InstructionError::Custom(code)) => {
+            let instruction = message.instructions().get(usize::from(*index))?;
+            let program_id = message
+                .static_account_keys()
+                .get(usize::from(instruction.program_id_index))?;
+            if *program_id == id() {
+                HelloWorldError::from_code(*code)
+            } else {
+                None
+            }
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::say_hello_ix;
+    use solana_client::rpc_response::RpcSimulateTransactionResult;
+    use solana_program::{message::Message, system_instruction};
+
+    #[test]
+    fn test_decode() {
+        let payer = Pubkey::new_unique();
+        let message = VersionedMessage::Legacy(Message::new(
+            &[
+                system_instruction::transfer(&payer, &Pubkey::new_unique(), 1),
+                say_hello_ix(&Pubkey::new_unique(), &payer),
+            ],
+            Some(&payer),
+        ));
+        let failure = |index: u8| {
+            TransactionError::InstructionError(
+                index,
+                InstructionError::Custom(HelloWorldError::ProgramPaused.code()),
+            )
+        };
+
+        // Custom errors are only decoded for the instructions of the program
+        assert!(matches!(
+            Error::from(ClientError::from(failure(1))).decode(&message),
+            Error::Program(HelloWorldError::ProgramPaused)
+        ));
+        assert!(matches!(
+            Error::from(ClientError::from(failure(0))).decode(&message),
+            Error::Rpc(_)
+        ));
+        assert!(matches!(
+            Error::AccountNotFound(payer).decode(&message),
+            Error::AccountNotFound(_)
+        ));
+
+        // Preflight failures carry the logs of the simulation
+        let preflight_failure = |index: u8| {
+            Error::from(ClientError::from(RpcError::RpcResponseError {
+                code: -32002,
+                message: "Transaction simulation failed".to_string(),
+                data: RpcResponseErrorData::SendTransactionPreflightFailure(
+                    RpcSimulateTransactionResult {
+                        err: Some(failure(index)),
+                        logs: Some(vec!["Program log: Program is paused".to_string()]),
+                        accounts: None,
+                        units_consumed: None,
+                    },
+                ),
+            }))
+        };
+        match preflight_failure(1).decode(&message) {
+            Error::Simulation {
+                error,
+                program_error,
+                logs,
+            } => {
+                assert_eq!(error, failure(1));
+                assert_eq!(program_error, Some(HelloWorldError::ProgramPaused));
+                assert_eq!(logs, vec!["Program log: Program is paused".to_string()]);
+            }
+            error => panic!("unexpected error {:?}", error),
+        }
+        assert!(matches!(
+            preflight_failure(0).decode(&message),
+            Error::Simulation {
+                program_error: None,
+                ..
+            }
+        ));
+    }
+}
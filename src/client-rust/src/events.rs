@@ -0,0 +1,318 @@
+//! Events of the program decoded from transaction logs
+//!
+//! The program reports what it did through its log messages, which are
+//! decoded here along with the raw payloads of any `sol_log_data` call. A
+//! greeting message is logged as is, so a message mimicking one of the
+//! formats below decodes as that event: only account state is authoritative.
+
+use crate::id;
+use solana_client::rpc_response::{RpcLogsResponse, RpcSimulateTransactionResult};
+use solana_program::pubkey::Pubkey;
+use std::str::FromStr;
+
+/// Something the program did, as reported in its logs
+#[derive(Clone, Debug, PartialEq)]
+pub enum HelloEvent {
+    /// A greeting account was greeted, `count` times overall
+    Greeted { count: u32 },
+    /// A batch greeting greeted `accounts` accounts
+    BatchGreeted { accounts: u64 },
+    /// The greeting address `account` of `user` was created
+    GreetingAccountCreated { account: Pubkey, user: Pubkey },
+    /// A greeting fee was charged
+    FeeCharged { lamports: u64 },
+    /// An expired greeting account was reaped
+    Reaped { lamports: u64 },
+    /// `greeter` registered its greeter account
+    GreeterRegistered { greeter: Pubkey },
+    /// The config was initialized with `admin`
+    ConfigInitialized { admin: Pubkey },
+    /// `admin` was proposed as the new config admin
+    AdminProposed { admin: Pubkey },
+    /// `admin` became the config admin
+    AdminSet { admin: Pubkey },
+    /// The program was paused or resumed
+    Paused { paused: bool },
+    /// `greeter` was added to the denylist
+    Denied { greeter: Pubkey },
+    /// `greeter` was removed from the denylist
+    Allowed { greeter: Pubkey },
+    /// Lamports were withdrawn from the treasury to `destination`
+    Withdrawn { lamports: u64, destination: Pubkey },
+    /// Fields logged with `sol_log_data`
+    Data(Vec<Vec<u8>>),
+}
+
+/// Events of the program in the logs of a transaction, including those of
+/// its inner invocations
+pub fn parse_logs(logs: &[String]) -> Vec<HelloEvent> {
+    let program_id = id().to_string();
+    // Programs being invoked, the innermost last
+    let mut invocations: Vec<&str> = vec![];
+    let mut events = vec![];
+    for log in logs {
+        if let Some(rest) = log.strip_prefix("Program log: ") {
+            if invocations.last() == Some(&program_id.as_str()) {
+                events.extend(parse_message(rest));
+            }
+        } else if let Some(rest) = log.strip_prefix("Program data: ") {
+            if invocations.last() == Some(&program_id.as_str()) {
+                let fields: Option<Vec<Vec<u8>>> = rest
+                    .split(' ')
+                    .map(|field| base64::decode(field).ok())
+                    .collect();
+                events.extend(fields.map(HelloEvent::Data));
+            }
+        } else if let Some(rest) = log.strip_prefix("Program ") {
+            let mut words = rest.split(' ');
+            match (words.next(), words.next()) {
+                (Some(program), Some("invoke")) => invocations.push(program),
+                (Some(_), Some("success")) => {
+                    invocations.pop();
+                }
+                (Some(_), Some("failed:")) => {
+                    invocations.pop();
+                }
+                _ => {}
+            }
+        }
+    }
+    events
+}
+
+/// Events of the program in a logs notification, none if the transaction
+/// failed
+pub fn parse_logs_response(response: &RpcLogsResponse) -> Vec<HelloEvent> {
+    match response.err {
+        Some(_) => vec![],
+        None => parse_logs(&response.logs),
+    }
+}
+
+/// Events of the program in a simulated transaction, none if the simulation
+/// failed
+pub fn parse_simulation(result: &RpcSimulateTransactionResult) -> Vec<HelloEvent> {
+    match (&result.err, &result.logs) {
+        (None, Some(logs)) => parse_logs(logs),
+        _ => vec![],
+    }
+}
+
+/// Event reported by the log message `message` of the program, if any
+fn parse_message(message: &str) -> Option<HelloEvent> {
+    let between = |prefix: &str, suffix: &str| {
+        message
+            .strip_prefix(prefix)
+            .and_then(|rest| rest.strip_suffix(suffix))
+    };
+    let pubkey = |prefix: &str| {
+        message
+            .strip_prefix(prefix)
+            .and_then(|rest| Pubkey::from_str(rest).ok())
+    };
+
+    if let Some(count) = between("Greeted ", " time(s)!") {
+        return Some(HelloEvent::Greeted {
+            count: count.parse().ok()?,
+        });
+    }
+    if let Some(accounts) = between("Greeted ", " account(s)") {
+        return Some(HelloEvent::BatchGreeted {
+            accounts: accounts.parse().ok()?,
+        });
+    }
+    if let Some(rest) = message.strip_prefix("Created greeting account ") {
+        let (account, user) = rest.split_once(" for ")?;
+        return Some(HelloEvent::GreetingAccountCreated {
+            account: Pubkey::from_str(account).ok()?,
+            user: Pubkey::from_str(user).ok()?,
+        });
+    }
+    if let Some(lamports) = between("Charged a fee of ", " lamport(s)") {
+        return Some(HelloEvent::FeeCharged {
+            lamports: lamports.parse().ok()?,
+        });
+    }
+    if let Some(lamports) = between("Reaped greeting account holding ", " lamport(s)") {
+        return Some(HelloEvent::Reaped {
+            lamports: lamports.parse().ok()?,
+        });
+    }
+    if let Some(greeter) = pubkey("Registered greeter ") {
+        return Some(HelloEvent::GreeterRegistered { greeter });
+    }
+    if let Some(admin) = pubkey("Config initialized with admin ") {
+        return Some(HelloEvent::ConfigInitialized { admin });
+    }
+    if let Some(admin) = between("Proposed ", " as config admin") {
+        return Some(HelloEvent::AdminProposed {
+            admin: Pubkey::from_str(admin).ok()?,
+        });
+    }
+    if let Some(admin) = pubkey("Config admin set to ") {
+        return Some(HelloEvent::AdminSet { admin });
+    }
+    match message {
+        "Program paused" => return Some(HelloEvent::Paused { paused: true }),
+        "Program resumed" => return Some(HelloEvent::Paused { paused: false }),
+        _ => {}
+    }
+    if let Some(greeter) = pubkey("Denied ") {
+        return Some(HelloEvent::Denied { greeter });
+    }
+    if let Some(greeter) = between("Allowed ", " again") {
+        return Some(HelloEvent::Allowed {
+            greeter: Pubkey::from_str(greeter).ok()?,
+        });
+    }
+    if let Some(rest) = message.strip_prefix("Withdrew ") {
+        let (lamports, destination) = rest.split_once(" lamport(s) to ")?;
+        return Some(HelloEvent::Withdrawn {
+            lamports: lamports.parse().ok()?,
+            destination: Pubkey::from_str(destination).ok()?,
+        });
+    }
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use solana_program::system_program;
+    use solana_sdk::transaction::TransactionError;
+
+    #[test]
+    fn test_parse_logs() {
+        let program_id = id();
+        let other_program = Pubkey::new_unique();
+        let account = Pubkey::new_unique();
+        let user = Pubkey::new_unique();
+        let logs: Vec<String> = vec![
+            format!("Program {} invoke [1]", program_id),
+            "Program log: Hello World Rust program entrypoint".to_string(),
+            format!("Program {} invoke [2]", system_program::id()),
+            format!("Program {} success", system_program::id()),
+            format!(
+                "Program log: Created greeting account {} for {}",
+                account, user
+            ),
+            "Program log: Greeted 3 time(s)!".to_string(),
+            format!("Program {} invoke [2]", other_program),
+            // Logs of other programs are not events of the program
+            "Program log: Greeted 7 time(s)!".to_string(),
+            format!("Program data: {}", base64::encode(b"other")),
+            format!(
+                "Program {} consumed 100 of 200000 compute units",
+                other_program
+            ),
+            format!("Program {} success", other_program),
+            format!(
+                "Program data: {} {}",
+                base64::encode(b"a"),
+                base64::encode(b"bc")
+            ),
+            "Program log: Charged a fee of 5000 lamport(s)".to_string(),
+            "Program log: unrelated".to_string(),
+            format!(
+                "Program {} consumed 5000 of 200000 compute units",
+                program_id
+            ),
+            format!("Program {} success", program_id),
+        ];
+        assert_eq!(
+            parse_logs(&logs),
+            vec![
+                HelloEvent::GreetingAccountCreated { account, user },
+                HelloEvent::Greeted { count: 3 },
+                HelloEvent::Data(vec![b"a".to_vec(), b"bc".to_vec()]),
+                HelloEvent::FeeCharged { lamports: 5000 },
+            ]
+        );
+
+        // Failed transactions have no events
+        let response = RpcLogsResponse {
+            signature: String::new(),
+            err: Some(TransactionError::AccountInUse),
+            logs: logs.clone(),
+        };
+        assert!(parse_logs_response(&response).is_empty());
+        let response = RpcLogsResponse {
+            err: None,
+            ..response
+        };
+        assert_eq!(parse_logs_response(&response).len(), 4);
+        let result = RpcSimulateTransactionResult {
+            err: None,
+            logs: Some(logs),
+            accounts: None,
+            units_consumed: None,
+        };
+        assert_eq!(parse_simulation(&result).len(), 4);
+    }
+
+    #[test]
+    fn test_parse_message() {
+        let key = Pubkey::new_unique();
+        let cases = vec![
+            (
+                "Greeted 1 account(s)".to_string(),
+                HelloEvent::BatchGreeted { accounts: 1 },
+            ),
+            (
+                "Reaped greeting account holding 10 lamport(s)".to_string(),
+                HelloEvent::Reaped { lamports: 10 },
+            ),
+            (
+                format!("Registered greeter {}", key),
+                HelloEvent::GreeterRegistered { greeter: key },
+            ),
+            (
+                format!("Config initialized with admin {}", key),
+                HelloEvent::ConfigInitialized { admin: key },
+            ),
+            (
+                format!("Proposed {} as config admin", key),
+                HelloEvent::AdminProposed { admin: key },
+            ),
+            (
+                format!("Config admin set to {}", key),
+                HelloEvent::AdminSet { admin: key },
+            ),
+            (
+                "Program paused".to_string(),
+                HelloEvent::Paused { paused: true },
+            ),
+            (
+                "Program resumed".to_string(),
+                HelloEvent::Paused { paused: false },
+            ),
+            (
+                format!("Denied {}", key),
+                HelloEvent::Denied { greeter: key },
+            ),
+            (
+                format!("Allowed {} again", key),
+                HelloEvent::Allowed { greeter: key },
+            ),
+            (
+                format!("Withdrew 42 lamport(s) to {}", key),
+                HelloEvent::Withdrawn {
+                    lamports: 42,
+                    destination: key,
+                },
+            ),
+        ];
+        for (message, event) in cases {
+            assert_eq!(parse_message(&message), Some(event), "{}", message);
+        }
+
+        for message in [
+            "Greeted many time(s)!",
+            "Denied someone",
+            "Withdrew 42 lamport(s)",
+            "Greeter is already registered",
+        ] {
+            assert_eq!(parse_message(message), None, "{}", message);
+        }
+    }
+}
@@ -0,0 +1,83 @@
+//! Filters of the program account queries
+
+use helloworld::state::{AccountState, GreetingAccount, GuardianSet, DISCRIMINATOR_LEN};
+use solana_client::rpc_filter::{Memcmp, MemcmpEncodedBytes, RpcFilterType};
+use solana_program::pubkey::Pubkey;
+
+/// Offset of `GuardianSet::owner` in guardian set accounts, after the
+/// discriminator, `is_initialized` and `greeting`
+const GUARDIAN_SET_OWNER_OFFSET: usize = DISCRIMINATOR_LEN + 1 + 32;
+
+/// Filter keeping the accounts holding `bytes` at `offset`
+fn memcmp(offset: usize, bytes: &[u8]) -> RpcFilterType {
+    RpcFilterType::Memcmp(Memcmp {
+        offset,
+        bytes: MemcmpEncodedBytes::Base58(bs58::encode(bytes).into_string()),
+        encoding: None,
+    })
+}
+
+/// Filter keeping the accounts holding a `T`, by its discriminator
+pub fn discriminator_filter<T: AccountState>() -> RpcFilterType {
+    memcmp(0, &T::DISCRIMINATOR)
+}
+
+/// Filters keeping the greeting accounts using the current layout
+pub fn greeting_filters() -> Vec<RpcFilterType> {
+    vec![
+        discriminator_filter::<GreetingAccount>(),
+        RpcFilterType::DataSize(GreetingAccount::LEN as u64),
+    ]
+}
+
+/// Filters keeping the guardian sets of the greeting accounts owned by
+/// `authority`
+pub fn authority_filters(authority: &Pubkey) -> Vec<RpcFilterType> {
+    vec![
+        discriminator_filter::<GuardianSet>(),
+        memcmp(GUARDIAN_SET_OWNER_OFFSET, authority.as_ref()),
+    ]
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Whether `data` passes every filter of `filters`
+    fn matches(filters: &[RpcFilterType], data: &[u8]) -> bool {
+        filters.iter().all(|filter| match filter {
+            RpcFilterType::DataSize(size) => data.len() as u64 == *size,
+            RpcFilterType::Memcmp(memcmp) => memcmp.bytes_match(data),
+            RpcFilterType::TokenAccountState => false,
+        })
+    }
+
+    #[test]
+    fn test_filters() {
+        let mut greeting = vec![0; GreetingAccount::LEN];
+        GreetingAccount::default().store(&mut greeting).unwrap();
+        assert!(matches(&greeting_filters(), &greeting));
+        assert!(!matches(
+            &greeting_filters(),
+            &greeting[..GreetingAccount::LEN - 1]
+        ));
+
+        let owner = Pubkey::new_unique();
+        let mut guardian_set = vec![0; GuardianSet::LEN];
+        GuardianSet {
+            is_initialized: true,
+            greeting: Pubkey::new_unique(),
+            owner,
+            ..GuardianSet::default()
+        }
+        .store(&mut guardian_set)
+        .unwrap();
+        assert!(matches(&authority_filters(&owner), &guardian_set));
+        assert!(!matches(
+            &authority_filters(&Pubkey::new_unique()),
+            &guardian_set
+        ));
+        assert!(!matches(&greeting_filters(), &guardian_set));
+        assert!(!matches(&authority_filters(&owner), &greeting));
+    }
+}
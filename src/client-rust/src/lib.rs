@@ -0,0 +1,198 @@
+//! Client library for the hello world program
+//!
+//! The builders target the program's declared id and share their account
+//! lists with the on-chain crate, which is linked without its entrypoint.
+//! `HelloWorldClient` builds on them to greet over JSON RPC, and
+//! `HelloWorldBanksClient` offers the same methods to program tests.
+//! Greetings signed against a `DurableNonce` can be submitted long after
+//! being signed offline, and greetings of many accounts fit in one versioned
+//! transaction by loading the accounts from a `LookupTable`.
+//!
+//! The clients sit behind the default `rpc` feature. Without it, only the
+//! instruction builders and address helpers are left, which build for
+//! `wasm32-unknown-unknown` and are exported to Javascript there. The
+//! `blocking` feature adds a synchronous `blocking::HelloWorldClient`.
+
+#[cfg(feature = "rpc")]
+mod banks;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+#[cfg(feature = "rpc")]
+mod cache;
+#[cfg(feature = "rpc")]
+mod config;
+#[cfg(feature = "rpc")]
+mod error;
+#[cfg(feature = "rpc")]
+mod events;
+#[cfg(feature = "rpc")]
+mod filters;
+#[cfg(feature = "rpc")]
+mod loader;
+#[cfg(feature = "rpc")]
+mod lookup;
+#[cfg(feature = "rpc")]
+mod nonce;
+#[cfg(feature = "rpc")]
+mod observer;
+mod pda;
+#[cfg(feature = "rpc")]
+mod priority;
+#[cfg(feature = "rpc")]
+mod retry;
+#[cfg(feature = "rpc")]
+mod rpc;
+#[cfg(feature = "rpc")]
+mod subscribe;
+#[cfg(feature = "rpc")]
+mod tpu;
+#[cfg(feature = "rpc")]
+mod wallet;
+mod wasm;
+
+use helloworld::instruction::{
+    initialize_config, initialize_if_needed, reap, say_hello_with_message,
+};
+#[cfg(feature = "rpc")]
+use helloworld::state::AccountState;
+#[cfg(feature = "rpc")]
+use solana_program::program_error::ProgramError;
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+};
+#[cfg(feature = "rpc")]
+use solana_sdk::account::Account;
+
+#[cfg(feature = "rpc")]
+pub use banks::HelloWorldBanksClient;
+#[cfg(feature = "rpc")]
+pub use config::{ClientConfig, Confirmation};
+#[cfg(feature = "rpc")]
+pub use error::Error;
+#[cfg(feature = "rpc")]
+pub use events::{parse_logs, parse_logs_response, parse_simulation, HelloEvent};
+#[cfg(feature = "rpc")]
+pub use filters::{authority_filters, discriminator_filter, greeting_filters};
+pub use helloworld::{
+    error::HelloWorldError,
+    id,
+    instruction::HelloInstruction,
+    state::{Config, GreeterAccount, GreetingAccount, GuardianSet},
+};
+#[cfg(feature = "rpc")]
+pub use loader::write_buffer_ixs;
+#[cfg(feature = "rpc")]
+pub use lookup::{compile_v0_message, create_lookup_table_ix, extend_lookup_table_ix, LookupTable};
+#[cfg(feature = "rpc")]
+pub use nonce::{say_hello_offline, DurableNonce};
+#[cfg(feature = "rpc")]
+pub use observer::Observer;
+pub use pda::{
+    find_config_address, find_greeter_address, find_greeting_address, find_mirror_address,
+    find_treasury_address,
+};
+#[cfg(feature = "rpc")]
+pub use priority::PriorityFee;
+#[cfg(feature = "rpc")]
+pub use retry::{RetryPolicy, SendFailure};
+#[cfg(feature = "rpc")]
+pub use rpc::HelloWorldClient;
+#[cfg(feature = "rpc")]
+pub use subscribe::{GreetingEvent, TransactionEvents};
+#[cfg(feature = "rpc")]
+pub use tpu::TpuSender;
+#[cfg(feature = "rpc")]
+pub use wallet::Wallet;
+
+/// Say hello to `greeted_pubkey` on behalf of `greeter_pubkey`, creating the
+/// account if it is the greeter's empty greeting address
+pub fn say_hello_ix(greeted_pubkey: &Pubkey, greeter_pubkey: &Pubkey) -> Instruction {
+    say_hello_with_message_ix(greeted_pubkey, greeter_pubkey, String::new())
+}
+
+/// Say hello to `greeted_pubkey` on behalf of `greeter_pubkey` with a message
+pub fn say_hello_with_message_ix(
+    greeted_pubkey: &Pubkey,
+    greeter_pubkey: &Pubkey,
+    message: String,
+) -> Instruction {
+    say_hello_with_message(&id(), greeted_pubkey, greeter_pubkey, vec![], message)
+}
+
+/// Say hello to each of `greeted_pubkeys` on behalf of `greeter_pubkey`
+pub fn say_hello_to_all_ix(
+    greeted_pubkeys: &[Pubkey],
+    greeter_pubkey: &Pubkey,
+) -> Vec<Instruction> {
+    greeted_pubkeys
+        .iter()
+        .map(|greeted_pubkey| say_hello_ix(greeted_pubkey, greeter_pubkey))
+        .collect()
+}
+
+/// Create the greeting address of `user_pubkey` unless it already exists
+pub fn initialize_ix(user_pubkey: &Pubkey) -> Instruction {
+    initialize_if_needed(&id(), user_pubkey)
+}
+
+/// Create the config account with `admin_pubkey` as its admin
+pub fn initialize_config_ix(admin_pubkey: &Pubkey) -> Instruction {
+    initialize_config(&id(), admin_pubkey)
+}
+
+/// Close the expired greeting account `greeted_pubkey` into the treasury,
+/// along with its mirror if `has_mirror` is set
+pub fn close_ix(greeted_pubkey: &Pubkey, has_mirror: bool) -> Instruction {
+    let mut instruction = reap(&id(), greeted_pubkey);
+    if has_mirror {
+        let mirror_address = find_mirror_address(greeted_pubkey).0;
+        instruction
+            .accounts
+            .push(AccountMeta::new(mirror_address, false));
+    }
+    instruction
+}
+
+/// State of a fetched account, `None` if there is no account
+#[cfg(feature = "rpc")]
+fn decode_account<T: AccountState>(account: Option<Account>) -> Result<Option<T>, Error> {
+    match account {
+        Some(account) if account.owner == id() => Ok(Some(T::load(&account.data)?)),
+        Some(_) => Err(ProgramError::IncorrectProgramId.into()),
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_builders() {
+        let user = Pubkey::new_unique();
+        let greeting_address = find_greeting_address(&user).0;
+
+        let ix = say_hello_ix(&greeting_address, &user);
+        assert_eq!(ix.program_id, id());
+        assert_eq!(ix.accounts[0], AccountMeta::new(greeting_address, false));
+        assert_eq!(ix.accounts[1], AccountMeta::new(user, true));
+
+        let greeted = [Pubkey::new_unique(), Pubkey::new_unique()];
+        let ixs = say_hello_to_all_ix(&greeted, &user);
+        assert_eq!(ixs.len(), 2);
+        assert_eq!(ixs[1], say_hello_ix(&greeted[1], &user));
+
+        let ix = initialize_ix(&user);
+        assert_eq!(ix.accounts[0], AccountMeta::new(greeting_address, false));
+        assert_eq!(ix.accounts[1], AccountMeta::new(user, true));
+
+        let ix = close_ix(&greeting_address, false);
+        assert_eq!(ix.accounts.len(), 3);
+        let ix = close_ix(&greeting_address, true);
+        assert_eq!(
+            ix.accounts[3],
+            AccountMeta::new(find_mirror_address(&greeting_address).0, false)
+        );
+    }
+}
@@ -0,0 +1,63 @@
+//! Deployment of the program through the upgradeable BPF loader
+
+use solana_program::{bpf_loader_upgradeable::write, instruction::Instruction, pubkey::Pubkey};
+
+/// Bytes of the program written by each transaction filling a buffer,
+/// leaving room in the packet for the compute budget and nonce instructions
+pub(crate) const WRITE_CHUNK_LEN: usize = 900;
+
+/// Instructions writing `program_data` to the buffer account
+/// `buffer_pubkey` whose authority is `authority_pubkey`, each fitting in a
+/// transaction of its own
+pub fn write_buffer_ixs(
+    buffer_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    program_data: &[u8],
+) -> Vec<Instruction> {
+    program_data
+        .chunks(WRITE_CHUNK_LEN)
+        .enumerate()
+        .map(|(index, chunk)| {
+            write(
+                buffer_pubkey,
+                authority_pubkey,
+                (index * WRITE_CHUNK_LEN) as u32,
+                chunk.to_vec(),
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use solana_program::loader_upgradeable_instruction::UpgradeableLoaderInstruction;
+    use solana_sdk::{
+        packet::PACKET_DATA_SIZE, signature::Keypair, signer::Signer, transaction::Transaction,
+    };
+
+    #[test]
+    fn test_write_buffer_ixs() {
+        let buffer = Pubkey::new_unique();
+        let authority = Keypair::new();
+        let program_data: Vec<u8> = (0..2000).map(|byte| byte as u8).collect();
+        let ixs = write_buffer_ixs(&buffer, &authority.pubkey(), &program_data);
+        assert_eq!(ixs.len(), 3);
+
+        let mut written = vec![];
+        for ix in &ixs {
+            match bincode::deserialize(&ix.data).unwrap() {
+                UpgradeableLoaderInstruction::Write { offset, bytes } => {
+                    assert_eq!(offset as usize, written.len());
+                    written.extend(bytes);
+                }
+                instruction => panic!("unexpected instruction {:?}", instruction),
+            }
+        }
+        assert_eq!(written, program_data);
+
+        // Each write fits in a packet
+        let transaction = Transaction::new_with_payer(&ixs[..1], Some(&authority.pubkey()));
+        assert!(bincode::serialize(&transaction).unwrap().len() < PACKET_DATA_SIZE);
+    }
+}
@@ -0,0 +1,328 @@
+//! Versioned transactions loading their accounts from address lookup tables
+
+use crate::Error;
+use solana_address_lookup_table_program::{
+    instruction::{create_lookup_table, extend_lookup_table},
+    state::AddressLookupTable,
+};
+use solana_program::{
+    hash::Hash,
+    instruction::{CompiledInstruction, Instruction},
+    message::{
+        v0::{Message, MessageAddressTableLookup},
+        MessageHeader,
+    },
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use solana_sdk::account::Account;
+use std::convert::TryFrom;
+
+/// Address lookup table along with the addresses it stores
+#[derive(Clone, Debug, PartialEq)]
+pub struct LookupTable {
+    /// Address of the lookup table account
+    pub key: Pubkey,
+    /// Addresses stored in the table, in order
+    pub addresses: Vec<Pubkey>,
+}
+
+impl LookupTable {
+    /// Lookup table stored in `account`, fetched from `key`
+    pub fn from_account(key: Pubkey, account: &Account) -> Result<Self, Error> {
+        if account.owner != solana_address_lookup_table_program::id() {
+            return Err(ProgramError::IncorrectProgramId.into());
+        }
+        let table = AddressLookupTable::deserialize(&account.data)
+            .map_err(|_| ProgramError::InvalidAccountData)?;
+        Ok(Self {
+            key,
+            addresses: table.addresses.to_vec(),
+        })
+    }
+}
+
+/// Create a lookup table owned by `authority_pubkey`, who also pays for it,
+/// returning the instruction and the address of the table
+///
+/// `recent_slot` must be a recent slot, it seeds the address of the table.
+pub fn create_lookup_table_ix(
+    authority_pubkey: &Pubkey,
+    recent_slot: u64,
+) -> (Instruction, Pubkey) {
+    create_lookup_table(*authority_pubkey, *authority_pubkey, recent_slot)
+}
+
+/// Append `addresses` to the lookup table `table_pubkey` of
+/// `authority_pubkey`, who pays for the extra space
+pub fn extend_lookup_table_ix(
+    table_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    addresses: Vec<Pubkey>,
+) -> Instruction {
+    extend_lookup_table(
+        *table_pubkey,
+        *authority_pubkey,
+        Some(*authority_pubkey),
+        addresses,
+    )
+}
+
+/// How a transaction uses one of its accounts
+#[derive(Clone, Copy, Default)]
+struct KeyMeta {
+    is_signer: bool,
+    is_writable: bool,
+    is_invoked: bool,
+}
+
+/// Compile `instructions`, paid by `payer`, into a v0 message loading from
+/// `lookup_tables` every account which is neither a signer nor a program
+///
+/// Panics if the message references more than 256 accounts.
+pub fn compile_v0_message(
+    payer: &Pubkey,
+    instructions: &[Instruction],
+    lookup_tables: &[LookupTable],
+    recent_blockhash: Hash,
+) -> Message {
+    let mut keys: Vec<(Pubkey, KeyMeta)> = vec![];
+    let mut add_key = |pubkey: &Pubkey, update: &dyn Fn(&mut KeyMeta)| {
+        let index = match keys.iter().position(|(key, _)| key == pubkey) {
+            Some(index) => index,
+            None => {
+                keys.push((*pubkey, KeyMeta::default()));
+                keys.len() - 1
+            }
+        };
+        update(&mut keys[index].1);
+    };
+    add_key(payer, &|meta| {
+        meta.is_signer = true;
+        meta.is_writable = true;
+    });
+    for instruction in instructions {
+        add_key(&instruction.program_id, &|meta| meta.is_invoked = true);
+        for account in &instruction.accounts {
+            add_key(&account.pubkey, &|meta| {
+                meta.is_signer |= account.is_signer;
+                meta.is_writable |= account.is_writable;
+            });
+        }
+    }
+
+    // Signers and programs must be static, the other accounts are loaded from
+    // the first table holding them
+    let mut address_table_lookups = vec![];
+    let mut writable_loaded = vec![];
+    let mut readonly_loaded = vec![];
+    for table in lookup_tables {
+        let mut lookup = MessageAddressTableLookup {
+            account_key: table.key,
+            ..MessageAddressTableLookup::default()
+        };
+        keys.retain(|(key, meta)| {
+            if meta.is_signer || meta.is_invoked {
+                return true;
+            }
+            let index = table
+                .addresses
+                .iter()
+                .position(|address| address == key)
+                .and_then(|index| u8::try_from(index).ok());
+            match index {
+                Some(index) if meta.is_writable => {
+                    lookup.writable_indexes.push(index);
+                    writable_loaded.push(*key);
+                    false
+                }
+                Some(index) => {
+                    lookup.readonly_indexes.push(index);
+                    readonly_loaded.push(*key);
+                    false
+                }
+                None => true,
+            }
+        });
+        if !lookup.writable_indexes.is_empty() || !lookup.readonly_indexes.is_empty() {
+            address_table_lookups.push(lookup);
+        }
+    }
+
+    // Writable signers come first, starting with the payer, then read-only
+    // signers, writable accounts and read-only accounts
+    keys.sort_by_key(|(_, meta)| (!meta.is_signer, !meta.is_writable));
+    let count = |filter: fn(&KeyMeta) -> bool| {
+        u8::try_from(keys.iter().filter(|(_, meta)| filter(meta)).count()).unwrap()
+    };
+    let header = MessageHeader {
+        num_required_signatures: count(|meta| meta.is_signer),
+        num_readonly_signed_accounts: count(|meta| meta.is_signer && !meta.is_writable),
+        num_readonly_unsigned_accounts: count(|meta| !meta.is_signer && !meta.is_writable),
+    };
+    let account_keys: Vec<Pubkey> = keys.into_iter().map(|(key, _)| key).collect();
+
+    let all_keys: Vec<&Pubkey> = account_keys
+        .iter()
+        .chain(&writable_loaded)
+        .chain(&readonly_loaded)
+        .collect();
+    let index_of = |pubkey: &Pubkey| {
+        let index = all_keys.iter().position(|key| *key == pubkey).unwrap();
+        u8::try_from(index).expect("too many accounts")
+    };
+    let instructions = instructions
+        .iter()
+        .map(|instruction| CompiledInstruction {
+            program_id_index: index_of(&instruction.program_id),
+            accounts: instruction
+                .accounts
+                .iter()
+                .map(|account| index_of(&account.pubkey))
+                .collect(),
+            data: instruction.data.clone(),
+        })
+        .collect();
+
+    Message {
+        header,
+        account_keys,
+        recent_blockhash,
+        instructions,
+        address_table_lookups,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{find_greeting_address, say_hello_to_all_ix};
+    use solana_address_lookup_table_program::state::LookupTableMeta;
+    use std::borrow::Cow;
+
+    #[test]
+    fn test_lookup_table() {
+        let key = Pubkey::new_unique();
+        let addresses = vec![Pubkey::new_unique(), Pubkey::new_unique()];
+        let data = AddressLookupTable {
+            meta: LookupTableMeta::new(Pubkey::new_unique()),
+            addresses: Cow::Borrowed(&addresses),
+        }
+        .serialize_for_tests()
+        .unwrap();
+        let mut account = Account::new(1, 0, &solana_address_lookup_table_program::id());
+        account.data = data;
+        assert_eq!(
+            LookupTable::from_account(key, &account).unwrap(),
+            LookupTable {
+                key,
+                addresses: addresses.clone(),
+            }
+        );
+
+        account.data.truncate(10);
+        assert!(matches!(
+            LookupTable::from_account(key, &account),
+            Err(Error::InvalidAccount(ProgramError::InvalidAccountData))
+        ));
+        account.owner = Pubkey::new_unique();
+        assert!(matches!(
+            LookupTable::from_account(key, &account),
+            Err(Error::InvalidAccount(ProgramError::IncorrectProgramId))
+        ));
+    }
+
+    #[test]
+    fn test_compile_v0_message() {
+        let greeter = Pubkey::new_unique();
+        let greeted: Vec<Pubkey> = (0..40)
+            .map(|_| find_greeting_address(&Pubkey::new_unique()).0)
+            .collect();
+        let instructions = say_hello_to_all_ix(&greeted, &greeter);
+        // Accounts shared by all greetings, besides the greeter
+        let shared: Vec<_> = instructions[0].accounts[2..].to_vec();
+        let writable = shared.iter().filter(|account| account.is_writable).count();
+        let shared: Vec<Pubkey> = shared.iter().map(|account| account.pubkey).collect();
+        // The greeter is a signer, so listing it in a table changes nothing
+        let tables = [
+            LookupTable {
+                key: Pubkey::new_unique(),
+                addresses: greeted[..30].to_vec(),
+            },
+            LookupTable {
+                key: Pubkey::new_unique(),
+                addresses: [&greeted[20..], &shared, &[greeter]].concat(),
+            },
+        ];
+        let message = compile_v0_message(&greeter, &instructions, &tables, Hash::new_unique());
+        message.sanitize(true).unwrap();
+
+        assert_eq!(message.account_keys[0], greeter);
+        assert_eq!(message.header.num_required_signatures, 1);
+        assert_eq!(message.header.num_readonly_signed_accounts, 0);
+        // Only the greeter and the program stay static
+        assert_eq!(message.account_keys.len(), 2);
+        assert_eq!(message.header.num_readonly_unsigned_accounts, 1);
+        assert_eq!(message.address_table_lookups.len(), 2);
+        assert_eq!(
+            message.address_table_lookups[0].writable_indexes,
+            (0..30).collect::<Vec<u8>>()
+        );
+        assert_eq!(
+            message.address_table_lookups[1].writable_indexes.len(),
+            10 + writable
+        );
+        assert_eq!(
+            message.address_table_lookups[1].readonly_indexes.len(),
+            shared.len() - writable
+        );
+
+        // Resolve the accounts of each instruction through the tables
+        let loaded: Vec<Pubkey> =
+            message
+                .account_keys
+                .iter()
+                .cloned()
+                .chain(message.address_table_lookups.iter().zip(&tables).flat_map(
+                    |(lookup, table)| {
+                        lookup
+                            .writable_indexes
+                            .iter()
+                            .map(move |index| table.addresses[*index as usize])
+                    },
+                ))
+                .chain(message.address_table_lookups.iter().zip(&tables).flat_map(
+                    |(lookup, table)| {
+                        lookup
+                            .readonly_indexes
+                            .iter()
+                            .map(move |index| table.addresses[*index as usize])
+                    },
+                ))
+                .collect();
+        for (compiled, instruction) in message.instructions.iter().zip(&instructions) {
+            assert_eq!(
+                loaded[compiled.program_id_index as usize],
+                instruction.program_id
+            );
+            let accounts: Vec<Pubkey> = compiled
+                .accounts
+                .iter()
+                .map(|index| loaded[*index as usize])
+                .collect();
+            let expected: Vec<Pubkey> = instruction
+                .accounts
+                .iter()
+                .map(|account| account.pubkey)
+                .collect();
+            assert_eq!(accounts, expected);
+            assert_eq!(compiled.data, instruction.data);
+        }
+
+        // Without tables every account is static
+        let message = compile_v0_message(&greeter, &instructions, &[], Hash::new_unique());
+        message.sanitize(true).unwrap();
+        assert!(message.address_table_lookups.is_empty());
+        assert_eq!(message.account_keys.len(), 2 + greeted.len() + shared.len());
+    }
+}
@@ -0,0 +1,131 @@
+//! Durable nonces letting greetings be signed offline and submitted later
+
+use crate::{find_greeting_address, say_hello_ix, Error};
+use solana_client::nonce_utils;
+use solana_program::{hash::Hash, pubkey::Pubkey, system_instruction};
+use solana_sdk::{
+    account::Account,
+    instruction::Instruction,
+    signature::{Keypair, Signer},
+    transaction::Transaction,
+};
+
+/// Nonce account whose stored blockhash replaces a recent one, so that the
+/// transaction does not expire until the nonce is advanced
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DurableNonce {
+    /// Address of the nonce account
+    pub nonce_pubkey: Pubkey,
+    /// Authority allowed to advance the nonce
+    pub authority: Pubkey,
+    /// Blockhash stored in the nonce account
+    pub blockhash: Hash,
+}
+
+impl DurableNonce {
+    /// Nonce stored in `account`, fetched from `nonce_pubkey`
+    pub fn from_account(nonce_pubkey: Pubkey, account: &Account) -> Result<Self, Error> {
+        let data = nonce_utils::data_from_account(account)?;
+        Ok(Self {
+            nonce_pubkey,
+            authority: data.authority,
+            blockhash: data.blockhash(),
+        })
+    }
+
+    /// Advance the nonce, which must be the first instruction of a transaction
+    /// signed against it
+    pub fn advance_ix(&self) -> Instruction {
+        system_instruction::advance_nonce_account(&self.nonce_pubkey, &self.authority)
+    }
+
+    /// Sign `instructions` against the nonce, `payer` paying for the
+    /// transaction and `nonce_authority` advancing the nonce first
+    ///
+    /// Panics if `nonce_authority` is not the authority of the nonce.
+    pub fn sign(
+        &self,
+        instructions: &[Instruction],
+        payer: &Keypair,
+        nonce_authority: &Keypair,
+    ) -> Transaction {
+        let mut nonced = vec![self.advance_ix()];
+        nonced.extend_from_slice(instructions);
+        Transaction::new_signed_with_payer(
+            &nonced,
+            Some(&payer.pubkey()),
+            &[payer, nonce_authority],
+            self.blockhash,
+        )
+    }
+}
+
+/// Greeting of the greeting address of `greeter`, signed against `nonce`
+/// whose authority is the greeter, to be submitted later with
+/// `HelloWorldClient::send_transaction`
+pub fn say_hello_offline(greeter: &Keypair, nonce: &DurableNonce) -> Transaction {
+    let greeting_address = find_greeting_address(&greeter.pubkey()).0;
+    nonce.sign(
+        &[say_hello_ix(&greeting_address, &greeter.pubkey())],
+        greeter,
+        greeter,
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use solana_program::{
+        nonce::{
+            state::{Data, Versions},
+            State,
+        },
+        system_program,
+    };
+
+    #[test]
+    fn test_durable_nonce() {
+        let greeter = Keypair::new();
+        let nonce_pubkey = Pubkey::new_unique();
+        let blockhash = Hash::new_unique();
+        let data = Data::new(
+            greeter.pubkey(),
+            solana_program::nonce::state::DurableNonce::from_blockhash(&blockhash, true),
+            5000,
+        );
+        let versions = Versions::new(State::Initialized(data.clone()), true);
+        let account = Account::new_data(1, &versions, &system_program::id()).unwrap();
+        let nonce = DurableNonce::from_account(nonce_pubkey, &account).unwrap();
+        assert_eq!(
+            nonce,
+            DurableNonce {
+                nonce_pubkey,
+                authority: greeter.pubkey(),
+                blockhash: data.blockhash(),
+            }
+        );
+
+        let transaction = say_hello_offline(&greeter, &nonce);
+        transaction.verify().unwrap();
+        assert_eq!(transaction.message.recent_blockhash, nonce.blockhash);
+        assert_eq!(
+            transaction.message.instructions[0].program_id(&transaction.message.account_keys),
+            &system_program::id()
+        );
+        assert_eq!(transaction.message.instructions.len(), 2);
+
+        // Uninitialized nonce accounts and accounts of other programs hold no
+        // nonce
+        let versions = Versions::new(State::Uninitialized, true);
+        let account = Account::new_data(1, &versions, &system_program::id()).unwrap();
+        assert!(matches!(
+            DurableNonce::from_account(nonce_pubkey, &account),
+            Err(Error::Nonce(_))
+        ));
+        let account = Account::new_data(1, &versions, &Pubkey::new_unique()).unwrap();
+        assert!(matches!(
+            DurableNonce::from_account(nonce_pubkey, &account),
+            Err(Error::Nonce(_))
+        ));
+    }
+}
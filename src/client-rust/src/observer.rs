@@ -0,0 +1,23 @@
+//! Hooks into the activity of the client, for metrics
+
+use crate::Error;
+use solana_sdk::signature::Signature;
+use std::time::Duration;
+
+/// Receiver of the activity of `HelloWorldClient`, such as an exporter of
+/// metrics
+///
+/// Every method does nothing unless implemented. They are called by the
+/// tasks sending the transactions, so they should return quickly.
+pub trait Observer: Send + Sync {
+    /// The transaction `signature` was submitted to the cluster, possibly
+    /// again after an expired attempt
+    fn on_send(&self, _signature: &Signature) {}
+
+    /// The transaction `signature` was confirmed `latency` after its
+    /// submission
+    fn on_confirm(&self, _signature: &Signature, _latency: Duration) {}
+
+    /// Sending a transaction failed for good with `error`
+    fn on_error(&self, _error: &Error) {}
+}
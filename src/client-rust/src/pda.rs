@@ -0,0 +1,66 @@
+//! Program addresses of the program's declared id, derived like the processor
+//! derives them
+
+use helloworld::{id, mirror, state};
+use solana_program::pubkey::Pubkey;
+
+/// Greeting address of `user` and its bump seed
+pub fn find_greeting_address(user: &Pubkey) -> (Pubkey, u8) {
+    state::find_greeting_address(&id(), user)
+}
+
+/// Address of the config account and its bump seed
+pub fn find_config_address() -> (Pubkey, u8) {
+    state::find_config_address(&id())
+}
+
+/// Address of the greeter account of `greeter` and its bump seed
+pub fn find_greeter_address(greeter: &Pubkey) -> (Pubkey, u8) {
+    state::find_greeter_address(&id(), greeter)
+}
+
+/// Address of the treasury and its bump seed
+pub fn find_treasury_address() -> (Pubkey, u8) {
+    state::find_treasury_address(&id())
+}
+
+/// Address of the mirror of `greeted_pubkey` and its bump seed
+pub fn find_mirror_address(greeted_pubkey: &Pubkey) -> (Pubkey, u8) {
+    mirror::find_mirror_address(&id(), greeted_pubkey)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_addresses() {
+        let user = Pubkey::new_unique();
+        let (address, bump_seed) = find_greeting_address(&user);
+        assert_eq!(
+            Pubkey::create_program_address(
+                &[state::GREETING_SEED, user.as_ref(), &[bump_seed]],
+                &id()
+            ),
+            Ok(address)
+        );
+        let (address, bump_seed) = find_config_address();
+        assert_eq!(
+            Pubkey::create_program_address(&[state::CONFIG_SEED, &[bump_seed]], &id()),
+            Ok(address)
+        );
+        let (address, bump_seed) = find_greeter_address(&user);
+        assert_eq!(
+            Pubkey::create_program_address(
+                &[state::GREETER_SEED, user.as_ref(), &[bump_seed]],
+                &id()
+            ),
+            Ok(address)
+        );
+        let (address, bump_seed) = find_treasury_address();
+        assert_eq!(
+            Pubkey::create_program_address(&[state::TREASURY_SEED, &[bump_seed]], &id()),
+            Ok(address)
+        );
+    }
+}
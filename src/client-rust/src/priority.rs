@@ -0,0 +1,54 @@
+//! Priority fees of the client's transactions
+
+use serde::Deserialize;
+
+/// How the client prices the compute units of its transactions
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum PriorityFee {
+    /// Pay no priority fee
+    #[default]
+    None,
+    /// Pay a fixed price, in micro-lamports per compute unit
+    Fixed(u64),
+    /// Pay the given percentile, from 0 to 100, of the prices recently paid
+    /// by transactions writing the same accounts
+    RecentPercentile(u8),
+}
+
+/// Entry of the `getRecentPrioritizationFees` RPC response
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct RecentPrioritizationFee {
+    /// Price paid in the slot, in micro-lamports per compute unit
+    pub prioritization_fee: u64,
+}
+
+/// Price at `percentile` of `prices`, 0 without any price
+pub(crate) fn percentile(mut prices: Vec<u64>, percentile: u8) -> u64 {
+    if prices.is_empty() {
+        return 0;
+    }
+    prices.sort_unstable();
+    let index = (prices.len() - 1) * usize::from(percentile.min(100)) / 100;
+    prices[index]
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_percentile() {
+        assert_eq!(percentile(vec![], 50), 0);
+        assert_eq!(percentile(vec![7], 0), 7);
+        assert_eq!(percentile(vec![7], 100), 7);
+
+        let prices = vec![50, 10, 40, 20, 30];
+        assert_eq!(percentile(prices.clone(), 0), 10);
+        assert_eq!(percentile(prices.clone(), 50), 30);
+        assert_eq!(percentile(prices.clone(), 75), 40);
+        assert_eq!(percentile(prices.clone(), 100), 50);
+        // Out of range percentiles are capped
+        assert_eq!(percentile(prices, 200), 50);
+    }
+}
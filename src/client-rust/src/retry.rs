@@ -0,0 +1,86 @@
+//! Retries of the transactions sent by the client
+
+use solana_client::client_error::ClientError;
+use solana_sdk::transaction::TransactionError;
+use std::time::Duration;
+use thiserror::Error;
+
+/// Interval between two checks of the status of a sent transaction
+pub(crate) const CONFIRMATION_POLL_INTERVAL: Duration = Duration::from_millis(400);
+
+/// Interval between two checks of the status of a transaction whose
+/// confirmation is awaited over a subscription, in case the notification is
+/// missed
+pub(crate) const SUBSCRIPTION_CHECK_INTERVAL: Duration = Duration::from_secs(2);
+
+/// How the client retries sending a transaction until its confirmation
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RetryPolicy {
+    /// Attempts at sending the transaction, each against a fresh blockhash
+    pub max_attempts: u32,
+    /// Delay before retrying after the first RPC error, doubled after each
+    /// later one
+    pub initial_backoff: Duration,
+    /// Longest delay between two attempts
+    pub max_backoff: Duration,
+    /// Time after which the client gives up, whatever the attempts left
+    pub timeout: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(8),
+            timeout: Duration::from_secs(90),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Delay before the attempt following the failed attempt `attempt`,
+    /// counted from 1
+    pub(crate) fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 1u32
+            .checked_shl(attempt.saturating_sub(1))
+            .unwrap_or(u32::MAX);
+        self.initial_backoff
+            .checked_mul(factor)
+            .map_or(self.max_backoff, |backoff| backoff.min(self.max_backoff))
+    }
+}
+
+/// Why the last attempt at sending a transaction failed
+#[derive(Debug, Error)]
+pub enum SendFailure {
+    /// The transaction failed, sending it again would fail the same way
+    #[error("Transaction failed: {0}")]
+    Transaction(TransactionError),
+    /// The blockhash expired before the transaction was confirmed
+    #[error("Blockhash expired")]
+    BlockhashExpired,
+    /// The RPC request failed
+    #[error(transparent)]
+    Rpc(Box<ClientError>),
+    /// The timeout of the retry policy elapsed before the confirmation
+    #[error("Timed out")]
+    TimedOut,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_backoff() {
+        let policy = RetryPolicy::default();
+        assert_eq!(policy.backoff(1), Duration::from_millis(500));
+        assert_eq!(policy.backoff(2), Duration::from_secs(1));
+        assert_eq!(policy.backoff(4), Duration::from_secs(4));
+        assert_eq!(policy.backoff(5), Duration::from_secs(8));
+        assert_eq!(policy.backoff(6), Duration::from_secs(8));
+        assert_eq!(policy.backoff(40), Duration::from_secs(8));
+        assert_eq!(policy.backoff(u32::MAX), Duration::from_secs(8));
+    }
+}
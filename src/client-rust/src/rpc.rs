@@ -0,0 +1,1636 @@
+//! High level client talking to a cluster over JSON RPC
+
+use crate::{
+    authority_filters,
+    cache::AccountCache,
+    compile_v0_message,
+    config::{Confirmation, AUTO_COMPUTE_UNIT_MARGIN, MAX_COMPUTE_UNIT_LIMIT},
+    create_lookup_table_ix, decode_account,
+    error::program_error,
+    extend_lookup_table_ix, find_config_address, find_greeter_address, find_greeting_address,
+    greeting_filters, id, initialize_config_ix, initialize_ix,
+    priority::{self, RecentPrioritizationFee},
+    retry::{CONFIRMATION_POLL_INTERVAL, SUBSCRIPTION_CHECK_INTERVAL},
+    say_hello_ix, say_hello_to_all_ix,
+    subscribe::{self, forward_greetings, forward_transaction_events},
+    write_buffer_ixs, ClientConfig, Config, DurableNonce, Error, GreeterAccount, GreetingAccount,
+    GreetingEvent, GuardianSet, LookupTable, Observer, PriorityFee, SendFailure, TpuSender,
+    TransactionEvents,
+};
+use futures::{
+    channel::mpsc,
+    stream::{self, Stream, StreamExt},
+};
+use helloworld::state::AccountState;
+use serde_json::json;
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::{
+    client_error::{ClientError, ClientErrorKind},
+    nonblocking::{pubsub_client::PubsubClient, rpc_client::RpcClient},
+    rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig, RpcSignatureSubscribeConfig},
+    rpc_filter::RpcFilterType,
+    rpc_request::RpcRequest,
+    rpc_response::{Response, RpcSignatureResult, RpcSimulateTransactionResult},
+};
+use solana_program::{
+    bpf_loader_upgradeable::{
+        self, create_buffer, deploy_with_max_program_len, upgrade, UpgradeableLoaderState,
+    },
+    hash::Hash,
+    message::VersionedMessage,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use solana_sdk::{
+    account::Account,
+    commitment_config::CommitmentConfig,
+    compute_budget::ComputeBudgetInstruction,
+    instruction::Instruction,
+    signature::{Keypair, Signature, Signer},
+    transaction::{Transaction, VersionedTransaction},
+};
+use std::{convert::TryFrom, sync::Arc, time::Instant};
+use tokio::time::{sleep, timeout};
+
+/// Client for the hello world program deployed at its declared id
+#[derive(Clone)]
+pub struct HelloWorldClient {
+    rpc_client: Arc<RpcClient>,
+    websocket_url: Option<String>,
+    tpu_sender: Option<TpuSender>,
+    config: ClientConfig,
+    account_cache: Arc<AccountCache>,
+    observer: Option<Arc<dyn Observer>>,
+}
+
+impl HelloWorldClient {
+    /// Client for the cluster serving JSON RPC at `rpc_url`
+    pub fn new(rpc_url: impl Into<String>) -> Self {
+        Self::with_rpc_client(RpcClient::new(rpc_url.into()))
+    }
+
+    /// Client sending its requests through `rpc_client`
+    pub fn with_rpc_client(rpc_client: RpcClient) -> Self {
+        Self {
+            rpc_client: Arc::new(rpc_client),
+            websocket_url: None,
+            tpu_sender: None,
+            config: ClientConfig::default(),
+            account_cache: Arc::default(),
+            observer: None,
+        }
+    }
+
+    /// Subscribe to the cluster at `websocket_url`, rather than the one
+    /// derived from the JSON RPC URL
+    pub fn with_websocket_url(mut self, websocket_url: impl Into<String>) -> Self {
+        self.websocket_url = Some(websocket_url.into());
+        self
+    }
+
+    /// Send the transactions straight to the leaders through `tpu_sender`,
+    /// falling back to JSON RPC whenever none of them can be reached
+    pub fn with_tpu_sender(mut self, tpu_sender: TpuSender) -> Self {
+        self.tpu_sender = Some(tpu_sender);
+        self
+    }
+
+    /// Report the transactions sent by the client to `observer`
+    pub fn with_observer(mut self, observer: impl Observer + 'static) -> Self {
+        self.observer = Some(Arc::new(observer));
+        self
+    }
+
+    /// Build and send the client's transactions following `config`
+    pub fn with_config(mut self, config: ClientConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Client sharing the connections of this one, but reading accounts and
+    /// confirming transactions at `commitment`
+    ///
+    /// This picks the commitment of a single call, such as
+    /// `client.at_commitment(CommitmentConfig::finalized()).say_hello(&greeter)`.
+    pub fn at_commitment(&self, commitment: CommitmentConfig) -> Self {
+        let mut client = self.clone();
+        client.config = client.config.commitment(commitment);
+        // Accounts read at another commitment may not be final enough
+        client.account_cache = Arc::default();
+        client
+    }
+
+    /// Stream of the updates of greeting accounts, such as greetings
+    ///
+    /// The subscription reconnects after the backoffs of the retry policy
+    /// whenever the connection fails, and ends once the stream is dropped. It
+    /// must be made from within a Tokio runtime.
+    pub fn subscribe_greetings(&self) -> impl Stream<Item = GreetingEvent> {
+        let (sender, receiver) = mpsc::unbounded();
+        tokio::spawn(forward_greetings(
+            self.websocket_url(),
+            self.commitment(),
+            self.config.retry_policy,
+            sender,
+        ));
+        receiver
+    }
+
+    /// Stream of the events of the successful transactions of the program,
+    /// decoded from their logs
+    ///
+    /// The subscription reconnects and ends as `subscribe_greetings`.
+    pub fn subscribe_transaction_events(&self) -> impl Stream<Item = TransactionEvents> {
+        let (sender, receiver) = mpsc::unbounded();
+        tokio::spawn(forward_transaction_events(
+            self.websocket_url(),
+            self.commitment(),
+            self.config.retry_policy,
+            sender,
+        ));
+        receiver
+    }
+
+    /// Greet the greeting address of `greeter`, creating it on the first
+    /// greeting
+    pub async fn say_hello(&self, greeter: &Keypair) -> Result<Signature, Error> {
+        let greeting_address = find_greeting_address(&greeter.pubkey()).0;
+        self.process(say_hello_ix(&greeting_address, &greeter.pubkey()), greeter)
+            .await
+    }
+
+    /// Greet the greeting address of each of `keys` in its own transaction,
+    /// sending the transactions concurrently up to the configured limit
+    ///
+    /// Returns the result of each greeting along with its greeter, in the
+    /// order of `keys`.
+    pub async fn send_greetings_batch(
+        &self,
+        keys: Vec<Keypair>,
+    ) -> Vec<(Pubkey, Result<Signature, Error>)> {
+        stream::iter(keys)
+            .map(|key| async move { (key.pubkey(), self.say_hello(&key).await) })
+            .buffered(self.config.batch_concurrency)
+            .collect()
+            .await
+    }
+
+    /// Greet each of `greeted` on behalf of `greeter` in a single versioned
+    /// transaction, loading the greeted accounts from `lookup_tables`
+    pub async fn say_hello_to_all(
+        &self,
+        greeter: &Keypair,
+        greeted: &[Pubkey],
+        lookup_tables: &[LookupTable],
+    ) -> Result<Signature, Error> {
+        let instructions = say_hello_to_all_ix(greeted, &greeter.pubkey());
+        let (mut instructions, blockhash, _) = self.prepare(&instructions).await?;
+        let compile = |instructions: &[Instruction]| {
+            compile_v0_message(&greeter.pubkey(), instructions, lookup_tables, blockhash)
+        };
+        self.auto_compute_budget(&mut instructions, |instructions| VersionedTransaction {
+            signatures: vec![Signature::default()],
+            message: VersionedMessage::V0(compile(instructions)),
+        })
+        .await?;
+        let message = compile(&instructions);
+        let transaction = VersionedTransaction::try_new(VersionedMessage::V0(message), &[greeter])?;
+        self.send_versioned_transaction(&transaction).await
+    }
+
+    /// Create a lookup table owned by `authority`, returning its address
+    pub async fn create_lookup_table(&self, authority: &Keypair) -> Result<Pubkey, Error> {
+        let recent_slot = self.rpc_client.get_slot().await?;
+        let (instruction, table_pubkey) = create_lookup_table_ix(&authority.pubkey(), recent_slot);
+        self.process(instruction, authority).await?;
+        Ok(table_pubkey)
+    }
+
+    /// Append `addresses` to the lookup table `table_pubkey` of `authority`
+    ///
+    /// The new addresses can only be looked up from the next slot on.
+    pub async fn extend_lookup_table(
+        &self,
+        authority: &Keypair,
+        table_pubkey: &Pubkey,
+        addresses: Vec<Pubkey>,
+    ) -> Result<Signature, Error> {
+        let instruction = extend_lookup_table_ix(table_pubkey, &authority.pubkey(), addresses);
+        self.process(instruction, authority).await
+    }
+
+    /// Lookup table stored in `table_pubkey`
+    pub async fn get_lookup_table(&self, table_pubkey: &Pubkey) -> Result<LookupTable, Error> {
+        let account = self.rpc_client.get_account(table_pubkey).await?;
+        LookupTable::from_account(*table_pubkey, &account)
+    }
+
+    /// Create the greeting address of `user` unless it already exists
+    pub async fn initialize(&self, user: &Keypair) -> Result<Signature, Error> {
+        self.process(initialize_ix(&user.pubkey()), user).await
+    }
+
+    /// Create the config account with `admin` as its admin, failing if it
+    /// already exists
+    pub async fn initialize_config(&self, admin: &Keypair) -> Result<Signature, Error> {
+        self.process(initialize_config_ix(&admin.pubkey()), admin)
+            .await
+    }
+
+    /// Airdrop `lamports` to `pubkey` from the faucet of the cluster and wait
+    /// for the confirmation of the airdrop
+    pub async fn request_airdrop(
+        &self,
+        pubkey: &Pubkey,
+        lamports: u64,
+    ) -> Result<Signature, Error> {
+        let signature = self.rpc_client.request_airdrop(pubkey, lamports).await?;
+        // The faucet picks the blockhash, so only the timeout bounds the wait
+        let deadline = Instant::now() + self.config.retry_policy.timeout;
+        if self.confirm(&signature, u64::MAX, deadline).await? {
+            Ok(signature)
+        } else {
+            Err(Error::SendFailed {
+                attempts: 1,
+                reason: SendFailure::TimedOut,
+            })
+        }
+    }
+
+    /// Greeting account at `pubkey`, `None` if there is no account
+    pub async fn get_greeting(&self, pubkey: &Pubkey) -> Result<Option<GreetingAccount>, Error> {
+        decode_account(self.get_account(pubkey).await?)
+    }
+
+    /// State of type `T` stored in the account at `pubkey`, checking that the
+    /// program owns the account and that it holds a `T`
+    pub async fn get_state<T: AccountState>(&self, pubkey: &Pubkey) -> Result<T, Error> {
+        decode_account(self.get_account(pubkey).await?)?.ok_or(Error::AccountNotFound(*pubkey))
+    }
+
+    /// Greeting account at `pubkey`
+    pub async fn get_greeting_account(&self, pubkey: &Pubkey) -> Result<GreetingAccount, Error> {
+        self.get_state(pubkey).await
+    }
+
+    /// Config of the program
+    pub async fn get_config(&self) -> Result<Config, Error> {
+        self.get_state(&find_config_address().0).await
+    }
+
+    /// Greeter account of `greeter`
+    pub async fn get_greeter_account(&self, greeter: &Pubkey) -> Result<GreeterAccount, Error> {
+        self.get_state(&find_greeter_address(greeter).0).await
+    }
+
+    /// Every greeting account using the current layout, along with its
+    /// address
+    pub async fn list_greetings(&self) -> Result<Vec<(Pubkey, GreetingAccount)>, Error> {
+        self.list(greeting_filters()).await
+    }
+
+    /// Guardian sets of the greeting accounts owned by `authority`, along
+    /// with their addresses
+    pub async fn list_by_authority(
+        &self,
+        authority: &Pubkey,
+    ) -> Result<Vec<(Pubkey, GuardianSet)>, Error> {
+        self.list(authority_filters(authority)).await
+    }
+
+    /// Deploy the program ELF `program_data` at the address of `program`,
+    /// upgradeable by `payer`
+    ///
+    /// The program is written to a new buffer first, then deployed from it
+    /// with room for programs twice as large.
+    pub async fn deploy(
+        &self,
+        payer: &Keypair,
+        program: &Keypair,
+        program_data: &[u8],
+    ) -> Result<Signature, Error> {
+        let buffer_pubkey = self.write_buffer(payer, program_data).await?;
+        let lamports = self
+            .rpc_client
+            .get_minimum_balance_for_rent_exemption(UpgradeableLoaderState::program_len()?)
+            .await?;
+        let instructions = deploy_with_max_program_len(
+            &payer.pubkey(),
+            &program.pubkey(),
+            &buffer_pubkey,
+            &payer.pubkey(),
+            lamports,
+            program_data.len().saturating_mul(2),
+        )?;
+        self.send_and_confirm_with_signers(&instructions, &[payer, program])
+            .await
+    }
+
+    /// Upgrade the program at `program_id` to the ELF `program_data`, with
+    /// `authority` as its upgrade authority
+    ///
+    /// The program is written to a new buffer first, whose lamports return to
+    /// `authority` once the program is upgraded from it.
+    pub async fn upgrade(
+        &self,
+        authority: &Keypair,
+        program_id: &Pubkey,
+        program_data: &[u8],
+    ) -> Result<Signature, Error> {
+        let buffer_pubkey = self.write_buffer(authority, program_data).await?;
+        let instruction = upgrade(
+            program_id,
+            &buffer_pubkey,
+            &authority.pubkey(),
+            &authority.pubkey(),
+        );
+        self.process(instruction, authority).await
+    }
+
+    /// Slot at which the program at `program_id` was last deployed or
+    /// upgraded, read from its program data account
+    pub async fn get_program_data_slot(&self, program_id: &Pubkey) -> Result<u64, Error> {
+        let program_data_pubkey =
+            Pubkey::find_program_address(&[program_id.as_ref()], &bpf_loader_upgradeable::id()).0;
+        let account = self
+            .rpc_client
+            .get_account_with_commitment(&program_data_pubkey, self.commitment())
+            .await?
+            .value
+            .ok_or(Error::AccountNotFound(program_data_pubkey))?;
+        match bincode::deserialize(&account.data) {
+            Ok(UpgradeableLoaderState::ProgramData { slot, .. })
+                if account.owner == bpf_loader_upgradeable::id() =>
+            {
+                Ok(slot)
+            }
+            _ => Err(ProgramError::InvalidAccountData.into()),
+        }
+    }
+
+    /// Write `program_data` to a new buffer account of the upgradeable
+    /// loader, whose authority is `payer`, returning its address
+    ///
+    /// The writes are sent concurrently up to the configured limit. A buffer
+    /// left incomplete by a failed write keeps its lamports until closed.
+    pub async fn write_buffer(
+        &self,
+        payer: &Keypair,
+        program_data: &[u8],
+    ) -> Result<Pubkey, Error> {
+        let buffer = Keypair::new();
+        let lamports = self
+            .rpc_client
+            .get_minimum_balance_for_rent_exemption(UpgradeableLoaderState::buffer_len(
+                program_data.len(),
+            )?)
+            .await?;
+        let instructions = create_buffer(
+            &payer.pubkey(),
+            &buffer.pubkey(),
+            &payer.pubkey(),
+            lamports,
+            program_data.len(),
+        )?;
+        self.send_and_confirm_with_signers(&instructions, &[payer, &buffer])
+            .await?;
+        stream::iter(write_buffer_ixs(
+            &buffer.pubkey(),
+            &payer.pubkey(),
+            program_data,
+        ))
+        .map(|instruction| self.process(instruction, payer))
+        .buffered(self.config.batch_concurrency)
+        .collect::<Vec<_>>()
+        .await
+        .into_iter()
+        .collect::<Result<Vec<_>, _>>()?;
+        Ok(buffer.pubkey())
+    }
+
+    /// Durable nonce stored in the nonce account `nonce_pubkey`
+    pub async fn get_durable_nonce(&self, nonce_pubkey: &Pubkey) -> Result<DurableNonce, Error> {
+        let account = self.rpc_client.get_account(nonce_pubkey).await?;
+        DurableNonce::from_account(*nonce_pubkey, &account)
+    }
+
+    /// Submit a transaction signed beforehand, such as a greeting signed
+    /// offline against a durable nonce, and wait for its confirmation
+    ///
+    /// The transaction is sent once, and given up once the timeout of the
+    /// retry policy passes.
+    pub async fn send_transaction(&self, transaction: &Transaction) -> Result<Signature, Error> {
+        self.send_versioned_transaction(&transaction.clone().into())
+            .await
+    }
+
+    /// Submit a versioned transaction and wait for its confirmation, giving
+    /// up once the timeout of the retry policy passes
+    pub async fn send_versioned_transaction(
+        &self,
+        transaction: &VersionedTransaction,
+    ) -> Result<Signature, Error> {
+        let deadline = Instant::now() + self.config.retry_policy.timeout;
+        let result = match self
+            .submit_and_confirm(transaction, u64::MAX, deadline)
+            .await
+        {
+            Ok(true) => Ok(transaction.signatures[0]),
+            Ok(false) => Err(Error::SendFailed {
+                attempts: 1,
+                reason: SendFailure::TimedOut,
+            }),
+            Err(error) => Err(error),
+        };
+        self.observe_failure(result)
+    }
+
+    /// Sign `instructions` with `signer`, also paying for the transaction,
+    /// and send them until their confirmation
+    ///
+    /// Each attempt signs against a fresh blockhash, so that an expired one is
+    /// replaced right away. RPC errors are retried after a backoff, while
+    /// failed transactions are not retried at all.
+    pub async fn send_and_confirm(
+        &self,
+        instructions: &[Instruction],
+        signer: &Keypair,
+    ) -> Result<Signature, Error> {
+        self.send_and_confirm_with_signers(instructions, &[signer])
+            .await
+    }
+
+    /// Sign `instructions` with `signers`, the first of which pays for the
+    /// transaction, and send them until their confirmation as
+    /// `send_and_confirm`
+    ///
+    /// Panics if `signers` is empty.
+    pub async fn send_and_confirm_with_signers(
+        &self,
+        instructions: &[Instruction],
+        signers: &[&Keypair],
+    ) -> Result<Signature, Error> {
+        let result = self.send_with_retries(instructions, signers).await;
+        self.observe_failure(result)
+    }
+
+    /// Send `instructions` signed by `signers` as
+    /// `send_and_confirm_with_signers`, without reporting failures
+    async fn send_with_retries(
+        &self,
+        instructions: &[Instruction],
+        signers: &[&Keypair],
+    ) -> Result<Signature, Error> {
+        let deadline = Instant::now() + self.config.retry_policy.timeout;
+        let mut attempts = 0;
+        loop {
+            attempts += 1;
+            let reason = match self.send_once(instructions, signers, deadline).await {
+                Ok(Some(signature)) => return Ok(signature),
+                Ok(None) if Instant::now() >= deadline => SendFailure::TimedOut,
+                Ok(None) => SendFailure::BlockhashExpired,
+                Err(Error::Rpc(error)) => match error.get_transaction_error() {
+                    Some(error) => {
+                        return Err(Error::SendFailed {
+                            attempts,
+                            reason: SendFailure::Transaction(error),
+                        })
+                    }
+                    None => SendFailure::Rpc(error),
+                },
+                Err(error) => return Err(error),
+            };
+            if attempts >= self.config.retry_policy.max_attempts || Instant::now() >= deadline {
+                return Err(Error::SendFailed { attempts, reason });
+            }
+            if let SendFailure::Rpc(_) = reason {
+                sleep(self.config.retry_policy.backoff(attempts)).await;
+            }
+        }
+    }
+
+    /// Account at `pubkey`, `None` if there is none, served from the account
+    /// cache if the config enables it
+    async fn get_account(&self, pubkey: &Pubkey) -> Result<Option<Account>, Error> {
+        let max_age = self.config.account_cache_slots;
+        if let Some(account) = max_age.and_then(|max_age| self.account_cache.get(pubkey, max_age)) {
+            return Ok(account);
+        }
+        let response = self
+            .rpc_client
+            .get_account_with_commitment(pubkey, self.commitment())
+            .await?;
+        if max_age.is_some() {
+            self.account_cache
+                .insert(*pubkey, response.context.slot, response.value.clone());
+        }
+        Ok(response.value)
+    }
+
+    /// Accounts of the program passing `filters`, decoded as `T`
+    async fn list<T: AccountState>(
+        &self,
+        filters: Vec<RpcFilterType>,
+    ) -> Result<Vec<(Pubkey, T)>, Error> {
+        let config = RpcProgramAccountsConfig {
+            filters: Some(filters),
+            account_config: RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                commitment: Some(self.commitment()),
+                ..RpcAccountInfoConfig::default()
+            },
+            with_context: None,
+        };
+        self.rpc_client
+            .get_program_accounts_with_config(&id(), config)
+            .await?
+            .into_iter()
+            .map(|(pubkey, account)| Ok((pubkey, T::load(&account.data)?)))
+            .collect()
+    }
+
+    /// Sign `instruction` with `signer`, also paying for the transaction, and
+    /// send it until its confirmation
+    async fn process(
+        &self,
+        instruction: Instruction,
+        signer: &Keypair,
+    ) -> Result<Signature, Error> {
+        self.send_and_confirm(&[instruction], signer).await
+    }
+
+    /// Send `instructions` signed by `signers`, the first paying, once,
+    /// `None` if the blockhash expires or `deadline` passes before their
+    /// confirmation
+    async fn send_once(
+        &self,
+        instructions: &[Instruction],
+        signers: &[&Keypair],
+        deadline: Instant,
+    ) -> Result<Option<Signature>, Error> {
+        let payer = signers[0].pubkey();
+        let (mut instructions, blockhash, last_valid_block_height) =
+            self.prepare(instructions).await?;
+        self.auto_compute_budget(&mut instructions, |instructions| {
+            Transaction::new_with_payer(instructions, Some(&payer)).into()
+        })
+        .await?;
+        let transaction: VersionedTransaction = Transaction::new_signed_with_payer(
+            &instructions,
+            Some(&payer),
+            &signers.to_vec(),
+            blockhash,
+        )
+        .into();
+        let confirmed = self
+            .submit_and_confirm(&transaction, last_valid_block_height, deadline)
+            .await?;
+        Ok(confirmed.then_some(transaction.signatures[0]))
+    }
+
+    /// Submit `transaction` and wait for its confirmation as `confirm`,
+    /// decoding its errors and reporting it to the observer
+    async fn submit_and_confirm(
+        &self,
+        transaction: &VersionedTransaction,
+        last_valid_block_height: u64,
+        deadline: Instant,
+    ) -> Result<bool, Error> {
+        let confirmed = async {
+            let signature = self.submit(transaction).await?;
+            let sent_at = Instant::now();
+            if let Some(observer) = &self.observer {
+                observer.on_send(&signature);
+            }
+            let confirmed = self
+                .confirm(&signature, last_valid_block_height, deadline)
+                .await?;
+            if let (Some(observer), true) = (&self.observer, confirmed) {
+                observer.on_confirm(&signature, sent_at.elapsed());
+            }
+            Ok::<_, Error>(confirmed)
+        };
+        let confirmed = confirmed.await;
+        // Whatever the outcome, the transaction may have written its accounts
+        let message = &transaction.message;
+        self.account_cache.invalidate(
+            message
+                .static_account_keys()
+                .iter()
+                .enumerate()
+                .filter(|(index, _)| message.is_maybe_writable(*index))
+                .map(|(_, pubkey)| pubkey),
+        );
+        confirmed.map_err(|error| error.decode(message))
+    }
+
+    /// Report the failure of sending a transaction, if `result` is one, to
+    /// the observer
+    fn observe_failure(&self, result: Result<Signature, Error>) -> Result<Signature, Error> {
+        if let (Some(observer), Err(error)) = (&self.observer, &result) {
+            observer.on_error(error);
+        }
+        result
+    }
+
+    /// Submit `transaction` to the leaders through the TPU sender if any,
+    /// falling back to JSON RPC
+    async fn submit(&self, transaction: &VersionedTransaction) -> Result<Signature, Error> {
+        let wire_transaction = serialize(transaction)?;
+        let sent = match &self.tpu_sender {
+            Some(tpu_sender) => tpu_sender.send(wire_transaction.clone()).await,
+            None => false,
+        };
+        if !sent {
+            self.rpc_client
+                .send::<String>(
+                    RpcRequest::SendTransaction,
+                    json!([base64::encode(wire_transaction), {
+                        "encoding": "base64",
+                        "preflightCommitment": self.commitment().commitment,
+                    }]),
+                )
+                .await?;
+        }
+        Ok(transaction.signatures[0])
+    }
+
+    /// Wait for the confirmation of the transaction `signature`, `false` if
+    /// the block height passes `last_valid_block_height` or `deadline` passes
+    /// first
+    async fn confirm(
+        &self,
+        signature: &Signature,
+        last_valid_block_height: u64,
+        deadline: Instant,
+    ) -> Result<bool, Error> {
+        match self.config.confirmation {
+            Confirmation::Polling => {
+                self.poll_signature(signature, last_valid_block_height, deadline)
+                    .await
+            }
+            Confirmation::Subscription => {
+                self.subscribe_signature(signature, last_valid_block_height, deadline)
+                    .await
+            }
+        }
+    }
+
+    /// Poll the status of `signature` until its confirmation, as `confirm`
+    async fn poll_signature(
+        &self,
+        signature: &Signature,
+        last_valid_block_height: u64,
+        deadline: Instant,
+    ) -> Result<bool, Error> {
+        while Instant::now() < deadline {
+            match self
+                .check_signature(signature, last_valid_block_height)
+                .await?
+            {
+                Some(confirmed) => return Ok(confirmed),
+                None => sleep(CONFIRMATION_POLL_INTERVAL).await,
+            }
+        }
+        Ok(false)
+    }
+
+    /// Wait for the notification of the confirmation of `signature`, as
+    /// `confirm`, falling back to polling if the connection closes
+    async fn subscribe_signature(
+        &self,
+        signature: &Signature,
+        last_valid_block_height: u64,
+        deadline: Instant,
+    ) -> Result<bool, Error> {
+        let client = PubsubClient::new(&self.websocket_url()).await?;
+        let config = RpcSignatureSubscribeConfig {
+            commitment: Some(self.commitment()),
+            enable_received_notification: Some(false),
+        };
+        let (mut notifications, _unsubscribe) =
+            client.signature_subscribe(signature, Some(config)).await?;
+        while Instant::now() < deadline {
+            // The transaction may be confirmed before the subscription, and
+            // its blockhash expires without any notification
+            if let Some(confirmed) = self
+                .check_signature(signature, last_valid_block_height)
+                .await?
+            {
+                return Ok(confirmed);
+            }
+            match timeout(SUBSCRIPTION_CHECK_INTERVAL, notifications.next()).await {
+                Ok(Some(Response {
+                    value: RpcSignatureResult::ProcessedSignature(result),
+                    ..
+                })) => {
+                    return match result.err {
+                        Some(error) => Err(ClientError::from(error).into()),
+                        None => Ok(true),
+                    }
+                }
+                Ok(Some(_)) | Err(_) => {}
+                Ok(None) => {
+                    return self
+                        .poll_signature(signature, last_valid_block_height, deadline)
+                        .await
+                }
+            }
+        }
+        Ok(false)
+    }
+
+    /// Whether the transaction `signature` is confirmed, `false` if the block
+    /// height passed `last_valid_block_height` without it, and `None` while
+    /// it may still be
+    async fn check_signature(
+        &self,
+        signature: &Signature,
+        last_valid_block_height: u64,
+    ) -> Result<Option<bool>, Error> {
+        let status = self
+            .rpc_client
+            .get_signature_status_with_commitment(signature, self.commitment())
+            .await?;
+        Ok(match status {
+            Some(Ok(())) => Some(true),
+            Some(Err(error)) => return Err(ClientError::from(error).into()),
+            None if last_valid_block_height == u64::MAX => None,
+            None => {
+                let block_height = self
+                    .rpc_client
+                    .get_block_height_with_commitment(self.commitment())
+                    .await?;
+                (block_height > last_valid_block_height).then_some(false)
+            }
+        })
+    }
+
+    /// Commitment at which the client reads accounts and confirms
+    /// transactions
+    fn commitment(&self) -> CommitmentConfig {
+        self.config
+            .commitment
+            .unwrap_or_else(|| self.rpc_client.commitment())
+    }
+
+    /// URL of the websocket endpoint of the cluster
+    fn websocket_url(&self) -> String {
+        self.websocket_url
+            .clone()
+            .unwrap_or_else(|| subscribe::websocket_url(&self.rpc_client.url()))
+    }
+
+    /// Instructions of a transaction running `instructions`, preceded by the
+    /// advance of the durable nonce and the compute budget, along with the
+    /// blockhash to sign it against and the last block height at which it is
+    /// valid
+    async fn prepare(
+        &self,
+        instructions: &[Instruction],
+    ) -> Result<(Vec<Instruction>, Hash, u64), Error> {
+        let mut prepared = vec![];
+        let (blockhash, last_valid_block_height) = match &self.config.nonce_pubkey {
+            Some(nonce_pubkey) => {
+                let nonce = self.get_durable_nonce(nonce_pubkey).await?;
+                prepared.push(nonce.advance_ix());
+                // Durable nonces do not expire
+                (nonce.blockhash, u64::MAX)
+            }
+            None => {
+                self.rpc_client
+                    .get_latest_blockhash_with_commitment(self.commitment())
+                    .await?
+            }
+        };
+        prepared.extend(self.compute_budget_instructions(instructions).await?);
+        prepared.extend_from_slice(instructions);
+        Ok((prepared, blockhash, last_valid_block_height))
+    }
+
+    /// Cap the compute units of `instructions`, prepared by `prepare`, at the
+    /// units they consume in a simulation of the transaction built by `build`,
+    /// plus a safety margin, if the config asks for it
+    async fn auto_compute_budget(
+        &self,
+        instructions: &mut Vec<Instruction>,
+        build: impl Fn(&[Instruction]) -> VersionedTransaction,
+    ) -> Result<(), Error> {
+        if !self.config.auto_compute_budget || self.config.compute_unit_limit.is_some() {
+            return Ok(());
+        }
+        // The compute budget follows the advance of the durable nonce, and the
+        // simulation must not run out of compute units before the transaction
+        let index = usize::from(self.config.nonce_pubkey.is_some());
+        let mut simulated = instructions.clone();
+        simulated.insert(
+            index,
+            ComputeBudgetInstruction::set_compute_unit_limit(MAX_COMPUTE_UNIT_LIMIT),
+        );
+        let transaction = build(&simulated);
+        let result = self.simulate(&transaction).await?;
+        if let Some(error) = result.err {
+            return Err(Error::Simulation {
+                program_error: program_error(&error, &transaction.message),
+                error,
+                logs: result.logs.unwrap_or_default(),
+            });
+        }
+        if let Some(units) = result.units_consumed {
+            let units = units.saturating_mul(100 + AUTO_COMPUTE_UNIT_MARGIN) / 100;
+            let units = u32::try_from(units).map_or(MAX_COMPUTE_UNIT_LIMIT, |units| {
+                units.min(MAX_COMPUTE_UNIT_LIMIT)
+            });
+            instructions.insert(
+                index,
+                ComputeBudgetInstruction::set_compute_unit_limit(units),
+            );
+        }
+        Ok(())
+    }
+
+    /// Simulate `transaction` against the latest blockhash, without checking
+    /// its signatures
+    async fn simulate(
+        &self,
+        transaction: &VersionedTransaction,
+    ) -> Result<RpcSimulateTransactionResult, Error> {
+        let response: Response<RpcSimulateTransactionResult> = self
+            .rpc_client
+            .send(
+                RpcRequest::SimulateTransaction,
+                json!([encode(transaction)?, {
+                    "encoding": "base64",
+                    "sigVerify": false,
+                    "replaceRecentBlockhash": true,
+                    "commitment": self.commitment().commitment,
+                }]),
+            )
+            .await?;
+        self.account_cache.observe(response.context.slot);
+        Ok(response.value)
+    }
+
+    /// Compute budget instructions to prepend to a transaction made of
+    /// `instructions`
+    async fn compute_budget_instructions(
+        &self,
+        instructions: &[Instruction],
+    ) -> Result<Vec<Instruction>, Error> {
+        let mut budget = vec![];
+        if let Some(units) = self.config.compute_unit_limit {
+            budget.push(ComputeBudgetInstruction::set_compute_unit_limit(units));
+        }
+        let price = match self.config.priority_fee {
+            PriorityFee::None => return Ok(budget),
+            PriorityFee::Fixed(price) => price,
+            PriorityFee::RecentPercentile(percentile) => {
+                let mut writable: Vec<String> = instructions
+                    .iter()
+                    .flat_map(|instruction| &instruction.accounts)
+                    .filter(|account| account.is_writable)
+                    .map(|account| account.pubkey.to_string())
+                    .collect();
+                writable.sort();
+                writable.dedup();
+                let fees: Vec<RecentPrioritizationFee> = self
+                    .rpc_client
+                    .send(
+                        RpcRequest::Custom {
+                            method: "getRecentPrioritizationFees",
+                        },
+                        json!([writable]),
+                    )
+                    .await?;
+                let prices = fees.iter().map(|fee| fee.prioritization_fee).collect();
+                priority::percentile(prices, percentile)
+            }
+        };
+        budget.push(ComputeBudgetInstruction::set_compute_unit_price(price));
+        Ok(budget)
+    }
+}
+
+/// `transaction` encoded in base64 for the RPC requests taking a transaction,
+/// the RPC client only encoding legacy transactions itself
+fn encode(transaction: &VersionedTransaction) -> Result<String, Error> {
+    Ok(base64::encode(serialize(transaction)?))
+}
+
+/// `transaction` in its wire format
+fn serialize(transaction: &VersionedTransaction) -> Result<Vec<u8>, Error> {
+    Ok(bincode::serialize(transaction)
+        .map_err(|error| ClientError::from(ClientErrorKind::Custom(error.to_string())))?)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{say_hello_offline, HelloWorldError, RetryPolicy};
+    use futures::SinkExt;
+    use serde_json::Value;
+    use solana_program::{
+        hash::Hash,
+        nonce::{
+            state::{Data, Versions},
+            State,
+        },
+        program_error::ProgramError,
+        system_program,
+    };
+    use solana_sdk::account::Account;
+    use solana_sdk::transaction::TransactionError;
+    use std::{collections::HashMap, sync::Mutex, time::Duration};
+    use tokio::net::TcpListener;
+    use tokio_tungstenite::{accept_async, tungstenite::Message};
+
+    #[tokio::test]
+    async fn test_rpc_client() {
+        let user = Keypair::new();
+        let greeting_address = find_greeting_address(&user.pubkey()).0;
+
+        let client = HelloWorldClient::with_rpc_client(RpcClient::new_mock("succeeds".to_string()));
+        client.initialize(&user).await.unwrap();
+        client.say_hello(&user).await.unwrap();
+        assert_eq!(client.get_greeting(&greeting_address).await.unwrap(), None);
+
+        let greeting = GreetingAccount {
+            version: GreetingAccount::VERSION,
+            counter: 3,
+            ..GreetingAccount::default()
+        };
+        let mut data = vec![0; GreetingAccount::LEN];
+        greeting.store(&mut data).unwrap();
+        let account = |owner: Pubkey| {
+            json!({
+                "context": { "slot": 1 },
+                "value": {
+                    "data": [base64::encode(&data), "base64"],
+                    "executable": false,
+                    "lamports": 1,
+                    "owner": owner.to_string(),
+                    "rentEpoch": 0,
+                },
+            })
+        };
+        let mut mocks = HashMap::new();
+        mocks.insert(RpcRequest::GetAccountInfo, account(id()));
+        let client = HelloWorldClient::with_rpc_client(RpcClient::new_mock_with_mocks(
+            "succeeds".to_string(),
+            mocks,
+        ));
+        assert_eq!(
+            client.get_greeting(&greeting_address).await.unwrap(),
+            Some(greeting.clone())
+        );
+
+        let mut mocks = HashMap::new();
+        mocks.insert(RpcRequest::GetAccountInfo, account(id()));
+        let client = HelloWorldClient::with_rpc_client(RpcClient::new_mock_with_mocks(
+            "succeeds".to_string(),
+            mocks,
+        ));
+        assert_eq!(
+            client
+                .get_greeting_account(&greeting_address)
+                .await
+                .unwrap(),
+            greeting
+        );
+        // The default mock holds no account
+        assert!(matches!(
+            client.get_config().await,
+            Err(Error::AccountNotFound(pubkey)) if pubkey == find_config_address().0
+        ));
+
+        // Accounts of other programs are not greeting accounts
+        let mut mocks = HashMap::new();
+        mocks.insert(RpcRequest::GetAccountInfo, account(Pubkey::new_unique()));
+        let client = HelloWorldClient::with_rpc_client(RpcClient::new_mock_with_mocks(
+            "succeeds".to_string(),
+            mocks,
+        ));
+        assert!(matches!(
+            client.get_greeting(&greeting_address).await,
+            Err(Error::InvalidAccount(ProgramError::IncorrectProgramId))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_priority_fee() {
+        let user = Pubkey::new_unique();
+        let ixs = [say_hello_ix(&find_greeting_address(&user).0, &user)];
+
+        let client = HelloWorldClient::new("http://localhost:8899");
+        assert!(client
+            .compute_budget_instructions(&ixs)
+            .await
+            .unwrap()
+            .is_empty());
+
+        let client = HelloWorldClient::new("http://localhost:8899").with_config(
+            ClientConfig::default()
+                .compute_unit_limit(50_000)
+                .priority_fee(PriorityFee::Fixed(10)),
+        );
+        assert_eq!(
+            client.compute_budget_instructions(&ixs).await.unwrap(),
+            vec![
+                ComputeBudgetInstruction::set_compute_unit_limit(50_000),
+                ComputeBudgetInstruction::set_compute_unit_price(10),
+            ]
+        );
+
+        let mut mocks = HashMap::new();
+        mocks.insert(
+            RpcRequest::Custom {
+                method: "getRecentPrioritizationFees",
+            },
+            json!([
+                { "slot": 1, "prioritizationFee": 0 },
+                { "slot": 2, "prioritizationFee": 300 },
+                { "slot": 3, "prioritizationFee": 100 },
+                { "slot": 4, "prioritizationFee": 200 },
+                { "slot": 5, "prioritizationFee": 400 },
+            ]),
+        );
+        let client = HelloWorldClient::with_rpc_client(RpcClient::new_mock_with_mocks(
+            "succeeds".to_string(),
+            mocks,
+        ))
+        .with_config(ClientConfig::default().priority_fee(PriorityFee::RecentPercentile(75)));
+        assert_eq!(
+            client.compute_budget_instructions(&ixs).await.unwrap(),
+            vec![ComputeBudgetInstruction::set_compute_unit_price(300)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_durable_nonce() {
+        let user = Keypair::new();
+        let nonce_pubkey = Pubkey::new_unique();
+        let data = Data::new(
+            user.pubkey(),
+            solana_program::nonce::state::DurableNonce::from_blockhash(&Hash::new_unique(), true),
+            5000,
+        );
+        let versions = Versions::new(State::Initialized(data.clone()), true);
+        let account = Account::new_data(1, &versions, &system_program::id()).unwrap();
+        let mut mocks = HashMap::new();
+        mocks.insert(
+            RpcRequest::GetAccountInfo,
+            json!({
+                "context": { "slot": 1 },
+                "value": {
+                    "data": [base64::encode(&account.data), "base64"],
+                    "executable": false,
+                    "lamports": account.lamports,
+                    "owner": account.owner.to_string(),
+                    "rentEpoch": 0,
+                },
+            }),
+        );
+        // Mocked responses are only served once
+        let client = || {
+            HelloWorldClient::with_rpc_client(RpcClient::new_mock_with_mocks(
+                "succeeds".to_string(),
+                mocks.clone(),
+            ))
+            .with_config(ClientConfig::default().durable_nonce(nonce_pubkey))
+        };
+        let nonce = client().get_durable_nonce(&nonce_pubkey).await.unwrap();
+        assert_eq!(nonce.authority, user.pubkey());
+        assert_eq!(nonce.blockhash, data.blockhash());
+        client().say_hello(&user).await.unwrap();
+        client()
+            .send_transaction(&say_hello_offline(&user, &nonce))
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_say_hello_to_all() {
+        let greeter = Keypair::new();
+        let greeted: Vec<Pubkey> = (0..40).map(|_| Pubkey::new_unique()).collect();
+        let table = LookupTable {
+            key: Pubkey::new_unique(),
+            addresses: greeted.clone(),
+        };
+
+        let mut mocks = HashMap::new();
+        mocks.insert(
+            RpcRequest::SendTransaction,
+            json!(Signature::default().to_string()),
+        );
+        let client = HelloWorldClient::with_rpc_client(RpcClient::new_mock_with_mocks(
+            "succeeds".to_string(),
+            mocks,
+        ));
+        client
+            .say_hello_to_all(&greeter, &greeted, &[table])
+            .await
+            .unwrap();
+
+        // Failed transactions are reported
+        let mut mocks = HashMap::new();
+        mocks.insert(
+            RpcRequest::SendTransaction,
+            json!(Signature::default().to_string()),
+        );
+        let client = HelloWorldClient::with_rpc_client(RpcClient::new_mock_with_mocks(
+            "instruction_error".to_string(),
+            mocks,
+        ));
+        assert!(matches!(
+            client.say_hello_to_all(&greeter, &greeted, &[]).await,
+            Err(Error::Rpc(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_retry() {
+        let user = Keypair::new();
+        let policy = RetryPolicy {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(1),
+            timeout: Duration::from_secs(10),
+        };
+        let client = |url: &str, mocks| {
+            HelloWorldClient::with_rpc_client(RpcClient::new_mock_with_mocks(
+                url.to_string(),
+                mocks,
+            ))
+            .with_config(ClientConfig::default().retry_policy(policy))
+        };
+
+        // RPC errors are retried until the attempts run out
+        assert!(matches!(
+            client("fails", HashMap::new()).say_hello(&user).await,
+            Err(Error::SendFailed {
+                attempts: 3,
+                reason: SendFailure::Rpc(_),
+            })
+        ));
+
+        // Failed transactions are not retried
+        assert!(matches!(
+            client("instruction_error", HashMap::new())
+                .say_hello(&user)
+                .await,
+            Err(Error::SendFailed {
+                attempts: 1,
+                reason: SendFailure::Transaction(_),
+            })
+        ));
+
+        // Expired blockhashes are replaced, the later attempts wait until the
+        // timeout
+        let mut mocks = HashMap::new();
+        mocks.insert(RpcRequest::GetBlockHeight, json!(u64::MAX));
+        let client = client("sig_not_found", mocks).with_config(
+            ClientConfig::default().retry_policy(RetryPolicy {
+                timeout: Duration::from_millis(500),
+                ..policy
+            }),
+        );
+        assert!(matches!(
+            client.say_hello(&user).await,
+            Err(Error::SendFailed {
+                attempts: 2,
+                reason: SendFailure::TimedOut,
+            })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_confirmation() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+
+        // Each connection notifies the outcome of a single transaction
+        let outcomes = vec![
+            Value::Null,
+            json!({ "InstructionError": [0, "UninitializedAccount"] }),
+        ];
+        tokio::spawn(async move {
+            for err in outcomes {
+                let (stream, _) = listener.accept().await.unwrap();
+                let mut ws = accept_async(stream).await.unwrap();
+                let request = match ws.next().await.unwrap().unwrap() {
+                    Message::Text(text) => serde_json::from_str::<Value>(&text).unwrap(),
+                    message => panic!("unexpected message {:?}", message),
+                };
+                assert_eq!(request["method"], "signatureSubscribe");
+                assert_eq!(request["params"][1]["commitment"], "finalized");
+                let response = json!({ "jsonrpc": "2.0", "result": 3, "id": request["id"] });
+                ws.send(Message::Text(response.to_string())).await.unwrap();
+                let notification = json!({
+                    "jsonrpc": "2.0",
+                    "method": "signatureNotification",
+                    "params": {
+                        "subscription": 3,
+                        "result": { "context": { "slot": 1 }, "value": { "err": err } },
+                    },
+                });
+                ws.send(Message::Text(notification.to_string()))
+                    .await
+                    .unwrap();
+                ws.close(None).await.unwrap();
+            }
+        });
+
+        // The status of the transaction is never found, so that only the
+        // notification confirms it
+        let user = Keypair::new();
+        let client =
+            HelloWorldClient::with_rpc_client(RpcClient::new_mock("sig_not_found".to_string()))
+                .with_websocket_url(url)
+                .with_config(ClientConfig::default().confirmation(Confirmation::Subscription));
+        let client = client.at_commitment(CommitmentConfig::finalized());
+        assert_eq!(
+            client.config.commitment,
+            Some(CommitmentConfig::finalized())
+        );
+        client.say_hello(&user).await.unwrap();
+        assert!(matches!(
+            client.say_hello(&user).await,
+            Err(Error::SendFailed {
+                attempts: 1,
+                reason: SendFailure::Transaction(TransactionError::InstructionError(..)),
+            })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_auto_compute_budget() {
+        let user = Keypair::new();
+        let ixs = vec![say_hello_ix(
+            &find_greeting_address(&user.pubkey()).0,
+            &user.pubkey(),
+        )];
+        let build =
+            |ixs: &[Instruction]| Transaction::new_with_payer(ixs, Some(&user.pubkey())).into();
+        let simulation = |units: u64, err: Value| {
+            let mut mocks = HashMap::new();
+            mocks.insert(
+                RpcRequest::SimulateTransaction,
+                json!({
+                    "context": { "slot": 1 },
+                    "value": {
+                        "err": err,
+                        "logs": ["Program log: Program is paused"],
+                        "accounts": null,
+                        "unitsConsumed": units,
+                    },
+                }),
+            );
+            HelloWorldClient::with_rpc_client(RpcClient::new_mock_with_mocks(
+                "succeeds".to_string(),
+                mocks,
+            ))
+        };
+        let config = ClientConfig::default().auto_compute_budget(true);
+
+        // The limit is the simulated units plus the margin
+        let mut prepared = ixs.clone();
+        simulation(10_000, Value::Null)
+            .with_config(config)
+            .auto_compute_budget(&mut prepared, build)
+            .await
+            .unwrap();
+        assert_eq!(
+            prepared,
+            [
+                vec![ComputeBudgetInstruction::set_compute_unit_limit(12_000)],
+                ixs.clone()
+            ]
+            .concat()
+        );
+
+        // Limits never exceed what a transaction can request
+        let mut prepared = ixs.clone();
+        simulation(u64::MAX, Value::Null)
+            .with_config(config)
+            .auto_compute_budget(&mut prepared, build)
+            .await
+            .unwrap();
+        assert_eq!(
+            prepared[0],
+            ComputeBudgetInstruction::set_compute_unit_limit(MAX_COMPUTE_UNIT_LIMIT)
+        );
+
+        // Explicit limits are kept
+        let mut prepared = ixs.clone();
+        simulation(10_000, Value::Null)
+            .with_config(config.compute_unit_limit(50_000))
+            .auto_compute_budget(&mut prepared, build)
+            .await
+            .unwrap();
+        assert_eq!(prepared, ixs);
+
+        // Transactions failing in the simulation are not sent, and the errors
+        // of the program are decoded
+        assert!(matches!(
+            simulation(10_000, json!("AccountInUse"))
+                .with_config(config)
+                .say_hello(&user)
+                .await,
+            Err(Error::Simulation {
+                error: TransactionError::AccountInUse,
+                program_error: None,
+                ..
+            })
+        ));
+        let paused =
+            json!({ "InstructionError": [1, { "Custom": HelloWorldError::ProgramPaused.code() }] });
+        match simulation(10_000, paused)
+            .with_config(config)
+            .say_hello(&user)
+            .await
+        {
+            Err(Error::Simulation {
+                program_error,
+                logs,
+                ..
+            }) => {
+                assert_eq!(program_error, Some(HelloWorldError::ProgramPaused));
+                assert_eq!(logs, vec!["Program log: Program is paused".to_string()]);
+            }
+            result => panic!("unexpected result {:?}", result),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_send_greetings_batch() {
+        let keys: Vec<Keypair> = (0..20).map(|_| Keypair::new()).collect();
+        let pubkeys: Vec<Pubkey> = keys.iter().map(|key| key.pubkey()).collect();
+        let client = HelloWorldClient::with_rpc_client(RpcClient::new_mock("succeeds".to_string()))
+            .with_config(ClientConfig::default().batch_concurrency(4));
+        let results = client.send_greetings_batch(keys).await;
+        assert_eq!(
+            results
+                .iter()
+                .map(|(pubkey, _)| *pubkey)
+                .collect::<Vec<_>>(),
+            pubkeys
+        );
+        assert!(results.iter().all(|(_, result)| result.is_ok()));
+
+        // Each greeting reports its own failure
+        let keys = vec![Keypair::new(), Keypair::new()];
+        let client =
+            HelloWorldClient::with_rpc_client(RpcClient::new_mock("instruction_error".to_string()));
+        let results = client.send_greetings_batch(keys).await;
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|(_, result)| matches!(
+            result,
+            Err(Error::SendFailed {
+                reason: SendFailure::Transaction(_),
+                ..
+            })
+        )));
+    }
+
+    #[tokio::test]
+    async fn test_observer() {
+        #[derive(Default)]
+        struct Recorder(Mutex<Vec<String>>);
+
+        impl Observer for Arc<Recorder> {
+            fn on_send(&self, _signature: &Signature) {
+                self.0.lock().unwrap().push("send".to_string());
+            }
+
+            fn on_confirm(&self, _signature: &Signature, _latency: Duration) {
+                self.0.lock().unwrap().push("confirm".to_string());
+            }
+
+            fn on_error(&self, error: &Error) {
+                self.0.lock().unwrap().push(format!("error {}", error));
+            }
+        }
+
+        let user = Keypair::new();
+        let recorder = Arc::new(Recorder::default());
+        let client = HelloWorldClient::with_rpc_client(RpcClient::new_mock("succeeds".to_string()))
+            .with_observer(recorder.clone());
+        client.say_hello(&user).await.unwrap();
+        assert_eq!(*recorder.0.lock().unwrap(), vec!["send", "confirm"]);
+
+        let recorder = Arc::new(Recorder::default());
+        let client =
+            HelloWorldClient::with_rpc_client(RpcClient::new_mock("instruction_error".to_string()))
+                .with_observer(recorder.clone());
+        let error = client.say_hello(&user).await.unwrap_err();
+        assert_eq!(
+            *recorder.0.lock().unwrap(),
+            vec!["send".to_string(), format!("error {}", error)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_account_cache() {
+        let user = Keypair::new();
+        let greeting_address = find_greeting_address(&user.pubkey()).0;
+        let greeting = GreetingAccount {
+            version: GreetingAccount::VERSION,
+            counter: 3,
+            ..GreetingAccount::default()
+        };
+        let mut data = vec![0; GreetingAccount::LEN];
+        greeting.store(&mut data).unwrap();
+        // Each mock answers a single request, later ones finding no account
+        let client = || {
+            let mut mocks = HashMap::new();
+            mocks.insert(
+                RpcRequest::GetAccountInfo,
+                json!({
+                    "context": { "slot": 1 },
+                    "value": {
+                        "data": [base64::encode(&data), "base64"],
+                        "executable": false,
+                        "lamports": 1,
+                        "owner": id().to_string(),
+                        "rentEpoch": 0,
+                    },
+                }),
+            );
+            HelloWorldClient::with_rpc_client(RpcClient::new_mock_with_mocks(
+                "succeeds".to_string(),
+                mocks,
+            ))
+        };
+
+        let uncached = client();
+        assert_eq!(
+            uncached.get_greeting(&greeting_address).await.unwrap(),
+            Some(greeting.clone())
+        );
+        assert_eq!(
+            uncached.get_greeting(&greeting_address).await.unwrap(),
+            None
+        );
+
+        let cached = client().with_config(ClientConfig::default().account_cache(10));
+        for _ in 0..2 {
+            assert_eq!(
+                cached
+                    .get_greeting_account(&greeting_address)
+                    .await
+                    .unwrap(),
+                greeting
+            );
+        }
+        // Another commitment has its own cache
+        assert_eq!(
+            cached
+                .at_commitment(CommitmentConfig::finalized())
+                .get_greeting(&greeting_address)
+                .await
+                .unwrap(),
+            None
+        );
+        // Greeting writes the greeting account, which is read again
+        cached.say_hello(&user).await.unwrap();
+        assert_eq!(cached.get_greeting(&greeting_address).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_deploy() {
+        let payer = Keypair::new();
+        let program = Keypair::new();
+        let client = HelloWorldClient::with_rpc_client(RpcClient::new_mock("succeeds".to_string()));
+        client.deploy(&payer, &program, &[0; 2000]).await.unwrap();
+        client.write_buffer(&payer, &[]).await.unwrap();
+        client
+            .upgrade(&payer, &program.pubkey(), &[0; 2000])
+            .await
+            .unwrap();
+
+        let account = |owner: Pubkey, state: &UpgradeableLoaderState| {
+            let mut mocks = HashMap::new();
+            mocks.insert(
+                RpcRequest::GetAccountInfo,
+                json!({
+                    "context": { "slot": 1 },
+                    "value": {
+                        "data": [base64::encode(bincode::serialize(state).unwrap()), "base64"],
+                        "executable": false,
+                        "lamports": 1,
+                        "owner": owner.to_string(),
+                        "rentEpoch": 0,
+                    },
+                }),
+            );
+            HelloWorldClient::with_rpc_client(RpcClient::new_mock_with_mocks(
+                "succeeds".to_string(),
+                mocks,
+            ))
+        };
+        let program_data = UpgradeableLoaderState::ProgramData {
+            slot: 42,
+            upgrade_authority_address: Some(payer.pubkey()),
+        };
+        assert_eq!(
+            account(bpf_loader_upgradeable::id(), &program_data)
+                .get_program_data_slot(&program.pubkey())
+                .await
+                .unwrap(),
+            42
+        );
+        assert!(matches!(
+            account(Pubkey::new_unique(), &program_data)
+                .get_program_data_slot(&program.pubkey())
+                .await,
+            Err(Error::InvalidAccount(ProgramError::InvalidAccountData))
+        ));
+        assert!(matches!(
+            account(
+                bpf_loader_upgradeable::id(),
+                &UpgradeableLoaderState::Uninitialized
+            )
+            .get_program_data_slot(&program.pubkey())
+            .await,
+            Err(Error::InvalidAccount(ProgramError::InvalidAccountData))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_list() {
+        let keyed = |pubkey: Pubkey, data: Vec<u8>| {
+            json!({
+                "pubkey": pubkey.to_string(),
+                "account": {
+                    "data": [base64::encode(&data), "base64"],
+                    "executable": false,
+                    "lamports": 1,
+                    "owner": id().to_string(),
+                    "rentEpoch": 0,
+                },
+            })
+        };
+        let client = |accounts: Value| {
+            let mut mocks = HashMap::new();
+            mocks.insert(RpcRequest::GetProgramAccounts, accounts);
+            HelloWorldClient::with_rpc_client(RpcClient::new_mock_with_mocks(
+                "succeeds".to_string(),
+                mocks,
+            ))
+        };
+
+        let greetings: Vec<(Pubkey, GreetingAccount)> = (1..=3)
+            .map(|counter| {
+                let greeting = GreetingAccount {
+                    version: GreetingAccount::VERSION,
+                    counter,
+                    ..GreetingAccount::default()
+                };
+                (Pubkey::new_unique(), greeting)
+            })
+            .collect();
+        let accounts = greetings
+            .iter()
+            .map(|(pubkey, greeting)| {
+                let mut data = vec![0; GreetingAccount::LEN];
+                greeting.store(&mut data).unwrap();
+                keyed(*pubkey, data)
+            })
+            .collect();
+        assert_eq!(
+            client(Value::Array(accounts))
+                .list_greetings()
+                .await
+                .unwrap(),
+            greetings
+        );
+
+        let owner = Pubkey::new_unique();
+        let guardian_set = GuardianSet {
+            is_initialized: true,
+            greeting: Pubkey::new_unique(),
+            owner,
+            ..GuardianSet::default()
+        };
+        let mut data = vec![0; GuardianSet::LEN];
+        guardian_set.store(&mut data).unwrap();
+        let pubkey = Pubkey::new_unique();
+        assert_eq!(
+            client(json!([keyed(pubkey, data)]))
+                .list_by_authority(&owner)
+                .await
+                .unwrap(),
+            vec![(pubkey, guardian_set)]
+        );
+
+        // Accounts holding other states fail to decode
+        let mut data = vec![0; GreetingAccount::LEN];
+        GreetingAccount::default().store(&mut data).unwrap();
+        assert!(matches!(
+            client(json!([keyed(pubkey, data)]))
+                .list_by_authority(&owner)
+                .await,
+            Err(Error::InvalidAccount(ProgramError::InvalidAccountData))
+        ));
+    }
+}
@@ -0,0 +1,353 @@
+//! Greeting and program events streamed over the websocket API
+
+use crate::{greeting_filters, id, parse_logs_response, GreetingAccount, HelloEvent, RetryPolicy};
+use futures::{channel::mpsc::UnboundedSender, Future, StreamExt};
+use helloworld::state::AccountState;
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::{
+    nonblocking::pubsub_client::{PubsubClient, PubsubClientError},
+    rpc_config::{
+        RpcAccountInfoConfig, RpcProgramAccountsConfig, RpcTransactionLogsConfig,
+        RpcTransactionLogsFilter,
+    },
+    rpc_response::{Response, RpcKeyedAccount, RpcLogsResponse},
+};
+use solana_program::pubkey::Pubkey;
+use solana_sdk::{account::Account, commitment_config::CommitmentConfig, signature::Signature};
+use std::str::FromStr;
+use tokio::time::sleep;
+use url::Url;
+
+/// Update of a greeting account, such as a greeting
+#[derive(Clone, Debug, PartialEq)]
+pub struct GreetingEvent {
+    /// Slot of the update
+    pub slot: u64,
+    /// Address of the greeting account
+    pub pubkey: Pubkey,
+    /// State of the greeting account after the update
+    pub greeting: GreetingAccount,
+}
+
+/// Events of the program in a successful transaction
+#[derive(Clone, Debug, PartialEq)]
+pub struct TransactionEvents {
+    /// Slot of the transaction
+    pub slot: u64,
+    /// Signature of the transaction
+    pub signature: Signature,
+    /// Events of the program, decoded from the logs of the transaction
+    pub events: Vec<HelloEvent>,
+}
+
+/// Websocket URL of the cluster serving JSON RPC at `rpc_url`, served on the
+/// next port as validators do
+pub(crate) fn websocket_url(rpc_url: &str) -> String {
+    let mut url = match Url::parse(rpc_url) {
+        Ok(url) => url,
+        Err(_) => return rpc_url.to_string(),
+    };
+    let scheme = if url.scheme() == "https" { "wss" } else { "ws" };
+    // Only the schemes of the same kind can be swapped, which is the case here
+    let _ = url.set_scheme(scheme);
+    if let Some(port) = url.port() {
+        let _ = url.set_port(Some(port.wrapping_add(1)));
+    }
+    url.to_string()
+}
+
+/// Forward the greetings notified by the cluster at `url` to `sender` until
+/// it is dropped, reconnecting after `retry_policy` backoffs
+pub(crate) async fn forward_greetings(
+    url: String,
+    commitment: CommitmentConfig,
+    retry_policy: RetryPolicy,
+    sender: UnboundedSender<GreetingEvent>,
+) {
+    forward(retry_policy, sender, |sender| {
+        stream_greetings(url.clone(), commitment, sender)
+    })
+    .await
+}
+
+/// Forward the events of the transactions of the program notified by the
+/// cluster at `url` to `sender` until it is dropped, reconnecting after
+/// `retry_policy` backoffs
+pub(crate) async fn forward_transaction_events(
+    url: String,
+    commitment: CommitmentConfig,
+    retry_policy: RetryPolicy,
+    sender: UnboundedSender<TransactionEvents>,
+) {
+    forward(retry_policy, sender, |sender| {
+        stream_transaction_events(url.clone(), commitment, sender)
+    })
+    .await
+}
+
+/// Run `stream`, forwarding notifications over a single connection, until
+/// `sender` is dropped, reconnecting after `retry_policy` backoffs
+async fn forward<T, S, F>(retry_policy: RetryPolicy, sender: UnboundedSender<T>, stream: S)
+where
+    S: Fn(UnboundedSender<T>) -> F,
+    F: Future<Output = Result<(), PubsubClientError>>,
+{
+    let mut failures = 0;
+    while !sender.is_closed() {
+        match stream(sender.clone()).await {
+            Ok(()) => failures = 0,
+            Err(_) => failures += 1,
+        }
+        if !sender.is_closed() {
+            sleep(retry_policy.backoff(failures.max(1))).await;
+        }
+    }
+}
+
+/// Forward the greetings notified over a single connection to `url` until
+/// the connection closes or `sender` is dropped
+async fn stream_greetings(
+    url: String,
+    commitment: CommitmentConfig,
+    sender: UnboundedSender<GreetingEvent>,
+) -> Result<(), PubsubClientError> {
+    let client = PubsubClient::new(&url).await?;
+    let config = RpcProgramAccountsConfig {
+        filters: Some(greeting_filters()),
+        account_config: RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            commitment: Some(commitment),
+            ..RpcAccountInfoConfig::default()
+        },
+        with_context: Some(true),
+    };
+    let (mut notifications, _unsubscribe) = client.program_subscribe(&id(), Some(config)).await?;
+    while let Some(notification) = notifications.next().await {
+        if let Some(event) = greeting_event(notification) {
+            if sender.unbounded_send(event).is_err() {
+                break;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Forward the events of the transactions notified over a single connection
+/// to `url` until the connection closes or `sender` is dropped
+async fn stream_transaction_events(
+    url: String,
+    commitment: CommitmentConfig,
+    sender: UnboundedSender<TransactionEvents>,
+) -> Result<(), PubsubClientError> {
+    let client = PubsubClient::new(&url).await?;
+    let (mut notifications, _unsubscribe) = client
+        .logs_subscribe(
+            RpcTransactionLogsFilter::Mentions(vec![id().to_string()]),
+            RpcTransactionLogsConfig {
+                commitment: Some(commitment),
+            },
+        )
+        .await?;
+    while let Some(notification) = notifications.next().await {
+        if let Some(events) = transaction_events(notification) {
+            if sender.unbounded_send(events).is_err() {
+                break;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Events of a logs notification, `None` if the transaction failed or the
+/// program logged no event
+fn transaction_events(notification: Response<RpcLogsResponse>) -> Option<TransactionEvents> {
+    let events = parse_logs_response(&notification.value);
+    if events.is_empty() {
+        return None;
+    }
+    Some(TransactionEvents {
+        slot: notification.context.slot,
+        signature: Signature::from_str(&notification.value.signature).ok()?,
+        events,
+    })
+}
+
+/// Greeting event of a program account notification, `None` if the account
+/// is not a greeting account
+fn greeting_event(notification: Response<RpcKeyedAccount>) -> Option<GreetingEvent> {
+    let pubkey = Pubkey::from_str(&notification.value.pubkey).ok()?;
+    let account: Account = notification.value.account.decode()?;
+    if account.owner != id() {
+        return None;
+    }
+    Some(GreetingEvent {
+        slot: notification.context.slot,
+        pubkey,
+        greeting: GreetingAccount::load(&account.data).ok()?,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{ClientConfig, HelloWorldClient};
+    use futures::SinkExt;
+    use serde_json::{json, Value};
+    use std::time::Duration;
+    use tokio::net::TcpListener;
+    use tokio_tungstenite::{accept_async, tungstenite::Message};
+
+    #[test]
+    fn test_websocket_url() {
+        assert_eq!(
+            websocket_url("http://localhost:8899"),
+            "ws://localhost:8900/"
+        );
+        assert_eq!(
+            websocket_url("https://api.devnet.solana.com"),
+            "wss://api.devnet.solana.com/"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_greetings() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        let notification = |counter: u32, owner: Pubkey| {
+            let mut data = vec![0; GreetingAccount::LEN];
+            GreetingAccount {
+                version: GreetingAccount::VERSION,
+                counter,
+                ..GreetingAccount::default()
+            }
+            .store(&mut data)
+            .unwrap();
+            json!({
+                "jsonrpc": "2.0",
+                "method": "programNotification",
+                "params": {
+                    "subscription": 7,
+                    "result": {
+                        "context": { "slot": counter },
+                        "value": {
+                            "pubkey": Pubkey::new_unique().to_string(),
+                            "account": {
+                                "data": [base64::encode(&data), "base64"],
+                                "executable": false,
+                                "lamports": 1,
+                                "owner": owner.to_string(),
+                                "rentEpoch": 0,
+                            },
+                        },
+                    },
+                },
+            })
+        };
+
+        // Each connection notifies a single greeting before closing, so that
+        // the client has to reconnect for the next one
+        tokio::spawn(async move {
+            for counter in 1..=2 {
+                let (stream, _) = listener.accept().await.unwrap();
+                let mut ws = accept_async(stream).await.unwrap();
+                let request = match ws.next().await.unwrap().unwrap() {
+                    Message::Text(text) => serde_json::from_str::<Value>(&text).unwrap(),
+                    message => panic!("unexpected message {:?}", message),
+                };
+                assert_eq!(request["method"], "programSubscribe");
+                assert_eq!(request["params"][0], id().to_string());
+                let response = json!({ "jsonrpc": "2.0", "result": 7, "id": request["id"] });
+                ws.send(Message::Text(response.to_string())).await.unwrap();
+                // Accounts of other programs are skipped
+                let skipped = notification(0, Pubkey::new_unique());
+                ws.send(Message::Text(skipped.to_string())).await.unwrap();
+                let greeting = notification(counter, id());
+                ws.send(Message::Text(greeting.to_string())).await.unwrap();
+                ws.close(None).await.unwrap();
+            }
+        });
+
+        let client = HelloWorldClient::new("http://localhost:8899")
+            .with_websocket_url(url)
+            .with_config(ClientConfig::default().retry_policy(RetryPolicy {
+                initial_backoff: Duration::from_millis(10),
+                ..RetryPolicy::default()
+            }));
+        let events: Vec<GreetingEvent> = client.subscribe_greetings().take(2).collect().await;
+        assert_eq!(
+            events
+                .iter()
+                .map(|event| (event.slot, event.greeting.counter))
+                .collect::<Vec<_>>(),
+            vec![(1, 1), (2, 2)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_transaction_events() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        let signature = Signature::new(&[1; 64]);
+        let notification = move |slot: u64, err: Value, message: &str| {
+            json!({
+                "jsonrpc": "2.0",
+                "method": "logsNotification",
+                "params": {
+                    "subscription": 3,
+                    "result": {
+                        "context": { "slot": slot },
+                        "value": {
+                            "signature": signature.to_string(),
+                            "err": err,
+                            "logs": [
+                                format!("Program {} invoke [1]", id()),
+                                format!("Program log: {}", message),
+                                format!("Program {} success", id()),
+                            ],
+                        },
+                    },
+                },
+            })
+        };
+
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut ws = accept_async(stream).await.unwrap();
+            let request = match ws.next().await.unwrap().unwrap() {
+                Message::Text(text) => serde_json::from_str::<Value>(&text).unwrap(),
+                message => panic!("unexpected message {:?}", message),
+            };
+            assert_eq!(request["method"], "logsSubscribe");
+            assert_eq!(request["params"][0]["mentions"][0], id().to_string());
+            let response = json!({ "jsonrpc": "2.0", "result": 3, "id": request["id"] });
+            ws.send(Message::Text(response.to_string())).await.unwrap();
+            // Failed transactions and transactions without events are skipped
+            for notification in [
+                notification(1, json!("AccountInUse"), "Greeted 1 time(s)!"),
+                notification(2, Value::Null, "unrelated"),
+                notification(3, Value::Null, "Greeted 3 time(s)!"),
+            ] {
+                ws.send(Message::Text(notification.to_string()))
+                    .await
+                    .unwrap();
+            }
+            // Keep the connection open until the client is done
+            while ws.next().await.is_some() {}
+        });
+
+        let client = HelloWorldClient::new("http://localhost:8899").with_websocket_url(url);
+        let events: Vec<TransactionEvents> = client
+            .subscribe_transaction_events()
+            .take(1)
+            .collect()
+            .await;
+        assert_eq!(
+            events,
+            vec![TransactionEvents {
+                slot: 3,
+                signature,
+                events: vec![HelloEvent::Greeted { count: 3 }],
+            }]
+        );
+    }
+}
@@ -0,0 +1,101 @@
+//! Sending of transactions straight to the leaders over QUIC
+
+use crate::Error;
+use solana_client::{
+    connection_cache::{ConnectionCache, DEFAULT_TPU_CONNECTION_POOL_SIZE},
+    rpc_client::RpcClient,
+    tpu_client::{TpuClient, TpuClientConfig},
+};
+use std::sync::Arc;
+use tokio::task::spawn_blocking;
+
+/// Sender of transactions straight to the TPU of the current and upcoming
+/// leaders over QUIC, skipping the RPC node and its preflight checks
+///
+/// The leaders are tracked in the background over JSON RPC and the
+/// websocket endpoint of the cluster.
+#[derive(Clone)]
+pub struct TpuSender {
+    tpu_client: Arc<TpuClient>,
+}
+
+impl TpuSender {
+    /// Sender tracking the leaders of the cluster serving JSON RPC at
+    /// `rpc_url` and websocket subscriptions at `websocket_url`
+    pub async fn new(
+        rpc_url: impl Into<String>,
+        websocket_url: impl Into<String>,
+    ) -> Result<Self, Error> {
+        let rpc_url = rpc_url.into();
+        Self::with_rpc_client(move || RpcClient::new(rpc_url), websocket_url).await
+    }
+
+    /// Sender tracking the leaders through the RPC client built by
+    /// `rpc_client`, and the websocket endpoint at `websocket_url` unless it
+    /// is empty
+    ///
+    /// The RPC client is blocking, so it is built and used outside of the
+    /// runtime.
+    pub async fn with_rpc_client(
+        rpc_client: impl FnOnce() -> RpcClient + Send + 'static,
+        websocket_url: impl Into<String>,
+    ) -> Result<Self, Error> {
+        let websocket_url = websocket_url.into();
+        let tpu_client = spawn_blocking(move || {
+            TpuClient::new_with_connection_cache(
+                Arc::new(rpc_client()),
+                &websocket_url,
+                TpuClientConfig::default(),
+                Arc::new(ConnectionCache::new(DEFAULT_TPU_CONNECTION_POOL_SIZE)),
+            )
+            .map_err(Error::from)
+        })
+        .await
+        .expect("creating the TPU client panicked")?;
+        Ok(Self {
+            tpu_client: Arc::new(tpu_client),
+        })
+    }
+
+    /// Send the serialized transaction `wire_transaction` to the leaders,
+    /// `false` if it reached none of them
+    pub(crate) async fn send(&self, wire_transaction: Vec<u8>) -> bool {
+        let tpu_client = self.tpu_client.clone();
+        spawn_blocking(move || tpu_client.send_wire_transaction(wire_transaction))
+            .await
+            .unwrap_or(false)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::HelloWorldClient;
+    use serde_json::json;
+    use solana_client::{nonblocking, rpc_request::RpcRequest};
+    use solana_sdk::signature::Keypair;
+    use std::collections::HashMap;
+
+    #[tokio::test]
+    async fn test_tpu_sender() {
+        // No node of the cluster serves a TPU
+        let tpu_sender = TpuSender::with_rpc_client(
+            || {
+                let mut mocks = HashMap::new();
+                mocks.insert(RpcRequest::GetClusterNodes, json!([]));
+                RpcClient::new_mock_with_mocks("succeeds".to_string(), mocks)
+            },
+            "",
+        )
+        .await
+        .unwrap();
+        assert!(!tpu_sender.send(vec![0; 8]).await);
+
+        // The transactions which reach no leader are sent over JSON RPC
+        let client = HelloWorldClient::with_rpc_client(
+            nonblocking::rpc_client::RpcClient::new_mock("succeeds".to_string()),
+        )
+        .with_tpu_sender(tpu_sender);
+        client.say_hello(&Keypair::new()).await.unwrap();
+    }
+}
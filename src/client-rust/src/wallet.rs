@@ -0,0 +1,128 @@
+//! Loading of the keypairs signing the transactions
+
+use crate::Error;
+use bip39::{Language, Mnemonic};
+use solana_sdk::{
+    derivation_path::DerivationPath,
+    signature::{
+        generate_seed_from_seed_phrase_and_passphrase, keypair_from_seed,
+        keypair_from_seed_and_derivation_path, read_keypair_file, Keypair,
+    },
+};
+use std::{env, path::PathBuf};
+
+/// Where the keypair of a wallet is loaded from
+#[derive(Clone, PartialEq)]
+pub enum Wallet {
+    /// JSON file of the keypair bytes, as written by `solana-keygen`
+    File(PathBuf),
+    /// Environment variable holding the keypair bytes in base58
+    Env(String),
+    /// English BIP39 seed phrase and its passphrase, from which the keypair
+    /// is derived along `derivation_path` if any, or else the way
+    /// `solana-keygen recover` does
+    SeedPhrase {
+        phrase: String,
+        passphrase: String,
+        derivation_path: Option<DerivationPath>,
+    },
+}
+
+impl Default for Wallet {
+    /// Keypair file of the Solana CLI, `~/.config/solana/id.json`
+    fn default() -> Self {
+        Self::File(
+            dirs_next::home_dir()
+                .unwrap_or_default()
+                .join(".config")
+                .join("solana")
+                .join("id.json"),
+        )
+    }
+}
+
+impl Wallet {
+    /// Keypair of the wallet
+    pub fn load(&self) -> Result<Keypair, Error> {
+        match self {
+            Self::File(path) => read_keypair_file(path)
+                .map_err(|error| Error::Keypair(format!("{}: {}", path.display(), error))),
+            Self::Env(name) => {
+                let encoded = env::var(name)
+                    .map_err(|error| Error::Keypair(format!("{}: {}", name, error)))?;
+                let bytes = bs58::decode(encoded.trim())
+                    .into_vec()
+                    .map_err(|error| Error::Keypair(format!("{}: {}", name, error)))?;
+                Keypair::from_bytes(&bytes)
+                    .map_err(|error| Error::Keypair(format!("{}: {}", name, error)))
+            }
+            Self::SeedPhrase {
+                phrase,
+                passphrase,
+                derivation_path,
+            } => {
+                Mnemonic::validate(phrase, Language::English)
+                    .map_err(|error| Error::Keypair(error.to_string()))?;
+                let seed = generate_seed_from_seed_phrase_and_passphrase(phrase, passphrase);
+                match derivation_path {
+                    Some(path) => keypair_from_seed_and_derivation_path(&seed, Some(path.clone())),
+                    None => keypair_from_seed(&seed),
+                }
+                .map_err(|error| Error::Keypair(error.to_string()))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use bip39::MnemonicType;
+    use solana_sdk::signature::{
+        keypair_from_seed_phrase_and_passphrase, write_keypair_file, Signer,
+    };
+
+    #[test]
+    fn test_wallet() {
+        let keypair = Keypair::new();
+
+        let path = env::temp_dir().join(format!("{}.json", keypair.pubkey()));
+        write_keypair_file(&keypair, &path).unwrap();
+        assert_eq!(Wallet::File(path.clone()).load().unwrap(), keypair);
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(Wallet::File(path).load(), Err(Error::Keypair(_))));
+
+        let name = format!("HELLOWORLD_TEST_KEYPAIR_{}", keypair.pubkey());
+        assert!(matches!(
+            Wallet::Env(name.clone()).load(),
+            Err(Error::Keypair(_))
+        ));
+        env::set_var(&name, keypair.to_base58_string());
+        assert_eq!(Wallet::Env(name.clone()).load().unwrap(), keypair);
+        env::set_var(&name, "not base58");
+        assert!(matches!(Wallet::Env(name).load(), Err(Error::Keypair(_))));
+
+        let mnemonic = Mnemonic::new(MnemonicType::Words12, Language::English);
+        let wallet = |phrase: &str, derivation_path| Wallet::SeedPhrase {
+            phrase: phrase.to_string(),
+            passphrase: "passphrase".to_string(),
+            derivation_path,
+        };
+        assert_eq!(
+            wallet(mnemonic.phrase(), None).load().unwrap(),
+            keypair_from_seed_phrase_and_passphrase(mnemonic.phrase(), "passphrase").unwrap()
+        );
+        let derivation_path = DerivationPath::new_bip44(Some(0), Some(0));
+        let seed = generate_seed_from_seed_phrase_and_passphrase(mnemonic.phrase(), "passphrase");
+        assert_eq!(
+            wallet(mnemonic.phrase(), Some(derivation_path.clone()))
+                .load()
+                .unwrap(),
+            keypair_from_seed_and_derivation_path(&seed, Some(derivation_path)).unwrap()
+        );
+        assert!(matches!(
+            wallet("hello world", None).load(),
+            Err(Error::Keypair(_))
+        ));
+    }
+}
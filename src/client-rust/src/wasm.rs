@@ -0,0 +1,38 @@
+//! Javascript interface of the instruction builders and address helpers
+#![cfg(target_arch = "wasm32")]
+
+use crate::{
+    find_config_address, find_greeter_address, find_greeting_address, initialize_ix,
+    say_hello_with_message_ix,
+};
+use solana_program::{instruction::Instruction, pubkey::Pubkey};
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen(js_name = sayHelloInstruction)]
+pub fn say_hello_instruction(
+    greeted_pubkey: &Pubkey,
+    greeter_pubkey: &Pubkey,
+    message: String,
+) -> Instruction {
+    say_hello_with_message_ix(greeted_pubkey, greeter_pubkey, message)
+}
+
+#[wasm_bindgen(js_name = initializeInstruction)]
+pub fn initialize_instruction(user_pubkey: &Pubkey) -> Instruction {
+    initialize_ix(user_pubkey)
+}
+
+#[wasm_bindgen(js_name = greetingAddress)]
+pub fn greeting_address(user: &Pubkey) -> Pubkey {
+    find_greeting_address(user).0
+}
+
+#[wasm_bindgen(js_name = greeterAddress)]
+pub fn greeter_address(greeter: &Pubkey) -> Pubkey {
+    find_greeter_address(greeter).0
+}
+
+#[wasm_bindgen(js_name = configAddress)]
+pub fn config_address() -> Pubkey {
+    find_config_address().0
+}
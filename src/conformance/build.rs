@@ -0,0 +1,43 @@
+//! Build the program the reference conformance test runs against, so it
+//! never goes stale relative to the program sources and the toolchain
+
+use std::{env, path::PathBuf, process::Command};
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    if env::var_os("CARGO_FEATURE_TEST_BPF").is_none() {
+        return;
+    }
+
+    let manifest_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
+    let program_dir = manifest_dir.join("../program-rust");
+    for path in ["Cargo.toml", "build.rs", "src"] {
+        println!(
+            "cargo:rerun-if-changed={}",
+            program_dir.join(path).display()
+        );
+    }
+
+    // A target directory of its own keeps the nested build from waiting on
+    // the lock of the one building this crate
+    let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
+    let program_out_dir = out_dir.join("program");
+    let status = Command::new(env::var("CARGO").unwrap_or_else(|_| "cargo".to_string()))
+        .arg("build-bpf")
+        .arg("--manifest-path")
+        .arg(program_dir.join("Cargo.toml"))
+        .arg("--bpf-out-dir")
+        .arg(&program_out_dir)
+        .env("CARGO_TARGET_DIR", out_dir.join("target"))
+        .status();
+    let program = match status {
+        Ok(status) if status.success() => program_out_dir.join("helloworld.so"),
+        _ => {
+            // Without the BPF toolchain, fall back to the build of
+            // `npm run build:program-rust`
+            println!("cargo:warning=cargo build-bpf failed, testing dist/program/helloworld.so");
+            manifest_dir.join("../../dist/program/helloworld.so")
+        }
+    };
+    println!("cargo:rustc-env=HELLOWORLD_PROGRAM={}", program.display());
+}
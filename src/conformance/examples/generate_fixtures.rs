@@ -0,0 +1,24 @@
+//! Regenerate the fixture program crates under `tests/fixtures`
+//!
+//! Usage: `cargo run --example generate_fixtures -- [count]`, writing the
+//! variants `0..count`, two by default.
+
+use helloworld_conformance::fixtures;
+use std::{env, process::exit};
+
+fn main() {
+    let count = match env::args().nth(1).map(|arg| arg.parse()) {
+        None => 2,
+        Some(Ok(count)) => count,
+        Some(Err(err)) => {
+            eprintln!("error: invalid count: {}", err);
+            exit(1);
+        }
+    };
+    let dir = fixtures::fixtures_dir();
+    if let Err(err) = fixtures::generate(&dir, count) {
+        eprintln!("error: cannot write {}: {}", dir.display(), err);
+        exit(1);
+    }
+    println!("Generated {} fixture(s) in {}", count, dir.display());
+}
@@ -0,0 +1,113 @@
+//! Numbered variants of a tiny program, to upgrade programs between in tests
+//!
+//! Variant `n` is the crate `helloworld<n>` under `tests/fixtures`, logging
+//! `n` on every instruction. The crates are checked in, and regenerated with
+//! `cargo run --example generate_fixtures -- <count>`.
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// Directory holding the fixture crates
+pub fn fixtures_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures")
+}
+
+/// Name of the crate of variant `n`, and of the program it builds
+pub fn fixture_name(n: usize) -> String {
+    format!("helloworld{}", n)
+}
+
+/// Write the crates of variants `0..count` to `dir`, replacing their sources
+pub fn generate(dir: &Path, count: usize) -> io::Result<()> {
+    for n in 0..count {
+        let crate_dir = dir.join(fixture_name(n));
+        fs::create_dir_all(crate_dir.join("src"))?;
+        fs::write(crate_dir.join("Cargo.toml"), cargo_toml(n))?;
+        fs::write(crate_dir.join("src/lib.rs"), lib_rs(n))?;
+    }
+    Ok(())
+}
+
+fn cargo_toml(n: usize) -> String {
+    format!(
+        r#"# Generated by `cargo run --example generate_fixtures`, do not edit
+
+[package]
+name = "{name}"
+version = "0.0.1"
+description = "Program variant {n} for the hello world upgrade tests"
+authors = ["Solana Maintainers <maintainers@solana.com>"]
+repository = "https://github.com/solana-labs/solana"
+license = "Apache-2.0"
+homepage = "https://solana.com/"
+edition = "2018"
+publish = false
+
+[features]
+# Checked by the `entrypoint!` macro
+custom-heap = []
+custom-panic = []
+
+[dependencies]
+solana-program = "~1.10.35"
+
+[lib]
+crate-type = ["cdylib", "lib"]
+
+[workspace]
+"#,
+        name = fixture_name(n),
+        n = n,
+    )
+}
+
+fn lib_rs(n: usize) -> String {
+    format!(
+        r#"//! Program variant {n} for the hello world upgrade tests, logging {n} on
+//! every instruction
+//!
+//! Generated by `cargo run --example generate_fixtures`, do not edit.
+
+use solana_program::{{
+    account_info::AccountInfo, entrypoint, entrypoint::ProgramResult, msg, pubkey::Pubkey,
+}};
+
+entrypoint!(process_instruction);
+
+fn process_instruction(
+    _program_id: &Pubkey,
+    _accounts: &[AccountInfo],
+    _instruction_data: &[u8],
+) -> ProgramResult {{
+    msg!("{n}");
+    Ok(())
+}}
+"#,
+        n = n,
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_fixtures_up_to_date() {
+        let dir = std::env::temp_dir().join(format!("helloworld-fixtures-{}", std::process::id()));
+        generate(&dir, 2).unwrap();
+        for n in 0..2 {
+            for file in ["Cargo.toml", "src/lib.rs"] {
+                let path = Path::new(&fixture_name(n)).join(file);
+                assert_eq!(
+                    fs::read_to_string(fixtures_dir().join(&path)).unwrap(),
+                    fs::read_to_string(dir.join(&path)).unwrap(),
+                    "{} is stale",
+                    path.display()
+                );
+            }
+        }
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,204 @@
+//! Program deployment and transaction helpers shared by the scenarios
+
+use helloworld::state::{find_config_address, find_treasury_address, AccountState, Config};
+use helloworld_test_utils::{add_upgradeable_program, set_non_upgradeable_program_account};
+use solana_program_test::{ProgramTest, ProgramTestBanksClientExt, ProgramTestContext};
+use solana_sdk::{
+    account::Account,
+    clock::Clock,
+    instruction::Instruction,
+    pubkey::Pubkey,
+    rent::Rent,
+    signature::{Keypair, Signer},
+    transaction::Transaction,
+};
+use std::fmt;
+
+/// Outcome of a scenario, describing the first unmet expectation
+pub type ScenarioResult = Result<(), String>;
+
+/// Fail the scenario unless `condition` holds
+macro_rules! check {
+    ($condition:expr, $($message:tt)+) => {
+        if !$condition {
+            return Err(format!($($message)+));
+        }
+    };
+}
+pub(crate) use check;
+
+/// Loader the program under test is deployed with
+///
+/// Loader-v4 is left out, as Solana 1.10 does not ship it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Loader {
+    /// The non-upgradeable loader, as programs made final are deployed
+    BpfLoader,
+    /// The upgradeable loader, as programs are deployed by default
+    Upgradeable,
+}
+
+impl Loader {
+    /// Every loader the scenarios run with
+    pub const ALL: [Loader; 2] = [Loader::BpfLoader, Loader::Upgradeable];
+}
+
+impl fmt::Display for Loader {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Loader::BpfLoader => write!(f, "bpf_loader"),
+            Loader::Upgradeable => write!(f, "bpf_loader_upgradeable"),
+        }
+    }
+}
+
+/// Accounts of a program deployment, before the bank starts
+pub struct Setup {
+    program_test: ProgramTest,
+    pub program_id: Pubkey,
+    pub loader: Loader,
+    pub admin: Keypair,
+}
+
+impl Setup {
+    /// Deploy `elf` with `loader`, the admin as upgrade authority if the
+    /// loader has one, along with an initialized config and a funded treasury
+    pub fn new(elf: &[u8], loader: Loader, config: Config) -> Self {
+        let program_id = helloworld::id();
+        let admin = Keypair::new();
+        let mut program_test = ProgramTest::default();
+        match loader {
+            Loader::BpfLoader => {
+                set_non_upgradeable_program_account(&mut program_test, program_id, elf)
+            }
+            Loader::Upgradeable => {
+                add_upgradeable_program(&mut program_test, program_id, elf, Some(admin.pubkey()));
+            }
+        }
+
+        // Native CPI cannot allocate the config account, so start from an
+        // initialized one
+        let mut config_data = vec![0; Config::LEN];
+        Config {
+            is_initialized: true,
+            admin: admin.pubkey(),
+            bump_seed: find_config_address(&program_id).1,
+            ..config
+        }
+        .store(&mut config_data)
+        .unwrap();
+        program_test.add_account(
+            find_config_address(&program_id).0,
+            Account {
+                lamports: Rent::default().minimum_balance(Config::LEN),
+                data: config_data,
+                owner: program_id,
+                ..Account::default()
+            },
+        );
+        program_test.add_account(
+            find_treasury_address(&program_id).0,
+            Account {
+                lamports: 1_000_000_000,
+                ..Account::default()
+            },
+        );
+
+        Self {
+            program_test,
+            program_id,
+            loader,
+            admin,
+        }
+    }
+
+    /// Add a rent-exempt account owned by the program holding `data`
+    pub fn add_program_account(&mut self, data: Vec<u8>) -> Pubkey {
+        let pubkey = Pubkey::new_unique();
+        self.add_account(
+            pubkey,
+            Account {
+                lamports: Rent::default().minimum_balance(data.len()),
+                data,
+                owner: self.program_id,
+                ..Account::default()
+            },
+        );
+        pubkey
+    }
+
+    pub fn add_account(&mut self, pubkey: Pubkey, account: Account) {
+        self.program_test.add_account(pubkey, account);
+    }
+
+    pub async fn start(self) -> Harness {
+        Harness {
+            context: self.program_test.start_with_context().await,
+            program_id: self.program_id,
+            loader: self.loader,
+            admin: self.admin,
+        }
+    }
+}
+
+/// A running deployment of the program under test
+pub struct Harness {
+    pub context: ProgramTestContext,
+    pub program_id: Pubkey,
+    pub loader: Loader,
+    pub admin: Keypair,
+}
+
+impl Harness {
+    /// Process `instructions` in a transaction paid for by the context payer
+    /// and signed by `signers`
+    pub async fn process(
+        &mut self,
+        instructions: &[Instruction],
+        signers: &[&Keypair],
+    ) -> Result<(), String> {
+        let recent_blockhash = self
+            .context
+            .banks_client
+            .get_new_latest_blockhash(&self.context.last_blockhash)
+            .await
+            .map_err(|err| err.to_string())?;
+        self.context.last_blockhash = recent_blockhash;
+
+        let mut transaction =
+            Transaction::new_with_payer(instructions, Some(&self.context.payer.pubkey()));
+        let mut all_signers = vec![&self.context.payer];
+        all_signers.extend_from_slice(signers);
+        transaction
+            .try_sign(&all_signers, recent_blockhash)
+            .map_err(|err| err.to_string())?;
+        self.context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .map_err(|err| err.to_string())
+    }
+
+    /// Process `instructions` signed by the admin
+    pub async fn process_as_admin(&mut self, instructions: &[Instruction]) -> Result<(), String> {
+        let admin = Keypair::from_bytes(&self.admin.to_bytes()).unwrap();
+        self.process(instructions, &[&admin]).await
+    }
+
+    pub async fn account(&mut self, pubkey: Pubkey) -> Option<Account> {
+        self.context.banks_client.get_account(pubkey).await.unwrap()
+    }
+
+    /// Load the state stored in `pubkey`
+    pub async fn state<T: AccountState>(&mut self, pubkey: Pubkey) -> Result<T, String> {
+        let account = self
+            .account(pubkey)
+            .await
+            .ok_or_else(|| format!("{} does not exist", pubkey))?;
+        T::load(&account.data).map_err(|err| format!("{} does not load: {}", pubkey, err))
+    }
+
+    pub async fn clock(&mut self) -> Clock {
+        self.context.banks_client.get_sysvar().await.unwrap()
+    }
+}
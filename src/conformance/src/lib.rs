@@ -0,0 +1,83 @@
+//! Conformance suite for the hello world program
+//!
+//! Forks and reimplementations of the program can check they behave like the
+//! reference by running every scenario against their build:
+//!
+//! ```no_run
+//! # async fn check() {
+//! helloworld_conformance::run_all("dist/program/helloworld.so")
+//!     .await
+//!     .unwrap();
+//! # }
+//! ```
+//!
+//! Each scenario runs once per loader in [`Loader::ALL`], deploying the
+//! program as on a cluster, and the program has to use the reference account
+//! layouts and instructions.
+
+pub mod fixtures;
+mod harness;
+mod scenarios;
+
+pub use harness::Loader;
+use std::{fmt, path::Path};
+
+/// A scenario the program under test does not pass
+#[derive(Debug)]
+pub struct Failure {
+    /// Name of the scenario
+    pub scenario: &'static str,
+    /// Loader the program was deployed with, if it was
+    pub loader: Option<Loader>,
+    /// First unmet expectation
+    pub reason: String,
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.loader {
+            Some(loader) => write!(f, "{} ({}): {}", self.scenario, loader, self.reason),
+            None => write!(f, "{}: {}", self.scenario, self.reason),
+        }
+    }
+}
+
+/// Run every scenario with every loader against the BPF build at
+/// `program_artifact_path`, returning the failed ones
+pub async fn run_all(program_artifact_path: impl AsRef<Path>) -> Result<(), Vec<Failure>> {
+    let path = program_artifact_path.as_ref();
+    let elf = std::fs::read(path).map_err(|err| {
+        vec![Failure {
+            scenario: "load",
+            loader: None,
+            reason: format!("cannot read {}: {}", path.display(), err),
+        }]
+    })?;
+
+    let mut failures = vec![];
+    for loader in Loader::ALL {
+        let results = vec![
+            ("greet", scenarios::greet(&elf, loader).await),
+            ("create", scenarios::create(&elf, loader).await),
+            ("upgrade", scenarios::upgrade(&elf, loader).await),
+            ("migrate", scenarios::migrate_layouts(&elf, loader).await),
+            ("close", scenarios::close(&elf, loader).await),
+            (
+                "access_control",
+                scenarios::access_control(&elf, loader).await,
+            ),
+        ];
+        failures.extend(results.into_iter().filter_map(|(scenario, result)| {
+            result.err().map(|reason| Failure {
+                scenario,
+                loader: Some(loader),
+                reason,
+            })
+        }));
+    }
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        Err(failures)
+    }
+}
@@ -0,0 +1,387 @@
+//! Behaviors every implementation of the program has to share
+//!
+//! BanksClient does not expose program logs, so every effect is asserted on
+//! the resulting account state.
+
+use crate::harness::{check, Loader, ScenarioResult, Setup};
+use helloworld::{
+    backup::GreetingDelta,
+    fees::FeePolicy,
+    instruction::{
+        apply_delta, batch_greet, export_delta, migrate, reap, say_hello, set_allowlist_root,
+        set_fee_policy, set_greeting_expiry, set_rate_limit, update_config, ConfigUpdate,
+    },
+    state::{
+        find_greeting_address, find_treasury_address, AccountState, Config, GreetingAccountV1,
+        GreetingAccountV2, GreetingAccountV3,
+    },
+    GreetingAccount,
+};
+use helloworld_test_utils::{buffer_account, warp_slots};
+use solana_sdk::{
+    bpf_loader_upgradeable,
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
+
+fn zeroed_greeting_account() -> Vec<u8> {
+    vec![0; GreetingAccount::LEN]
+}
+
+/// Greetings count up and record who greeted when, unless paused
+pub async fn greet(elf: &[u8], loader: Loader) -> ScenarioResult {
+    let mut setup = Setup::new(elf, loader, Config::default());
+    let greeted: Vec<Pubkey> = (0..3)
+        .map(|_| setup.add_program_account(zeroed_greeting_account()))
+        .collect();
+    let mut harness = setup.start().await;
+    let program_id = harness.program_id;
+    let greeter = Keypair::new();
+
+    let ix = say_hello(&program_id, &greeted[0], &greeter.pubkey(), vec![]);
+    harness.process(&[ix], &[&greeter]).await?;
+    let greeting: GreetingAccount = harness.state(greeted[0]).await?;
+    let slot = harness.clock().await.slot;
+    check!(
+        greeting.counter == 1,
+        "SayHello counted {}",
+        greeting.counter
+    );
+    check!(
+        greeting.last_greeter == greeter.pubkey(),
+        "SayHello recorded greeter {}",
+        greeting.last_greeter
+    );
+    check!(
+        greeting.last_slot <= slot && greeting.last_slot > 0,
+        "SayHello recorded slot {}",
+        greeting.last_slot
+    );
+
+    let ix = batch_greet(&program_id, &greeted, &greeter.pubkey(), vec![]);
+    harness.process(&[ix], &[&greeter]).await?;
+    for (pubkey, expected) in greeted.iter().zip([2, 1, 1]) {
+        let greeting: GreetingAccount = harness.state(*pubkey).await?;
+        check!(
+            greeting.counter == expected,
+            "BatchGreet left {} at {} instead of {}",
+            pubkey,
+            greeting.counter,
+            expected
+        );
+    }
+
+    let pause = |paused| {
+        update_config(
+            &program_id,
+            &harness.admin.pubkey(),
+            ConfigUpdate {
+                paused: Some(paused),
+                ..ConfigUpdate::default()
+            },
+        )
+    };
+    let (pause_ix, resume_ix) = (pause(true), pause(false));
+    harness.process_as_admin(&[pause_ix]).await?;
+    let ix = say_hello(&program_id, &greeted[0], &greeter.pubkey(), vec![]);
+    check!(
+        harness
+            .process(std::slice::from_ref(&ix), &[&greeter])
+            .await
+            .is_err(),
+        "SayHello succeeded while paused"
+    );
+    harness.process_as_admin(&[resume_ix]).await?;
+    harness.process(&[ix], &[&greeter]).await?;
+
+    Ok(())
+}
+
+/// Greeting your own greeting address creates it with its canonical bump
+pub async fn create(elf: &[u8], loader: Loader) -> ScenarioResult {
+    let mut harness = Setup::new(elf, loader, Config::default()).start().await;
+    let program_id = harness.program_id;
+    let user = Keypair::new();
+    let other = Keypair::new();
+    let ix = solana_sdk::system_instruction::transfer(
+        &harness.context.payer.pubkey(),
+        &user.pubkey(),
+        1_000_000_000,
+    );
+    harness.process(&[ix], &[]).await?;
+    let (greeting_address, bump_seed) = find_greeting_address(&program_id, &user.pubkey());
+
+    let ix = say_hello(&program_id, &greeting_address, &other.pubkey(), vec![]);
+    check!(
+        harness.process(&[ix], &[&other]).await.is_err(),
+        "Created the greeting address of someone else"
+    );
+
+    let ix = say_hello(&program_id, &greeting_address, &user.pubkey(), vec![]);
+    harness.process(&[ix], &[&user]).await?;
+    let account = harness
+        .account(greeting_address)
+        .await
+        .ok_or("Greeting address was not created")?;
+    check!(
+        account.owner == program_id,
+        "Greeting address is owned by {}",
+        account.owner
+    );
+    let greeting: GreetingAccount = harness.state(greeting_address).await?;
+    check!(
+        greeting.counter == 1 && greeting.bump_seed == bump_seed,
+        "Created greeting address holds {:?}",
+        greeting
+    );
+
+    Ok(())
+}
+
+/// Accounts keep working across an upgrade of the program, while programs of
+/// the non-upgradeable loader refuse upgrades and keep running
+pub async fn upgrade(elf: &[u8], loader: Loader) -> ScenarioResult {
+    let mut setup = Setup::new(elf, loader, Config::default());
+    let greeted = setup.add_program_account(zeroed_greeting_account());
+    let buffer = Pubkey::new_unique();
+    setup.add_account(buffer, buffer_account(elf, setup.admin.pubkey()));
+    let mut harness = setup.start().await;
+    let program_id = harness.program_id;
+    let greeter = harness.context.payer.pubkey();
+
+    let ix = say_hello(&program_id, &greeted, &greeter, vec![]);
+    harness.process(&[ix], &[]).await?;
+
+    let ix = bpf_loader_upgradeable::upgrade(
+        &program_id,
+        &buffer,
+        &harness.admin.pubkey(),
+        &harness.admin.pubkey(),
+    );
+    match harness.loader {
+        Loader::BpfLoader => check!(
+            harness.process_as_admin(&[ix]).await.is_err(),
+            "Upgraded a program of the non-upgradeable loader"
+        ),
+        Loader::Upgradeable => {
+            harness.process_as_admin(&[ix]).await?;
+            // Upgraded programs can only run from the next slot on
+            warp_slots(&mut harness.context, 2)
+                .await
+                .map_err(|err| err.to_string())?;
+        }
+    }
+
+    let ix = say_hello(&program_id, &greeted, &greeter, vec![]);
+    harness.process(&[ix], &[]).await?;
+    let greeting: GreetingAccount = harness.state(greeted).await?;
+    check!(
+        greeting.counter == 2,
+        "Program counted {} after the upgrade",
+        greeting.counter
+    );
+
+    Ok(())
+}
+
+/// Accounts using older layouts migrate in place, keeping their state
+pub async fn migrate_layouts(elf: &[u8], loader: Loader) -> ScenarioResult {
+    let greeter = Pubkey::new_unique();
+    let v1 = GreetingAccountV1 {
+        counter: 7,
+        expires_at: 100,
+        last_greeter: greeter,
+        last_slot: 42,
+        ..GreetingAccountV1::default()
+    };
+    let v2 = GreetingAccountV2 {
+        version: 2,
+        counter: 3,
+        last_greeter: greeter,
+        ..GreetingAccountV2::default()
+    };
+    let v3 = GreetingAccountV3 {
+        version: 3,
+        counter: 5,
+        last_greeter: greeter,
+        bump_seed: 255,
+        ..GreetingAccountV3::default()
+    };
+
+    let mut setup = Setup::new(elf, loader, Config::default());
+    let mut data = vec![0; GreetingAccountV1::LEN];
+    v1.store(&mut data).unwrap();
+    let v1_pubkey = setup.add_program_account(data);
+    let mut data = vec![0; GreetingAccountV2::LEN];
+    v2.store(&mut data).unwrap();
+    let v2_pubkey = setup.add_program_account(data);
+    let mut data = vec![0; GreetingAccountV3::LEN];
+    v3.store(&mut data).unwrap();
+    let v3_pubkey = setup.add_program_account(data);
+    let current = setup.add_program_account(zeroed_greeting_account());
+    let mut harness = setup.start().await;
+    let program_id = harness.program_id;
+    let payer = harness.context.payer.pubkey();
+
+    for (pubkey, migrated) in [
+        (v1_pubkey, GreetingAccount::from(v1)),
+        (v2_pubkey, GreetingAccount::from(v2)),
+        (v3_pubkey, GreetingAccount::from(v3)),
+    ] {
+        let ix = say_hello(&program_id, &pubkey, &payer, vec![]);
+        check!(
+            harness.process(&[ix], &[]).await.is_err(),
+            "Greeted {} before migrating it",
+            pubkey
+        );
+        harness
+            .process(&[migrate(&program_id, &pubkey, &payer)], &[])
+            .await?;
+        let greeting: GreetingAccount = harness.state(pubkey).await?;
+        check!(
+            greeting == migrated,
+            "Migrated {} into {:?}",
+            pubkey,
+            greeting
+        );
+    }
+
+    // Migrating the current layout changes nothing
+    let ix = say_hello(&program_id, &current, &payer, vec![]);
+    harness.process(&[ix], &[]).await?;
+    let before = harness.account(current).await;
+    harness
+        .process(&[migrate(&program_id, &current, &payer)], &[])
+        .await?;
+    check!(
+        harness.account(current).await == before,
+        "Migrating the current layout changed the account"
+    );
+
+    Ok(())
+}
+
+/// Expired greetings are closed into the treasury, live ones are kept
+pub async fn close(elf: &[u8], loader: Loader) -> ScenarioResult {
+    const GREETING_TTL: i64 = 60;
+
+    let mut setup = Setup::new(
+        elf,
+        loader,
+        Config {
+            greeting_ttl: GREETING_TTL,
+            ..Config::default()
+        },
+    );
+    let greeted = setup.add_program_account(zeroed_greeting_account());
+    let mut harness = setup.start().await;
+    let program_id = harness.program_id;
+    let treasury = find_treasury_address(&program_id).0;
+    let payer = harness.context.payer.pubkey();
+
+    harness
+        .process(&[say_hello(&program_id, &greeted, &payer, vec![])], &[])
+        .await?;
+    let greeting: GreetingAccount = harness.state(greeted).await?;
+    let ix = reap(&program_id, &greeted);
+    check!(
+        harness
+            .process(std::slice::from_ref(&ix), &[])
+            .await
+            .is_err(),
+        "Reaped a live greeting"
+    );
+
+    let mut clock = harness.clock().await;
+    clock.unix_timestamp = greeting.expires_at;
+    harness.context.set_sysvar(&clock);
+    let greeted_lamports = harness.account(greeted).await.unwrap().lamports;
+    let treasury_lamports = harness.account(treasury).await.unwrap().lamports;
+    harness.process(&[ix], &[]).await?;
+    check!(
+        harness.account(greeted).await.is_none(),
+        "Reaped greeting account still exists"
+    );
+    let collected = harness.account(treasury).await.unwrap().lamports - treasury_lamports;
+    check!(
+        collected == greeted_lamports,
+        "Treasury collected {} lamports out of {}",
+        collected,
+        greeted_lamports
+    );
+
+    Ok(())
+}
+
+/// Admin instructions succeed for the admin only, and only when signed
+pub async fn access_control(elf: &[u8], loader: Loader) -> ScenarioResult {
+    let mut setup = Setup::new(elf, loader, Config::default());
+    let greeted = setup.add_program_account(zeroed_greeting_account());
+    let backup = setup.add_program_account(vec![0; GreetingDelta::LEN]);
+    let mut harness = setup.start().await;
+    let program_id = harness.program_id;
+
+    type Builder<'a> = Box<dyn Fn(&Pubkey) -> Instruction + 'a>;
+    let matrix: Vec<(&str, Builder)> = vec![
+        (
+            "SetAllowlistRoot",
+            Box::new(|admin| set_allowlist_root(&program_id, admin, None)),
+        ),
+        (
+            "SetRateLimit",
+            Box::new(|admin| set_rate_limit(&program_id, admin, 0)),
+        ),
+        (
+            "SetGreetingExpiry",
+            Box::new(|admin| set_greeting_expiry(&program_id, admin, 0)),
+        ),
+        (
+            "SetFeePolicy",
+            Box::new(|admin| set_fee_policy(&program_id, admin, FeePolicy::Free)),
+        ),
+        (
+            "UpdateConfig",
+            Box::new(|admin| update_config(&program_id, admin, ConfigUpdate::default())),
+        ),
+        (
+            "ExportDelta",
+            Box::new(|admin| export_delta(&program_id, &greeted, &backup, admin, 0)),
+        ),
+        (
+            "ApplyDelta",
+            Box::new(|admin| apply_delta(&program_id, &greeted, admin, GreetingDelta::default())),
+        ),
+    ];
+
+    let intruder = Keypair::new();
+    let admin = harness.admin.pubkey();
+    for (name, builder) in &matrix {
+        check!(
+            harness
+                .process(&[builder(&intruder.pubkey())], &[&intruder])
+                .await
+                .is_err(),
+            "{} succeeded for a non-admin",
+            name
+        );
+
+        let mut unsigned = builder(&admin);
+        for account in &mut unsigned.accounts {
+            if account.pubkey == admin {
+                account.is_signer = false;
+            }
+        }
+        check!(
+            harness.process(&[unsigned], &[]).await.is_err(),
+            "{} succeeded without the admin signature",
+            name
+        );
+
+        if let Err(err) = harness.process_as_admin(&[builder(&admin)]).await {
+            return Err(format!("{} failed for the admin: {}", name, err));
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,19 @@
+//! Program variant 0 for the hello world upgrade tests, logging 0 on
+//! every instruction
+//!
+//! Generated by `cargo run --example generate_fixtures`, do not edit.
+
+use solana_program::{
+    account_info::AccountInfo, entrypoint, entrypoint::ProgramResult, msg, pubkey::Pubkey,
+};
+
+entrypoint!(process_instruction);
+
+fn process_instruction(
+    _program_id: &Pubkey,
+    _accounts: &[AccountInfo],
+    _instruction_data: &[u8],
+) -> ProgramResult {
+    msg!("0");
+    Ok(())
+}
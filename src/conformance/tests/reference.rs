@@ -0,0 +1,15 @@
+// Runs against the BPF build of the reference program made by `build.rs`, or
+// by `npm run build:program-rust` without the BPF toolchain
+#![cfg(feature = "test-bpf")]
+
+use solana_program_test::*;
+
+#[tokio::test]
+async fn test_reference_conformance() {
+    if let Err(failures) = helloworld_conformance::run_all(env!("HELLOWORLD_PROGRAM")).await {
+        for failure in &failures {
+            println!("{}", failure);
+        }
+        panic!("{} scenario(s) failed", failures.len());
+    }
+}
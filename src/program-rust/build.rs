@@ -0,0 +1,21 @@
+//! Record how the program is built, embedded by `security::BUILD_INFO`
+
+use std::{env, process::Command};
+
+fn main() {
+    // Always define the commit, so it can be embedded with `env!`
+    println!("cargo:rerun-if-env-changed=HELLOWORLD_COMMIT");
+    println!(
+        "cargo:rustc-env=HELLOWORLD_COMMIT={}",
+        env::var("HELLOWORLD_COMMIT").unwrap_or_default()
+    );
+
+    let rustc = env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+    let toolchain = Command::new(rustc)
+        .arg("--version")
+        .output()
+        .ok()
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .unwrap_or_default();
+    println!("cargo:rustc-env=HELLOWORLD_TOOLCHAIN={}", toolchain.trim());
+}
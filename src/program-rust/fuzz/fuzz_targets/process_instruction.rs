@@ -0,0 +1,175 @@
+//! Feed arbitrary instruction data and accounts into the native processor,
+//! which must fail cleanly rather than panic, whatever it is given
+//!
+//! Run from `src/program-rust` with `cargo +nightly fuzz run process_instruction`
+#![no_main]
+
+use helloworld::{
+    mirror::find_mirror_address,
+    process_instruction,
+    state::{
+        find_config_address, find_denylist_address, find_greeter_address, find_greeting_address,
+        find_history_address, find_leaderboard_address, find_metadata_address,
+        find_multisig_address, find_proposal_address, find_registry_address,
+        find_timelock_address, find_treasury_address,
+    },
+};
+use libfuzzer_sys::fuzz_target;
+use solana_program::{
+    account_info::AccountInfo,
+    clock::Clock,
+    entrypoint::SUCCESS,
+    program_stubs::{set_syscall_stubs, SyscallStubs},
+    pubkey::Pubkey,
+    rent::Rent,
+    secp256k1_program, system_program, sysvar,
+};
+use std::sync::OnceLock;
+
+/// Most accounts passed to a single instruction
+const MAX_ACCOUNTS: usize = 16;
+/// Largest account data, enough for every state account
+const MAX_DATA_LEN: usize = 4096;
+/// Users the synthesized accounts can belong to
+const USERS: u8 = 4;
+
+/// Serve the clock and rent sysvars outside of the runtime, CPIs keep the
+/// default stubs and do nothing
+struct SysvarStubs;
+
+impl SyscallStubs for SysvarStubs {
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        unsafe { *(var_addr as *mut Clock) = Clock::default() };
+        SUCCESS
+    }
+
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        unsafe { *(var_addr as *mut Rent) = Rent::default() };
+        SUCCESS
+    }
+}
+
+/// Keys the processor looks for, so the fuzzer gets past address checks,
+/// along with keys it knows nothing about
+fn key_pool() -> &'static [Pubkey] {
+    static POOL: OnceLock<Vec<Pubkey>> = OnceLock::new();
+    POOL.get_or_init(|| {
+        set_syscall_stubs(Box::new(SysvarStubs));
+
+        let program_id = helloworld::id();
+        let mut pool = vec![
+            program_id,
+            system_program::id(),
+            sysvar::instructions::id(),
+            secp256k1_program::id(),
+            find_config_address(&program_id).0,
+            find_treasury_address(&program_id).0,
+            find_registry_address(&program_id).0,
+            find_history_address(&program_id).0,
+            find_leaderboard_address(&program_id).0,
+            find_metadata_address(&program_id).0,
+            find_denylist_address(&program_id).0,
+            find_multisig_address(&program_id).0,
+            find_timelock_address(&program_id).0,
+            find_proposal_address(&program_id, 0).0,
+        ];
+        for user in 0..USERS {
+            let user = Pubkey::new_from_array([user + 1; 32]);
+            let greeting = find_greeting_address(&program_id, &user).0;
+            pool.extend([
+                user,
+                greeting,
+                find_greeter_address(&program_id, &user).0,
+                find_mirror_address(&program_id, &greeting).0,
+            ]);
+        }
+        pool
+    })
+}
+
+/// Reads the fuzzer input front to back, yielding zeroes once it runs out
+struct Input<'a>(&'a [u8]);
+
+impl<'a> Input<'a> {
+    fn bytes(&mut self, len: usize) -> &'a [u8] {
+        let (bytes, rest) = self.0.split_at(len.min(self.0.len()));
+        self.0 = rest;
+        bytes
+    }
+
+    fn u8(&mut self) -> u8 {
+        self.bytes(1).first().copied().unwrap_or_default()
+    }
+
+    fn u16(&mut self) -> u16 {
+        u16::from_le_bytes([self.u8(), self.u8()])
+    }
+
+    fn u64(&mut self) -> u64 {
+        let mut bytes = [0; 8];
+        bytes.iter_mut().for_each(|byte| *byte = self.u8());
+        u64::from_le_bytes(bytes)
+    }
+
+    /// A key from the pool, or a key outside of it
+    fn key(&mut self, pool: &[Pubkey]) -> Pubkey {
+        let choice = self.u8() as usize;
+        pool.get(choice)
+            .copied()
+            .unwrap_or_else(|| Pubkey::new_from_array([choice as u8; 32]))
+    }
+}
+
+/// Account synthesized from the fuzzer input
+struct Account {
+    key: Pubkey,
+    is_signer: bool,
+    is_writable: bool,
+    lamports: u64,
+    data: Vec<u8>,
+    owner: Pubkey,
+    executable: bool,
+}
+
+fuzz_target!(|data: &[u8]| {
+    let pool = key_pool();
+    let mut input = Input(data);
+
+    let mut accounts: Vec<Account> = (0..input.u8() as usize % (MAX_ACCOUNTS + 1))
+        .map(|_| {
+            let flags = input.u8();
+            let key = input.key(pool);
+            let owner = input.key(pool);
+            let lamports = input.u64();
+            let data_len = input.u16() as usize % (MAX_DATA_LEN + 1);
+            let data = input.bytes(data_len).to_vec();
+            Account {
+                key,
+                is_signer: flags & 1 != 0,
+                is_writable: flags & 2 != 0,
+                lamports,
+                data,
+                owner,
+                executable: flags & 4 != 0,
+            }
+        })
+        .collect();
+    let instruction_data = input.0;
+
+    let account_infos: Vec<AccountInfo> = accounts
+        .iter_mut()
+        .map(|account| {
+            AccountInfo::new(
+                &account.key,
+                account.is_signer,
+                account.is_writable,
+                &mut account.lamports,
+                &mut account.data,
+                &account.owner,
+                account.executable,
+                0,
+            )
+        })
+        .collect();
+    let _ = process_instruction(&helloworld::id(), &account_infos, instruction_data);
+});
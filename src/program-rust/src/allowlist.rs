@@ -0,0 +1,84 @@
+//! Merkle allowlist of greeter keys
+//!
+//! Leaves are `hash(0x00 || key)` and inner nodes `hash(0x01 || left || right)`
+//! with the two children sorted, so a proof is just the list of sibling hashes
+//! from the leaf up to the root.
+
+use solana_program::hash::hashv;
+
+const LEAF_PREFIX: &[u8] = &[0];
+const NODE_PREFIX: &[u8] = &[1];
+
+/// Hash of an allowlisted key, either a Solana pubkey or an Ethereum address
+pub fn leaf(key: &[u8]) -> [u8; 32] {
+    hashv(&[LEAF_PREFIX, key]).to_bytes()
+}
+
+fn node(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+    let (left, right) = if a <= b { (a, b) } else { (b, a) };
+    hashv(&[NODE_PREFIX, left, right]).to_bytes()
+}
+
+/// Whether `proof` proves `key` is in the allowlist with the given `root`
+pub fn verify(root: &[u8; 32], key: &[u8], proof: &[[u8; 32]]) -> bool {
+    let computed_root = proof
+        .iter()
+        .fold(leaf(key), |hash, sibling| node(&hash, sibling));
+    computed_root == *root
+}
+
+/// Root of the allowlist containing `keys`
+pub fn root(keys: &[&[u8]]) -> [u8; 32] {
+    let mut level: Vec<[u8; 32]> = keys.iter().map(|key| leaf(key)).collect();
+    if level.is_empty() {
+        return [0; 32];
+    }
+    while level.len() > 1 {
+        level = next_level(&level);
+    }
+    level[0]
+}
+
+/// Proof that `keys[index]` is in the allowlist containing `keys`
+pub fn proof(keys: &[&[u8]], mut index: usize) -> Vec<[u8; 32]> {
+    let mut level: Vec<[u8; 32]> = keys.iter().map(|key| leaf(key)).collect();
+    let mut proof = vec![];
+    while level.len() > 1 {
+        // An unpaired last node is promoted without a sibling
+        if let Some(sibling) = level.get(index ^ 1) {
+            proof.push(*sibling);
+        }
+        level = next_level(&level);
+        index /= 2;
+    }
+    proof
+}
+
+fn next_level(level: &[[u8; 32]]) -> Vec<[u8; 32]> {
+    level
+        .chunks(2)
+        .map(|pair| match pair {
+            [a, b] => node(a, b),
+            [a] => *a,
+            _ => unreachable!(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_proofs() {
+        for size in 1..=9u8 {
+            let keys: Vec<[u8; 32]> = (0..size).map(|i| [i; 32]).collect();
+            let keys: Vec<&[u8]> = keys.iter().map(|key| &key[..]).collect();
+            let root = root(&keys);
+            for (index, key) in keys.iter().enumerate() {
+                assert!(verify(&root, key, &proof(&keys, index)));
+            }
+            assert!(!verify(&root, &[size; 32], &proof(&keys, 0)));
+        }
+    }
+}
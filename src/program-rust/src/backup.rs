@@ -0,0 +1,197 @@
+//! Differential greeting account backups
+//!
+//! Greetings are the only writes a backup cares about, so the `counter` of a
+//! greeting account doubles as its version. A `GreetingDelta` carries the
+//! fields changed between two versions, and restoring a greeting account
+//! means applying deltas in order, starting from a full backup since version
+//! 0.
+//!
+//! Mirrors and greeting addresses only exist within one deployment, so
+//! `has_mirror` and `bump_seed` are never backed up.
+
+use crate::state::{AccountState, GreetingAccount, DISCRIMINATOR_LEN};
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{msg, program_error::ProgramError, pubkey::Pubkey};
+
+/// Greeting account fields changed since a version
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Default, PartialEq)]
+pub struct GreetingDelta {
+    /// Version the delta applies on top of, 0 for a full backup
+    pub since_version: u32,
+    /// Version reached once the delta is applied
+    pub version: u32,
+    /// New `expires_at`, if changed
+    pub expires_at: Option<i64>,
+    /// New `last_greeter`, if changed
+    pub last_greeter: Option<Pubkey>,
+    /// New `last_slot`, if changed
+    pub last_slot: Option<u64>,
+}
+
+impl GreetingDelta {
+    /// Space needed by a backup account holding the largest delta
+    pub const LEN: usize = DISCRIMINATOR_LEN + 4 + 4 + (1 + 8) + (1 + 32) + (1 + 8);
+
+    /// Delta exported on chain, where only the current state is known: every
+    /// field a greeting may have changed, unless none happened since
+    /// `since_version`
+    pub fn since(
+        greeting_account: &GreetingAccount,
+        since_version: u32,
+    ) -> Result<Self, ProgramError> {
+        if since_version > greeting_account.counter {
+            msg!(
+                "Greeting account is only at version {}",
+                greeting_account.counter
+            );
+            return Err(ProgramError::InvalidArgument);
+        }
+        if since_version == greeting_account.counter {
+            return Ok(Self {
+                since_version,
+                version: since_version,
+                ..Self::default()
+            });
+        }
+        Ok(Self {
+            since_version,
+            version: greeting_account.counter,
+            expires_at: Some(greeting_account.expires_at),
+            last_greeter: Some(greeting_account.last_greeter),
+            last_slot: Some(greeting_account.last_slot),
+        })
+    }
+
+    /// Delta holding only the fields differing between two snapshots of a
+    /// greeting account, `None` if `base` is newer than `current`
+    pub fn encode(base: &GreetingAccount, current: &GreetingAccount) -> Option<Self> {
+        if base.counter > current.counter {
+            return None;
+        }
+        fn changed<T: Copy + PartialEq>(base: T, current: T) -> Option<T> {
+            if base == current {
+                None
+            } else {
+                Some(current)
+            }
+        }
+        Some(Self {
+            since_version: base.counter,
+            version: current.counter,
+            expires_at: changed(base.expires_at, current.expires_at),
+            last_greeter: changed(base.last_greeter, current.last_greeter),
+            last_slot: changed(base.last_slot, current.last_slot),
+        })
+    }
+
+    /// Apply the delta to a greeting account at version `since_version`
+    pub fn apply(&self, greeting_account: &mut GreetingAccount) -> Result<(), ProgramError> {
+        if greeting_account.counter != self.since_version {
+            msg!(
+                "Delta applies to version {}, not {}",
+                self.since_version,
+                greeting_account.counter
+            );
+            return Err(ProgramError::InvalidArgument);
+        }
+        if self.version < self.since_version {
+            msg!("Delta goes back in versions");
+            return Err(ProgramError::InvalidArgument);
+        }
+        greeting_account.counter = self.version;
+        if let Some(expires_at) = self.expires_at {
+            greeting_account.expires_at = expires_at;
+        }
+        if let Some(last_greeter) = self.last_greeter {
+            greeting_account.last_greeter = last_greeter;
+        }
+        if let Some(last_slot) = self.last_slot {
+            greeting_account.last_slot = last_slot;
+        }
+        Ok(())
+    }
+}
+
+impl AccountState for GreetingDelta {
+    const DISCRIMINATOR: [u8; DISCRIMINATOR_LEN] = *b"delta\0\0\0";
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn greeting_account(counter: u32, last_slot: u64) -> GreetingAccount {
+        GreetingAccount {
+            version: GreetingAccount::VERSION,
+            counter,
+            expires_at: 100,
+            last_greeter: Pubkey::new_from_array([9; 32]),
+            last_slot,
+            ..GreetingAccount::default()
+        }
+    }
+
+    #[test]
+    fn test_encode() {
+        let base = greeting_account(2, 10);
+        let current = greeting_account(5, 20);
+
+        // Only the changed fields are carried
+        let delta = GreetingDelta::encode(&base, &current).unwrap();
+        assert_eq!(
+            delta,
+            GreetingDelta {
+                since_version: 2,
+                version: 5,
+                last_slot: Some(20),
+                ..GreetingDelta::default()
+            }
+        );
+        let mut restored = base.clone();
+        delta.apply(&mut restored).unwrap();
+        assert_eq!(restored, current);
+
+        // Deltas cannot be encoded backwards, nor applied out of order
+        assert_eq!(GreetingDelta::encode(&current, &base), None);
+        assert!(delta.apply(&mut restored).is_err());
+    }
+
+    #[test]
+    fn test_since() {
+        let current = greeting_account(5, 20);
+
+        let mut restored = GreetingAccount {
+            version: GreetingAccount::VERSION,
+            ..GreetingAccount::default()
+        };
+        GreetingDelta::since(&current, 0)
+            .unwrap()
+            .apply(&mut restored)
+            .unwrap();
+        assert_eq!(restored, current);
+
+        let delta = GreetingDelta::since(&current, 5).unwrap();
+        assert_eq!(delta.expires_at, None);
+        delta.apply(&mut restored).unwrap();
+        assert_eq!(restored, current);
+
+        assert!(GreetingDelta::since(&current, 6).is_err());
+    }
+
+    #[test]
+    fn test_len() {
+        let delta = GreetingDelta {
+            expires_at: Some(0),
+            last_greeter: Some(Pubkey::default()),
+            last_slot: Some(0),
+            ..GreetingDelta::default()
+        };
+        let mut data = [0; GreetingDelta::LEN];
+        delta.store(&mut data).unwrap();
+        assert_eq!(GreetingDelta::load(&data).unwrap(), delta);
+        assert_eq!(
+            delta.try_to_vec().unwrap().len() + DISCRIMINATOR_LEN,
+            GreetingDelta::LEN
+        );
+    }
+}
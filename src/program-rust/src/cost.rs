@@ -0,0 +1,124 @@
+//! Cost model comparing one `SayHello` transaction per account against
+//! `BatchGreet` transactions, used by clients to decide when to batch
+
+/// Largest serialized transaction accepted by the cluster, matching
+/// `solana_sdk::packet::PACKET_DATA_SIZE`
+pub const MAX_TRANSACTION_SIZE: usize = 1280 - 40 - 8;
+
+const SIGNATURE_SIZE: usize = 64;
+const PUBKEY_SIZE: usize = 32;
+const BLOCKHASH_SIZE: usize = 32;
+const MESSAGE_HEADER_SIZE: usize = 3;
+/// `BatchGreet` instruction data with an empty allowlist proof
+const INSTRUCTION_DATA_SIZE: usize = 1 + 4;
+
+/// Fees and size limits for greeting transactions signed by the fee payer only
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CostModel {
+    /// Lamports charged for every transaction signature
+    pub lamports_per_signature: u64,
+}
+
+impl CostModel {
+    pub fn new(lamports_per_signature: u64) -> Self {
+        Self {
+            lamports_per_signature,
+        }
+    }
+
+    /// Serialized size of a transaction carrying a single greeting instruction
+    /// for `greetings` accounts, greeted by the fee payer
+    pub fn transaction_size(greetings: usize) -> usize {
+        // fee payer, config, greeter account, treasury, system program,
+        // instructions sysvar and program id
+        let num_keys = greetings + 7;
+        // greeter, config, greeter account, treasury, system program and
+        // instructions sysvar
+        let num_instruction_accounts = greetings + 6;
+        short_vec_len(1)
+            + SIGNATURE_SIZE
+            + MESSAGE_HEADER_SIZE
+            + short_vec_len(num_keys)
+            + num_keys * PUBKEY_SIZE
+            + BLOCKHASH_SIZE
+            + short_vec_len(1)
+            + 1 // program id index
+            + short_vec_len(num_instruction_accounts)
+            + num_instruction_accounts
+            + short_vec_len(INSTRUCTION_DATA_SIZE)
+            + INSTRUCTION_DATA_SIZE
+    }
+
+    /// Most accounts a single `BatchGreet` transaction can greet
+    pub fn max_batch_size() -> usize {
+        let mut greetings = 1;
+        while Self::transaction_size(greetings + 1) <= MAX_TRANSACTION_SIZE {
+            greetings += 1;
+        }
+        greetings
+    }
+
+    /// Number of accounts greeted by each transaction when greeting
+    /// `greetings` accounts with as few `BatchGreet` transactions as possible
+    pub fn batches(greetings: usize) -> Vec<usize> {
+        let max_batch_size = Self::max_batch_size();
+        let mut batches = vec![max_batch_size; greetings / max_batch_size];
+        let remainder = greetings % max_batch_size;
+        if remainder > 0 {
+            batches.push(remainder);
+        }
+        batches
+    }
+
+    /// Fee for greeting `greetings` accounts with one `SayHello` transaction each
+    pub fn single_fee(&self, greetings: usize) -> u64 {
+        self.lamports_per_signature * greetings as u64
+    }
+
+    /// Fee for greeting `greetings` accounts with `BatchGreet` transactions
+    pub fn batched_fee(&self, greetings: usize) -> u64 {
+        self.lamports_per_signature * Self::batches(greetings).len() as u64
+    }
+
+    /// Whether batching `greetings` accounts is cheaper than greeting them one
+    /// transaction at a time
+    pub fn should_batch(&self, greetings: usize) -> bool {
+        self.batched_fee(greetings) < self.single_fee(greetings)
+    }
+}
+
+fn short_vec_len(len: usize) -> usize {
+    match len {
+        0..=0x7f => 1,
+        0x80..=0x3fff => 2,
+        _ => 3,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_batches() {
+        let max_batch_size = CostModel::max_batch_size();
+        assert!(CostModel::transaction_size(max_batch_size) <= MAX_TRANSACTION_SIZE);
+        assert!(CostModel::transaction_size(max_batch_size + 1) > MAX_TRANSACTION_SIZE);
+
+        assert!(CostModel::batches(0).is_empty());
+        assert_eq!(CostModel::batches(1), vec![1]);
+        assert_eq!(
+            CostModel::batches(max_batch_size + 1),
+            vec![max_batch_size, 1]
+        );
+    }
+
+    #[test]
+    fn test_should_batch() {
+        let cost_model = CostModel::new(5000);
+        assert!(!cost_model.should_batch(1));
+        assert!(cost_model.should_batch(2));
+        assert_eq!(cost_model.single_fee(10), 50_000);
+        assert_eq!(cost_model.batched_fee(10), 5000);
+    }
+}
@@ -0,0 +1,152 @@
+//! Errors specific to the hello world program
+
+use solana_program::program_error::ProgramError;
+use thiserror::Error;
+
+/// Errors returned by the program as `ProgramError::Custom`
+///
+/// Codes are part of the program interface, so variants keep their explicit
+/// discriminants and new ones are only ever appended, to `ALL` as well.
+#[derive(Clone, Copy, Debug, Eq, Error, PartialEq)]
+pub enum HelloWorldError {
+    /// A counter would go past its maximum
+    #[error("Counter overflow")]
+    CounterOverflow = 0,
+    /// An account being initialized does not hold enough lamports to be rent
+    /// exempt
+    #[error("Not rent exempt")]
+    NotRentExempt = 1,
+    /// A channel name is empty or longer than `MAX_CHANNEL_NAME_LEN`
+    #[error("Invalid channel name")]
+    InvalidChannelName = 2,
+    /// A user already has `MAX_CHANNELS` channels
+    #[error("Too many channels")]
+    TooManyChannels = 3,
+    /// The instruction data is not a `HelloInstruction`
+    #[error("Invalid instruction")]
+    InvalidInstruction = 4,
+    /// An account the instruction relies on has not been initialized yet
+    #[error("Account not initialized")]
+    AccountNotInitialized = 5,
+    /// The signer is not allowed to perform the instruction
+    #[error("Unauthorized")]
+    Unauthorized = 6,
+    /// The config pauses every instruction but the admin ones
+    #[error("Program is paused")]
+    ProgramPaused = 7,
+    /// The greeter greeted within the config's rate limit window
+    #[error("Rate limited")]
+    RateLimited = 8,
+    /// An account the instruction updates was passed as read-only
+    #[error("Account not writable")]
+    AccountNotWritable = 9,
+    /// A greeting account was entered again while calling out to another
+    /// program
+    #[error("Reentrant call")]
+    ReentrantCall = 10,
+    /// The greeter is on the denylist
+    #[error("Greeter is denied")]
+    GreeterDenied = 11,
+    /// The denylist already holds `MAX_DENIED` greeters
+    #[error("Denylist is full")]
+    DenylistFull = 12,
+    /// A multisig proposal has fewer approvals than the multisig threshold
+    #[error("Insufficient approvals")]
+    InsufficientApprovals = 13,
+    /// An administrative action must go through the timelock
+    #[error("Action is timelocked")]
+    Timelocked = 14,
+    /// The program was invoked through CPI while the config only allows
+    /// top-level greetings
+    #[error("Invocation through CPI is forbidden")]
+    CpiForbidden = 15,
+    /// The same account was passed in several writable positions
+    #[error("Duplicate account")]
+    DuplicateAccount = 16,
+    /// A lamport amount or slot computation does not fit its type
+    #[error("Arithmetic overflow")]
+    ArithmeticOverflow = 17,
+    /// A greeting message is longer than `MAX_MESSAGE_LEN` or the config's
+    /// limit
+    #[error("Message too long")]
+    MessageTooLong = 18,
+    /// A greeting message holds control characters
+    #[error("Invalid message")]
+    InvalidMessage = 19,
+}
+
+impl From<HelloWorldError> for ProgramError {
+    fn from(error: HelloWorldError) -> Self {
+        ProgramError::Custom(error.code())
+    }
+}
+
+impl HelloWorldError {
+    /// Every error, in code order
+    pub const ALL: [Self; 20] = [
+        Self::CounterOverflow,
+        Self::NotRentExempt,
+        Self::InvalidChannelName,
+        Self::TooManyChannels,
+        Self::InvalidInstruction,
+        Self::AccountNotInitialized,
+        Self::Unauthorized,
+        Self::ProgramPaused,
+        Self::RateLimited,
+        Self::AccountNotWritable,
+        Self::ReentrantCall,
+        Self::GreeterDenied,
+        Self::DenylistFull,
+        Self::InsufficientApprovals,
+        Self::Timelocked,
+        Self::CpiForbidden,
+        Self::DuplicateAccount,
+        Self::ArithmeticOverflow,
+        Self::MessageTooLong,
+        Self::InvalidMessage,
+    ];
+
+    /// Code of the error in `ProgramError::Custom`
+    pub fn code(self) -> u32 {
+        self as u32
+    }
+
+    /// Error with the code `code`, as found in `InstructionError::Custom`
+    pub fn from_code(code: u32) -> Option<Self> {
+        Self::ALL.get(code as usize).copied()
+    }
+}
+
+/// Code and message of every error, for clients rendering
+/// `InstructionError::Custom`
+pub fn error_codes() -> Vec<(u32, String)> {
+    HelloWorldError::ALL
+        .iter()
+        .map(|error| (error.code(), error.to_string()))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_codes() {
+        for (index, error) in HelloWorldError::ALL.iter().enumerate() {
+            assert_eq!(error.code(), index as u32);
+            assert_eq!(HelloWorldError::from_code(error.code()), Some(*error));
+            assert_eq!(
+                ProgramError::from(*error),
+                ProgramError::Custom(index as u32)
+            );
+        }
+        assert_eq!(
+            HelloWorldError::from_code(HelloWorldError::ALL.len() as u32),
+            None
+        );
+
+        let codes = error_codes();
+        assert_eq!(codes.len(), HelloWorldError::ALL.len());
+        assert_eq!(codes[6], (6, "Unauthorized".to_string()));
+    }
+}
@@ -0,0 +1,237 @@
+//! Greeting fee policies
+//!
+//! The config picks one `FeePolicy`, which prices every greeting in lamports
+//! paid by the greeter into the treasury.
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{msg, program_error::ProgramError};
+
+/// Maximum number of tiers of a `FeePolicy::TieredByCount`
+pub const MAX_FEE_TIERS: usize = 4;
+
+/// How greetings are priced
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub enum FeePolicy {
+    /// Greetings cost nothing
+    Free,
+    /// Every greeting costs the same
+    Flat {
+        /// Lamports per greeting
+        lamports: u64,
+    },
+    /// Greetings cost a base fee plus a fee per byte of their message
+    PerMessageByte {
+        /// Lamports per greeting
+        base_lamports: u64,
+        /// Lamports per message byte
+        lamports_per_byte: u64,
+    },
+    /// Greetings get more expensive as the greeted account is greeted more
+    TieredByCount {
+        /// Tiers sorted by strictly increasing `min_count`, at most
+        /// `MAX_FEE_TIERS`
+        tiers: Vec<FeeTier>,
+    },
+}
+
+/// Price of greetings once an account has been greeted `min_count` times
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq)]
+pub struct FeeTier {
+    /// Greetings the account already received for the tier to apply
+    pub min_count: u32,
+    /// Lamports per greeting within the tier
+    pub lamports: u64,
+}
+
+// `#[default]` enum variants need a newer compiler than the BPF toolchain
+#[allow(clippy::derivable_impls)]
+impl Default for FeePolicy {
+    fn default() -> Self {
+        Self::Free
+    }
+}
+
+impl FeePolicy {
+    /// Space needed by the largest policy
+    pub const MAX_LEN: usize = 1 + 4 + (4 + 8) * MAX_FEE_TIERS;
+
+    /// Check the policy can be stored in the config and priced consistently
+    pub fn validate(&self) -> Result<(), ProgramError> {
+        if let Self::TieredByCount { tiers } = self {
+            if tiers.len() > MAX_FEE_TIERS {
+                msg!("At most {} fee tiers are supported", MAX_FEE_TIERS);
+                return Err(ProgramError::InvalidArgument);
+            }
+            if tiers
+                .windows(2)
+                .any(|pair| pair[0].min_count >= pair[1].min_count)
+            {
+                msg!("Fee tiers must be sorted by increasing count");
+                return Err(ProgramError::InvalidArgument);
+            }
+        }
+        Ok(())
+    }
+
+    /// Fee for greeting an account already greeted `count` times with a
+    /// `message_len` bytes long message, `None` on overflow
+    pub fn fee(&self, count: u32, message_len: usize) -> Option<u64> {
+        match self {
+            Self::Free => Some(0),
+            Self::Flat { lamports } => Some(*lamports),
+            Self::PerMessageByte {
+                base_lamports,
+                lamports_per_byte,
+            } => lamports_per_byte
+                .checked_mul(message_len as u64)?
+                .checked_add(*base_lamports),
+            Self::TieredByCount { tiers } => Some(
+                tiers
+                    .iter()
+                    .rev()
+                    .find(|tier| tier.min_count <= count)
+                    .map_or(0, |tier| tier.lamports),
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::Rng;
+
+    const CASES: usize = 1000;
+
+    impl FeePolicy {
+        /// Random policy, not necessarily a valid one
+        pub(crate) fn random(rng: &mut impl Rng) -> Self {
+            match rng.gen_range(0, 4) {
+                0 => Self::Free,
+                1 => Self::Flat {
+                    lamports: rng.gen(),
+                },
+                2 => Self::PerMessageByte {
+                    base_lamports: rng.gen(),
+                    lamports_per_byte: rng.gen(),
+                },
+                _ => Self::TieredByCount {
+                    tiers: (0..rng.gen_range(0, MAX_FEE_TIERS + 1))
+                        .map(|_| FeeTier {
+                            min_count: rng.gen(),
+                            lamports: rng.gen(),
+                        })
+                        .collect(),
+                },
+            }
+        }
+    }
+
+    #[test]
+    fn test_free() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..CASES {
+            assert_eq!(FeePolicy::Free.fee(rng.gen(), rng.gen()), Some(0));
+        }
+    }
+
+    #[test]
+    fn test_flat() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..CASES {
+            let lamports = rng.gen();
+            let policy = FeePolicy::Flat { lamports };
+            assert_eq!(policy.fee(rng.gen(), rng.gen()), Some(lamports));
+        }
+    }
+
+    #[test]
+    fn test_per_message_byte() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..CASES {
+            let base_lamports = rng.gen_range(0, u32::MAX as u64);
+            let lamports_per_byte = rng.gen_range(0, u16::MAX as u64);
+            let message_len = rng.gen_range(0, u16::MAX as usize);
+            let policy = FeePolicy::PerMessageByte {
+                base_lamports,
+                lamports_per_byte,
+            };
+            let fee = policy.fee(rng.gen(), message_len).unwrap();
+            assert_eq!(fee, base_lamports + lamports_per_byte * message_len as u64);
+            // Longer messages never cost less
+            assert!(policy.fee(rng.gen(), message_len + 1).unwrap() >= fee);
+        }
+
+        let policy = FeePolicy::PerMessageByte {
+            base_lamports: u64::MAX,
+            lamports_per_byte: 1,
+        };
+        assert_eq!(policy.fee(0, 1), None);
+    }
+
+    #[test]
+    fn test_tiered_by_count() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..CASES {
+            let mut min_count = 0;
+            let tiers: Vec<FeeTier> = (0..rng.gen_range(0, MAX_FEE_TIERS + 1))
+                .map(|_| {
+                    min_count += rng.gen_range(1, 100);
+                    FeeTier {
+                        min_count,
+                        lamports: rng.gen(),
+                    }
+                })
+                .collect();
+            let policy = FeePolicy::TieredByCount {
+                tiers: tiers.clone(),
+            };
+            policy.validate().unwrap();
+
+            let count = rng.gen_range(0, min_count + 100);
+            let reached = tiers
+                .iter()
+                .take_while(|tier| tier.min_count <= count)
+                .count();
+            let expected = match reached {
+                0 => 0,
+                _ => tiers[reached - 1].lamports,
+            };
+            assert_eq!(policy.fee(count, rng.gen()), Some(expected));
+            // Each tier starts exactly at its count
+            for tier in &tiers {
+                assert_eq!(policy.fee(tier.min_count, 0), Some(tier.lamports));
+            }
+        }
+    }
+
+    #[test]
+    fn test_validate() {
+        let tier = |min_count| FeeTier {
+            min_count,
+            lamports: 1,
+        };
+        assert!(FeePolicy::TieredByCount {
+            tiers: vec![tier(1), tier(1)]
+        }
+        .validate()
+        .is_err());
+        assert!(FeePolicy::TieredByCount {
+            tiers: vec![tier(2), tier(1)]
+        }
+        .validate()
+        .is_err());
+        assert!(FeePolicy::TieredByCount {
+            tiers: (0..=MAX_FEE_TIERS as u32).map(tier).collect()
+        }
+        .validate()
+        .is_err());
+
+        // The largest valid policy fits in its reserved space
+        let policy = FeePolicy::TieredByCount {
+            tiers: (0..MAX_FEE_TIERS as u32).map(tier).collect(),
+        };
+        policy.validate().unwrap();
+        assert_eq!(policy.try_to_vec().unwrap().len(), FeePolicy::MAX_LEN);
+    }
+}
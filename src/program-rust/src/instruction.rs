@@ -0,0 +1,1356 @@
+use crate::{
+    backup::GreetingDelta,
+    fees::FeePolicy,
+    mirror::find_mirror_address,
+    state::{
+        find_channel_address, find_channels_address, find_config_address, find_denylist_address,
+        find_greeter_address, find_greeting_address, find_history_address,
+        find_leaderboard_address, find_metadata_address, find_multisig_address,
+        find_proposal_address, find_registry_address, find_timelock_address, find_treasury_address,
+    },
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    system_program, sysvar,
+};
+
+/// Longest greeting message in bytes, configs can only lower it
+pub const MAX_MESSAGE_LEN: usize = 280;
+
+/// Instructions supported by the hello world program
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub enum HelloInstruction {
+    /// Say hello to a single account
+    ///
+    /// Accounts expected:
+    /// 0. `[writable]` The account to say hello to, created if it is the
+    ///    greeter's empty greeting address, see `find_greeting_address`
+    /// 1. `[writable, signer]` The greeter, paying the greeting fee and for
+    ///    the creation of its greeting address
+    /// 2. `[]` The config account
+    /// 3. `[writable]` The greeter account, registered if the config rate
+    ///    limits greeters
+    /// 4. `[writable]` The treasury
+    /// 5. `[]` The system program
+    /// 6. `[writable]` The registry, recording the greeting address if it is
+    ///    created, see `find_registry_address`
+    /// 7. `[writable]` The history, recording the greeting, see
+    ///    `find_history_address`
+    /// 8. `[writable]` The leaderboard, ranking the greeter if it is
+    ///    registered, see `find_leaderboard_address`
+    /// 9. `[]` The denylist, see `find_denylist_address`
+    /// 10. `[]` The instructions sysvar
+    /// 11. `[writable]` The mirror of the greeted account, if it has one
+    SayHello {
+        /// Proof the greeter is allowlisted, if the config restricts greeters
+        proof: Vec<[u8; 32]>,
+        /// Greeting message, possibly empty, of at most `MAX_MESSAGE_LEN`
+        /// bytes and without control characters
+        message: String,
+    },
+
+    /// Say hello to every account passed, paying for a single instruction
+    ///
+    /// Accounts expected:
+    /// 0. `[writable, signer]` The greeter, paying the greeting fees
+    /// 1. `[]` The config account
+    /// 2. `[writable]` The greeter account, registered if the config rate
+    ///    limits greeters
+    /// 3. `[writable]` The treasury
+    /// 4. `[]` The system program
+    /// 5. `[]` The instructions sysvar
+    /// 6. ..6+N `[writable]` The accounts to say hello to, each followed by
+    ///    its mirror if it has one
+    BatchGreet {
+        /// Proof the greeter is allowlisted, if the config restricts greeters
+        proof: Vec<[u8; 32]>,
+    },
+
+    /// Say hello to a single account on behalf of an Ethereum key
+    ///
+    /// The instruction immediately before this one must be a secp256k1
+    /// program instruction verifying a signature by `eth_address` over
+    /// `secp256k1_greeting_message(greeted_pubkey, counter)`, where `counter`
+    /// is the current greeting count of the account.
+    ///
+    /// Accounts expected:
+    /// 0. `[writable]` The account to say hello to
+    /// 1. `[]` The instructions sysvar
+    /// 2. `[]` The config account
+    /// 3. `[writable, signer]` The payer of the greeting fee
+    /// 4. `[writable]` The treasury
+    /// 5. `[]` The system program
+    /// 6. `[writable]` The mirror of the greeted account, if it has one
+    SayHelloSecp256k1 {
+        /// Ethereum address of the greeter
+        eth_address: [u8; 20],
+        /// Proof `eth_address` is allowlisted, if the config restricts greeters
+        proof: Vec<[u8; 32]>,
+    },
+
+    /// Register guardians able to recover ownership of a greeting account
+    ///
+    /// Accounts expected:
+    /// 0. `[writable]` The guardian set account, owned by the program and
+    ///    holding at least `GuardianSet::LEN` zeroed bytes
+    /// 1. `[]` The greeting account whose ownership is recorded
+    /// 2. `[signer]` The owner of the greeting account
+    /// 3. `[]` The config account
+    RegisterGuardians {
+        /// Guardian pubkeys, at most `MAX_GUARDIANS`
+        guardians: Vec<Pubkey>,
+        /// Number of guardians required to start a recovery
+        threshold: u8,
+        /// Slots the owner has to cancel a recovery before it can complete
+        recovery_delay: u64,
+    },
+
+    /// Start recovering ownership of a greeting account to a new key
+    ///
+    /// Accounts expected:
+    /// 0. `[writable]` The guardian set account
+    /// 1. `[]` The config account
+    /// 2. ..2+M `[signer]` At least `threshold` distinct guardians
+    InitiateRecovery {
+        /// Owner once the recovery completes
+        new_owner: Pubkey,
+    },
+
+    /// Complete a pending recovery once its delay has passed
+    ///
+    /// Accounts expected:
+    /// 0. `[writable]` The guardian set account
+    /// 1. `[]` The config account
+    RecoverOwnership,
+
+    /// Cancel a pending recovery
+    ///
+    /// Accounts expected:
+    /// 0. `[writable]` The guardian set account
+    /// 1. `[signer]` The current owner
+    /// 2. `[]` The config account
+    CancelRecovery,
+
+    /// Create the config account with the signer as admin
+    ///
+    /// Accounts expected:
+    /// 0. `[writable]` The config account, see `find_config_address`
+    /// 1. `[writable, signer]` The admin, funding the config account
+    /// 2. `[]` The system program
+    InitializeConfig,
+
+    /// Restrict greeters to a Merkle allowlist, or lift the restriction
+    ///
+    /// Accounts expected:
+    /// 0. `[writable]` The config account
+    /// 1. `[signer]` The admin
+    SetAllowlistRoot {
+        /// Root built by `allowlist::root`, or `None` to allow every greeter
+        root: Option<[u8; 32]>,
+    },
+
+    /// Create the greeter account tracking the signer's greetings
+    ///
+    /// Accounts expected:
+    /// 0. `[writable]` The greeter account, see `find_greeter_address`
+    /// 1. `[writable, signer]` The greeter, funding the greeter account
+    /// 2. `[]` The system program
+    /// 3. `[]` The config account
+    RegisterGreeter,
+
+    /// Set how many slots registered greeters wait between greetings
+    ///
+    /// Accounts expected:
+    /// 0. `[writable]` The config account
+    /// 1. `[signer]` The admin
+    SetRateLimit {
+        /// Slots between greetings by the same greeter, 0 to disable
+        slots: u64,
+    },
+
+    /// Make greetings expire
+    ///
+    /// Accounts expected:
+    /// 0. `[writable]` The config account
+    /// 1. `[signer]` The admin
+    SetGreetingExpiry {
+        /// Seconds a greeting keeps the greeted account alive, 0 to disable
+        ttl: i64,
+    },
+
+    /// Close an expired greeting account, sending its lamports to the
+    /// treasury. Anyone can reap.
+    ///
+    /// Accounts expected:
+    /// 0. `[writable]` The expired greeting account
+    /// 1. `[writable]` The treasury
+    /// 2. `[]` The config account
+    /// 3. `[writable]` The mirror of the greeting account, if it has one,
+    ///    closed along with it
+    Reap,
+
+    /// Create the mirror of a greeting account, a fixed-layout copy of its
+    /// state for other programs to read, see the `mirror` module
+    ///
+    /// Accounts expected:
+    /// 0. `[writable]` The greeting account
+    /// 1. `[writable]` The mirror account, `mirror::find_mirror_address`
+    /// 2. `[writable, signer]` The payer
+    /// 3. `[]` The system program
+    /// 4. `[]` The config account
+    CreateMirror,
+
+    /// Set how greetings are priced, failing under a timelock, see
+    /// `QueueAction`
+    ///
+    /// Accounts expected:
+    /// 0. `[writable]` The config account
+    /// 1. `[signer]` The admin
+    SetFeePolicy {
+        /// Policy pricing every greeting
+        policy: FeePolicy,
+    },
+
+    /// Upgrades a greeting account to the current layout in place, topping
+    /// up its rent exemption from the payer. Accounts already using the
+    /// current layout are left untouched.
+    ///
+    /// Accounts expected:
+    /// 0. `[writable]` The greeting account
+    /// 1. `[writable, signer]` The payer
+    /// 2. `[]` The system program
+    /// 3. `[]` The config account
+    Migrate,
+
+    /// Exports the fields of a greeting account changed since a version into
+    /// a backup account, see `backup`
+    ///
+    /// Accounts expected:
+    /// 0. `[]` The greeting account
+    /// 1. `[writable]` The backup account, `GreetingDelta::LEN` bytes
+    /// 2. `[]` The config account
+    /// 3. `[signer]` The admin
+    ExportDelta {
+        /// Version the backup being extended is at, 0 for a full backup
+        since_version: u32,
+    },
+
+    /// Restores a greeting account by applying a delta on top of it
+    ///
+    /// Accounts expected:
+    /// 0. `[writable]` The greeting account
+    /// 1. `[]` The config account
+    /// 2. `[signer]` The admin
+    /// 3. `[writable]` The mirror account, if the greeting account has one
+    ApplyDelta {
+        /// Delta starting at the version of the greeting account
+        delta: GreetingDelta,
+    },
+
+    /// Change several config settings at once, leaving the others as they are.
+    /// Under a timelock, only pausing takes effect right away, see
+    /// `QueueAction`.
+    ///
+    /// Accounts expected:
+    /// 0. `[writable]` The config account
+    /// 1. `[signer]` The admin
+    UpdateConfig {
+        /// Settings to change
+        update: ConfigUpdate,
+    },
+
+    /// Create the registry of greeting addresses. Greeting addresses created
+    /// before are not recorded.
+    ///
+    /// Accounts expected:
+    /// 0. `[writable]` The registry account, see `find_registry_address`
+    /// 1. `[writable, signer]` The payer
+    /// 2. `[]` The system program
+    InitializeRegistry,
+
+    /// Create the history of the latest `SayHello` greetings
+    ///
+    /// Accounts expected:
+    /// 0. `[writable]` The history account, see `find_history_address`
+    /// 1. `[writable, signer]` The payer
+    /// 2. `[]` The system program
+    InitializeHistory,
+
+    /// Create the leaderboard of the registered greeters with the most
+    /// greetings
+    ///
+    /// Accounts expected:
+    /// 0. `[writable]` The leaderboard account, see `find_leaderboard_address`
+    /// 1. `[writable, signer]` The payer
+    /// 2. `[]` The system program
+    InitializeLeaderboard,
+
+    /// Write the name, version and build commit of the deployed program to the
+    /// metadata account, creating it on first use
+    ///
+    /// Accounts expected:
+    /// 0. `[writable]` The metadata account, see `find_metadata_address`
+    /// 1. `[writable, signer]` The payer
+    /// 2. `[]` The system program
+    WriteMetadata,
+
+    /// Create a named channel of the user, a greeting account with its own
+    /// counter next to the user's greeting address
+    ///
+    /// Accounts expected:
+    /// 0. `[writable]` The channel, see `find_channel_address`
+    /// 1. `[writable, signer]` The user, paying for the channel
+    /// 2. `[writable]` The channel list of the user, created with its first
+    ///    channel, see `find_channels_address`
+    /// 3. `[]` The system program
+    /// 4. `[writable]` The registry, recording the channel, see
+    ///    `find_registry_address`
+    /// 5. `[]` The config account
+    CreateChannel {
+        /// Channel name, at most `MAX_CHANNEL_NAME_LEN` bytes
+        name: String,
+    },
+
+    /// Turn the treasury into a program account keeping running totals of
+    /// the lamports it collects and pays out, keeping what it already holds
+    ///
+    /// Accounts expected:
+    /// 0. `[writable]` The treasury, see `find_treasury_address`
+    /// 1. `[writable, signer]` The payer, topping the treasury up to the
+    ///    rent-exempt minimum
+    /// 2. `[]` The system program
+    InitializeTreasury,
+
+    /// Move lamports out of the initialized treasury, keeping it rent exempt
+    ///
+    /// Accounts expected:
+    /// 0. `[writable]` The treasury
+    /// 1. `[writable]` The account receiving the lamports
+    /// 2. `[]` The config account
+    /// 3. `[signer]` The config admin
+    Withdraw {
+        /// Lamports to withdraw
+        amount: u64,
+    },
+
+    /// Deny a greeter from saying hello, creating the denylist on first use
+    ///
+    /// Accounts expected:
+    /// 0. `[writable]` The denylist, see `find_denylist_address`
+    /// 1. `[]` The config account
+    /// 2. `[writable, signer]` The admin, funding the denylist
+    /// 3. `[]` The system program
+    AddToDenylist {
+        /// Greeter to deny
+        greeter: Pubkey,
+    },
+
+    /// Allow a denied greeter to say hello again
+    ///
+    /// Accounts expected:
+    /// 0. `[writable]` The denylist
+    /// 1. `[]` The config account
+    /// 2. `[signer]` The admin
+    RemoveFromDenylist {
+        /// Greeter to allow again
+        greeter: Pubkey,
+    },
+
+    /// Create the multisig and hand it the config, after which config updates
+    /// and withdrawals need `threshold` of its signers to approve a proposal
+    ///
+    /// Accounts expected:
+    /// 0. `[writable]` The multisig account, see `find_multisig_address`
+    /// 1. `[writable]` The config account
+    /// 2. `[writable, signer]` The current admin, funding the multisig
+    /// 3. `[]` The system program
+    CreateMultisig {
+        /// Signers of the multisig, at most `MAX_MULTISIG_SIGNERS`
+        signers: Vec<Pubkey>,
+        /// Number of approvals a proposal needs to be executed
+        threshold: u8,
+    },
+
+    /// Propose an admin action to the multisig, approved by the proposer
+    ///
+    /// Accounts expected:
+    /// 0. `[writable]` The proposal account, see `find_proposal_address` for
+    ///    the next proposal index of the multisig
+    /// 1. `[writable]` The multisig account
+    /// 2. `[writable, signer]` The proposer, a multisig signer funding the
+    ///    proposal
+    /// 3. `[]` The system program
+    Propose {
+        /// Action executed once the proposal is approved
+        action: MultisigAction,
+    },
+
+    /// Approve a pending proposal
+    ///
+    /// Accounts expected:
+    /// 0. `[writable]` The proposal account
+    /// 1. `[]` The multisig account
+    /// 2. `[signer]` A multisig signer
+    Approve,
+
+    /// Execute a proposal approved by the threshold of multisig signers.
+    /// Anyone can execute.
+    ///
+    /// Accounts expected:
+    /// 0. `[writable]` The proposal account
+    /// 1. `[]` The multisig account
+    /// 2. `[writable]` The config account
+    /// 3. `[writable]` The treasury, for a `MultisigAction::Withdraw`
+    /// 4. `[writable]` The withdrawal destination, for a
+    ///    `MultisigAction::Withdraw`
+    Execute,
+
+    /// Queue a config update, executable once the config's `timelock_slots`
+    /// have passed. Creates the timelock account on first use.
+    ///
+    /// Accounts expected:
+    /// 0. `[writable]` The timelock account, see `find_timelock_address`
+    /// 1. `[]` The config account
+    /// 2. `[writable, signer]` The admin, funding the timelock
+    /// 3. `[]` The system program
+    QueueAction {
+        /// Update applied once the action executes
+        update: ConfigUpdate,
+    },
+
+    /// Execute a queued action whose delay has passed. Anyone can execute.
+    ///
+    /// Accounts expected:
+    /// 0. `[writable]` The timelock account
+    /// 1. `[writable]` The config account
+    ExecuteAction {
+        /// Id of the queued action
+        id: u64,
+    },
+
+    /// Drop a queued action before it executes
+    ///
+    /// Accounts expected:
+    /// 0. `[writable]` The timelock account
+    /// 1. `[]` The config account
+    /// 2. `[signer]` The admin
+    CancelAction {
+        /// Id of the queued action
+        id: u64,
+    },
+
+    /// Create the signer's greeting account unless it already exists
+    ///
+    /// Succeeds without changes if the account is already a greeting account
+    /// at the signer's greeting address, but fails if it holds anything else.
+    ///
+    /// Accounts expected:
+    /// 0. `[writable]` The greeting account, see `find_greeting_address`
+    /// 1. `[writable, signer]` The user, funding the greeting account
+    /// 2. `[]` The system program
+    /// 3. `[]` The config account
+    /// 4. `[writable]` The registry account
+    InitializeIfNeeded,
+
+    /// Take over the config as the admin the current one proposed
+    ///
+    /// Accounts expected:
+    /// 0. `[writable]` The config account
+    /// 1. `[signer]` The proposed admin
+    AcceptAdmin,
+}
+
+/// Config settings changed by `UpdateConfig`, `None` to keep the current one
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Default, PartialEq)]
+pub struct ConfigUpdate {
+    /// Propose a new admin, who takes over once they accept with
+    /// `AcceptAdmin`, or withdraw the proposal by proposing the current admin
+    pub admin: Option<Pubkey>,
+    /// Pause or resume every instruction but the admin ones
+    pub paused: Option<bool>,
+    /// Price greetings differently
+    pub fee_policy: Option<FeePolicy>,
+    /// Delay administrative actions by a number of slots
+    pub timelock_slots: Option<u64>,
+    /// Forbid or allow greeting through CPI
+    pub top_level_only: Option<bool>,
+    /// Redact greeting messages from logs or log them again
+    pub redact_logs: Option<bool>,
+    /// Cap greeting messages at a number of bytes, 0 for `MAX_MESSAGE_LEN`
+    pub max_message_len: Option<u16>,
+}
+
+impl ConfigUpdate {
+    /// Space needed by the largest update
+    pub const MAX_LEN: usize =
+        (1 + 32) + (1 + 1) + (1 + FeePolicy::MAX_LEN) + (1 + 8) + (1 + 1) + (1 + 1) + (1 + 2);
+
+    /// Whether the update changes settings a timelock delays, that is
+    /// anything but pausing
+    pub fn is_timelocked(&self) -> bool {
+        self.admin.is_some()
+            || self.fee_policy.is_some()
+            || self.timelock_slots.is_some()
+            || self.top_level_only.is_some()
+            || self.redact_logs.is_some()
+            || self.max_message_len.is_some()
+    }
+}
+
+/// Admin action a multisig proposal performs once approved
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub enum MultisigAction {
+    /// Change config settings, like `UpdateConfig`
+    UpdateConfig(ConfigUpdate),
+    /// Move lamports out of the treasury, like `Withdraw`
+    Withdraw {
+        /// Account receiving the lamports
+        destination: Pubkey,
+        /// Lamports to withdraw
+        amount: u64,
+    },
+}
+
+// `#[default]` enum variants need a newer compiler than the BPF toolchain
+#[allow(clippy::derivable_impls)]
+impl Default for MultisigAction {
+    fn default() -> Self {
+        Self::UpdateConfig(ConfigUpdate::default())
+    }
+}
+
+impl MultisigAction {
+    /// Space needed by the largest action
+    pub const MAX_LEN: usize = 1 + ConfigUpdate::MAX_LEN;
+}
+
+/// Create a `SayHello` instruction without a message
+pub fn say_hello(
+    program_id: &Pubkey,
+    greeted_pubkey: &Pubkey,
+    greeter_pubkey: &Pubkey,
+    proof: Vec<[u8; 32]>,
+) -> Instruction {
+    say_hello_with_message(
+        program_id,
+        greeted_pubkey,
+        greeter_pubkey,
+        proof,
+        String::new(),
+    )
+}
+
+/// Create a `SayHello` instruction
+pub fn say_hello_with_message(
+    program_id: &Pubkey,
+    greeted_pubkey: &Pubkey,
+    greeter_pubkey: &Pubkey,
+    proof: Vec<[u8; 32]>,
+    message: String,
+) -> Instruction {
+    Instruction::new_with_borsh(
+        *program_id,
+        &HelloInstruction::SayHello { proof, message },
+        vec![
+            AccountMeta::new(*greeted_pubkey, false),
+            AccountMeta::new(*greeter_pubkey, true),
+            AccountMeta::new_readonly(find_config_address(program_id).0, false),
+            AccountMeta::new(find_greeter_address(program_id, greeter_pubkey).0, false),
+            AccountMeta::new(find_treasury_address(program_id).0, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new(find_registry_address(program_id).0, false),
+            AccountMeta::new(find_history_address(program_id).0, false),
+            AccountMeta::new(find_leaderboard_address(program_id).0, false),
+            AccountMeta::new_readonly(find_denylist_address(program_id).0, false),
+            AccountMeta::new_readonly(sysvar::instructions::id(), false),
+        ],
+    )
+}
+
+/// Create a `BatchGreet` instruction
+pub fn batch_greet(
+    program_id: &Pubkey,
+    greeted_pubkeys: &[Pubkey],
+    greeter_pubkey: &Pubkey,
+    proof: Vec<[u8; 32]>,
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(*greeter_pubkey, true),
+        AccountMeta::new_readonly(find_config_address(program_id).0, false),
+        AccountMeta::new(find_greeter_address(program_id, greeter_pubkey).0, false),
+        AccountMeta::new(find_treasury_address(program_id).0, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(sysvar::instructions::id(), false),
+    ];
+    accounts.extend(
+        greeted_pubkeys
+            .iter()
+            .map(|pubkey| AccountMeta::new(*pubkey, false)),
+    );
+    Instruction::new_with_borsh(
+        *program_id,
+        &HelloInstruction::BatchGreet { proof },
+        accounts,
+    )
+}
+
+/// Create a `SayHelloSecp256k1` instruction
+pub fn say_hello_secp256k1(
+    program_id: &Pubkey,
+    greeted_pubkey: &Pubkey,
+    eth_address: [u8; 20],
+    proof: Vec<[u8; 32]>,
+    payer: &Pubkey,
+) -> Instruction {
+    Instruction::new_with_borsh(
+        *program_id,
+        &HelloInstruction::SayHelloSecp256k1 { eth_address, proof },
+        vec![
+            AccountMeta::new(*greeted_pubkey, false),
+            AccountMeta::new_readonly(sysvar::instructions::id(), false),
+            AccountMeta::new_readonly(find_config_address(program_id).0, false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new(find_treasury_address(program_id).0, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+/// Message an Ethereum key signs to greet `greeted_pubkey` when its greeting
+/// count is `counter`, so each signature can only be used once
+pub fn secp256k1_greeting_message(greeted_pubkey: &Pubkey, counter: u32) -> Vec<u8> {
+    let mut message = greeted_pubkey.to_bytes().to_vec();
+    message.extend_from_slice(&counter.to_le_bytes());
+    message
+}
+
+/// Create a `RegisterGuardians` instruction
+pub fn register_guardians(
+    program_id: &Pubkey,
+    guardian_set_pubkey: &Pubkey,
+    greeting_pubkey: &Pubkey,
+    owner_pubkey: &Pubkey,
+    guardians: Vec<Pubkey>,
+    threshold: u8,
+    recovery_delay: u64,
+) -> Instruction {
+    Instruction::new_with_borsh(
+        *program_id,
+        &HelloInstruction::RegisterGuardians {
+            guardians,
+            threshold,
+            recovery_delay,
+        },
+        vec![
+            AccountMeta::new(*guardian_set_pubkey, false),
+            AccountMeta::new_readonly(*greeting_pubkey, false),
+            AccountMeta::new_readonly(*owner_pubkey, true),
+            AccountMeta::new_readonly(find_config_address(program_id).0, false),
+        ],
+    )
+}
+
+/// Create an `InitiateRecovery` instruction
+pub fn initiate_recovery(
+    program_id: &Pubkey,
+    guardian_set_pubkey: &Pubkey,
+    guardian_pubkeys: &[Pubkey],
+    new_owner: &Pubkey,
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(*guardian_set_pubkey, false),
+        AccountMeta::new_readonly(find_config_address(program_id).0, false),
+    ];
+    accounts.extend(
+        guardian_pubkeys
+            .iter()
+            .map(|pubkey| AccountMeta::new_readonly(*pubkey, true)),
+    );
+    Instruction::new_with_borsh(
+        *program_id,
+        &HelloInstruction::InitiateRecovery {
+            new_owner: *new_owner,
+        },
+        accounts,
+    )
+}
+
+/// Create a `RecoverOwnership` instruction
+pub fn recover_ownership(program_id: &Pubkey, guardian_set_pubkey: &Pubkey) -> Instruction {
+    Instruction::new_with_borsh(
+        *program_id,
+        &HelloInstruction::RecoverOwnership,
+        vec![
+            AccountMeta::new(*guardian_set_pubkey, false),
+            AccountMeta::new_readonly(find_config_address(program_id).0, false),
+        ],
+    )
+}
+
+/// Create a `CancelRecovery` instruction
+pub fn cancel_recovery(
+    program_id: &Pubkey,
+    guardian_set_pubkey: &Pubkey,
+    owner_pubkey: &Pubkey,
+) -> Instruction {
+    Instruction::new_with_borsh(
+        *program_id,
+        &HelloInstruction::CancelRecovery,
+        vec![
+            AccountMeta::new(*guardian_set_pubkey, false),
+            AccountMeta::new_readonly(*owner_pubkey, true),
+            AccountMeta::new_readonly(find_config_address(program_id).0, false),
+        ],
+    )
+}
+
+/// Create an `InitializeConfig` instruction
+pub fn initialize_config(program_id: &Pubkey, admin_pubkey: &Pubkey) -> Instruction {
+    Instruction::new_with_borsh(
+        *program_id,
+        &HelloInstruction::InitializeConfig,
+        vec![
+            AccountMeta::new(find_config_address(program_id).0, false),
+            AccountMeta::new(*admin_pubkey, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+/// Create a `SetAllowlistRoot` instruction
+pub fn set_allowlist_root(
+    program_id: &Pubkey,
+    admin_pubkey: &Pubkey,
+    root: Option<[u8; 32]>,
+) -> Instruction {
+    Instruction::new_with_borsh(
+        *program_id,
+        &HelloInstruction::SetAllowlistRoot { root },
+        vec![
+            AccountMeta::new(find_config_address(program_id).0, false),
+            AccountMeta::new_readonly(*admin_pubkey, true),
+        ],
+    )
+}
+
+/// Create a `RegisterGreeter` instruction
+pub fn register_greeter(program_id: &Pubkey, greeter_pubkey: &Pubkey) -> Instruction {
+    Instruction::new_with_borsh(
+        *program_id,
+        &HelloInstruction::RegisterGreeter,
+        vec![
+            AccountMeta::new(find_greeter_address(program_id, greeter_pubkey).0, false),
+            AccountMeta::new(*greeter_pubkey, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(find_config_address(program_id).0, false),
+        ],
+    )
+}
+
+/// Create a `SetRateLimit` instruction
+pub fn set_rate_limit(program_id: &Pubkey, admin_pubkey: &Pubkey, slots: u64) -> Instruction {
+    Instruction::new_with_borsh(
+        *program_id,
+        &HelloInstruction::SetRateLimit { slots },
+        vec![
+            AccountMeta::new(find_config_address(program_id).0, false),
+            AccountMeta::new_readonly(*admin_pubkey, true),
+        ],
+    )
+}
+
+/// Create a `SetGreetingExpiry` instruction
+pub fn set_greeting_expiry(program_id: &Pubkey, admin_pubkey: &Pubkey, ttl: i64) -> Instruction {
+    Instruction::new_with_borsh(
+        *program_id,
+        &HelloInstruction::SetGreetingExpiry { ttl },
+        vec![
+            AccountMeta::new(find_config_address(program_id).0, false),
+            AccountMeta::new_readonly(*admin_pubkey, true),
+        ],
+    )
+}
+
+/// Create a `Reap` instruction
+pub fn reap(program_id: &Pubkey, greeted_pubkey: &Pubkey) -> Instruction {
+    Instruction::new_with_borsh(
+        *program_id,
+        &HelloInstruction::Reap,
+        vec![
+            AccountMeta::new(*greeted_pubkey, false),
+            AccountMeta::new(find_treasury_address(program_id).0, false),
+            AccountMeta::new_readonly(find_config_address(program_id).0, false),
+        ],
+    )
+}
+
+/// Create a `CreateMirror` instruction
+pub fn create_mirror(program_id: &Pubkey, greeted_pubkey: &Pubkey, payer: &Pubkey) -> Instruction {
+    Instruction::new_with_borsh(
+        *program_id,
+        &HelloInstruction::CreateMirror,
+        vec![
+            AccountMeta::new(*greeted_pubkey, false),
+            AccountMeta::new(find_mirror_address(program_id, greeted_pubkey).0, false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(find_config_address(program_id).0, false),
+        ],
+    )
+}
+
+/// Account to pass after `greeted_pubkey` once it has a mirror
+pub fn mirror_account_meta(program_id: &Pubkey, greeted_pubkey: &Pubkey) -> AccountMeta {
+    AccountMeta::new(find_mirror_address(program_id, greeted_pubkey).0, false)
+}
+
+/// Create a `SetFeePolicy` instruction
+pub fn set_fee_policy(
+    program_id: &Pubkey,
+    admin_pubkey: &Pubkey,
+    policy: FeePolicy,
+) -> Instruction {
+    Instruction::new_with_borsh(
+        *program_id,
+        &HelloInstruction::SetFeePolicy { policy },
+        vec![
+            AccountMeta::new(find_config_address(program_id).0, false),
+            AccountMeta::new_readonly(*admin_pubkey, true),
+        ],
+    )
+}
+
+/// Create a `Migrate` instruction
+pub fn migrate(program_id: &Pubkey, greeted_pubkey: &Pubkey, payer: &Pubkey) -> Instruction {
+    Instruction::new_with_borsh(
+        *program_id,
+        &HelloInstruction::Migrate,
+        vec![
+            AccountMeta::new(*greeted_pubkey, false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(find_config_address(program_id).0, false),
+        ],
+    )
+}
+
+/// Create an `ExportDelta` instruction
+pub fn export_delta(
+    program_id: &Pubkey,
+    greeted_pubkey: &Pubkey,
+    backup_pubkey: &Pubkey,
+    admin_pubkey: &Pubkey,
+    since_version: u32,
+) -> Instruction {
+    Instruction::new_with_borsh(
+        *program_id,
+        &HelloInstruction::ExportDelta { since_version },
+        vec![
+            AccountMeta::new_readonly(*greeted_pubkey, false),
+            AccountMeta::new(*backup_pubkey, false),
+            AccountMeta::new_readonly(find_config_address(program_id).0, false),
+            AccountMeta::new_readonly(*admin_pubkey, true),
+        ],
+    )
+}
+
+/// Create an `ApplyDelta` instruction
+pub fn apply_delta(
+    program_id: &Pubkey,
+    greeted_pubkey: &Pubkey,
+    admin_pubkey: &Pubkey,
+    delta: GreetingDelta,
+) -> Instruction {
+    Instruction::new_with_borsh(
+        *program_id,
+        &HelloInstruction::ApplyDelta { delta },
+        vec![
+            AccountMeta::new(*greeted_pubkey, false),
+            AccountMeta::new_readonly(find_config_address(program_id).0, false),
+            AccountMeta::new_readonly(*admin_pubkey, true),
+        ],
+    )
+}
+
+/// Create an `UpdateConfig` instruction
+pub fn update_config(
+    program_id: &Pubkey,
+    admin_pubkey: &Pubkey,
+    update: ConfigUpdate,
+) -> Instruction {
+    Instruction::new_with_borsh(
+        *program_id,
+        &HelloInstruction::UpdateConfig { update },
+        vec![
+            AccountMeta::new(find_config_address(program_id).0, false),
+            AccountMeta::new_readonly(*admin_pubkey, true),
+        ],
+    )
+}
+
+/// Create an `InitializeRegistry` instruction
+pub fn initialize_registry(program_id: &Pubkey, payer: &Pubkey) -> Instruction {
+    Instruction::new_with_borsh(
+        *program_id,
+        &HelloInstruction::InitializeRegistry,
+        vec![
+            AccountMeta::new(find_registry_address(program_id).0, false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+/// Create an `InitializeHistory` instruction
+pub fn initialize_history(program_id: &Pubkey, payer: &Pubkey) -> Instruction {
+    Instruction::new_with_borsh(
+        *program_id,
+        &HelloInstruction::InitializeHistory,
+        vec![
+            AccountMeta::new(find_history_address(program_id).0, false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+/// Create an `InitializeLeaderboard` instruction
+pub fn initialize_leaderboard(program_id: &Pubkey, payer: &Pubkey) -> Instruction {
+    Instruction::new_with_borsh(
+        *program_id,
+        &HelloInstruction::InitializeLeaderboard,
+        vec![
+            AccountMeta::new(find_leaderboard_address(program_id).0, false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+/// Create a `WriteMetadata` instruction
+pub fn write_metadata(program_id: &Pubkey, payer: &Pubkey) -> Instruction {
+    Instruction::new_with_borsh(
+        *program_id,
+        &HelloInstruction::WriteMetadata,
+        vec![
+            AccountMeta::new(find_metadata_address(program_id).0, false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+/// Create a `CreateChannel` instruction
+pub fn create_channel(program_id: &Pubkey, user: &Pubkey, name: &str) -> Instruction {
+    Instruction::new_with_borsh(
+        *program_id,
+        &HelloInstruction::CreateChannel {
+            name: name.to_string(),
+        },
+        vec![
+            AccountMeta::new(find_channel_address(program_id, user, name).0, false),
+            AccountMeta::new(*user, true),
+            AccountMeta::new(find_channels_address(program_id, user).0, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new(find_registry_address(program_id).0, false),
+            AccountMeta::new_readonly(find_config_address(program_id).0, false),
+        ],
+    )
+}
+
+/// Create an `InitializeTreasury` instruction
+pub fn initialize_treasury(program_id: &Pubkey, payer: &Pubkey) -> Instruction {
+    Instruction::new_with_borsh(
+        *program_id,
+        &HelloInstruction::InitializeTreasury,
+        vec![
+            AccountMeta::new(find_treasury_address(program_id).0, false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+/// Create a `Withdraw` instruction
+pub fn withdraw(
+    program_id: &Pubkey,
+    destination: &Pubkey,
+    admin_pubkey: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    Instruction::new_with_borsh(
+        *program_id,
+        &HelloInstruction::Withdraw { amount },
+        vec![
+            AccountMeta::new(find_treasury_address(program_id).0, false),
+            AccountMeta::new(*destination, false),
+            AccountMeta::new_readonly(find_config_address(program_id).0, false),
+            AccountMeta::new_readonly(*admin_pubkey, true),
+        ],
+    )
+}
+
+/// Create an `AddToDenylist` instruction
+pub fn add_to_denylist(
+    program_id: &Pubkey,
+    admin_pubkey: &Pubkey,
+    greeter: &Pubkey,
+) -> Instruction {
+    Instruction::new_with_borsh(
+        *program_id,
+        &HelloInstruction::AddToDenylist { greeter: *greeter },
+        vec![
+            AccountMeta::new(find_denylist_address(program_id).0, false),
+            AccountMeta::new_readonly(find_config_address(program_id).0, false),
+            AccountMeta::new(*admin_pubkey, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+/// Create a `RemoveFromDenylist` instruction
+pub fn remove_from_denylist(
+    program_id: &Pubkey,
+    admin_pubkey: &Pubkey,
+    greeter: &Pubkey,
+) -> Instruction {
+    Instruction::new_with_borsh(
+        *program_id,
+        &HelloInstruction::RemoveFromDenylist { greeter: *greeter },
+        vec![
+            AccountMeta::new(find_denylist_address(program_id).0, false),
+            AccountMeta::new_readonly(find_config_address(program_id).0, false),
+            AccountMeta::new_readonly(*admin_pubkey, true),
+        ],
+    )
+}
+
+/// Create a `CreateMultisig` instruction
+pub fn create_multisig(
+    program_id: &Pubkey,
+    admin_pubkey: &Pubkey,
+    signers: Vec<Pubkey>,
+    threshold: u8,
+) -> Instruction {
+    Instruction::new_with_borsh(
+        *program_id,
+        &HelloInstruction::CreateMultisig { signers, threshold },
+        vec![
+            AccountMeta::new(find_multisig_address(program_id).0, false),
+            AccountMeta::new(find_config_address(program_id).0, false),
+            AccountMeta::new(*admin_pubkey, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+/// Create a `Propose` instruction for the proposal at `index`, the current
+/// `Multisig::proposal_count`
+pub fn propose(
+    program_id: &Pubkey,
+    proposer_pubkey: &Pubkey,
+    index: u64,
+    action: MultisigAction,
+) -> Instruction {
+    Instruction::new_with_borsh(
+        *program_id,
+        &HelloInstruction::Propose { action },
+        vec![
+            AccountMeta::new(find_proposal_address(program_id, index).0, false),
+            AccountMeta::new(find_multisig_address(program_id).0, false),
+            AccountMeta::new(*proposer_pubkey, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+/// Create an `Approve` instruction
+pub fn approve(program_id: &Pubkey, signer_pubkey: &Pubkey, index: u64) -> Instruction {
+    Instruction::new_with_borsh(
+        *program_id,
+        &HelloInstruction::Approve,
+        vec![
+            AccountMeta::new(find_proposal_address(program_id, index).0, false),
+            AccountMeta::new_readonly(find_multisig_address(program_id).0, false),
+            AccountMeta::new_readonly(*signer_pubkey, true),
+        ],
+    )
+}
+
+/// Create an `Execute` instruction for the proposal at `index` performing
+/// `action`
+pub fn execute(program_id: &Pubkey, index: u64, action: &MultisigAction) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(find_proposal_address(program_id, index).0, false),
+        AccountMeta::new_readonly(find_multisig_address(program_id).0, false),
+        AccountMeta::new(find_config_address(program_id).0, false),
+    ];
+    if let MultisigAction::Withdraw { destination, .. } = action {
+        accounts.push(AccountMeta::new(find_treasury_address(program_id).0, false));
+        accounts.push(AccountMeta::new(*destination, false));
+    }
+    Instruction::new_with_borsh(*program_id, &HelloInstruction::Execute, accounts)
+}
+
+/// Create a `QueueAction` instruction
+pub fn queue_action(
+    program_id: &Pubkey,
+    admin_pubkey: &Pubkey,
+    update: ConfigUpdate,
+) -> Instruction {
+    Instruction::new_with_borsh(
+        *program_id,
+        &HelloInstruction::QueueAction { update },
+        vec![
+            AccountMeta::new(find_timelock_address(program_id).0, false),
+            AccountMeta::new_readonly(find_config_address(program_id).0, false),
+            AccountMeta::new(*admin_pubkey, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+/// Create an `ExecuteAction` instruction
+pub fn execute_action(program_id: &Pubkey, id: u64) -> Instruction {
+    Instruction::new_with_borsh(
+        *program_id,
+        &HelloInstruction::ExecuteAction { id },
+        vec![
+            AccountMeta::new(find_timelock_address(program_id).0, false),
+            AccountMeta::new(find_config_address(program_id).0, false),
+        ],
+    )
+}
+
+/// Create a `CancelAction` instruction
+pub fn cancel_action(program_id: &Pubkey, admin_pubkey: &Pubkey, id: u64) -> Instruction {
+    Instruction::new_with_borsh(
+        *program_id,
+        &HelloInstruction::CancelAction { id },
+        vec![
+            AccountMeta::new(find_timelock_address(program_id).0, false),
+            AccountMeta::new_readonly(find_config_address(program_id).0, false),
+            AccountMeta::new_readonly(*admin_pubkey, true),
+        ],
+    )
+}
+
+/// Create an `InitializeIfNeeded` instruction
+pub fn initialize_if_needed(program_id: &Pubkey, user_pubkey: &Pubkey) -> Instruction {
+    Instruction::new_with_borsh(
+        *program_id,
+        &HelloInstruction::InitializeIfNeeded,
+        vec![
+            AccountMeta::new(find_greeting_address(program_id, user_pubkey).0, false),
+            AccountMeta::new(*user_pubkey, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(find_config_address(program_id).0, false),
+            AccountMeta::new(find_registry_address(program_id).0, false),
+        ],
+    )
+}
+
+/// Create an `AcceptAdmin` instruction
+pub fn accept_admin(program_id: &Pubkey, pending_admin_pubkey: &Pubkey) -> Instruction {
+    Instruction::new_with_borsh(
+        *program_id,
+        &HelloInstruction::AcceptAdmin,
+        vec![
+            AccountMeta::new(find_config_address(program_id).0, false),
+            AccountMeta::new_readonly(*pending_admin_pubkey, true),
+        ],
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::{distributions::Alphanumeric, Rng};
+
+    const CASES: usize = 1000;
+
+    /// Number of `HelloInstruction` variants
+    const VARIANTS: u8 = 37;
+
+    fn random_pubkey(rng: &mut impl Rng) -> Pubkey {
+        Pubkey::new_from_array(rng.gen())
+    }
+
+    fn random_option<R: Rng, T>(rng: &mut R, f: impl FnOnce(&mut R) -> T) -> Option<T> {
+        if rng.gen() {
+            Some(f(rng))
+        } else {
+            None
+        }
+    }
+
+    fn random_pubkeys(rng: &mut impl Rng) -> Vec<Pubkey> {
+        (0..rng.gen_range(0, 10))
+            .map(|_| random_pubkey(rng))
+            .collect()
+    }
+
+    fn random_proof(rng: &mut impl Rng) -> Vec<[u8; 32]> {
+        (0..rng.gen_range(0, 10)).map(|_| rng.gen()).collect()
+    }
+
+    fn random_string(rng: &mut impl Rng) -> String {
+        let len = rng.gen_range(0, 40);
+        rng.sample_iter(Alphanumeric).take(len).collect()
+    }
+
+    impl ConfigUpdate {
+        /// Random update, not necessarily a valid one
+        pub(crate) fn random(rng: &mut impl Rng) -> Self {
+            Self {
+                admin: random_option(rng, random_pubkey),
+                paused: random_option(rng, Rng::gen),
+                fee_policy: random_option(rng, FeePolicy::random),
+                timelock_slots: random_option(rng, Rng::gen),
+                top_level_only: random_option(rng, Rng::gen),
+                redact_logs: random_option(rng, Rng::gen),
+                max_message_len: random_option(rng, Rng::gen),
+            }
+        }
+    }
+
+    impl MultisigAction {
+        /// Random action, not necessarily a valid one
+        pub(crate) fn random(rng: &mut impl Rng) -> Self {
+            if rng.gen() {
+                Self::UpdateConfig(ConfigUpdate::random(rng))
+            } else {
+                Self::Withdraw {
+                    destination: random_pubkey(rng),
+                    amount: rng.gen(),
+                }
+            }
+        }
+    }
+
+    /// Random instruction encoded with the tag `variant`
+    fn random_instruction(rng: &mut impl Rng, variant: u8) -> HelloInstruction {
+        match variant {
+            0 => HelloInstruction::SayHello {
+                proof: random_proof(rng),
+                message: random_string(rng),
+            },
+            1 => HelloInstruction::BatchGreet {
+                proof: random_proof(rng),
+            },
+            2 => HelloInstruction::SayHelloSecp256k1 {
+                eth_address: rng.gen(),
+                proof: random_proof(rng),
+            },
+            3 => HelloInstruction::RegisterGuardians {
+                guardians: random_pubkeys(rng),
+                threshold: rng.gen(),
+                recovery_delay: rng.gen(),
+            },
+            4 => HelloInstruction::InitiateRecovery {
+                new_owner: random_pubkey(rng),
+            },
+            5 => HelloInstruction::RecoverOwnership,
+            6 => HelloInstruction::CancelRecovery,
+            7 => HelloInstruction::InitializeConfig,
+            8 => HelloInstruction::SetAllowlistRoot {
+                root: random_option(rng, Rng::gen),
+            },
+            9 => HelloInstruction::RegisterGreeter,
+            10 => HelloInstruction::SetRateLimit { slots: rng.gen() },
+            11 => HelloInstruction::SetGreetingExpiry { ttl: rng.gen() },
+            12 => HelloInstruction::Reap,
+            13 => HelloInstruction::CreateMirror,
+            14 => HelloInstruction::SetFeePolicy {
+                policy: FeePolicy::random(rng),
+            },
+            15 => HelloInstruction::Migrate,
+            16 => HelloInstruction::ExportDelta {
+                since_version: rng.gen(),
+            },
+            17 => HelloInstruction::ApplyDelta {
+                delta: GreetingDelta {
+                    since_version: rng.gen(),
+                    version: rng.gen(),
+                    expires_at: random_option(rng, Rng::gen),
+                    last_greeter: random_option(rng, random_pubkey),
+                    last_slot: random_option(rng, Rng::gen),
+                },
+            },
+            18 => HelloInstruction::UpdateConfig {
+                update: ConfigUpdate::random(rng),
+            },
+            19 => HelloInstruction::InitializeRegistry,
+            20 => HelloInstruction::InitializeHistory,
+            21 => HelloInstruction::InitializeLeaderboard,
+            22 => HelloInstruction::WriteMetadata,
+            23 => HelloInstruction::CreateChannel {
+                name: random_string(rng),
+            },
+            24 => HelloInstruction::InitializeTreasury,
+            25 => HelloInstruction::Withdraw { amount: rng.gen() },
+            26 => HelloInstruction::AddToDenylist {
+                greeter: random_pubkey(rng),
+            },
+            27 => HelloInstruction::RemoveFromDenylist {
+                greeter: random_pubkey(rng),
+            },
+            28 => HelloInstruction::CreateMultisig {
+                signers: random_pubkeys(rng),
+                threshold: rng.gen(),
+            },
+            29 => HelloInstruction::Propose {
+                action: MultisigAction::random(rng),
+            },
+            30 => HelloInstruction::Approve,
+            31 => HelloInstruction::Execute,
+            32 => HelloInstruction::QueueAction {
+                update: ConfigUpdate::random(rng),
+            },
+            33 => HelloInstruction::ExecuteAction { id: rng.gen() },
+            34 => HelloInstruction::CancelAction { id: rng.gen() },
+            35 => HelloInstruction::InitializeIfNeeded,
+            36 => HelloInstruction::AcceptAdmin,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        // New variants need to be added to `random_instruction`
+        assert!(HelloInstruction::try_from_slice(&[VARIANTS]).is_err());
+
+        let mut rng = rand::thread_rng();
+        for _ in 0..CASES {
+            let variant = rng.gen_range(0, VARIANTS);
+            let instruction = random_instruction(&mut rng, variant);
+            let data = instruction.try_to_vec().unwrap();
+            assert_eq!(data[0], variant);
+            assert_eq!(
+                HelloInstruction::try_from_slice(&data).unwrap(),
+                instruction
+            );
+
+            // Truncated and padded data is rejected
+            for len in 0..data.len() {
+                assert!(HelloInstruction::try_from_slice(&data[..len]).is_err());
+            }
+            let mut padded = data;
+            padded.push(rng.gen());
+            assert!(HelloInstruction::try_from_slice(&padded).is_err());
+        }
+    }
+
+    #[test]
+    fn test_garbage() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..CASES {
+            let len = rng.gen_range(0, 256);
+            let data: Vec<u8> = (0..len).map(|_| rng.gen()).collect();
+            // Must not panic, whatever the outcome
+            let _ = HelloInstruction::try_from_slice(&data);
+        }
+    }
+}
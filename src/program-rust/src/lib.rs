@@ -1,67 +1,78 @@
-use borsh::{BorshDeserialize, BorshSerialize};
-use solana_program::{
-    account_info::{next_account_info, AccountInfo},
-    entrypoint,
-    entrypoint::ProgramResult,
-    msg,
-    program_error::ProgramError,
-    pubkey::Pubkey,
-};
+pub mod allowlist;
+pub mod backup;
+pub mod cost;
+pub mod error;
+pub mod fees;
+pub mod instruction;
+pub mod mirror;
+pub mod processor;
+pub mod security;
+pub mod state;
 
-/// Define the type of state stored in accounts
-#[derive(BorshSerialize, BorshDeserialize, Debug)]
-pub struct GreetingAccount {
-    /// number of greetings
-    pub counter: u32,
-}
-
-// Declare and export the program's entrypoint
-entrypoint!(process_instruction);
-
-// Program entrypoint's implementation
-pub fn process_instruction(
-    program_id: &Pubkey, // Public key of the account the hello world program was loaded into
-    accounts: &[AccountInfo], // The account to say hello to
-    _instruction_data: &[u8], // Ignored, all helloworld instructions are hellos
-) -> ProgramResult {
-    msg!("Hello World Rust program entrypoint");
+use solana_program::declare_id;
 
-    // Iterating accounts is safer than indexing
-    let accounts_iter = &mut accounts.iter();
+pub use processor::process_instruction;
+pub use state::GreetingAccount;
 
-    // Get the account to say hello to
-    let account = next_account_info(accounts_iter)?;
-
-    // The account must be owned by the program in order to modify its data
-    if account.owner != program_id {
-        msg!("Greeted account does not have the correct program id");
-        return Err(ProgramError::IncorrectProgramId);
-    }
+// Address of the program, matching `helloworld-keypair.json`
+declare_id!("D8BRe1wwXm1rWPSwwLcbHk6Axwj39xFUSrm5xuTP7EeT");
 
-    // Increment and store the number of times the account has been greeted
-    let mut greeting_account = GreetingAccount::try_from_slice(&account.data.borrow())?;
-    greeting_account.counter += 1;
-    greeting_account.serialize(&mut &mut account.data.borrow_mut()[..])?;
-
-    msg!("Greeted {} time(s)!", greeting_account.counter);
-
-    Ok(())
-}
+// Declare and export the program's entrypoint, unless the program is linked
+// into another one
+#[cfg(all(feature = "entrypoint", not(feature = "no-entrypoint")))]
+solana_program::entrypoint!(process_instruction);
 
 // Sanity tests
 #[cfg(test)]
 mod test {
     use super::*;
-    use solana_program::clock::Epoch;
-    use std::mem;
+    use crate::{
+        instruction::HelloInstruction,
+        state::{
+            find_config_address, find_denylist_address, find_greeter_address, find_history_address,
+            find_leaderboard_address, find_registry_address, find_treasury_address, AccountState,
+        },
+    };
+    use borsh::BorshSerialize;
+    use solana_program::{
+        account_info::AccountInfo,
+        clock::{Clock, Epoch},
+        entrypoint::SUCCESS,
+        program_stubs::{set_syscall_stubs, SyscallStubs},
+        pubkey::Pubkey,
+        rent::Rent,
+        system_program, sysvar,
+    };
+
+    const SLOT: u64 = 42;
+
+    /// Serve the clock and rent sysvars outside of the runtime
+    struct SysvarStubs;
+
+    impl SyscallStubs for SysvarStubs {
+        fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+            let clock = Clock {
+                slot: SLOT,
+                ..Clock::default()
+            };
+            unsafe { *(var_addr as *mut Clock) = clock };
+            SUCCESS
+        }
+
+        fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+            unsafe { *(var_addr as *mut Rent) = Rent::default() };
+            SUCCESS
+        }
+    }
 
     #[test]
     fn test_sanity() {
-        let program_id = Pubkey::default();
+        set_syscall_stubs(Box::new(SysvarStubs));
+        let program_id = id();
         let key = Pubkey::default();
-        let mut lamports = 0;
-        let mut data = vec![0; mem::size_of::<u32>()];
-        let owner = Pubkey::default();
+        let mut lamports = Rent::default().minimum_balance(GreetingAccount::LEN);
+        let mut data = vec![0; GreetingAccount::LEN];
+        let owner = program_id;
         let account = AccountInfo::new(
             &key,
             false,
@@ -72,29 +83,173 @@ mod test {
             false,
             Epoch::default(),
         );
-        let instruction_data: Vec<u8> = Vec::new();
+        let greeter_key = Pubkey::new_unique();
+        let mut greeter_lamports = 0;
+        let mut greeter_data = vec![];
+        let greeter = AccountInfo::new(
+            &greeter_key,
+            true,
+            false,
+            &mut greeter_lamports,
+            &mut greeter_data,
+            &system_program::ID,
+            false,
+            Epoch::default(),
+        );
+        let config_key = find_config_address(&program_id).0;
+        let mut config_lamports = 0;
+        let mut config_data = vec![];
+        let config = AccountInfo::new(
+            &config_key,
+            false,
+            false,
+            &mut config_lamports,
+            &mut config_data,
+            &system_program::ID,
+            false,
+            Epoch::default(),
+        );
+        let greeter_account_key = find_greeter_address(&program_id, &greeter_key).0;
+        let mut greeter_account_lamports = 0;
+        let mut greeter_account_data = vec![];
+        let greeter_account = AccountInfo::new(
+            &greeter_account_key,
+            false,
+            true,
+            &mut greeter_account_lamports,
+            &mut greeter_account_data,
+            &system_program::ID,
+            false,
+            Epoch::default(),
+        );
+        let treasury_key = find_treasury_address(&program_id).0;
+        let mut treasury_lamports = 0;
+        let mut treasury_data = vec![];
+        let treasury = AccountInfo::new(
+            &treasury_key,
+            false,
+            true,
+            &mut treasury_lamports,
+            &mut treasury_data,
+            &system_program::ID,
+            false,
+            Epoch::default(),
+        );
+        let mut system_program_lamports = 0;
+        let mut system_program_data = vec![];
+        let system_program = AccountInfo::new(
+            &system_program::ID,
+            false,
+            false,
+            &mut system_program_lamports,
+            &mut system_program_data,
+            &system_program::ID,
+            true,
+            Epoch::default(),
+        );
+        let registry_key = find_registry_address(&program_id).0;
+        let mut registry_lamports = 0;
+        let mut registry_data = vec![];
+        let registry = AccountInfo::new(
+            &registry_key,
+            false,
+            true,
+            &mut registry_lamports,
+            &mut registry_data,
+            &system_program::ID,
+            false,
+            Epoch::default(),
+        );
+        let history_key = find_history_address(&program_id).0;
+        let mut history_lamports = 0;
+        let mut history_data = vec![];
+        let history = AccountInfo::new(
+            &history_key,
+            false,
+            true,
+            &mut history_lamports,
+            &mut history_data,
+            &system_program::ID,
+            false,
+            Epoch::default(),
+        );
+        let leaderboard_key = find_leaderboard_address(&program_id).0;
+        let mut leaderboard_lamports = 0;
+        let mut leaderboard_data = vec![];
+        let leaderboard = AccountInfo::new(
+            &leaderboard_key,
+            false,
+            true,
+            &mut leaderboard_lamports,
+            &mut leaderboard_data,
+            &system_program::ID,
+            false,
+            Epoch::default(),
+        );
+        let denylist_key = find_denylist_address(&program_id).0;
+        let mut denylist_lamports = 0;
+        let mut denylist_data = vec![];
+        let denylist = AccountInfo::new(
+            &denylist_key,
+            false,
+            false,
+            &mut denylist_lamports,
+            &mut denylist_data,
+            &system_program::ID,
+            false,
+            Epoch::default(),
+        );
+        let instructions_key = sysvar::instructions::id();
+        let mut instructions_lamports = 0;
+        let mut instructions_data = vec![];
+        let instructions = AccountInfo::new(
+            &instructions_key,
+            false,
+            false,
+            &mut instructions_lamports,
+            &mut instructions_data,
+            &sysvar::ID,
+            false,
+            Epoch::default(),
+        );
+        let instruction_data = HelloInstruction::SayHello {
+            proof: vec![],
+            message: String::new(),
+        }
+        .try_to_vec()
+        .unwrap();
 
-        let accounts = vec![account];
+        let accounts = vec![
+            account,
+            greeter,
+            config,
+            greeter_account,
+            treasury,
+            system_program,
+            registry,
+            history,
+            leaderboard,
+            denylist,
+            instructions,
+        ];
 
         assert_eq!(
-            GreetingAccount::try_from_slice(&accounts[0].data.borrow())
+            GreetingAccount::load(&accounts[0].data.borrow())
                 .unwrap()
                 .counter,
             0
         );
         process_instruction(&program_id, &accounts, &instruction_data).unwrap();
         assert_eq!(
-            GreetingAccount::try_from_slice(&accounts[0].data.borrow())
+            GreetingAccount::load(&accounts[0].data.borrow())
                 .unwrap()
                 .counter,
             1
         );
         process_instruction(&program_id, &accounts, &instruction_data).unwrap();
-        assert_eq!(
-            GreetingAccount::try_from_slice(&accounts[0].data.borrow())
-                .unwrap()
-                .counter,
-            2
-        );
+        let greeting_account = GreetingAccount::load(&accounts[0].data.borrow()).unwrap();
+        assert_eq!(greeting_account.counter, 2);
+        assert_eq!(greeting_account.last_greeter, greeter_key);
+        assert_eq!(greeting_account.last_slot, SLOT);
     }
 }
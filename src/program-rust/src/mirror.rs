@@ -0,0 +1,149 @@
+//! Stable-layout mirror of a greeting account
+//!
+//! Other on-chain programs can read a mirror at the fixed offsets below without
+//! depending on this crate or on Borsh. The layout only ever grows at the end,
+//! and a changed meaning of existing bytes bumps `VERSION`. Integers are
+//! little-endian.
+//!
+//! | offset | size | field                                   |
+//! |--------|------|-----------------------------------------|
+//! | 0      | 4    | `MAGIC`                                 |
+//! | 4      | 1    | layout version                          |
+//! | 5      | 1    | bump seed of the mirror address         |
+//! | 6      | 2    | padding                                 |
+//! | 8      | 32   | greeting account mirrored               |
+//! | 40     | 4    | greeting counter                        |
+//! | 44     | 4    | padding                                 |
+//! | 48     | 8    | expiry timestamp, 0 if it never expires |
+//! | 56     | 8    | slot of the latest update               |
+
+use crate::state::GreetingAccount;
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+use std::convert::TryInto;
+
+/// Seed prefix of the mirror accounts
+pub const MIRROR_SEED: &[u8] = b"mirror";
+
+/// Marks an initialized mirror account, and tells mirrors apart from the
+/// discriminated `state` accounts
+pub const MAGIC: [u8; 4] = *b"HWMR";
+/// Current layout version
+pub const VERSION: u8 = 1;
+
+// Byte offsets of the fields, see the layout above
+pub const MAGIC_OFFSET: usize = 0;
+pub const VERSION_OFFSET: usize = 4;
+pub const BUMP_OFFSET: usize = 5;
+pub const GREETING_OFFSET: usize = 8;
+pub const COUNTER_OFFSET: usize = 40;
+pub const EXPIRES_AT_OFFSET: usize = 48;
+pub const SLOT_OFFSET: usize = 56;
+
+/// Space needed by a mirror account
+pub const LEN: usize = 64;
+
+/// Address of the mirror of `greeting` and its bump seed
+pub fn find_mirror_address(program_id: &Pubkey, greeting: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[MIRROR_SEED, greeting.as_ref()], program_id)
+}
+
+/// Decoded contents of a mirror account
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Mirror {
+    pub bump_seed: u8,
+    pub greeting: Pubkey,
+    pub counter: u32,
+    pub expires_at: i64,
+    pub slot: u64,
+}
+
+impl Mirror {
+    /// Mirror of `greeting_account` as of `slot`
+    pub fn new(
+        bump_seed: u8,
+        greeting: Pubkey,
+        greeting_account: &GreetingAccount,
+        slot: u64,
+    ) -> Self {
+        Self {
+            bump_seed,
+            greeting,
+            counter: greeting_account.counter,
+            expires_at: greeting_account.expires_at,
+            slot,
+        }
+    }
+
+    /// Write the mirror into `data`, which must hold at least `LEN` bytes
+    pub fn pack(&self, data: &mut [u8]) -> Result<(), ProgramError> {
+        if data.len() < LEN {
+            return Err(ProgramError::AccountDataTooSmall);
+        }
+        data[..LEN].fill(0);
+        data[MAGIC_OFFSET..MAGIC_OFFSET + 4].copy_from_slice(&MAGIC);
+        data[VERSION_OFFSET] = VERSION;
+        data[BUMP_OFFSET] = self.bump_seed;
+        data[GREETING_OFFSET..GREETING_OFFSET + 32].copy_from_slice(self.greeting.as_ref());
+        data[COUNTER_OFFSET..COUNTER_OFFSET + 4].copy_from_slice(&self.counter.to_le_bytes());
+        data[EXPIRES_AT_OFFSET..EXPIRES_AT_OFFSET + 8]
+            .copy_from_slice(&self.expires_at.to_le_bytes());
+        data[SLOT_OFFSET..SLOT_OFFSET + 8].copy_from_slice(&self.slot.to_le_bytes());
+        Ok(())
+    }
+
+    /// Read an initialized mirror from `data`
+    pub fn unpack(data: &[u8]) -> Result<Self, ProgramError> {
+        if data.len() < LEN {
+            return Err(ProgramError::AccountDataTooSmall);
+        }
+        if data[MAGIC_OFFSET..MAGIC_OFFSET + 4] != MAGIC || data[VERSION_OFFSET] != VERSION {
+            return Err(ProgramError::UninitializedAccount);
+        }
+        Ok(Self {
+            bump_seed: data[BUMP_OFFSET],
+            greeting: Pubkey::new_from_array(
+                data[GREETING_OFFSET..GREETING_OFFSET + 32]
+                    .try_into()
+                    .unwrap(),
+            ),
+            counter: u32::from_le_bytes(
+                data[COUNTER_OFFSET..COUNTER_OFFSET + 4].try_into().unwrap(),
+            ),
+            expires_at: i64::from_le_bytes(
+                data[EXPIRES_AT_OFFSET..EXPIRES_AT_OFFSET + 8]
+                    .try_into()
+                    .unwrap(),
+            ),
+            slot: u64::from_le_bytes(data[SLOT_OFFSET..SLOT_OFFSET + 8].try_into().unwrap()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_layout() {
+        let mirror = Mirror {
+            bump_seed: 254,
+            greeting: Pubkey::new_unique(),
+            counter: 0x0102_0304,
+            expires_at: -2,
+            slot: 42,
+        };
+        let mut data = [0xff; LEN];
+        mirror.pack(&mut data).unwrap();
+
+        assert_eq!(&data[..4], b"HWMR");
+        assert_eq!(data[4], 1);
+        assert_eq!(data[5], 254);
+        assert_eq!(&data[6..8], &[0, 0]);
+        assert_eq!(&data[8..40], mirror.greeting.as_ref());
+        assert_eq!(&data[40..48], &[4, 3, 2, 1, 0, 0, 0, 0]);
+        assert_eq!(&data[48..56], &(-2i64).to_le_bytes());
+        assert_eq!(&data[56..64], &42u64.to_le_bytes());
+        assert_eq!(Mirror::unpack(&data).unwrap(), mirror);
+        assert!(Mirror::unpack(&[0; LEN]).is_err());
+    }
+}
@@ -0,0 +1,2829 @@
+use crate::{
+    allowlist,
+    backup::GreetingDelta,
+    error::HelloWorldError,
+    fees::FeePolicy,
+    instruction::{
+        secp256k1_greeting_message, ConfigUpdate, HelloInstruction, MultisigAction, MAX_MESSAGE_LEN,
+    },
+    mirror::{self, find_mirror_address, Mirror, MIRROR_SEED},
+    state::{
+        find_channels_address, find_config_address, find_denylist_address, find_greeter_address,
+        find_history_address, find_leaderboard_address, find_metadata_address,
+        find_multisig_address, find_proposal_address, find_registry_address, find_timelock_address,
+        find_treasury_address, AccountState, Channels, Config, Denylist, GreeterAccount,
+        GreetingAccount, GreetingAccountPod, GreetingAccountV1, GreetingAccountV2,
+        GreetingAccountV3, GuardianSet, History, Leaderboard, Metadata, Multisig, PendingRecovery,
+        Proposal, QueuedAction, Registry, Timelock, Treasury, CHANNELS_SEED, CONFIG_SEED,
+        DENYLIST_SEED, DISCRIMINATOR_LEN, GREETER_SEED, GREETING_SEED, HISTORY_SEED,
+        LEADERBOARD_SEED, MAX_GUARDIANS, MAX_MULTISIG_SIGNERS, MAX_QUEUED_ACTIONS, METADATA_SEED,
+        MULTISIG_SEED, PROPOSAL_SEED, REGISTRY_SEED, TIMELOCK_SEED, TREASURY_SEED,
+    },
+};
+use borsh::BorshDeserialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    hash::hash,
+    msg,
+    program::{invoke, invoke_signed},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    secp256k1_program, system_instruction, system_program,
+    sysvar::{
+        self,
+        instructions::{get_instruction_relative, load_current_index_checked},
+        rent::Rent,
+        Sysvar,
+    },
+};
+use std::slice::Iter;
+
+/// Size of an Ethereum address
+const ETH_ADDRESS_SIZE: usize = 20;
+
+/// Size of the serialized offsets of one secp256k1 signature
+const SECP256K1_SIGNATURE_OFFSETS_SIZE: usize = 11;
+
+// Program entrypoint's implementation
+pub fn process_instruction(
+    program_id: &Pubkey, // Public key of the account the hello world program was loaded into
+    accounts: &[AccountInfo], // The accounts to say hello to
+    instruction_data: &[u8], // Borsh encoded `HelloInstruction`
+) -> ProgramResult {
+    msg!("Hello World Rust program entrypoint");
+
+    // Instructions are only ever built for the declared id, running under
+    // any other id means the program was deployed or invoked by mistake
+    if !crate::check_id(program_id) {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    check_no_duplicate_writable(accounts)?;
+
+    let instruction = HelloInstruction::try_from_slice(instruction_data)
+        .map_err(|_| HelloWorldError::InvalidInstruction)?;
+
+    match instruction {
+        HelloInstruction::SayHello { proof, message } => {
+            // Turn invalid messages away before looking at any account
+            check_message(None, &message)?;
+            process_say_hello(program_id, accounts, &proof, &message)
+        }
+        HelloInstruction::BatchGreet { proof } => process_batch_greet(program_id, accounts, &proof),
+        HelloInstruction::SayHelloSecp256k1 { eth_address, proof } => {
+            process_say_hello_secp256k1(program_id, accounts, eth_address, &proof)
+        }
+        HelloInstruction::RegisterGuardians {
+            guardians,
+            threshold,
+            recovery_delay,
+        } => process_register_guardians(program_id, accounts, guardians, threshold, recovery_delay),
+        HelloInstruction::InitiateRecovery { new_owner } => {
+            process_initiate_recovery(program_id, accounts, new_owner)
+        }
+        HelloInstruction::RecoverOwnership => process_recover_ownership(program_id, accounts),
+        HelloInstruction::CancelRecovery => process_cancel_recovery(program_id, accounts),
+        HelloInstruction::InitializeConfig => process_initialize_config(program_id, accounts),
+        HelloInstruction::SetAllowlistRoot { root } => {
+            process_set_allowlist_root(program_id, accounts, root)
+        }
+        HelloInstruction::RegisterGreeter => process_register_greeter(program_id, accounts),
+        HelloInstruction::SetRateLimit { slots } => {
+            process_set_rate_limit(program_id, accounts, slots)
+        }
+        HelloInstruction::SetGreetingExpiry { ttl } => {
+            process_set_greeting_expiry(program_id, accounts, ttl)
+        }
+        HelloInstruction::Reap => process_reap(program_id, accounts),
+        HelloInstruction::CreateMirror => process_create_mirror(program_id, accounts),
+        HelloInstruction::SetFeePolicy { policy } => {
+            process_set_fee_policy(program_id, accounts, policy)
+        }
+        HelloInstruction::Migrate => process_migrate(program_id, accounts),
+        HelloInstruction::ExportDelta { since_version } => {
+            process_export_delta(program_id, accounts, since_version)
+        }
+        HelloInstruction::ApplyDelta { delta } => process_apply_delta(program_id, accounts, &delta),
+        HelloInstruction::UpdateConfig { update } => {
+            process_update_config(program_id, accounts, update)
+        }
+        HelloInstruction::InitializeRegistry => process_initialize_registry(program_id, accounts),
+        HelloInstruction::InitializeHistory => process_initialize_history(program_id, accounts),
+        HelloInstruction::InitializeLeaderboard => {
+            process_initialize_leaderboard(program_id, accounts)
+        }
+        HelloInstruction::WriteMetadata => process_write_metadata(program_id, accounts),
+        HelloInstruction::CreateChannel { name } => {
+            process_create_channel(program_id, accounts, &name)
+        }
+        HelloInstruction::InitializeTreasury => process_initialize_treasury(program_id, accounts),
+        HelloInstruction::Withdraw { amount } => process_withdraw(program_id, accounts, amount),
+        HelloInstruction::AddToDenylist { greeter } => {
+            process_add_to_denylist(program_id, accounts, greeter)
+        }
+        HelloInstruction::RemoveFromDenylist { greeter } => {
+            process_remove_from_denylist(program_id, accounts, &greeter)
+        }
+        HelloInstruction::CreateMultisig { signers, threshold } => {
+            process_create_multisig(program_id, accounts, signers, threshold)
+        }
+        HelloInstruction::Propose { action } => process_propose(program_id, accounts, action),
+        HelloInstruction::Approve => process_approve(program_id, accounts),
+        HelloInstruction::Execute => process_execute(program_id, accounts),
+        HelloInstruction::QueueAction { update } => {
+            process_queue_action(program_id, accounts, update)
+        }
+        HelloInstruction::ExecuteAction { id } => process_execute_action(program_id, accounts, id),
+        HelloInstruction::CancelAction { id } => process_cancel_action(program_id, accounts, id),
+        HelloInstruction::InitializeIfNeeded => process_initialize_if_needed(program_id, accounts),
+        HelloInstruction::AcceptAdmin => process_accept_admin(program_id, accounts),
+    }
+}
+
+fn process_say_hello(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    proof: &[[u8; 32]],
+    message: &str,
+) -> ProgramResult {
+    // Iterating accounts is safer than indexing
+    let accounts_iter = &mut accounts.iter();
+
+    // Get the account to say hello to
+    let account = next_account_info(accounts_iter)?;
+    let greeter = next_account_info(accounts_iter)?;
+    let config_account = next_account_info(accounts_iter)?;
+    let greeter_account = next_account_info(accounts_iter)?;
+    let treasury = next_account_info(accounts_iter)?;
+    let system_program_account = next_system_program(accounts_iter)?;
+    let registry_account = next_account_info(accounts_iter)?;
+    let history_account = next_account_info(accounts_iter)?;
+    let leaderboard_account = next_account_info(accounts_iter)?;
+    let denylist_account = next_account_info(accounts_iter)?;
+    let instructions_sysvar = next_account_info(accounts_iter)?;
+
+    let config = check_greeter(program_id, config_account, greeter, greeter_account, proof)?;
+    check_message(config.as_ref(), message)?;
+    check_top_level(program_id, config.as_ref(), instructions_sysvar)?;
+    check_not_denied(program_id, denylist_account, greeter.key)?;
+    update_leaderboard(
+        program_id,
+        leaderboard_account,
+        greeter.key,
+        greeter_account,
+    )?;
+
+    if !message.is_empty() {
+        msg!("{}", message_log(config.as_ref(), message));
+    }
+    // Greeting an empty account creates it if it is the greeter's greeting
+    // address
+    if account.data_is_empty() {
+        create_greeting_account(program_id, account, greeter, system_program_account)?;
+        register_greeting_account(program_id, registry_account, account.key)?;
+    }
+    let count = greet(
+        program_id,
+        config.as_ref(),
+        greeter.key,
+        account,
+        accounts_iter,
+    )?;
+    record_history(program_id, history_account, greeter.key, message)?;
+
+    // Guard the account while the fee transfer calls out to the system
+    // program, anything re-entering the program in between sees it as busy
+    let fee = greeting_fee(config.as_ref(), count, message.len())?;
+    set_processing(account, true)?;
+    charge_fee(program_id, greeter, treasury, system_program_account, fee)?;
+    set_processing(account, false)
+}
+
+fn process_batch_greet(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    proof: &[[u8; 32]],
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let greeter = next_account_info(accounts_iter)?;
+    let config_account = next_account_info(accounts_iter)?;
+    let greeter_account = next_account_info(accounts_iter)?;
+    let treasury = next_account_info(accounts_iter)?;
+    let system_program_account = next_system_program(accounts_iter)?;
+    let instructions_sysvar = next_account_info(accounts_iter)?;
+
+    let config = check_greeter(program_id, config_account, greeter, greeter_account, proof)?;
+    check_top_level(program_id, config.as_ref(), instructions_sysvar)?;
+
+    let mut greeted = 0;
+    let mut fee = 0u64;
+    while let Some(account) = accounts_iter.next() {
+        let count = greet(
+            program_id,
+            config.as_ref(),
+            greeter.key,
+            account,
+            accounts_iter,
+        )?;
+        fee = fee
+            .checked_add(greeting_fee(config.as_ref(), count, 0)?)
+            .ok_or(HelloWorldError::ArithmeticOverflow)?;
+        greeted += 1;
+    }
+    msg!("Greeted {} account(s)", greeted);
+
+    charge_fee(program_id, greeter, treasury, system_program_account, fee)
+}
+
+fn process_say_hello_secp256k1(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    eth_address: [u8; ETH_ADDRESS_SIZE],
+    proof: &[[u8; 32]],
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let account = next_account_info(accounts_iter)?;
+    let instructions_sysvar = next_account_info(accounts_iter)?;
+    let config_account = next_account_info(accounts_iter)?;
+    let payer = next_account_info(accounts_iter)?;
+    let treasury = next_account_info(accounts_iter)?;
+    let system_program_account = next_system_program(accounts_iter)?;
+
+    if !payer.is_signer {
+        msg!("Payer must sign");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    let config = load_config(program_id, config_account)?;
+    check_not_paused(config.as_ref())?;
+    check_top_level(program_id, config.as_ref(), instructions_sysvar)?;
+    check_allowlist(config.as_ref(), &eth_address, proof)?;
+
+    let counter = load_checked::<GreetingAccount>(program_id, account)?.counter;
+    verify_secp256k1_signature(
+        instructions_sysvar,
+        &eth_address,
+        &secp256k1_greeting_message(account.key, counter),
+    )?;
+    msg!("Greeting on behalf of 0x{}", hex(&eth_address));
+
+    greet(
+        program_id,
+        config.as_ref(),
+        &Pubkey::default(),
+        account,
+        accounts_iter,
+    )?;
+
+    let fee = greeting_fee(config.as_ref(), counter, 0)?;
+    charge_fee(program_id, payer, treasury, system_program_account, fee)
+}
+
+fn process_register_guardians(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    guardians: Vec<Pubkey>,
+    threshold: u8,
+    recovery_delay: u64,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let guardian_set_account = next_account_info(accounts_iter)?;
+    let greeting_account = next_account_info(accounts_iter)?;
+    let owner = next_account_info(accounts_iter)?;
+    let config_account = next_account_info(accounts_iter)?;
+
+    check_not_paused(load_config(program_id, config_account)?.as_ref())?;
+    if !owner.is_signer {
+        msg!("Greeting account owner must sign");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if greeting_account.owner != program_id {
+        msg!("Greeting account does not have the correct program id");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let mut guardian_set = load_guardian_set(program_id, guardian_set_account)?;
+    if guardian_set.is_initialized {
+        msg!("Guardians are already registered");
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    if guardians.len() > MAX_GUARDIANS {
+        msg!("At most {} guardians can be registered", MAX_GUARDIANS);
+        return Err(ProgramError::InvalidArgument);
+    }
+    if threshold == 0 || threshold as usize > guardians.len() {
+        msg!("Threshold must be between 1 and the number of guardians");
+        return Err(ProgramError::InvalidArgument);
+    }
+    if (1..guardians.len()).any(|i| guardians[..i].contains(&guardians[i])) {
+        msg!("Guardians must be distinct");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    guardian_set = GuardianSet {
+        is_initialized: true,
+        greeting: *greeting_account.key,
+        owner: *owner.key,
+        threshold,
+        recovery_delay,
+        guardians,
+        pending_recovery: None,
+    };
+    guardian_set.store(&mut guardian_set_account.data.borrow_mut())?;
+
+    msg!(
+        "Registered {} guardian(s) with a threshold of {}",
+        guardian_set.guardians.len(),
+        threshold
+    );
+
+    Ok(())
+}
+
+fn process_initiate_recovery(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    new_owner: Pubkey,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let guardian_set_account = next_account_info(accounts_iter)?;
+    let config_account = next_account_info(accounts_iter)?;
+
+    check_not_paused(load_config(program_id, config_account)?.as_ref())?;
+    let mut guardian_set = load_initialized_guardian_set(program_id, guardian_set_account)?;
+
+    // Count each signing guardian once, however many times it is passed
+    let mut approvals: Vec<&Pubkey> = Vec::new();
+    for signer in accounts_iter.filter(|account| account.is_signer) {
+        if guardian_set.guardians.contains(signer.key) && !approvals.contains(&signer.key) {
+            approvals.push(signer.key);
+        }
+    }
+    if approvals.len() < guardian_set.threshold as usize {
+        msg!(
+            "Recovery needs {} guardian signature(s), got {}",
+            guardian_set.threshold,
+            approvals.len()
+        );
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let recoverable_slot = Clock::get()?
+        .slot
+        .checked_add(guardian_set.recovery_delay)
+        .ok_or(HelloWorldError::ArithmeticOverflow)?;
+    guardian_set.pending_recovery = Some(PendingRecovery {
+        new_owner,
+        recoverable_slot,
+    });
+    guardian_set.store(&mut guardian_set_account.data.borrow_mut())?;
+
+    msg!(
+        "Recovery to {} can complete at slot {}",
+        new_owner,
+        recoverable_slot
+    );
+
+    Ok(())
+}
+
+fn process_recover_ownership(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let guardian_set_account = next_account_info(accounts_iter)?;
+    let config_account = next_account_info(accounts_iter)?;
+
+    check_not_paused(load_config(program_id, config_account)?.as_ref())?;
+    let mut guardian_set = load_initialized_guardian_set(program_id, guardian_set_account)?;
+
+    let pending_recovery = guardian_set.pending_recovery.take().ok_or_else(|| {
+        msg!("No recovery is pending");
+        ProgramError::InvalidArgument
+    })?;
+    if Clock::get()?.slot < pending_recovery.recoverable_slot {
+        msg!(
+            "Recovery cannot complete before slot {}",
+            pending_recovery.recoverable_slot
+        );
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    guardian_set.owner = pending_recovery.new_owner;
+    guardian_set.store(&mut guardian_set_account.data.borrow_mut())?;
+
+    msg!("Ownership recovered to {}", guardian_set.owner);
+
+    Ok(())
+}
+
+fn process_cancel_recovery(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let guardian_set_account = next_account_info(accounts_iter)?;
+    let owner = next_account_info(accounts_iter)?;
+    let config_account = next_account_info(accounts_iter)?;
+
+    check_not_paused(load_config(program_id, config_account)?.as_ref())?;
+    let mut guardian_set = load_initialized_guardian_set(program_id, guardian_set_account)?;
+    if !owner.is_signer {
+        msg!("Owner must sign");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if *owner.key != guardian_set.owner {
+        msg!("Only the current owner can cancel a recovery");
+        return Err(HelloWorldError::Unauthorized.into());
+    }
+    if guardian_set.pending_recovery.take().is_none() {
+        msg!("No recovery is pending");
+        return Err(ProgramError::InvalidArgument);
+    }
+    guardian_set.store(&mut guardian_set_account.data.borrow_mut())?;
+
+    msg!("Recovery cancelled");
+
+    Ok(())
+}
+
+fn process_initialize_config(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let config_account = next_account_info(accounts_iter)?;
+    let admin = next_account_info(accounts_iter)?;
+    let system_program_account = next_system_program(accounts_iter)?;
+
+    check_writable(config_account)?;
+
+    let (config_address, bump_seed) = find_config_address(program_id);
+    if *config_account.key != config_address {
+        msg!("Config account does not have the expected address");
+        return Err(ProgramError::InvalidArgument);
+    }
+    if !admin.is_signer {
+        msg!("Admin must sign");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if config_account.owner == program_id {
+        msg!("Config is already initialized");
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    invoke_signed(
+        &system_instruction::create_account(
+            admin.key,
+            config_account.key,
+            Rent::get()?.minimum_balance(Config::LEN),
+            Config::LEN as u64,
+            program_id,
+        ),
+        &[
+            admin.clone(),
+            config_account.clone(),
+            system_program_account.clone(),
+        ],
+        &[&[CONFIG_SEED, &[bump_seed]]],
+    )?;
+
+    let config = Config {
+        is_initialized: true,
+        admin: *admin.key,
+        bump_seed,
+        ..Config::default()
+    };
+    config.store(&mut config_account.data.borrow_mut())?;
+
+    msg!("Config initialized with admin {}", admin.key);
+
+    Ok(())
+}
+
+fn process_set_allowlist_root(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    root: Option<[u8; 32]>,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let config_account = next_account_info(accounts_iter)?;
+    let admin = next_account_info(accounts_iter)?;
+
+    check_writable(config_account)?;
+
+    let mut config = load_config_as_admin(program_id, config_account, admin)?;
+    config.allowlist_root = root;
+    config.store(&mut config_account.data.borrow_mut())?;
+
+    match root {
+        Some(_) => msg!("Greeters restricted to the allowlist"),
+        None => msg!("Allowlist lifted"),
+    }
+
+    Ok(())
+}
+
+fn process_register_greeter(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let greeter_account = next_account_info(accounts_iter)?;
+    let greeter = next_account_info(accounts_iter)?;
+    let system_program_account = next_system_program(accounts_iter)?;
+    let config_account = next_account_info(accounts_iter)?;
+
+    check_writable(greeter_account)?;
+    check_not_paused(load_config(program_id, config_account)?.as_ref())?;
+
+    let (greeter_address, bump_seed) = find_greeter_address(program_id, greeter.key);
+    if *greeter_account.key != greeter_address {
+        msg!("Greeter account does not have the expected address");
+        return Err(ProgramError::InvalidArgument);
+    }
+    if !greeter.is_signer {
+        msg!("Greeter must sign");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if greeter_account.owner == program_id {
+        msg!("Greeter is already registered");
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    invoke_signed(
+        &system_instruction::create_account(
+            greeter.key,
+            greeter_account.key,
+            Rent::get()?.minimum_balance(GreeterAccount::LEN),
+            GreeterAccount::LEN as u64,
+            program_id,
+        ),
+        &[
+            greeter.clone(),
+            greeter_account.clone(),
+            system_program_account.clone(),
+        ],
+        &[&[GREETER_SEED, greeter.key.as_ref(), &[bump_seed]]],
+    )?;
+
+    let greeter_state = GreeterAccount {
+        is_initialized: true,
+        bump_seed,
+        ..GreeterAccount::default()
+    };
+    greeter_state.store(&mut greeter_account.data.borrow_mut())?;
+
+    msg!("Registered greeter {}", greeter.key);
+
+    Ok(())
+}
+
+fn process_set_rate_limit(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    slots: u64,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let config_account = next_account_info(accounts_iter)?;
+    let admin = next_account_info(accounts_iter)?;
+
+    check_writable(config_account)?;
+
+    let mut config = load_config_as_admin(program_id, config_account, admin)?;
+    config.rate_limit_slots = slots;
+    config.store(&mut config_account.data.borrow_mut())?;
+
+    msg!(
+        "Greeters rate limited to one greeting every {} slot(s)",
+        slots
+    );
+
+    Ok(())
+}
+
+fn process_set_greeting_expiry(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    ttl: i64,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let config_account = next_account_info(accounts_iter)?;
+    let admin = next_account_info(accounts_iter)?;
+
+    check_writable(config_account)?;
+
+    if ttl < 0 {
+        msg!("Greeting TTL cannot be negative");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let mut config = load_config_as_admin(program_id, config_account, admin)?;
+    config.greeting_ttl = ttl;
+    config.store(&mut config_account.data.borrow_mut())?;
+
+    msg!("Greetings expire after {} second(s)", ttl);
+
+    Ok(())
+}
+
+fn process_reap(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let account = next_account_info(accounts_iter)?;
+    let treasury = next_account_info(accounts_iter)?;
+    let config_account = next_account_info(accounts_iter)?;
+
+    check_writable(account)?;
+    check_writable(treasury)?;
+    check_not_paused(load_config(program_id, config_account)?.as_ref())?;
+
+    let greeting_account = load_checked::<GreetingAccount>(program_id, account)?;
+    check_treasury(program_id, treasury)?;
+
+    let now = Clock::get()?.unix_timestamp;
+    if greeting_account.expires_at == 0 || now < greeting_account.expires_at {
+        msg!("Greeting has not expired");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let lamports = close_account(account, treasury)?;
+
+    msg!("Reaped greeting account holding {} lamport(s)", lamports);
+
+    let mut collected = lamports;
+    if greeting_account.has_mirror {
+        let mirror_account = next_account_info(accounts_iter)?;
+        load_mirror(program_id, account.key, mirror_account)?;
+        let lamports = close_account(mirror_account, treasury)?;
+        collected = collected
+            .checked_add(lamports)
+            .ok_or(HelloWorldError::ArithmeticOverflow)?;
+    }
+
+    record_treasury_income(program_id, treasury, collected)
+}
+
+fn process_create_mirror(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let account = next_account_info(accounts_iter)?;
+    let mirror_account = next_account_info(accounts_iter)?;
+    let payer = next_account_info(accounts_iter)?;
+    let system_program_account = next_system_program(accounts_iter)?;
+    let config_account = next_account_info(accounts_iter)?;
+
+    check_writable(account)?;
+    check_writable(mirror_account)?;
+    check_not_paused(load_config(program_id, config_account)?.as_ref())?;
+
+    if !payer.is_signer {
+        msg!("Payer must sign");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    let mut greeting_account = load_checked::<GreetingAccount>(program_id, account)?;
+    if greeting_account.has_mirror {
+        msg!("Greeting account is already mirrored");
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+    let (mirror_address, bump_seed) = find_mirror_address(program_id, account.key);
+    if *mirror_account.key != mirror_address {
+        msg!("Mirror account does not have the expected address");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    invoke_signed(
+        &system_instruction::create_account(
+            payer.key,
+            mirror_account.key,
+            Rent::get()?.minimum_balance(mirror::LEN),
+            mirror::LEN as u64,
+            program_id,
+        ),
+        &[
+            payer.clone(),
+            mirror_account.clone(),
+            system_program_account.clone(),
+        ],
+        &[&[MIRROR_SEED, account.key.as_ref(), &[bump_seed]]],
+    )?;
+
+    let slot = Clock::get()?.slot;
+    Mirror::new(bump_seed, *account.key, &greeting_account, slot)
+        .pack(&mut mirror_account.data.borrow_mut())?;
+    greeting_account.has_mirror = true;
+    greeting_account.store(&mut account.data.borrow_mut())?;
+
+    msg!("Mirroring {} into {}", account.key, mirror_account.key);
+
+    Ok(())
+}
+
+fn process_set_fee_policy(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    policy: FeePolicy,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let config_account = next_account_info(accounts_iter)?;
+    let admin = next_account_info(accounts_iter)?;
+
+    check_writable(config_account)?;
+
+    policy.validate()?;
+    let mut config = load_config_as_admin(program_id, config_account, admin)?;
+    check_not_timelocked(&config)?;
+    msg!("Greetings priced with {:?}", policy);
+    config.fee_policy = policy;
+    config.store(&mut config_account.data.borrow_mut())?;
+
+    Ok(())
+}
+
+fn process_migrate(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let account = next_account_info(accounts_iter)?;
+    let payer = next_account_info(accounts_iter)?;
+    let system_program_account = next_system_program(accounts_iter)?;
+    let config_account = next_account_info(accounts_iter)?;
+
+    check_writable(account)?;
+    check_not_paused(load_config(program_id, config_account)?.as_ref())?;
+
+    if !payer.is_signer {
+        msg!("Payer must sign");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if account.owner != program_id {
+        msg!("Greeted account does not have the correct program id");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let greeting_account: GreetingAccount = match account.data_len() {
+        GreetingAccount::LEN => {
+            GreetingAccount::load(&account.data.borrow())?;
+            msg!(
+                "Greeting account is already at version {}",
+                GreetingAccount::VERSION
+            );
+            return Ok(());
+        }
+        GreetingAccountV1::LEN => GreetingAccountV1::load(&account.data.borrow())?.into(),
+        GreetingAccountV2::LEN => GreetingAccountV2::load(&account.data.borrow())?.into(),
+        GreetingAccountV3::LEN => GreetingAccountV3::load(&account.data.borrow())?.into(),
+        _ => {
+            msg!("Greeting account has an unknown layout");
+            return Err(ProgramError::InvalidAccountData);
+        }
+    };
+
+    let rent_exempt_lamports = Rent::get()?.minimum_balance(GreetingAccount::LEN);
+    let top_up = rent_exempt_lamports.saturating_sub(account.lamports());
+    if top_up > 0 {
+        invoke(
+            &system_instruction::transfer(payer.key, account.key, top_up),
+            &[
+                payer.clone(),
+                account.clone(),
+                system_program_account.clone(),
+            ],
+        )?;
+    }
+    account.realloc(GreetingAccount::LEN, false)?;
+    greeting_account.store(&mut account.data.borrow_mut())?;
+
+    msg!(
+        "Migrated greeting account to version {}",
+        GreetingAccount::VERSION
+    );
+
+    Ok(())
+}
+
+fn process_export_delta(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    since_version: u32,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let account = next_account_info(accounts_iter)?;
+    let backup_account = next_account_info(accounts_iter)?;
+    let config_account = next_account_info(accounts_iter)?;
+    let admin = next_account_info(accounts_iter)?;
+
+    check_writable(backup_account)?;
+
+    load_config_as_admin(program_id, config_account, admin)?;
+    let greeting_account = load_checked::<GreetingAccount>(program_id, account)?;
+    // Only overwrite empty or backup accounts
+    load_checked::<GreetingDelta>(program_id, backup_account)?;
+
+    let delta = GreetingDelta::since(&greeting_account, since_version)?;
+    delta.store(&mut backup_account.data.borrow_mut())?;
+
+    msg!(
+        "Exported {} from version {} to {}",
+        account.key,
+        delta.since_version,
+        delta.version
+    );
+
+    Ok(())
+}
+
+fn process_apply_delta(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    delta: &GreetingDelta,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let account = next_account_info(accounts_iter)?;
+    let config_account = next_account_info(accounts_iter)?;
+    let admin = next_account_info(accounts_iter)?;
+
+    check_writable(account)?;
+
+    load_config_as_admin(program_id, config_account, admin)?;
+    let mut greeting_account = load_checked::<GreetingAccount>(program_id, account)?;
+    delta.apply(&mut greeting_account)?;
+    greeting_account.store(&mut account.data.borrow_mut())?;
+
+    msg!(
+        "Restored {} from version {} to {}",
+        account.key,
+        delta.since_version,
+        delta.version
+    );
+
+    if greeting_account.has_mirror {
+        let mirror_account = next_account_info(accounts_iter)?;
+        let mirror = load_mirror(program_id, account.key, mirror_account)?;
+        Mirror::new(
+            mirror.bump_seed,
+            *account.key,
+            &greeting_account,
+            Clock::get()?.slot,
+        )
+        .pack(&mut mirror_account.data.borrow_mut())?;
+    }
+
+    Ok(())
+}
+
+fn process_update_config(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    update: ConfigUpdate,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let config_account = next_account_info(accounts_iter)?;
+    let admin = next_account_info(accounts_iter)?;
+
+    check_writable(config_account)?;
+
+    let mut config = load_config_as_admin(program_id, config_account, admin)?;
+    if update.is_timelocked() {
+        check_not_timelocked(&config)?;
+    }
+    apply_config_update(&mut config, update)?;
+    config.store(&mut config_account.data.borrow_mut())?;
+
+    Ok(())
+}
+
+fn process_initialize_registry(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let registry_account = next_account_info(accounts_iter)?;
+    let payer = next_account_info(accounts_iter)?;
+    let system_program_account = next_system_program(accounts_iter)?;
+
+    check_writable(registry_account)?;
+
+    let (registry_address, bump_seed) = find_registry_address(program_id);
+    if *registry_account.key != registry_address {
+        msg!("Registry account does not have the expected address");
+        return Err(ProgramError::InvalidArgument);
+    }
+    if !payer.is_signer {
+        msg!("Payer must sign");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if registry_account.owner == program_id {
+        msg!("Registry is already initialized");
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    invoke_signed(
+        &system_instruction::create_account(
+            payer.key,
+            registry_account.key,
+            Rent::get()?.minimum_balance(Registry::LEN),
+            Registry::LEN as u64,
+            program_id,
+        ),
+        &[
+            payer.clone(),
+            registry_account.clone(),
+            system_program_account.clone(),
+        ],
+        &[&[REGISTRY_SEED, &[bump_seed]]],
+    )?;
+
+    let registry = Registry {
+        is_initialized: true,
+        bump_seed,
+        ..Registry::default()
+    };
+    registry.store(&mut registry_account.data.borrow_mut())?;
+
+    msg!("Registry initialized");
+
+    Ok(())
+}
+
+fn process_initialize_history(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let history_account = next_account_info(accounts_iter)?;
+    let payer = next_account_info(accounts_iter)?;
+    let system_program_account = next_system_program(accounts_iter)?;
+
+    check_writable(history_account)?;
+
+    let (history_address, bump_seed) = find_history_address(program_id);
+    if *history_account.key != history_address {
+        msg!("History account does not have the expected address");
+        return Err(ProgramError::InvalidArgument);
+    }
+    if !payer.is_signer {
+        msg!("Payer must sign");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if history_account.owner == program_id {
+        msg!("History is already initialized");
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    invoke_signed(
+        &system_instruction::create_account(
+            payer.key,
+            history_account.key,
+            Rent::get()?.minimum_balance(History::LEN),
+            History::LEN as u64,
+            program_id,
+        ),
+        &[
+            payer.clone(),
+            history_account.clone(),
+            system_program_account.clone(),
+        ],
+        &[&[HISTORY_SEED, &[bump_seed]]],
+    )?;
+
+    let history = History {
+        is_initialized: true,
+        bump_seed,
+        ..History::default()
+    };
+    history.store(&mut history_account.data.borrow_mut())?;
+
+    msg!("History initialized");
+
+    Ok(())
+}
+
+fn process_initialize_leaderboard(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let leaderboard_account = next_account_info(accounts_iter)?;
+    let payer = next_account_info(accounts_iter)?;
+    let system_program_account = next_system_program(accounts_iter)?;
+
+    check_writable(leaderboard_account)?;
+
+    let (leaderboard_address, bump_seed) = find_leaderboard_address(program_id);
+    if *leaderboard_account.key != leaderboard_address {
+        msg!("Leaderboard account does not have the expected address");
+        return Err(ProgramError::InvalidArgument);
+    }
+    if !payer.is_signer {
+        msg!("Payer must sign");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if leaderboard_account.owner == program_id {
+        msg!("Leaderboard is already initialized");
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    invoke_signed(
+        &system_instruction::create_account(
+            payer.key,
+            leaderboard_account.key,
+            Rent::get()?.minimum_balance(Leaderboard::LEN),
+            Leaderboard::LEN as u64,
+            program_id,
+        ),
+        &[
+            payer.clone(),
+            leaderboard_account.clone(),
+            system_program_account.clone(),
+        ],
+        &[&[LEADERBOARD_SEED, &[bump_seed]]],
+    )?;
+
+    let leaderboard = Leaderboard {
+        is_initialized: true,
+        bump_seed,
+        ..Leaderboard::default()
+    };
+    leaderboard.store(&mut leaderboard_account.data.borrow_mut())?;
+
+    msg!("Leaderboard initialized");
+
+    Ok(())
+}
+
+fn process_write_metadata(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let metadata_account = next_account_info(accounts_iter)?;
+    let payer = next_account_info(accounts_iter)?;
+    let system_program_account = next_system_program(accounts_iter)?;
+
+    check_writable(metadata_account)?;
+
+    if !payer.is_signer {
+        msg!("Payer must sign");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let bump_seed = if metadata_account.owner == program_id {
+        let bump_seed = load_checked::<Metadata>(program_id, metadata_account)?.bump_seed;
+        check_stored_address(program_id, metadata_account, &[METADATA_SEED], bump_seed)?;
+        bump_seed
+    } else {
+        let (metadata_address, bump_seed) = find_metadata_address(program_id);
+        if *metadata_account.key != metadata_address {
+            msg!("Metadata account does not have the expected address");
+            return Err(ProgramError::InvalidArgument);
+        }
+        invoke_signed(
+            &system_instruction::create_account(
+                payer.key,
+                metadata_account.key,
+                Rent::get()?.minimum_balance(Metadata::LEN),
+                Metadata::LEN as u64,
+                program_id,
+            ),
+            &[
+                payer.clone(),
+                metadata_account.clone(),
+                system_program_account.clone(),
+            ],
+            &[&[METADATA_SEED, &[bump_seed]]],
+        )?;
+        bump_seed
+    };
+
+    // The metadata describes the running program, so anyone may refresh it
+    // after an upgrade
+    let metadata = Metadata::current(bump_seed);
+    metadata.store(&mut metadata_account.data.borrow_mut())?;
+
+    msg!(
+        "Metadata written: {} {} {}",
+        metadata.name,
+        metadata.version,
+        metadata.commit
+    );
+
+    Ok(())
+}
+
+fn process_create_channel(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    name: &str,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let channel_account = next_account_info(accounts_iter)?;
+    let user = next_account_info(accounts_iter)?;
+    let channels_account = next_account_info(accounts_iter)?;
+    let system_program_account = next_system_program(accounts_iter)?;
+    let registry_account = next_account_info(accounts_iter)?;
+    let config_account = next_account_info(accounts_iter)?;
+
+    check_writable(channel_account)?;
+    check_writable(channels_account)?;
+    check_not_paused(load_config(program_id, config_account)?.as_ref())?;
+
+    if !user.is_signer {
+        msg!("User must sign");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let (mut channels, bump_seed) = if channels_account.owner == program_id {
+        let channels = load_checked::<Channels>(program_id, channels_account)?;
+        check_stored_address(
+            program_id,
+            channels_account,
+            &[CHANNELS_SEED, user.key.as_ref()],
+            channels.bump_seed,
+        )?;
+        let bump_seed = channels.bump_seed;
+        (channels, bump_seed)
+    } else {
+        let (channels_address, bump_seed) = find_channels_address(program_id, user.key);
+        if *channels_account.key != channels_address {
+            msg!("Channel list does not have the expected address");
+            return Err(ProgramError::InvalidArgument);
+        }
+        (Channels::default(), bump_seed)
+    };
+    // Validates the name before it is used as a seed
+    channels.add(name)?;
+
+    if channels_account.owner != program_id {
+        invoke_signed(
+            &system_instruction::create_account(
+                user.key,
+                channels_account.key,
+                Rent::get()?.minimum_balance(Channels::LEN),
+                Channels::LEN as u64,
+                program_id,
+            ),
+            &[
+                user.clone(),
+                channels_account.clone(),
+                system_program_account.clone(),
+            ],
+            &[&[CHANNELS_SEED, user.key.as_ref(), &[bump_seed]]],
+        )?;
+    }
+    channels.is_initialized = true;
+    channels.bump_seed = bump_seed;
+    channels.store(&mut channels_account.data.borrow_mut())?;
+
+    create_greeting_pda(
+        program_id,
+        channel_account,
+        user,
+        system_program_account,
+        &[GREETING_SEED, user.key.as_ref(), name.as_bytes()],
+    )?;
+    register_greeting_account(program_id, registry_account, channel_account.key)?;
+
+    msg!("Created channel {} for {}", name, user.key);
+
+    Ok(())
+}
+
+fn process_initialize_treasury(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let treasury = next_account_info(accounts_iter)?;
+    let payer = next_account_info(accounts_iter)?;
+    let system_program_account = next_system_program(accounts_iter)?;
+
+    check_writable(treasury)?;
+
+    let (treasury_address, bump_seed) = find_treasury_address(program_id);
+    if *treasury.key != treasury_address {
+        msg!("Treasury does not have the expected address");
+        return Err(ProgramError::InvalidArgument);
+    }
+    if !payer.is_signer {
+        msg!("Payer must sign");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if treasury.owner == program_id {
+        msg!("Treasury is already initialized");
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    // The treasury may already hold fees, which create_account refuses, so
+    // top it up and allocate it in place instead
+    let rent_exempt_lamports = Rent::get()?.minimum_balance(Treasury::LEN);
+    let top_up = rent_exempt_lamports.saturating_sub(treasury.lamports());
+    if top_up > 0 {
+        invoke(
+            &system_instruction::transfer(payer.key, treasury.key, top_up),
+            &[
+                payer.clone(),
+                treasury.clone(),
+                system_program_account.clone(),
+            ],
+        )?;
+    }
+    let seeds: &[&[u8]] = &[TREASURY_SEED, &[bump_seed]];
+    invoke_signed(
+        &system_instruction::allocate(treasury.key, Treasury::LEN as u64),
+        &[treasury.clone(), system_program_account.clone()],
+        &[seeds],
+    )?;
+    invoke_signed(
+        &system_instruction::assign(treasury.key, program_id),
+        &[treasury.clone(), system_program_account.clone()],
+        &[seeds],
+    )?;
+
+    let state = Treasury {
+        is_initialized: true,
+        bump_seed,
+        ..Treasury::default()
+    };
+    state.store(&mut treasury.data.borrow_mut())?;
+
+    msg!(
+        "Treasury initialized holding {} lamport(s)",
+        treasury.lamports()
+    );
+
+    Ok(())
+}
+
+fn process_withdraw(program_id: &Pubkey, accounts: &[AccountInfo], amount: u64) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let treasury = next_account_info(accounts_iter)?;
+    let destination = next_account_info(accounts_iter)?;
+    let config_account = next_account_info(accounts_iter)?;
+    let admin = next_account_info(accounts_iter)?;
+
+    check_writable(treasury)?;
+    check_writable(destination)?;
+
+    load_config_as_admin(program_id, config_account, admin)?;
+    withdraw_from_treasury(program_id, treasury, destination, amount)
+}
+
+fn process_add_to_denylist(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    greeter: Pubkey,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let denylist_account = next_account_info(accounts_iter)?;
+    let config_account = next_account_info(accounts_iter)?;
+    let admin = next_account_info(accounts_iter)?;
+    let system_program_account = next_system_program(accounts_iter)?;
+
+    check_writable(denylist_account)?;
+
+    load_config_as_admin(program_id, config_account, admin)?;
+    let (mut denylist, bump_seed) = if denylist_account.owner == program_id {
+        let denylist = load_checked::<Denylist>(program_id, denylist_account)?;
+        check_stored_address(
+            program_id,
+            denylist_account,
+            &[DENYLIST_SEED],
+            denylist.bump_seed,
+        )?;
+        let bump_seed = denylist.bump_seed;
+        (denylist, bump_seed)
+    } else {
+        let (denylist_address, bump_seed) = find_denylist_address(program_id);
+        if *denylist_account.key != denylist_address {
+            msg!("Denylist does not have the expected address");
+            return Err(ProgramError::InvalidArgument);
+        }
+        (Denylist::default(), bump_seed)
+    };
+    if let Err(err) = denylist.add(greeter) {
+        msg!("Cannot deny {}", greeter);
+        return Err(err);
+    }
+
+    if denylist_account.owner != program_id {
+        invoke_signed(
+            &system_instruction::create_account(
+                admin.key,
+                denylist_account.key,
+                Rent::get()?.minimum_balance(Denylist::LEN),
+                Denylist::LEN as u64,
+                program_id,
+            ),
+            &[
+                admin.clone(),
+                denylist_account.clone(),
+                system_program_account.clone(),
+            ],
+            &[&[DENYLIST_SEED, &[bump_seed]]],
+        )?;
+    }
+    denylist.is_initialized = true;
+    denylist.bump_seed = bump_seed;
+    denylist.store(&mut denylist_account.data.borrow_mut())?;
+
+    msg!("Denied {}", greeter);
+
+    Ok(())
+}
+
+fn process_remove_from_denylist(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    greeter: &Pubkey,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let denylist_account = next_account_info(accounts_iter)?;
+    let config_account = next_account_info(accounts_iter)?;
+    let admin = next_account_info(accounts_iter)?;
+
+    check_writable(denylist_account)?;
+
+    load_config_as_admin(program_id, config_account, admin)?;
+    let mut denylist = load_checked::<Denylist>(program_id, denylist_account)?;
+    check_stored_address(
+        program_id,
+        denylist_account,
+        &[DENYLIST_SEED],
+        denylist.bump_seed,
+    )?;
+    if let Err(err) = denylist.remove(greeter) {
+        msg!("{} is not denied", greeter);
+        return Err(err);
+    }
+    denylist.store(&mut denylist_account.data.borrow_mut())?;
+
+    msg!("Allowed {} again", greeter);
+
+    Ok(())
+}
+
+fn process_create_multisig(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    signers: Vec<Pubkey>,
+    threshold: u8,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let multisig_account = next_account_info(accounts_iter)?;
+    let config_account = next_account_info(accounts_iter)?;
+    let admin = next_account_info(accounts_iter)?;
+    let system_program_account = next_system_program(accounts_iter)?;
+
+    check_writable(multisig_account)?;
+    check_writable(config_account)?;
+
+    let mut config = load_config_as_admin(program_id, config_account, admin)?;
+    let (multisig_address, bump_seed) = find_multisig_address(program_id);
+    if *multisig_account.key != multisig_address {
+        msg!("Multisig account does not have the expected address");
+        return Err(ProgramError::InvalidArgument);
+    }
+    if multisig_account.owner == program_id {
+        msg!("Multisig is already created");
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    if signers.len() > MAX_MULTISIG_SIGNERS {
+        msg!("At most {} signers are supported", MAX_MULTISIG_SIGNERS);
+        return Err(ProgramError::InvalidArgument);
+    }
+    if threshold == 0 || threshold as usize > signers.len() {
+        msg!("Threshold must be between 1 and the number of signers");
+        return Err(ProgramError::InvalidArgument);
+    }
+    if (1..signers.len()).any(|i| signers[..i].contains(&signers[i])) {
+        msg!("Signers must be distinct");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    invoke_signed(
+        &system_instruction::create_account(
+            admin.key,
+            multisig_account.key,
+            Rent::get()?.minimum_balance(Multisig::LEN),
+            Multisig::LEN as u64,
+            program_id,
+        ),
+        &[
+            admin.clone(),
+            multisig_account.clone(),
+            system_program_account.clone(),
+        ],
+        &[&[MULTISIG_SEED, &[bump_seed]]],
+    )?;
+
+    let multisig = Multisig {
+        is_initialized: true,
+        threshold,
+        signers,
+        proposal_count: 0,
+        bump_seed,
+    };
+    multisig.store(&mut multisig_account.data.borrow_mut())?;
+    config.admin = multisig_address;
+    config.store(&mut config_account.data.borrow_mut())?;
+
+    msg!(
+        "Config handed to a {} of {} multisig",
+        threshold,
+        multisig.signers.len()
+    );
+
+    Ok(())
+}
+
+fn process_propose(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    action: MultisigAction,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let proposal_account = next_account_info(accounts_iter)?;
+    let multisig_account = next_account_info(accounts_iter)?;
+    let proposer = next_account_info(accounts_iter)?;
+    let system_program_account = next_system_program(accounts_iter)?;
+
+    check_writable(proposal_account)?;
+    check_writable(multisig_account)?;
+
+    let mut multisig = load_multisig(program_id, multisig_account)?;
+    check_multisig_signer(&multisig, proposer)?;
+
+    let index = multisig.proposal_count;
+    let (proposal_address, bump_seed) = find_proposal_address(program_id, index);
+    if *proposal_account.key != proposal_address {
+        msg!("Proposal account is not the address of proposal {}", index);
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    invoke_signed(
+        &system_instruction::create_account(
+            proposer.key,
+            proposal_account.key,
+            Rent::get()?.minimum_balance(Proposal::LEN),
+            Proposal::LEN as u64,
+            program_id,
+        ),
+        &[
+            proposer.clone(),
+            proposal_account.clone(),
+            system_program_account.clone(),
+        ],
+        &[&[PROPOSAL_SEED, &index.to_le_bytes(), &[bump_seed]]],
+    )?;
+
+    msg!("Proposal {}: {:?}", index, action);
+    Proposal {
+        is_initialized: true,
+        index,
+        action,
+        approvals: vec![*proposer.key],
+        executed: false,
+        bump_seed,
+    }
+    .store(&mut proposal_account.data.borrow_mut())?;
+    multisig.proposal_count = index
+        .checked_add(1)
+        .ok_or(HelloWorldError::CounterOverflow)?;
+    multisig.store(&mut multisig_account.data.borrow_mut())?;
+
+    Ok(())
+}
+
+fn process_approve(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let proposal_account = next_account_info(accounts_iter)?;
+    let multisig_account = next_account_info(accounts_iter)?;
+    let signer = next_account_info(accounts_iter)?;
+
+    check_writable(proposal_account)?;
+
+    let multisig = load_multisig(program_id, multisig_account)?;
+    check_multisig_signer(&multisig, signer)?;
+    let mut proposal = load_pending_proposal(program_id, proposal_account)?;
+    if proposal.approvals.contains(signer.key) {
+        msg!(
+            "{} already approved proposal {}",
+            signer.key,
+            proposal.index
+        );
+        return Err(ProgramError::InvalidArgument);
+    }
+    proposal.approvals.push(*signer.key);
+    proposal.store(&mut proposal_account.data.borrow_mut())?;
+
+    msg!(
+        "Proposal {} has {} of {} approval(s)",
+        proposal.index,
+        proposal.approvals.len(),
+        multisig.threshold
+    );
+
+    Ok(())
+}
+
+fn process_execute(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let proposal_account = next_account_info(accounts_iter)?;
+    let multisig_account = next_account_info(accounts_iter)?;
+    let config_account = next_account_info(accounts_iter)?;
+
+    check_writable(proposal_account)?;
+    check_writable(config_account)?;
+
+    let multisig = load_multisig(program_id, multisig_account)?;
+    let mut proposal = load_pending_proposal(program_id, proposal_account)?;
+    if proposal.approvals.len() < multisig.threshold as usize {
+        msg!(
+            "Proposal {} needs {} approval(s), got {}",
+            proposal.index,
+            multisig.threshold,
+            proposal.approvals.len()
+        );
+        return Err(HelloWorldError::InsufficientApprovals.into());
+    }
+    let mut config = load_initialized_config(program_id, config_account)?;
+    if config.admin != *multisig_account.key {
+        msg!("The multisig is not the config admin");
+        return Err(HelloWorldError::Unauthorized.into());
+    }
+
+    proposal.executed = true;
+    proposal.store(&mut proposal_account.data.borrow_mut())?;
+    msg!("Executing proposal {}", proposal.index);
+    match proposal.action {
+        // Proposals already wait for their approvals, and the multisig cannot
+        // sign `QueueAction`, so they skip the timelock
+        MultisigAction::UpdateConfig(update) => {
+            apply_config_update(&mut config, update)?;
+            config.store(&mut config_account.data.borrow_mut())
+        }
+        MultisigAction::Withdraw {
+            destination,
+            amount,
+        } => {
+            let treasury = next_account_info(accounts_iter)?;
+            let destination_account = next_account_info(accounts_iter)?;
+            check_writable(treasury)?;
+            check_writable(destination_account)?;
+            if *destination_account.key != destination {
+                msg!("Withdrawal destination does not match the proposal");
+                return Err(ProgramError::InvalidArgument);
+            }
+            withdraw_from_treasury(program_id, treasury, destination_account, amount)
+        }
+    }
+}
+
+fn process_queue_action(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    update: ConfigUpdate,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let timelock_account = next_account_info(accounts_iter)?;
+    let config_account = next_account_info(accounts_iter)?;
+    let admin = next_account_info(accounts_iter)?;
+    let system_program_account = next_system_program(accounts_iter)?;
+
+    check_writable(timelock_account)?;
+
+    let config = load_config_as_admin(program_id, config_account, admin)?;
+    if let Some(fee_policy) = &update.fee_policy {
+        fee_policy.validate()?;
+    }
+    let mut timelock = if timelock_account.owner == program_id {
+        load_timelock(program_id, timelock_account)?
+    } else {
+        let (timelock_address, bump_seed) = find_timelock_address(program_id);
+        if *timelock_account.key != timelock_address {
+            msg!("Timelock does not have the expected address");
+            return Err(ProgramError::InvalidArgument);
+        }
+        invoke_signed(
+            &system_instruction::create_account(
+                admin.key,
+                timelock_account.key,
+                Rent::get()?.minimum_balance(Timelock::LEN),
+                Timelock::LEN as u64,
+                program_id,
+            ),
+            &[
+                admin.clone(),
+                timelock_account.clone(),
+                system_program_account.clone(),
+            ],
+            &[&[TIMELOCK_SEED, &[bump_seed]]],
+        )?;
+        Timelock {
+            is_initialized: true,
+            bump_seed,
+            ..Timelock::default()
+        }
+    };
+    if timelock.queued.len() == MAX_QUEUED_ACTIONS {
+        msg!("At most {} actions can be queued", MAX_QUEUED_ACTIONS);
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let id = timelock.next_id;
+    let executable_slot = Clock::get()?
+        .slot
+        .checked_add(config.timelock_slots)
+        .ok_or(HelloWorldError::ArithmeticOverflow)?;
+    msg!(
+        "Action {} can execute at slot {}: {:?}",
+        id,
+        executable_slot,
+        update
+    );
+    timelock.queued.push(QueuedAction {
+        id,
+        update,
+        executable_slot,
+    });
+    timelock.next_id = id.checked_add(1).ok_or(HelloWorldError::CounterOverflow)?;
+    timelock.store(&mut timelock_account.data.borrow_mut())?;
+
+    Ok(())
+}
+
+fn process_execute_action(program_id: &Pubkey, accounts: &[AccountInfo], id: u64) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let timelock_account = next_account_info(accounts_iter)?;
+    let config_account = next_account_info(accounts_iter)?;
+
+    check_writable(timelock_account)?;
+    check_writable(config_account)?;
+
+    let mut timelock = load_timelock(program_id, timelock_account)?;
+    let mut config = load_initialized_config(program_id, config_account)?;
+    let action = timelock.take(id).ok_or_else(|| {
+        msg!("No action {} is queued", id);
+        ProgramError::InvalidArgument
+    })?;
+    if Clock::get()?.slot < action.executable_slot {
+        msg!(
+            "Action {} cannot execute before slot {}",
+            id,
+            action.executable_slot
+        );
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    msg!("Executing action {}", id);
+    apply_config_update(&mut config, action.update)?;
+    config.store(&mut config_account.data.borrow_mut())?;
+    timelock.store(&mut timelock_account.data.borrow_mut())?;
+
+    Ok(())
+}
+
+fn process_cancel_action(program_id: &Pubkey, accounts: &[AccountInfo], id: u64) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let timelock_account = next_account_info(accounts_iter)?;
+    let config_account = next_account_info(accounts_iter)?;
+    let admin = next_account_info(accounts_iter)?;
+
+    check_writable(timelock_account)?;
+
+    load_config_as_admin(program_id, config_account, admin)?;
+    let mut timelock = load_timelock(program_id, timelock_account)?;
+    if timelock.take(id).is_none() {
+        msg!("No action {} is queued", id);
+        return Err(ProgramError::InvalidArgument);
+    }
+    timelock.store(&mut timelock_account.data.borrow_mut())?;
+
+    msg!("Cancelled action {}", id);
+
+    Ok(())
+}
+
+fn process_initialize_if_needed(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let account = next_account_info(accounts_iter)?;
+    let user = next_account_info(accounts_iter)?;
+    let system_program_account = next_system_program(accounts_iter)?;
+    let config_account = next_account_info(accounts_iter)?;
+    let registry_account = next_account_info(accounts_iter)?;
+
+    check_writable(account)?;
+
+    if !user.is_signer {
+        msg!("User must sign");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    let config = load_config(program_id, config_account)?;
+    check_not_paused(config.as_ref())?;
+
+    // An existing account must be a current greeting account at the user's
+    // greeting address, anything else means the caller expects another
+    // account than the one it passed
+    if account.owner == program_id {
+        let greeting_account = load_checked::<GreetingAccount>(program_id, account)?;
+        check_stored_address(
+            program_id,
+            account,
+            &[GREETING_SEED, user.key.as_ref()],
+            greeting_account.bump_seed,
+        )?;
+        msg!("Greeting account {} already exists", account.key);
+        return Ok(());
+    }
+    if !account.data_is_empty() {
+        msg!("Account {} belongs to another program", account.key);
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    create_greeting_account(program_id, account, user, system_program_account)?;
+    register_greeting_account(program_id, registry_account, account.key)
+}
+
+fn process_accept_admin(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let config_account = next_account_info(accounts_iter)?;
+    let new_admin = next_account_info(accounts_iter)?;
+
+    check_writable(config_account)?;
+
+    let mut config = load_initialized_config(program_id, config_account)?;
+    if !new_admin.is_signer {
+        msg!("Proposed admin must sign");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if config.pending_admin != Some(*new_admin.key) {
+        msg!("{} is not the proposed admin", new_admin.key);
+        return Err(HelloWorldError::Unauthorized.into());
+    }
+    config.admin = *new_admin.key;
+    config.pending_admin = None;
+    config.store(&mut config_account.data.borrow_mut())?;
+
+    msg!("Config admin set to {}", new_admin.key);
+
+    Ok(())
+}
+
+/// Apply the settings changed by `update` to `config`
+fn apply_config_update(config: &mut Config, update: ConfigUpdate) -> ProgramResult {
+    if let Some(new_admin) = update.admin {
+        if new_admin == config.admin {
+            msg!("Admin transfer withdrawn");
+            config.pending_admin = None;
+        } else {
+            msg!("Proposed {} as config admin", new_admin);
+            config.pending_admin = Some(new_admin);
+        }
+    }
+    if let Some(paused) = update.paused {
+        msg!("Program {}", if paused { "paused" } else { "resumed" });
+        config.paused = paused;
+    }
+    if let Some(fee_policy) = update.fee_policy {
+        fee_policy.validate()?;
+        msg!("Greetings priced with {:?}", fee_policy);
+        config.fee_policy = fee_policy;
+    }
+    if let Some(timelock_slots) = update.timelock_slots {
+        msg!("Actions timelocked for {} slot(s)", timelock_slots);
+        config.timelock_slots = timelock_slots;
+    }
+    if let Some(top_level_only) = update.top_level_only {
+        msg!(
+            "Greeting through CPI {}",
+            if top_level_only {
+                "forbidden"
+            } else {
+                "allowed"
+            }
+        );
+        config.top_level_only = top_level_only;
+    }
+    if let Some(redact_logs) = update.redact_logs {
+        msg!(
+            "Greeting messages {} logs",
+            if redact_logs {
+                "redacted from"
+            } else {
+                "shown in"
+            }
+        );
+        config.redact_logs = redact_logs;
+    }
+    if let Some(max_message_len) = update.max_message_len {
+        if usize::from(max_message_len) > MAX_MESSAGE_LEN {
+            msg!("Messages cannot exceed {} bytes", MAX_MESSAGE_LEN);
+            return Err(ProgramError::InvalidArgument);
+        }
+        config.max_message_len = max_message_len;
+        msg!(
+            "Greeting messages capped at {} byte(s)",
+            config.max_message_len()
+        );
+    }
+    Ok(())
+}
+
+/// Move `amount` lamports out of the initialized treasury into `destination`,
+/// keeping the treasury rent exempt
+fn withdraw_from_treasury(
+    program_id: &Pubkey,
+    treasury: &AccountInfo,
+    destination: &AccountInfo,
+    amount: u64,
+) -> ProgramResult {
+    let mut state = load_checked::<Treasury>(program_id, treasury)?;
+    if destination.key == treasury.key {
+        msg!("Cannot withdraw into the treasury");
+        return Err(ProgramError::InvalidArgument);
+    }
+    check_stored_address(program_id, treasury, &[TREASURY_SEED], state.bump_seed)?;
+
+    let withdrawable = treasury
+        .lamports()
+        .saturating_sub(Rent::get()?.minimum_balance(treasury.data_len()));
+    if amount > withdrawable {
+        msg!(
+            "Treasury only holds {} withdrawable lamport(s)",
+            withdrawable
+        );
+        return Err(ProgramError::InsufficientFunds);
+    }
+
+    move_lamports(treasury, destination, amount)?;
+    state.total_withdrawn = state
+        .total_withdrawn
+        .checked_add(amount)
+        .ok_or(HelloWorldError::CounterOverflow)?;
+    state.store(&mut treasury.data.borrow_mut())?;
+
+    msg!("Withdrew {} lamport(s) to {}", amount, destination.key);
+
+    Ok(())
+}
+
+/// Load the config account, or `None` if it has not been initialized yet
+fn load_config(
+    program_id: &Pubkey,
+    config_account: &AccountInfo,
+) -> Result<Option<Config>, ProgramError> {
+    if config_account.owner != program_id {
+        // Without a stored bump seed, only finding the address tells the
+        // uninitialized config apart from any other account
+        if *config_account.key != find_config_address(program_id).0 {
+            msg!("Config account does not have the expected address");
+            return Err(ProgramError::InvalidArgument);
+        }
+        return Ok(None);
+    }
+    let config = load_checked::<Config>(program_id, config_account)?;
+    check_stored_address(program_id, config_account, &[CONFIG_SEED], config.bump_seed)?;
+    Ok(Some(config))
+}
+
+/// Load the config account, failing if it has not been initialized yet
+fn load_initialized_config(
+    program_id: &Pubkey,
+    config_account: &AccountInfo,
+) -> Result<Config, ProgramError> {
+    load_config(program_id, config_account)?.ok_or_else(|| {
+        msg!("Config is not initialized");
+        HelloWorldError::AccountNotInitialized.into()
+    })
+}
+
+/// Load the initialized config account, checking `admin` signed as its admin
+fn load_config_as_admin(
+    program_id: &Pubkey,
+    config_account: &AccountInfo,
+    admin: &AccountInfo,
+) -> Result<Config, ProgramError> {
+    let config = load_initialized_config(program_id, config_account)?;
+    if !admin.is_signer {
+        msg!("Admin must sign");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if *admin.key != config.admin {
+        msg!("Only the admin can change the config");
+        return Err(HelloWorldError::Unauthorized.into());
+    }
+    Ok(config)
+}
+
+/// Verify the greeter signed, is allowlisted if the config restricts
+/// greeters, and is not rate limited, then record the greeting and return the
+/// config
+fn check_greeter(
+    program_id: &Pubkey,
+    config_account: &AccountInfo,
+    greeter: &AccountInfo,
+    greeter_account: &AccountInfo,
+    proof: &[[u8; 32]],
+) -> Result<Option<Config>, ProgramError> {
+    if !greeter.is_signer {
+        msg!("Greeter must sign");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    let config = load_config(program_id, config_account)?;
+    check_not_paused(config.as_ref())?;
+    check_allowlist(config.as_ref(), greeter.key.as_ref(), proof)?;
+    record_greeting(program_id, config.as_ref(), greeter, greeter_account)?;
+    Ok(config)
+}
+
+fn check_not_paused(config: Option<&Config>) -> ProgramResult {
+    if matches!(config, Some(config) if config.paused) {
+        msg!("Program is paused");
+        return Err(HelloWorldError::ProgramPaused.into());
+    }
+    Ok(())
+}
+
+fn check_allowlist(config: Option<&Config>, key: &[u8], proof: &[[u8; 32]]) -> ProgramResult {
+    if let Some(root) = config.and_then(|config| config.allowlist_root) {
+        if !allowlist::verify(&root, key, proof) {
+            msg!("Greeter is not in the allowlist");
+            return Err(HelloWorldError::Unauthorized.into());
+        }
+    }
+    Ok(())
+}
+
+/// Reject greetings through CPI if the config only allows top-level ones
+///
+/// The instructions sysvar only lists top-level instructions, so the program
+/// runs through CPI exactly when the current one belongs to another program.
+fn check_top_level(
+    program_id: &Pubkey,
+    config: Option<&Config>,
+    instructions_sysvar: &AccountInfo,
+) -> ProgramResult {
+    check_instructions_sysvar(instructions_sysvar)?;
+    if matches!(config, Some(config) if config.top_level_only)
+        && get_instruction_relative(0, instructions_sysvar)?.program_id != *program_id
+    {
+        msg!("Program cannot be invoked through CPI");
+        return Err(HelloWorldError::CpiForbidden.into());
+    }
+    Ok(())
+}
+
+/// Reject an administrative action taking effect right away under a timelock
+fn check_not_timelocked(config: &Config) -> ProgramResult {
+    if config.timelock_slots > 0 {
+        msg!(
+            "Actions are timelocked for {} slot(s), queue it instead",
+            config.timelock_slots
+        );
+        return Err(HelloWorldError::Timelocked.into());
+    }
+    Ok(())
+}
+
+/// Load the timelock account, failing if it has not been created yet
+fn load_timelock(
+    program_id: &Pubkey,
+    timelock_account: &AccountInfo,
+) -> Result<Timelock, ProgramError> {
+    let timelock = load_checked::<Timelock>(program_id, timelock_account)?;
+    check_stored_address(
+        program_id,
+        timelock_account,
+        &[TIMELOCK_SEED],
+        timelock.bump_seed,
+    )?;
+    Ok(timelock)
+}
+
+/// Load the multisig account, failing if it has not been created yet
+fn load_multisig(
+    program_id: &Pubkey,
+    multisig_account: &AccountInfo,
+) -> Result<Multisig, ProgramError> {
+    let multisig = load_checked::<Multisig>(program_id, multisig_account)?;
+    check_stored_address(
+        program_id,
+        multisig_account,
+        &[MULTISIG_SEED],
+        multisig.bump_seed,
+    )?;
+    Ok(multisig)
+}
+
+/// Verify `signer` signed and is one of the multisig signers
+fn check_multisig_signer(multisig: &Multisig, signer: &AccountInfo) -> ProgramResult {
+    if !signer.is_signer {
+        msg!("Multisig signer must sign");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if !multisig.signers.contains(signer.key) {
+        msg!("{} is not a multisig signer", signer.key);
+        return Err(HelloWorldError::Unauthorized.into());
+    }
+    Ok(())
+}
+
+/// Load a proposal that has not been executed yet
+fn load_pending_proposal(
+    program_id: &Pubkey,
+    proposal_account: &AccountInfo,
+) -> Result<Proposal, ProgramError> {
+    let proposal = load_checked::<Proposal>(program_id, proposal_account)?;
+    check_stored_address(
+        program_id,
+        proposal_account,
+        &[PROPOSAL_SEED, &proposal.index.to_le_bytes()],
+        proposal.bump_seed,
+    )?;
+    if proposal.executed {
+        msg!("Proposal {} is already executed", proposal.index);
+        return Err(ProgramError::InvalidArgument);
+    }
+    Ok(proposal)
+}
+
+/// Reject `greeter` if the denylist has been created and holds it
+fn check_not_denied(
+    program_id: &Pubkey,
+    denylist_account: &AccountInfo,
+    greeter: &Pubkey,
+) -> ProgramResult {
+    if denylist_account.owner != program_id {
+        if *denylist_account.key != find_denylist_address(program_id).0 {
+            msg!("Denylist does not have the expected address");
+            return Err(ProgramError::InvalidArgument);
+        }
+        return Ok(());
+    }
+
+    let denylist = load_checked::<Denylist>(program_id, denylist_account)?;
+    check_stored_address(
+        program_id,
+        denylist_account,
+        &[DENYLIST_SEED],
+        denylist.bump_seed,
+    )?;
+    if denylist.is_denied(greeter) {
+        msg!("Greeter {} is denied", greeter);
+        return Err(HelloWorldError::GreeterDenied.into());
+    }
+    Ok(())
+}
+
+/// Track the greeting in the greeter account, rejecting it if the greeter
+/// greeted within the config's rate limit window
+fn record_greeting(
+    program_id: &Pubkey,
+    config: Option<&Config>,
+    greeter: &AccountInfo,
+    greeter_account: &AccountInfo,
+) -> ProgramResult {
+    let rate_limit_slots = config.map_or(0, |config| config.rate_limit_slots);
+    if greeter_account.owner != program_id {
+        if *greeter_account.key != find_greeter_address(program_id, greeter.key).0 {
+            msg!("Greeter account does not have the expected address");
+            return Err(ProgramError::InvalidArgument);
+        }
+        if rate_limit_slots > 0 {
+            msg!("Greeter must register while greetings are rate limited");
+            return Err(HelloWorldError::AccountNotInitialized.into());
+        }
+        return Ok(());
+    }
+
+    check_writable(greeter_account)?;
+    let mut greeter_state = load_checked::<GreeterAccount>(program_id, greeter_account)?;
+    check_stored_address(
+        program_id,
+        greeter_account,
+        &[GREETER_SEED, greeter.key.as_ref()],
+        greeter_state.bump_seed,
+    )?;
+    let slot = Clock::get()?.slot;
+    if greeter_state.greetings > 0
+        && slot
+            < greeter_state
+                .last_greeting_slot
+                .saturating_add(rate_limit_slots)
+    {
+        msg!(
+            "Greeter cannot greet again before slot {}",
+            greeter_state
+                .last_greeting_slot
+                .saturating_add(rate_limit_slots)
+        );
+        return Err(HelloWorldError::RateLimited.into());
+    }
+
+    greeter_state.greetings = greeter_state
+        .greetings
+        .checked_add(1)
+        .ok_or(HelloWorldError::CounterOverflow)?;
+    greeter_state.last_greeting_slot = slot;
+    greeter_state.store(&mut greeter_account.data.borrow_mut())?;
+
+    Ok(())
+}
+
+fn load_guardian_set(
+    program_id: &Pubkey,
+    guardian_set_account: &AccountInfo,
+) -> Result<GuardianSet, ProgramError> {
+    if guardian_set_account.owner != program_id {
+        msg!("Guardian set account does not have the correct program id");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    // Every guardian instruction updates the guardian set
+    check_writable(guardian_set_account)?;
+    if guardian_set_account.data_len() < GuardianSet::LEN {
+        msg!("Guardian set account is too small");
+        return Err(ProgramError::AccountDataTooSmall);
+    }
+    GuardianSet::load(&guardian_set_account.data.borrow())
+}
+
+fn load_initialized_guardian_set(
+    program_id: &Pubkey,
+    guardian_set_account: &AccountInfo,
+) -> Result<GuardianSet, ProgramError> {
+    let guardian_set = load_guardian_set(program_id, guardian_set_account)?;
+    if !guardian_set.is_initialized {
+        msg!("No guardians are registered");
+        return Err(HelloWorldError::AccountNotInitialized.into());
+    }
+    Ok(guardian_set)
+}
+
+fn check_treasury(program_id: &Pubkey, treasury: &AccountInfo) -> ProgramResult {
+    if *treasury.key != find_treasury_address(program_id).0 {
+        msg!("Treasury does not have the expected address");
+        return Err(ProgramError::InvalidArgument);
+    }
+    Ok(())
+}
+
+/// Fee for greeting an account already greeted `count` times, under the
+/// config's fee policy
+fn greeting_fee(
+    config: Option<&Config>,
+    count: u32,
+    message_len: usize,
+) -> Result<u64, ProgramError> {
+    config
+        .map_or(Some(0), |config| config.fee_policy.fee(count, message_len))
+        .ok_or_else(|| {
+            msg!("Greeting fee overflows");
+            HelloWorldError::ArithmeticOverflow.into()
+        })
+}
+
+/// Fail if `message` is longer than the config allows, or than
+/// `MAX_MESSAGE_LEN` without a config, or if it holds control characters
+/// that could garble how indexers and explorers render it
+///
+/// Borsh already rejects messages that are not valid UTF-8 when decoding the
+/// instruction.
+fn check_message(config: Option<&Config>, message: &str) -> ProgramResult {
+    let max_message_len = config.map_or(MAX_MESSAGE_LEN, Config::max_message_len);
+    if message.len() > max_message_len {
+        msg!(
+            "Message is {} bytes long, at most {} are allowed",
+            message.len(),
+            max_message_len
+        );
+        return Err(HelloWorldError::MessageTooLong.into());
+    }
+    if message.chars().any(char::is_control) {
+        msg!("Message holds control characters");
+        return Err(HelloWorldError::InvalidMessage.into());
+    }
+    Ok(())
+}
+
+/// Log line for a greeting message, giving only its length and the hash
+/// recorded in the history when the config redacts logs
+fn message_log(config: Option<&Config>, message: &str) -> String {
+    if matches!(config, Some(config) if config.redact_logs) {
+        format!(
+            "Greeting message: {} byte(s), hash {}",
+            message.len(),
+            hash(message.as_bytes())
+        )
+    } else {
+        format!("Greeting message: {}", message)
+    }
+}
+
+/// Close `account` into `destination`, returning the lamports it held
+///
+/// Draining the lamports alone only deletes the account once the transaction
+/// completes. Zeroing the data and handing the account back to the system
+/// program also keeps later instructions of the same transaction from
+/// reviving it with stale state.
+fn close_account(account: &AccountInfo, destination: &AccountInfo) -> Result<u64, ProgramError> {
+    let lamports = account.lamports();
+    move_lamports(account, destination, lamports)?;
+    account.data.borrow_mut().fill(0);
+    account.assign(&system_program::id());
+    Ok(lamports)
+}
+
+/// Move `amount` lamports between accounts the program can debit, failing
+/// rather than wrapping if `from` holds less or `to` would overflow
+fn move_lamports(from: &AccountInfo, to: &AccountInfo, amount: u64) -> ProgramResult {
+    let from_lamports = from
+        .lamports()
+        .checked_sub(amount)
+        .ok_or(ProgramError::InsufficientFunds)?;
+    let to_lamports = to
+        .lamports()
+        .checked_add(amount)
+        .ok_or(HelloWorldError::ArithmeticOverflow)?;
+    **from.lamports.borrow_mut() = from_lamports;
+    **to.lamports.borrow_mut() = to_lamports;
+    Ok(())
+}
+
+/// Verify `account` is the program address derived from `seeds` and the
+/// canonical bump seed stored in it when it was created, which is much cheaper
+/// than finding the bump seed again
+fn check_stored_address(
+    program_id: &Pubkey,
+    account: &AccountInfo,
+    seeds: &[&[u8]],
+    bump_seed: u8,
+) -> ProgramResult {
+    let bump_seed = [bump_seed];
+    let mut seeds = seeds.to_vec();
+    seeds.push(&bump_seed);
+    match Pubkey::create_program_address(&seeds, program_id) {
+        Ok(address) if address == *account.key => Ok(()),
+        _ => {
+            msg!("Account {} does not have the expected address", account.key);
+            Err(ProgramError::InvalidArgument)
+        }
+    }
+}
+
+/// Verify `account` was passed as writable, so clients passing the wrong
+/// `AccountMeta` flags find out when simulating the transaction
+fn check_writable(account: &AccountInfo) -> ProgramResult {
+    if !account.is_writable {
+        msg!("Account {} must be writable", account.key);
+        return Err(HelloWorldError::AccountNotWritable.into());
+    }
+    Ok(())
+}
+
+/// Reject an account passed in several writable positions, where every
+/// position would read and write the same data behind the other's back
+fn check_no_duplicate_writable(accounts: &[AccountInfo]) -> ProgramResult {
+    for (i, account) in accounts.iter().enumerate() {
+        if account.is_writable
+            && accounts[..i]
+                .iter()
+                .any(|other| other.is_writable && other.key == account.key)
+        {
+            msg!("Account {} is passed more than once", account.key);
+            return Err(HelloWorldError::DuplicateAccount.into());
+        }
+    }
+    Ok(())
+}
+
+/// Check `account` is the instructions sysvar rather than an account forging
+/// its layout. Clock and rent are read with `Sysvar::get` instead, so they
+/// never come from the account list.
+fn check_instructions_sysvar(account: &AccountInfo) -> ProgramResult {
+    if *account.key != sysvar::instructions::id() {
+        msg!("Expected the instructions sysvar, got {}", account.key);
+        return Err(ProgramError::UnsupportedSysvar);
+    }
+    Ok(())
+}
+
+/// Take the next account from `accounts_iter`, checking it is the system
+/// program rather than an account standing in for it
+fn next_system_program<'a, 'b>(
+    accounts_iter: &mut Iter<'a, AccountInfo<'b>>,
+) -> Result<&'a AccountInfo<'b>, ProgramError> {
+    let account = next_account_info(accounts_iter)?;
+    if *account.key != system_program::id() {
+        msg!("Expected the system program, got {}", account.key);
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    Ok(account)
+}
+
+/// Load the state of `account`, checking it is owned by the program before its
+/// discriminator and, for versioned state, its layout version
+fn load_checked<T: AccountState>(
+    program_id: &Pubkey,
+    account: &AccountInfo,
+) -> Result<T, ProgramError> {
+    if account.owner != program_id {
+        msg!(
+            "Account {} does not have the correct program id",
+            account.key
+        );
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    T::load(&account.data.borrow())
+}
+
+/// Verify `account` holds enough lamports to be rent exempt with `data_len`
+/// bytes of data
+fn check_rent_exempt(account: &AccountInfo, data_len: usize) -> ProgramResult {
+    if !Rent::get()?.is_exempt(account.lamports(), data_len) {
+        msg!("Account {} is not rent exempt", account.key);
+        return Err(HelloWorldError::NotRentExempt.into());
+    }
+    Ok(())
+}
+
+/// Transfer `fee` lamports from `payer` to the treasury
+fn charge_fee<'a>(
+    program_id: &Pubkey,
+    payer: &AccountInfo<'a>,
+    treasury: &AccountInfo<'a>,
+    system_program_account: &AccountInfo<'a>,
+    fee: u64,
+) -> ProgramResult {
+    check_treasury(program_id, treasury)?;
+    if fee == 0 {
+        return Ok(());
+    }
+    check_writable(treasury)?;
+
+    invoke(
+        &system_instruction::transfer(payer.key, treasury.key, fee),
+        &[
+            payer.clone(),
+            treasury.clone(),
+            system_program_account.clone(),
+        ],
+    )?;
+    msg!("Charged a fee of {} lamport(s)", fee);
+
+    record_treasury_income(program_id, treasury, fee)
+}
+
+/// Add `amount` to the running total of the treasury, if it has been
+/// initialized
+fn record_treasury_income(
+    program_id: &Pubkey,
+    treasury: &AccountInfo,
+    amount: u64,
+) -> ProgramResult {
+    if treasury.owner != program_id {
+        return Ok(());
+    }
+    let mut state = load_checked::<Treasury>(program_id, treasury)?;
+    state.total_collected = state
+        .total_collected
+        .checked_add(amount)
+        .ok_or(HelloWorldError::CounterOverflow)?;
+    state.store(&mut treasury.data.borrow_mut())
+}
+
+/// Create the greeting address of `user`, paid for by the user
+fn create_greeting_account<'a>(
+    program_id: &Pubkey,
+    account: &AccountInfo<'a>,
+    user: &AccountInfo<'a>,
+    system_program_account: &AccountInfo<'a>,
+) -> ProgramResult {
+    create_greeting_pda(
+        program_id,
+        account,
+        user,
+        system_program_account,
+        &[GREETING_SEED, user.key.as_ref()],
+    )
+}
+
+/// Create the greeting account derived from `seeds`, paid for by `user`
+fn create_greeting_pda<'a>(
+    program_id: &Pubkey,
+    account: &AccountInfo<'a>,
+    user: &AccountInfo<'a>,
+    system_program_account: &AccountInfo<'a>,
+    seeds: &[&[u8]],
+) -> ProgramResult {
+    let (greeting_address, bump_seed) = Pubkey::find_program_address(seeds, program_id);
+    if *account.key != greeting_address {
+        msg!(
+            "Greeting account is not the greeting address of {}",
+            user.key
+        );
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let bump_seed_bytes = [bump_seed];
+    let mut signer_seeds = seeds.to_vec();
+    signer_seeds.push(&bump_seed_bytes);
+    invoke_signed(
+        &system_instruction::create_account(
+            user.key,
+            account.key,
+            Rent::get()?.minimum_balance(GreetingAccount::LEN),
+            GreetingAccount::LEN as u64,
+            program_id,
+        ),
+        &[
+            user.clone(),
+            account.clone(),
+            system_program_account.clone(),
+        ],
+        &[&signer_seeds],
+    )?;
+
+    GreetingAccount {
+        version: GreetingAccount::VERSION,
+        bump_seed,
+        ..GreetingAccount::default()
+    }
+    .store(&mut account.data.borrow_mut())?;
+
+    msg!("Created greeting account {} for {}", account.key, user.key);
+
+    Ok(())
+}
+
+/// Record a newly created greeting address in the registry, if it has been
+/// initialized
+fn register_greeting_account(
+    program_id: &Pubkey,
+    registry_account: &AccountInfo,
+    greeting_address: &Pubkey,
+) -> ProgramResult {
+    if registry_account.owner != program_id {
+        if *registry_account.key != find_registry_address(program_id).0 {
+            msg!("Registry account does not have the expected address");
+            return Err(ProgramError::InvalidArgument);
+        }
+        return Ok(());
+    }
+
+    check_writable(registry_account)?;
+    let mut registry = load_checked::<Registry>(program_id, registry_account)?;
+    check_stored_address(
+        program_id,
+        registry_account,
+        &[REGISTRY_SEED],
+        registry.bump_seed,
+    )?;
+    registry.record(*greeting_address)?;
+    registry.store(&mut registry_account.data.borrow_mut())
+}
+
+/// Record a greeting in the history, if it has been initialized
+fn record_history(
+    program_id: &Pubkey,
+    history_account: &AccountInfo,
+    greeter: &Pubkey,
+    message: &str,
+) -> ProgramResult {
+    if history_account.owner != program_id {
+        if *history_account.key != find_history_address(program_id).0 {
+            msg!("History account does not have the expected address");
+            return Err(ProgramError::InvalidArgument);
+        }
+        return Ok(());
+    }
+
+    check_writable(history_account)?;
+    let mut history = load_checked::<History>(program_id, history_account)?;
+    check_stored_address(
+        program_id,
+        history_account,
+        &[HISTORY_SEED],
+        history.bump_seed,
+    )?;
+    history.record(*greeter, Clock::get()?.slot, hash(message.as_bytes()))?;
+    history.store(&mut history_account.data.borrow_mut())
+}
+
+/// Rank a registered greeter on the leaderboard with its greeting count, if
+/// the leaderboard has been initialized
+fn update_leaderboard(
+    program_id: &Pubkey,
+    leaderboard_account: &AccountInfo,
+    greeter: &Pubkey,
+    greeter_account: &AccountInfo,
+) -> ProgramResult {
+    if leaderboard_account.owner != program_id {
+        if *leaderboard_account.key != find_leaderboard_address(program_id).0 {
+            msg!("Leaderboard account does not have the expected address");
+            return Err(ProgramError::InvalidArgument);
+        }
+        return Ok(());
+    }
+
+    // Unregistered greeters are not ranked, registered ones had their greeter
+    // account checked by `record_greeting`
+    if greeter_account.owner != program_id {
+        return Ok(());
+    }
+    check_writable(leaderboard_account)?;
+    let greetings = load_checked::<GreeterAccount>(program_id, greeter_account)?.greetings;
+    let mut leaderboard = load_checked::<Leaderboard>(program_id, leaderboard_account)?;
+    check_stored_address(
+        program_id,
+        leaderboard_account,
+        &[LEADERBOARD_SEED],
+        leaderboard.bump_seed,
+    )?;
+    leaderboard.update(*greeter, greetings);
+    leaderboard.store(&mut leaderboard_account.data.borrow_mut())
+}
+
+/// Load the mirror of the greeting account `greeting`
+fn load_mirror(
+    program_id: &Pubkey,
+    greeting: &Pubkey,
+    mirror_account: &AccountInfo,
+) -> Result<Mirror, ProgramError> {
+    if mirror_account.owner != program_id {
+        msg!("Mirror account does not have the correct program id");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    // Mirrors are loaded to be updated or closed along with their greeting
+    // account
+    check_writable(mirror_account)?;
+    let mirror = Mirror::unpack(&mirror_account.data.borrow())?;
+    if mirror.greeting != *greeting {
+        msg!("Mirror account does not mirror {}", greeting);
+        return Err(ProgramError::InvalidArgument);
+    }
+    Ok(mirror)
+}
+
+/// Greet `account` on behalf of `greeter`, updating its mirror, taken from
+/// `accounts_iter`, if it has one, and return how many times it was greeted
+/// before
+fn greet<'a, 'b: 'a>(
+    program_id: &Pubkey,
+    config: Option<&Config>,
+    greeter: &Pubkey,
+    account: &AccountInfo<'b>,
+    accounts_iter: &mut Iter<'a, AccountInfo<'b>>,
+) -> Result<u32, ProgramError> {
+    // The account must be owned by the program in order to modify its data
+    if account.owner != program_id {
+        msg!("Greeted account does not have the correct program id");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    check_writable(account)?;
+
+    // Increment the number of times the account has been greeted in place,
+    // greetings being the hottest path
+    let mut data = account.data.borrow_mut();
+    // Greeting a zeroed account initializes it, so it has to stay alive
+    if data.get(..DISCRIMINATOR_LEN) == Some(&[0; DISCRIMINATOR_LEN]) {
+        check_rent_exempt(account, data.len())?;
+    }
+    let greeting_account = GreetingAccountPod::from_account_data(&mut data)?;
+    if greeting_account.processing() {
+        msg!("Greeting account is already being processed");
+        return Err(HelloWorldError::ReentrantCall.into());
+    }
+    let clock = Clock::get()?;
+    let count = greeting_account.counter();
+    let new_count = count
+        .checked_add(1)
+        .ok_or(HelloWorldError::CounterOverflow)?;
+    greeting_account.set_counter(new_count);
+    greeting_account.last_greeter = *greeter;
+    greeting_account.set_last_slot(clock.slot);
+
+    // Every greeting pushes the expiry back
+    let greeting_ttl = config.map_or(0, |config| config.greeting_ttl);
+    if greeting_ttl > 0 {
+        greeting_account.set_expires_at(clock.unix_timestamp.saturating_add(greeting_ttl));
+    }
+
+    msg!("Greeted {} time(s)!", new_count);
+
+    if greeting_account.has_mirror() {
+        let mirror_account = next_account_info(accounts_iter)?;
+        let mirror = load_mirror(program_id, account.key, mirror_account)?;
+        Mirror::new(
+            mirror.bump_seed,
+            *account.key,
+            &GreetingAccount::from(&*greeting_account),
+            clock.slot,
+        )
+        .pack(&mut mirror_account.data.borrow_mut())?;
+    }
+
+    Ok(count)
+}
+
+/// Flag the greeting `account` as being processed, or clear the flag
+fn set_processing(account: &AccountInfo, processing: bool) -> ProgramResult {
+    GreetingAccountPod::from_account_data(&mut account.data.borrow_mut())?
+        .set_processing(processing);
+    Ok(())
+}
+
+/// Verify the previous instruction is a secp256k1 program instruction that
+/// checked a single signature by `eth_address` over `message`, with all of its
+/// data contained in that instruction
+fn verify_secp256k1_signature(
+    instructions_sysvar: &AccountInfo,
+    eth_address: &[u8; ETH_ADDRESS_SIZE],
+    message: &[u8],
+) -> ProgramResult {
+    check_instructions_sysvar(instructions_sysvar)?;
+    let current_index = load_current_index_checked(instructions_sysvar)?;
+    let instruction = match get_instruction_relative(-1, instructions_sysvar) {
+        Ok(instruction) => instruction,
+        Err(err) => {
+            msg!("Missing secp256k1 signature verification instruction");
+            return Err(err);
+        }
+    };
+    if instruction.program_id != secp256k1_program::id() {
+        msg!("Previous instruction is not a secp256k1 signature verification");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let data = &instruction.data;
+    if data.len() < 1 + SECP256K1_SIGNATURE_OFFSETS_SIZE || data[0] != 1 {
+        msg!("Expected exactly one secp256k1 signature");
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let offsets = &data[1..1 + SECP256K1_SIGNATURE_OFFSETS_SIZE];
+    let read_u16 = |at: usize| u16::from_le_bytes([offsets[at], offsets[at + 1]]) as usize;
+    let signature_instruction_index = offsets[2];
+    let eth_address_offset = read_u16(3);
+    let eth_address_instruction_index = offsets[5];
+    let message_data_offset = read_u16(6);
+    let message_data_size = read_u16(8);
+    let message_instruction_index = offsets[10];
+
+    // The precompile verified data at the given instruction indices, which
+    // must all point at the secp256k1 instruction itself
+    let verified_index = (current_index - 1) as u8;
+    if signature_instruction_index != verified_index
+        || eth_address_instruction_index != verified_index
+        || message_instruction_index != verified_index
+    {
+        msg!("secp256k1 signature data must be contained in its own instruction");
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let signed_eth_address = data
+        .get(eth_address_offset..eth_address_offset + ETH_ADDRESS_SIZE)
+        .ok_or(ProgramError::InvalidInstructionData)?;
+    if signed_eth_address != eth_address {
+        msg!("secp256k1 signature is not by the greeting Ethereum address");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let signed_message = data
+        .get(message_data_offset..message_data_offset + message_data_size)
+        .ok_or(ProgramError::InvalidInstructionData)?;
+    if signed_message != message {
+        msg!("secp256k1 signature is not over the expected greeting message");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    Ok(())
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::Rng;
+
+    const CASES: usize = 1000;
+
+    #[test]
+    fn test_move_lamports() {
+        let (from_key, to_key, owner) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::default(),
+        );
+        let mut rng = rand::thread_rng();
+        let boundaries = [0, 1, u64::MAX - 1, u64::MAX];
+        for case in 0..CASES {
+            let (mut from_lamports, mut to_lamports) = match case {
+                // Every pair of boundary values, then random ones
+                case if case < boundaries.len().pow(2) => (
+                    boundaries[case / boundaries.len()],
+                    boundaries[case % boundaries.len()],
+                ),
+                _ => (rng.gen(), rng.gen()),
+            };
+            let amount = match rng.gen_range(0, 3) {
+                0 => from_lamports,
+                1 => from_lamports.saturating_add(1),
+                _ => rng.gen(),
+            };
+            let (expected_from, expected_to) = (from_lamports, to_lamports);
+            let (mut from_data, mut to_data) = (vec![], vec![]);
+            let from = AccountInfo::new(
+                &from_key,
+                false,
+                true,
+                &mut from_lamports,
+                &mut from_data,
+                &owner,
+                false,
+                0,
+            );
+            let to = AccountInfo::new(
+                &to_key,
+                false,
+                true,
+                &mut to_lamports,
+                &mut to_data,
+                &owner,
+                false,
+                0,
+            );
+
+            let result = move_lamports(&from, &to, amount);
+            match (
+                expected_from.checked_sub(amount),
+                expected_to.checked_add(amount),
+            ) {
+                (Some(from_left), Some(to_total)) => {
+                    assert_eq!(result, Ok(()));
+                    assert_eq!((from.lamports(), to.lamports()), (from_left, to_total));
+                }
+                (from_left, _) => {
+                    let error = if from_left.is_none() {
+                        ProgramError::InsufficientFunds
+                    } else {
+                        HelloWorldError::ArithmeticOverflow.into()
+                    };
+                    assert_eq!(result, Err(error));
+                    // Nothing moves on failure
+                    assert_eq!(
+                        (from.lamports(), to.lamports()),
+                        (expected_from, expected_to)
+                    );
+                }
+            }
+        }
+    }
+    #[test]
+    fn test_check_message() {
+        for message in ["", "hello", "héllo wörld", "👋 hello"] {
+            assert_eq!(check_message(None, message), Ok(()));
+        }
+        for message in ["hello\n", "\0", "bell\u{7}", "\u{1b}[31mred", "\u{85}"] {
+            assert_eq!(
+                check_message(None, message),
+                Err(HelloWorldError::InvalidMessage.into())
+            );
+        }
+    }
+
+    #[test]
+    fn test_message_log() {
+        let message = "secret greeting";
+        let mut config = Config::default();
+        assert_eq!(
+            message_log(None, message),
+            "Greeting message: secret greeting"
+        );
+        assert_eq!(
+            message_log(Some(&config), message),
+            "Greeting message: secret greeting"
+        );
+
+        config.redact_logs = true;
+        let log = message_log(Some(&config), message);
+        assert!(!log.contains(message));
+        assert_eq!(
+            log,
+            format!(
+                "Greeting message: 15 byte(s), hash {}",
+                hash(message.as_bytes())
+            )
+        );
+    }
+}
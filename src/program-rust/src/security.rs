@@ -0,0 +1,74 @@
+//! Contact and provenance information embedded in the program binary, so
+//! auditors and scanners can tell who to reach about the deployed program and
+//! what it was built from
+
+/// Security contacts in the `security.txt` format of the `solana-security-txt`
+/// crate, read by its `query-security-txt` tool from the `.security.txt`
+/// section of the program
+///
+/// Left out when the program is linked into another one, which embeds its own.
+#[cfg(all(feature = "entrypoint", not(feature = "no-entrypoint")))]
+#[cfg_attr(target_arch = "bpf", link_section = ".security.txt")]
+#[no_mangle]
+pub static SECURITY_TXT: &str = concat!(
+    "=======BEGIN SECURITY.TXT V1=======\0",
+    "name\0",
+    "Hello World\0",
+    "project_url\0",
+    "https://github.com/solana-labs/example-helloworld\0",
+    "contacts\0",
+    "email:maintainers@solana.com\0",
+    "policy\0",
+    "https://github.com/solana-labs/solana/blob/master/SECURITY.md\0",
+    "source_code\0",
+    "https://github.com/solana-labs/example-helloworld\0",
+    "source_revision\0",
+    env!("HELLOWORLD_COMMIT"),
+    "\0",
+    "=======END SECURITY.TXT V1=======\0"
+);
+
+/// Commit and toolchain the program was built with, as `key=value` lines in
+/// the `.build_info` section of the program
+#[cfg(all(feature = "entrypoint", not(feature = "no-entrypoint")))]
+#[cfg_attr(target_arch = "bpf", link_section = ".build_info")]
+#[no_mangle]
+pub static BUILD_INFO: &str = concat!(
+    "commit=",
+    env!("HELLOWORLD_COMMIT"),
+    "\ntoolchain=",
+    env!("HELLOWORLD_TOOLCHAIN"),
+    "\n"
+);
+
+#[cfg(all(test, feature = "entrypoint", not(feature = "no-entrypoint")))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_security_txt() {
+        let fields: Vec<&str> = SECURITY_TXT
+            .strip_prefix("=======BEGIN SECURITY.TXT V1=======\0")
+            .and_then(|fields| fields.strip_suffix("=======END SECURITY.TXT V1=======\0"))
+            .unwrap()
+            .split_terminator('\0')
+            .collect();
+        // Fields come in name and value pairs
+        assert_eq!(fields.len() % 2, 0);
+        let names: Vec<&str> = fields.iter().step_by(2).copied().collect();
+        assert_eq!(
+            names,
+            [
+                "name",
+                "project_url",
+                "contacts",
+                "policy",
+                "source_code",
+                "source_revision"
+            ]
+        );
+
+        assert!(BUILD_INFO.starts_with(&format!("commit={}\n", env!("HELLOWORLD_COMMIT"))));
+        assert!(BUILD_INFO.contains("\ntoolchain=rustc "));
+    }
+}
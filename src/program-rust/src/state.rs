@@ -0,0 +1,1875 @@
+use crate::{
+    error::HelloWorldError,
+    fees::FeePolicy,
+    instruction::{ConfigUpdate, MultisigAction, MAX_MESSAGE_LEN},
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use bytemuck::{Pod, Zeroable};
+use solana_program::{
+    hash::{hashv, Hash},
+    msg,
+    program_error::ProgramError,
+    program_pack::{IsInitialized, Pack, Sealed},
+    pubkey::{Pubkey, MAX_SEED_LEN},
+};
+
+/// Size of the type discriminator prefixing every state account
+pub const DISCRIMINATOR_LEN: usize = 8;
+
+/// State stored in a program-owned account, prefixed with a discriminator
+/// telling account types apart, e.g. in `getProgramAccounts` memcmp filters
+///
+/// A zeroed discriminator marks a freshly allocated account, which loads as the
+/// zeroed state.
+pub trait AccountState: BorshSerialize + BorshDeserialize {
+    /// Type discriminator stored in the first `DISCRIMINATOR_LEN` bytes
+    const DISCRIMINATOR: [u8; DISCRIMINATOR_LEN];
+
+    /// Load the state from account data, checking its discriminator
+    fn load(data: &[u8]) -> Result<Self, ProgramError> {
+        let mut state = state_data(data, &Self::DISCRIMINATOR)?;
+        Ok(Self::deserialize(&mut state)?)
+    }
+
+    /// Store the state and its discriminator into account data
+    fn store(&self, data: &mut [u8]) -> Result<(), ProgramError> {
+        if data.len() < DISCRIMINATOR_LEN {
+            return Err(ProgramError::AccountDataTooSmall);
+        }
+        let (discriminator, mut state) = data.split_at_mut(DISCRIMINATOR_LEN);
+        discriminator.copy_from_slice(&Self::DISCRIMINATOR);
+        Ok(self.serialize(&mut state)?)
+    }
+}
+
+/// Account data following a zeroed discriminator or `discriminator`
+fn state_data<'a>(
+    data: &'a [u8],
+    discriminator: &[u8; DISCRIMINATOR_LEN],
+) -> Result<&'a [u8], ProgramError> {
+    if data.len() < DISCRIMINATOR_LEN {
+        return Err(ProgramError::AccountDataTooSmall);
+    }
+    let (prefix, state) = data.split_at(DISCRIMINATOR_LEN);
+    if prefix != discriminator && prefix != [0; DISCRIMINATOR_LEN] {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    Ok(state)
+}
+
+/// Define the type of state stored in accounts
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Default, PartialEq)]
+pub struct GreetingAccount {
+    /// layout version, `GreetingAccount::VERSION` once stored
+    pub version: u8,
+    /// number of greetings
+    pub counter: u32,
+    /// unix timestamp after which anyone can reap the account, 0 if it never
+    /// expires
+    pub expires_at: i64,
+    /// whether a mirror account tracks this account, in which case the mirror
+    /// has to be passed along with it
+    pub has_mirror: bool,
+    /// signer of the latest greeting, the default pubkey for greetings on
+    /// behalf of an Ethereum key
+    pub last_greeter: Pubkey,
+    /// slot of the latest greeting
+    pub last_slot: u64,
+    /// canonical bump seed of the account if it is the greeting address of a
+    /// user, see `find_greeting_address`, 0 otherwise
+    pub bump_seed: u8,
+    /// set while a greeting calls out to other programs, so the account
+    /// cannot be greeted again until the call returns
+    pub processing: bool,
+}
+
+impl GreetingAccount {
+    /// Space needed by a greeting account
+    pub const LEN: usize = DISCRIMINATOR_LEN + 1 + 4 + 8 + 1 + 32 + 8 + 1 + 1;
+
+    /// Current layout version
+    pub const VERSION: u8 = 4;
+}
+
+impl AccountState for GreetingAccount {
+    const DISCRIMINATOR: [u8; DISCRIMINATOR_LEN] = *b"greeting";
+
+    /// Load a greeting account, failing for accounts still using an older
+    /// layout, which need a `Migrate` first
+    fn load(data: &[u8]) -> Result<Self, ProgramError> {
+        if data.len() < Self::LEN {
+            return Err(ProgramError::AccountDataTooSmall);
+        }
+        let mut greeting_account = Self::deserialize(&mut state_data(data, &Self::DISCRIMINATOR)?)?;
+        match greeting_account.version {
+            // Freshly allocated
+            0 => greeting_account.version = Self::VERSION,
+            Self::VERSION => {}
+            _ => return Err(ProgramError::InvalidAccountData),
+        }
+        Ok(greeting_account)
+    }
+}
+
+/// Greeting account layout before `GreetingAccount::version` was added,
+/// recognized by its size
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Default, PartialEq)]
+pub struct GreetingAccountV1 {
+    pub counter: u32,
+    pub expires_at: i64,
+    pub has_mirror: bool,
+    pub last_greeter: Pubkey,
+    pub last_slot: u64,
+}
+
+impl GreetingAccountV1 {
+    /// Size of a greeting account using this layout
+    pub const LEN: usize = DISCRIMINATOR_LEN + 4 + 8 + 1 + 32 + 8;
+}
+
+impl AccountState for GreetingAccountV1 {
+    const DISCRIMINATOR: [u8; DISCRIMINATOR_LEN] = GreetingAccount::DISCRIMINATOR;
+}
+
+impl From<GreetingAccountV1> for GreetingAccount {
+    fn from(greeting_account: GreetingAccountV1) -> Self {
+        Self {
+            version: Self::VERSION,
+            counter: greeting_account.counter,
+            expires_at: greeting_account.expires_at,
+            has_mirror: greeting_account.has_mirror,
+            last_greeter: greeting_account.last_greeter,
+            last_slot: greeting_account.last_slot,
+            bump_seed: 0,
+            processing: false,
+        }
+    }
+}
+
+/// Greeting account layout before `GreetingAccount::bump_seed` was added,
+/// recognized by its size
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Default, PartialEq)]
+pub struct GreetingAccountV2 {
+    pub version: u8,
+    pub counter: u32,
+    pub expires_at: i64,
+    pub has_mirror: bool,
+    pub last_greeter: Pubkey,
+    pub last_slot: u64,
+}
+
+impl GreetingAccountV2 {
+    /// Size of a greeting account using this layout
+    pub const LEN: usize = DISCRIMINATOR_LEN + 1 + 4 + 8 + 1 + 32 + 8;
+}
+
+impl AccountState for GreetingAccountV2 {
+    const DISCRIMINATOR: [u8; DISCRIMINATOR_LEN] = GreetingAccount::DISCRIMINATOR;
+}
+
+impl From<GreetingAccountV2> for GreetingAccount {
+    fn from(greeting_account: GreetingAccountV2) -> Self {
+        Self {
+            version: Self::VERSION,
+            counter: greeting_account.counter,
+            expires_at: greeting_account.expires_at,
+            has_mirror: greeting_account.has_mirror,
+            last_greeter: greeting_account.last_greeter,
+            last_slot: greeting_account.last_slot,
+            bump_seed: 0,
+            processing: false,
+        }
+    }
+}
+
+/// Greeting account layout before `GreetingAccount::processing` was added,
+/// recognized by its size
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Default, PartialEq)]
+pub struct GreetingAccountV3 {
+    pub version: u8,
+    pub counter: u32,
+    pub expires_at: i64,
+    pub has_mirror: bool,
+    pub last_greeter: Pubkey,
+    pub last_slot: u64,
+    pub bump_seed: u8,
+}
+
+impl GreetingAccountV3 {
+    /// Size of a greeting account using this layout
+    pub const LEN: usize = DISCRIMINATOR_LEN + 1 + 4 + 8 + 1 + 32 + 8 + 1;
+}
+
+impl AccountState for GreetingAccountV3 {
+    const DISCRIMINATOR: [u8; DISCRIMINATOR_LEN] = GreetingAccount::DISCRIMINATOR;
+}
+
+impl From<GreetingAccountV3> for GreetingAccount {
+    fn from(greeting_account: GreetingAccountV3) -> Self {
+        Self {
+            version: Self::VERSION,
+            counter: greeting_account.counter,
+            expires_at: greeting_account.expires_at,
+            has_mirror: greeting_account.has_mirror,
+            last_greeter: greeting_account.last_greeter,
+            last_slot: greeting_account.last_slot,
+            bump_seed: greeting_account.bump_seed,
+            processing: false,
+        }
+    }
+}
+
+/// Seed prefix of the per-user greeting accounts
+pub const GREETING_SEED: &[u8] = b"greeting";
+
+/// Greeting address of `user` and its bump seed
+///
+/// Greeting a user's own greeting address creates it on the fly, giving every
+/// wallet a counter without any setup.
+pub fn find_greeting_address(program_id: &Pubkey, user: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[GREETING_SEED, user.as_ref()], program_id)
+}
+
+/// Greeting address of the channel `name` of `user` and its bump seed
+///
+/// Channels give a user independent counters next to its greeting address,
+/// see `CreateChannel`.
+pub fn find_channel_address(program_id: &Pubkey, user: &Pubkey, name: &str) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[GREETING_SEED, user.as_ref(), name.as_bytes()], program_id)
+}
+
+/// Seed prefix of the per-user channel lists
+pub const CHANNELS_SEED: &[u8] = b"channels";
+
+/// Maximum number of channels per user
+pub const MAX_CHANNELS: usize = 8;
+
+/// Maximum length of a channel name, which is used as a seed
+pub const MAX_CHANNEL_NAME_LEN: usize = MAX_SEED_LEN;
+
+/// Channels created by a user, stored in the PDA derived from `CHANNELS_SEED`
+/// and the user, letting clients list them
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Default, PartialEq)]
+pub struct Channels {
+    /// set by the first `CreateChannel` of the user
+    pub is_initialized: bool,
+    /// channel names, in creation order
+    pub names: Vec<String>,
+    /// canonical bump seed of the channel list address
+    pub bump_seed: u8,
+}
+
+impl Channels {
+    /// Space needed by a channel list
+    pub const LEN: usize =
+        DISCRIMINATOR_LEN + 1 + 4 + MAX_CHANNELS * (4 + MAX_CHANNEL_NAME_LEN) + 1;
+
+    /// Add the channel `name`, failing if it is not a valid name, already
+    /// exists or the list is full
+    pub fn add(&mut self, name: &str) -> Result<(), ProgramError> {
+        if name.is_empty() || name.len() > MAX_CHANNEL_NAME_LEN {
+            return Err(HelloWorldError::InvalidChannelName.into());
+        }
+        if self.names.iter().any(|existing| existing == name) {
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+        if self.names.len() == MAX_CHANNELS {
+            return Err(HelloWorldError::TooManyChannels.into());
+        }
+        self.names.push(name.to_string());
+        Ok(())
+    }
+}
+
+impl AccountState for Channels {
+    const DISCRIMINATOR: [u8; DISCRIMINATOR_LEN] = *b"channels";
+}
+
+/// Channel list address of `user` and its bump seed
+pub fn find_channels_address(program_id: &Pubkey, user: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[CHANNELS_SEED, user.as_ref()], program_id)
+}
+
+/// Seed of the registry of greeting addresses
+pub const REGISTRY_SEED: &[u8] = b"registry";
+
+/// Number of greeting addresses the registry keeps track of
+pub const REGISTRY_RECENT_LEN: usize = 16;
+
+/// Greeting addresses created by the program, stored in the PDA derived from
+/// `REGISTRY_SEED`, letting clients discover greeting accounts without
+/// scanning every program account
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Default, PartialEq)]
+pub struct Registry {
+    /// set by `InitializeRegistry`
+    pub is_initialized: bool,
+    /// number of greeting addresses created since the registry was
+    /// initialized
+    pub count: u64,
+    /// latest greeting addresses created, oldest first, at most
+    /// `REGISTRY_RECENT_LEN`
+    pub recent: Vec<Pubkey>,
+    /// canonical bump seed of the registry address
+    pub bump_seed: u8,
+}
+
+impl Registry {
+    /// Space needed by the registry account
+    pub const LEN: usize = DISCRIMINATOR_LEN + 1 + 8 + (4 + 32 * REGISTRY_RECENT_LEN) + 1;
+
+    /// Record the creation of `greeting_address`, forgetting the oldest
+    /// recent one if needed
+    pub fn record(&mut self, greeting_address: Pubkey) -> Result<(), HelloWorldError> {
+        self.count = self
+            .count
+            .checked_add(1)
+            .ok_or(HelloWorldError::CounterOverflow)?;
+        if self.recent.len() == REGISTRY_RECENT_LEN {
+            self.recent.remove(0);
+        }
+        self.recent.push(greeting_address);
+        Ok(())
+    }
+}
+
+impl AccountState for Registry {
+    const DISCRIMINATOR: [u8; DISCRIMINATOR_LEN] = *b"registry";
+}
+
+/// Address of the registry account and its bump seed
+pub fn find_registry_address(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[REGISTRY_SEED], program_id)
+}
+
+/// Zero-copy view of a stored `GreetingAccount`
+///
+/// The fields mirror its Borsh layout byte for byte, with integers kept as
+/// little-endian byte arrays so the view has no alignment requirements, which
+/// lets greetings update accounts in place instead of (de)serializing them.
+#[derive(Clone, Copy, Debug)]
+#[repr(C)]
+pub struct GreetingAccountPod {
+    pub discriminator: [u8; DISCRIMINATOR_LEN],
+    pub version: u8,
+    pub counter: [u8; 4],
+    pub expires_at: [u8; 8],
+    pub has_mirror: u8,
+    pub last_greeter: Pubkey,
+    pub last_slot: [u8; 8],
+    pub bump_seed: u8,
+    pub processing: u8,
+}
+
+// Every field is a byte array, so there is no padding and any bytes are a
+// valid value
+unsafe impl Zeroable for GreetingAccountPod {}
+unsafe impl Pod for GreetingAccountPod {}
+
+impl GreetingAccountPod {
+    /// View greeting account data in place, with the same checks as
+    /// `GreetingAccount::load`, initializing fresh accounts
+    pub fn from_account_data(data: &mut [u8]) -> Result<&mut Self, ProgramError> {
+        if data.len() < GreetingAccount::LEN {
+            return Err(ProgramError::AccountDataTooSmall);
+        }
+        let greeting_account: &mut Self =
+            bytemuck::from_bytes_mut(&mut data[..GreetingAccount::LEN]);
+        if greeting_account.discriminator == [0; DISCRIMINATOR_LEN] {
+            greeting_account.discriminator = GreetingAccount::DISCRIMINATOR;
+        } else if greeting_account.discriminator != GreetingAccount::DISCRIMINATOR {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        match greeting_account.version {
+            // Freshly allocated
+            0 => greeting_account.version = GreetingAccount::VERSION,
+            GreetingAccount::VERSION => {}
+            _ => {
+                msg!("Unknown greeting account version");
+                return Err(ProgramError::InvalidAccountData);
+            }
+        }
+        Ok(greeting_account)
+    }
+
+    pub fn counter(&self) -> u32 {
+        u32::from_le_bytes(self.counter)
+    }
+
+    pub fn set_counter(&mut self, counter: u32) {
+        self.counter = counter.to_le_bytes();
+    }
+
+    pub fn expires_at(&self) -> i64 {
+        i64::from_le_bytes(self.expires_at)
+    }
+
+    pub fn set_expires_at(&mut self, expires_at: i64) {
+        self.expires_at = expires_at.to_le_bytes();
+    }
+
+    pub fn has_mirror(&self) -> bool {
+        self.has_mirror != 0
+    }
+
+    pub fn last_slot(&self) -> u64 {
+        u64::from_le_bytes(self.last_slot)
+    }
+
+    pub fn set_last_slot(&mut self, last_slot: u64) {
+        self.last_slot = last_slot.to_le_bytes();
+    }
+
+    pub fn processing(&self) -> bool {
+        self.processing != 0
+    }
+
+    pub fn set_processing(&mut self, processing: bool) {
+        self.processing = processing as u8;
+    }
+}
+
+impl From<&GreetingAccountPod> for GreetingAccount {
+    fn from(greeting_account: &GreetingAccountPod) -> Self {
+        Self {
+            version: greeting_account.version,
+            counter: greeting_account.counter(),
+            expires_at: greeting_account.expires_at(),
+            has_mirror: greeting_account.has_mirror(),
+            last_greeter: greeting_account.last_greeter,
+            last_slot: greeting_account.last_slot(),
+            bump_seed: greeting_account.bump_seed,
+            processing: greeting_account.processing(),
+        }
+    }
+}
+
+/// Seed of the greeting history account
+pub const HISTORY_SEED: &[u8] = b"history";
+
+/// Number of greetings the history keeps
+pub const HISTORY_LEN: usize = 32;
+
+/// A greeting recorded in the history
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct HistoryEntry {
+    /// signer of the greeting
+    pub greeter: Pubkey,
+    /// slot of the greeting
+    pub slot: u64,
+    /// hash of the greeting message, of the empty message if there was none
+    pub message_hash: Hash,
+    /// SHA-256 of the previous greeting's chain hash, the greeter and the
+    /// slot, making the sequence of greetings tamper-evident
+    pub chain_hash: Hash,
+}
+
+impl HistoryEntry {
+    const LEN: usize = 32 + 8 + 32 + 32;
+
+    /// Chain hash of a greeting by `greeter` at `slot` following a greeting
+    /// with the chain hash `previous`, the default hash for the first one
+    pub fn next_chain_hash(previous: &Hash, greeter: &Pubkey, slot: u64) -> Hash {
+        hashv(&[previous.as_ref(), greeter.as_ref(), &slot.to_le_bytes()])
+    }
+}
+
+/// Latest `SayHello` greetings, stored in the PDA derived from `HISTORY_SEED`
+///
+/// Entries form a ring buffer: each greeting overwrites the oldest one, so
+/// the account keeps a fixed size. Use `History::chronological` to read them
+/// in order, and `History::verify_chain` to check their chain hashes.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Default, PartialEq)]
+pub struct History {
+    /// set by `InitializeHistory`
+    pub is_initialized: bool,
+    /// number of greetings recorded since the history was initialized, the
+    /// next one being written at `count % HISTORY_LEN`
+    pub count: u64,
+    /// recorded greetings, in ring buffer order
+    pub entries: [HistoryEntry; HISTORY_LEN],
+    /// canonical bump seed of the history address
+    pub bump_seed: u8,
+}
+
+impl History {
+    /// Space needed by the history account
+    pub const LEN: usize = DISCRIMINATOR_LEN + 1 + 8 + HistoryEntry::LEN * HISTORY_LEN + 1;
+
+    /// Record a greeting, chained to the latest one, overwriting the oldest
+    /// one once the history is full
+    pub fn record(
+        &mut self,
+        greeter: Pubkey,
+        slot: u64,
+        message_hash: Hash,
+    ) -> Result<(), HelloWorldError> {
+        let count = self
+            .count
+            .checked_add(1)
+            .ok_or(HelloWorldError::CounterOverflow)?;
+        let previous = match self.count {
+            0 => Hash::default(),
+            _ => self.entries[((self.count - 1) % HISTORY_LEN as u64) as usize].chain_hash,
+        };
+        self.entries[(self.count % HISTORY_LEN as u64) as usize] = HistoryEntry {
+            greeter,
+            slot,
+            message_hash,
+            chain_hash: HistoryEntry::next_chain_hash(&previous, &greeter, slot),
+        };
+        self.count = count;
+        Ok(())
+    }
+
+    /// Recorded greetings, oldest first
+    pub fn chronological(&self) -> Vec<HistoryEntry> {
+        if self.count <= HISTORY_LEN as u64 {
+            return self.entries[..self.count as usize].to_vec();
+        }
+        let (newest, oldest) = self
+            .entries
+            .split_at((self.count % HISTORY_LEN as u64) as usize);
+        [oldest, newest].concat()
+    }
+
+    /// Walk the recorded greetings oldest first, checking each one chains to
+    /// the one before, and return the chronological index of the first one
+    /// that does not
+    ///
+    /// Once greetings were overwritten, the chain is trusted from the oldest
+    /// greeting left.
+    pub fn verify_chain(&self) -> Result<(), usize> {
+        let entries = self.chronological();
+        let overwritten = self.count > HISTORY_LEN as u64;
+        let mut previous = match entries.first() {
+            Some(oldest) if overwritten => oldest.chain_hash,
+            _ => Hash::default(),
+        };
+        for (index, entry) in entries.iter().enumerate().skip(overwritten as usize) {
+            if entry.chain_hash
+                != HistoryEntry::next_chain_hash(&previous, &entry.greeter, entry.slot)
+            {
+                return Err(index);
+            }
+            previous = entry.chain_hash;
+        }
+        Ok(())
+    }
+}
+
+impl AccountState for History {
+    const DISCRIMINATOR: [u8; DISCRIMINATOR_LEN] = *b"history\0";
+}
+
+/// Address of the history account and its bump seed
+pub fn find_history_address(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[HISTORY_SEED], program_id)
+}
+
+/// Seed of the program metadata account
+pub const METADATA_SEED: &[u8] = b"metadata";
+
+/// Maximum length of each metadata field, fitting a hex SHA-1 commit hash
+pub const MAX_METADATA_FIELD_LEN: usize = 40;
+
+/// What program is deployed, stored in the PDA derived from `METADATA_SEED` for
+/// explorers and the CLI to display
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Default, PartialEq)]
+pub struct Metadata {
+    /// set by the first `WriteMetadata`
+    pub is_initialized: bool,
+    /// crate name of the program
+    pub name: String,
+    /// semver of the program
+    pub version: String,
+    /// commit the program was built from, taken from the `HELLOWORLD_COMMIT`
+    /// environment variable at build time, empty if it was not set
+    pub commit: String,
+    /// canonical bump seed of the metadata address
+    pub bump_seed: u8,
+}
+
+impl Metadata {
+    /// Space needed by the metadata account
+    pub const LEN: usize = DISCRIMINATOR_LEN + 1 + 3 * (4 + MAX_METADATA_FIELD_LEN) + 1;
+
+    /// Metadata of this build of the program
+    pub fn current(bump_seed: u8) -> Self {
+        let field = |value: &str| value.chars().take(MAX_METADATA_FIELD_LEN).collect();
+        Self {
+            is_initialized: true,
+            name: field(env!("CARGO_PKG_NAME")),
+            version: field(env!("CARGO_PKG_VERSION")),
+            commit: field(option_env!("HELLOWORLD_COMMIT").unwrap_or_default()),
+            bump_seed,
+        }
+    }
+}
+
+impl AccountState for Metadata {
+    const DISCRIMINATOR: [u8; DISCRIMINATOR_LEN] = *b"metadata";
+}
+
+/// Address of the metadata account and its bump seed
+pub fn find_metadata_address(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[METADATA_SEED], program_id)
+}
+
+/// Seed of the program's singleton config account
+pub const CONFIG_SEED: &[u8] = b"config";
+
+/// Program-wide settings, stored in the PDA derived from `CONFIG_SEED`
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Default, PartialEq)]
+pub struct Config {
+    /// set by `InitializeConfig`
+    pub is_initialized: bool,
+    /// authority allowed to change the config
+    pub admin: Pubkey,
+    /// whether every instruction but the admin ones is paused
+    pub paused: bool,
+    /// root of the Merkle allowlist of greeters, if greetings are restricted
+    pub allowlist_root: Option<[u8; 32]>,
+    /// slots a registered greeter has to wait between greetings, 0 to disable
+    pub rate_limit_slots: u64,
+    /// seconds a greeting keeps an account alive, 0 for greetings to never
+    /// expire
+    pub greeting_ttl: i64,
+    /// how greetings are priced
+    pub fee_policy: FeePolicy,
+    /// slots a queued administrative action waits before it can execute, 0
+    /// for administrative actions to take effect right away
+    pub timelock_slots: u64,
+    /// whether greetings are rejected when the program is invoked through
+    /// CPI rather than by a top-level instruction
+    pub top_level_only: bool,
+    /// whether logs give the length and hash of greeting messages rather than
+    /// their contents
+    pub redact_logs: bool,
+    /// longest greeting message in bytes, 0 for `MAX_MESSAGE_LEN`
+    pub max_message_len: u16,
+    /// admin proposed by the current one, who takes over once they accept
+    pub pending_admin: Option<Pubkey>,
+    /// canonical bump seed of the config address
+    pub bump_seed: u8,
+}
+
+impl Config {
+    /// Space needed by the config account
+    pub const LEN: usize = DISCRIMINATOR_LEN
+        + 1
+        + 32
+        + 1
+        + (1 + 32)
+        + 8
+        + 8
+        + FeePolicy::MAX_LEN
+        + 8
+        + 1
+        + 1
+        + 2
+        + (1 + 32)
+        + 1;
+
+    /// Longest greeting message in bytes
+    pub fn max_message_len(&self) -> usize {
+        match self.max_message_len {
+            0 => MAX_MESSAGE_LEN,
+            max_message_len => max_message_len.into(),
+        }
+    }
+}
+
+impl AccountState for Config {
+    const DISCRIMINATOR: [u8; DISCRIMINATOR_LEN] = *b"config\0\0";
+}
+
+/// Address of the config account and its bump seed
+pub fn find_config_address(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[CONFIG_SEED], program_id)
+}
+
+/// Seed of the treasury collecting greeting fees and reaped lamports
+pub const TREASURY_SEED: &[u8] = b"treasury";
+
+/// Address of the treasury and its bump seed
+///
+/// Until `InitializeTreasury` runs, the treasury is a system account holding no
+/// data, which has to be funded up to the rent-exempt minimum before it can
+/// collect smaller amounts.
+pub fn find_treasury_address(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[TREASURY_SEED], program_id)
+}
+
+/// Running totals of the treasury, stored in the treasury itself once it is
+/// initialized
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Default, PartialEq)]
+pub struct Treasury {
+    /// set by `InitializeTreasury`
+    pub is_initialized: bool,
+    /// lamports collected from fees and reaped accounts since initialization
+    pub total_collected: u64,
+    /// lamports withdrawn by the admin
+    pub total_withdrawn: u64,
+    /// canonical bump seed of the treasury address
+    pub bump_seed: u8,
+}
+
+impl Treasury {
+    /// Space needed by the treasury
+    pub const LEN: usize = DISCRIMINATOR_LEN + 1 + 8 + 8 + 1;
+}
+
+impl AccountState for Treasury {
+    const DISCRIMINATOR: [u8; DISCRIMINATOR_LEN] = *b"treasury";
+}
+
+/// Seed prefix of the per-greeter accounts
+pub const GREETER_SEED: &[u8] = b"greeter";
+
+/// Greeting activity of a single greeter, stored in the PDA derived from
+/// `GREETER_SEED` and the greeter's pubkey
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Default, PartialEq)]
+pub struct GreeterAccount {
+    /// set by `RegisterGreeter`
+    pub is_initialized: bool,
+    /// number of greeting instructions sent by the greeter
+    pub greetings: u64,
+    /// slot of the greeter's latest greeting
+    pub last_greeting_slot: u64,
+    /// canonical bump seed of the greeter account address
+    pub bump_seed: u8,
+}
+
+impl GreeterAccount {
+    /// Space needed by a greeter account
+    pub const LEN: usize = DISCRIMINATOR_LEN + 1 + 8 + 8 + 1;
+}
+
+impl AccountState for GreeterAccount {
+    const DISCRIMINATOR: [u8; DISCRIMINATOR_LEN] = *b"greeter\0";
+}
+
+/// Address of the greeter account of `greeter` and its bump seed
+pub fn find_greeter_address(program_id: &Pubkey, greeter: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[GREETER_SEED, greeter.as_ref()], program_id)
+}
+
+/// Seed of the leaderboard account
+pub const LEADERBOARD_SEED: &[u8] = b"leaderboard";
+
+/// Number of greeters ranked on the leaderboard
+pub const LEADERBOARD_LEN: usize = 10;
+
+/// A greeter ranked on the leaderboard
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct LeaderboardEntry {
+    pub greeter: Pubkey,
+    /// greetings sent by the greeter, see `GreeterAccount::greetings`
+    pub greetings: u64,
+}
+
+impl LeaderboardEntry {
+    const LEN: usize = 32 + 8;
+}
+
+/// Registered greeters with the most greetings, stored in the PDA derived from
+/// `LEADERBOARD_SEED`
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Default, PartialEq)]
+pub struct Leaderboard {
+    /// set by `InitializeLeaderboard`
+    pub is_initialized: bool,
+    /// at most `LEADERBOARD_LEN` greeters, most greetings first, greeters with
+    /// as many greetings ordered by pubkey
+    pub entries: Vec<LeaderboardEntry>,
+    /// canonical bump seed of the leaderboard address
+    pub bump_seed: u8,
+}
+
+impl Leaderboard {
+    /// Space needed by the leaderboard account
+    pub const LEN: usize =
+        DISCRIMINATOR_LEN + 1 + (4 + LeaderboardEntry::LEN * LEADERBOARD_LEN) + 1;
+
+    /// Rank `greeter` with its new greeting count, evicting the last greeter
+    /// if the leaderboard overflows
+    pub fn update(&mut self, greeter: Pubkey, greetings: u64) {
+        self.entries.retain(|entry| entry.greeter != greeter);
+        let entry = LeaderboardEntry { greeter, greetings };
+        let rank = self
+            .entries
+            .partition_point(|other| Self::ranks_before(other, &entry));
+        if rank < LEADERBOARD_LEN {
+            self.entries.insert(rank, entry);
+            self.entries.truncate(LEADERBOARD_LEN);
+        }
+    }
+
+    fn ranks_before(entry: &LeaderboardEntry, other: &LeaderboardEntry) -> bool {
+        (entry.greetings, other.greeter) > (other.greetings, entry.greeter)
+    }
+}
+
+impl AccountState for Leaderboard {
+    const DISCRIMINATOR: [u8; DISCRIMINATOR_LEN] = *b"leaders\0";
+}
+
+/// Address of the leaderboard account and its bump seed
+pub fn find_leaderboard_address(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[LEADERBOARD_SEED], program_id)
+}
+
+/// Maximum number of guardians a greeting account owner can register
+pub const MAX_GUARDIANS: usize = 8;
+
+/// Guardians able to recover ownership of a greeting account after its owner
+/// key is lost
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Default, PartialEq)]
+pub struct GuardianSet {
+    /// set once the owner registers guardians
+    pub is_initialized: bool,
+    /// greeting account whose ownership is recorded
+    pub greeting: Pubkey,
+    /// current owner of the greeting account
+    pub owner: Pubkey,
+    /// number of guardians required to start a recovery
+    pub threshold: u8,
+    /// slots the owner has to cancel a recovery before it can complete
+    pub recovery_delay: u64,
+    /// registered guardians, at most `MAX_GUARDIANS`
+    pub guardians: Vec<Pubkey>,
+    /// recovery started by the guardians, if any
+    pub pending_recovery: Option<PendingRecovery>,
+}
+
+impl GuardianSet {
+    /// Space needed by a guardian set account holding `MAX_GUARDIANS` guardians
+    pub const LEN: usize = DISCRIMINATOR_LEN
+        + 1
+        + 32
+        + 32
+        + 1
+        + 8
+        + (4 + 32 * MAX_GUARDIANS)
+        + (1 + PendingRecovery::LEN);
+}
+
+impl AccountState for GuardianSet {
+    const DISCRIMINATOR: [u8; DISCRIMINATOR_LEN] = *b"guardian";
+}
+
+/// Ownership recovery started by the guardians
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub struct PendingRecovery {
+    /// owner once the recovery completes
+    pub new_owner: Pubkey,
+    /// first slot at which the recovery can complete
+    pub recoverable_slot: u64,
+}
+
+impl PendingRecovery {
+    const LEN: usize = 32 + 8;
+}
+
+/// Seed of the denylist account
+pub const DENYLIST_SEED: &[u8] = b"denylist";
+
+/// Maximum number of greeters the denylist holds
+pub const MAX_DENIED: usize = 32;
+
+/// Greeters the admin denies greeting with `SayHello`, stored in the PDA
+/// derived from `DENYLIST_SEED`
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Default, PartialEq)]
+pub struct Denylist {
+    /// set by the first `AddToDenylist`
+    pub is_initialized: bool,
+    /// denied greeters, at most `MAX_DENIED`
+    pub denied: Vec<Pubkey>,
+    /// canonical bump seed of the denylist address
+    pub bump_seed: u8,
+}
+
+impl Denylist {
+    /// Space needed by the denylist account
+    pub const LEN: usize = DISCRIMINATOR_LEN + 1 + (4 + 32 * MAX_DENIED) + 1;
+
+    /// Whether `greeter` is denied
+    pub fn is_denied(&self, greeter: &Pubkey) -> bool {
+        self.denied.contains(greeter)
+    }
+
+    /// Deny `greeter`, failing if it already is or the list is full
+    pub fn add(&mut self, greeter: Pubkey) -> Result<(), ProgramError> {
+        if self.is_denied(&greeter) {
+            return Err(ProgramError::InvalidArgument);
+        }
+        if self.denied.len() == MAX_DENIED {
+            return Err(HelloWorldError::DenylistFull.into());
+        }
+        self.denied.push(greeter);
+        Ok(())
+    }
+
+    /// Allow `greeter` again, failing if it is not denied
+    pub fn remove(&mut self, greeter: &Pubkey) -> Result<(), ProgramError> {
+        let index = self
+            .denied
+            .iter()
+            .position(|denied| denied == greeter)
+            .ok_or(ProgramError::InvalidArgument)?;
+        self.denied.remove(index);
+        Ok(())
+    }
+}
+
+impl AccountState for Denylist {
+    const DISCRIMINATOR: [u8; DISCRIMINATOR_LEN] = *b"denylist";
+}
+
+/// Address of the denylist account and its bump seed
+pub fn find_denylist_address(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[DENYLIST_SEED], program_id)
+}
+
+/// Seed of the multisig account
+pub const MULTISIG_SEED: &[u8] = b"multisig";
+
+/// Maximum number of signers of the multisig
+pub const MAX_MULTISIG_SIGNERS: usize = 8;
+
+/// Signers jointly acting as the config admin, stored in the PDA derived from
+/// `MULTISIG_SEED` once `CreateMultisig` hands it the config
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Default, PartialEq)]
+pub struct Multisig {
+    /// set by `CreateMultisig`
+    pub is_initialized: bool,
+    /// number of approvals a proposal needs to be executed
+    pub threshold: u8,
+    /// signers able to propose and approve, at most `MAX_MULTISIG_SIGNERS`
+    pub signers: Vec<Pubkey>,
+    /// number of proposals made, and index of the next one
+    pub proposal_count: u64,
+    /// canonical bump seed of the multisig address
+    pub bump_seed: u8,
+}
+
+impl Multisig {
+    /// Space needed by the multisig account
+    pub const LEN: usize = DISCRIMINATOR_LEN + 1 + 1 + (4 + 32 * MAX_MULTISIG_SIGNERS) + 8 + 1;
+}
+
+impl AccountState for Multisig {
+    const DISCRIMINATOR: [u8; DISCRIMINATOR_LEN] = *b"multisig";
+}
+
+/// Address of the multisig account and its bump seed
+pub fn find_multisig_address(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[MULTISIG_SEED], program_id)
+}
+
+/// Seed prefix of the multisig proposals
+pub const PROPOSAL_SEED: &[u8] = b"proposal";
+
+/// Admin action proposed to the multisig, stored in the PDA derived from
+/// `PROPOSAL_SEED` and its index
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Default, PartialEq)]
+pub struct Proposal {
+    /// set by `Propose`
+    pub is_initialized: bool,
+    /// index of the proposal, its position in the multisig's proposals
+    pub index: u64,
+    /// action performed once executed
+    pub action: MultisigAction,
+    /// multisig signers who approved the proposal
+    pub approvals: Vec<Pubkey>,
+    /// set once the proposal is executed, after which it cannot be approved
+    /// or executed again
+    pub executed: bool,
+    /// canonical bump seed of the proposal address
+    pub bump_seed: u8,
+}
+
+impl Proposal {
+    /// Space needed by a proposal account
+    pub const LEN: usize = DISCRIMINATOR_LEN
+        + 1
+        + 8
+        + MultisigAction::MAX_LEN
+        + (4 + 32 * MAX_MULTISIG_SIGNERS)
+        + 1
+        + 1;
+}
+
+impl AccountState for Proposal {
+    const DISCRIMINATOR: [u8; DISCRIMINATOR_LEN] = *b"proposal";
+}
+
+/// Address of the multisig proposal at `index` and its bump seed
+pub fn find_proposal_address(program_id: &Pubkey, index: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[PROPOSAL_SEED, &index.to_le_bytes()], program_id)
+}
+
+/// Seed of the timelock account
+pub const TIMELOCK_SEED: &[u8] = b"timelock";
+
+/// Maximum number of actions queued in the timelock at once
+pub const MAX_QUEUED_ACTIONS: usize = 8;
+
+/// Config update waiting in the timelock
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub struct QueuedAction {
+    /// id given by `QueueAction`
+    pub id: u64,
+    /// update applied once the action executes
+    pub update: ConfigUpdate,
+    /// first slot at which the action can execute
+    pub executable_slot: u64,
+}
+
+impl QueuedAction {
+    const LEN: usize = 8 + ConfigUpdate::MAX_LEN + 8;
+}
+
+/// Administrative actions waiting for the config's `timelock_slots` to pass,
+/// stored in the PDA derived from `TIMELOCK_SEED`
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Default, PartialEq)]
+pub struct Timelock {
+    /// set by the first `QueueAction`
+    pub is_initialized: bool,
+    /// id of the next queued action
+    pub next_id: u64,
+    /// actions waiting to execute, at most `MAX_QUEUED_ACTIONS`
+    pub queued: Vec<QueuedAction>,
+    /// canonical bump seed of the timelock address
+    pub bump_seed: u8,
+}
+
+impl Timelock {
+    /// Space needed by the timelock account
+    pub const LEN: usize =
+        DISCRIMINATOR_LEN + 1 + 8 + (4 + QueuedAction::LEN * MAX_QUEUED_ACTIONS) + 1;
+
+    /// Take the queued action `id` out of the queue, if any
+    pub fn take(&mut self, id: u64) -> Option<QueuedAction> {
+        let index = self.queued.iter().position(|action| action.id == id)?;
+        Some(self.queued.remove(index))
+    }
+}
+
+impl AccountState for Timelock {
+    const DISCRIMINATOR: [u8; DISCRIMINATOR_LEN] = *b"timelock";
+}
+
+/// Address of the timelock account and its bump seed
+pub fn find_timelock_address(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[TIMELOCK_SEED], program_id)
+}
+
+/// Implement the SPL `Pack` interface on top of the `AccountState` layout of
+/// each state type, letting other programs and clients size and unpack
+/// accounts without depending on Borsh
+macro_rules! impl_pack {
+    ($($state:ty),+) => {$(
+        impl Sealed for $state {}
+
+        impl Pack for $state {
+            const LEN: usize = <$state>::LEN;
+
+            fn pack_into_slice(&self, dst: &mut [u8]) {
+                // `LEN` fits the largest state, so storing cannot fail
+                self.store(dst).unwrap()
+            }
+
+            fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+                Self::load(src)
+            }
+        }
+    )+};
+}
+
+impl_pack!(
+    GreetingAccount,
+    Registry,
+    History,
+    Config,
+    GreeterAccount,
+    Leaderboard,
+    GuardianSet,
+    Metadata,
+    Channels,
+    Treasury,
+    Denylist,
+    Multisig,
+    Proposal,
+    Timelock
+);
+
+impl IsInitialized for GreetingAccount {
+    /// Fresh greeting accounts load as initialized, only older layouts need to
+    /// be migrated first
+    fn is_initialized(&self) -> bool {
+        self.version == Self::VERSION
+    }
+}
+
+impl IsInitialized for Registry {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl IsInitialized for History {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl IsInitialized for Config {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl IsInitialized for GreeterAccount {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl IsInitialized for Leaderboard {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl IsInitialized for GuardianSet {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl IsInitialized for Metadata {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl IsInitialized for Channels {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl IsInitialized for Treasury {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl IsInitialized for Denylist {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl IsInitialized for Multisig {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl IsInitialized for Proposal {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl IsInitialized for Timelock {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::fees::{FeeTier, MAX_FEE_TIERS};
+    use rand::{distributions::Alphanumeric, Rng};
+    use std::fmt::Debug;
+
+    const CASES: usize = 1000;
+
+    #[test]
+    fn test_greeting_account_layout() {
+        let greeting_account = GreetingAccount {
+            version: GreetingAccount::VERSION,
+            counter: 0x0102_0304,
+            expires_at: -2,
+            has_mirror: true,
+            last_greeter: Pubkey::new_from_array([9; 32]),
+            last_slot: 42,
+            bump_seed: 255,
+            processing: true,
+        };
+        let mut data = [0; GreetingAccount::LEN];
+        greeting_account.store(&mut data).unwrap();
+
+        // Existing accounts depend on these offsets, changing them needs a new
+        // version and a `Migrate` path
+        assert_eq!(&data[0..8], b"greeting");
+        assert_eq!(data[8], 4);
+        assert_eq!(&data[9..13], &[4, 3, 2, 1]);
+        assert_eq!(&data[13..21], &(-2i64).to_le_bytes());
+        assert_eq!(data[21], 1);
+        assert_eq!(&data[22..54], &[9; 32]);
+        assert_eq!(&data[54..62], &42u64.to_le_bytes());
+        assert_eq!(data[62], 255);
+        assert_eq!(data[63], 1);
+        assert_eq!(GreetingAccount::load(&data).unwrap(), greeting_account);
+
+        // Fresh accounts load as the current version
+        assert_eq!(
+            GreetingAccount::load(&[0; GreetingAccount::LEN]).unwrap(),
+            GreetingAccount {
+                version: GreetingAccount::VERSION,
+                ..GreetingAccount::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_old_greeting_accounts() {
+        let greeting_account_v1 = GreetingAccountV1 {
+            counter: 7,
+            expires_at: 100,
+            has_mirror: true,
+            last_greeter: Pubkey::new_from_array([9; 32]),
+            last_slot: 42,
+        };
+        let mut data = [0; GreetingAccountV1::LEN];
+        greeting_account_v1.store(&mut data).unwrap();
+
+        // Old accounts stay decodable, but only through their own layout
+        assert_eq!(GreetingAccountV1::load(&data).unwrap(), greeting_account_v1);
+        assert!(GreetingAccount::load(&data).is_err());
+        assert_eq!(
+            GreetingAccount::from(greeting_account_v1),
+            GreetingAccount {
+                version: GreetingAccount::VERSION,
+                counter: 7,
+                expires_at: 100,
+                has_mirror: true,
+                last_greeter: Pubkey::new_from_array([9; 32]),
+                last_slot: 42,
+                bump_seed: 0,
+                processing: false,
+            }
+        );
+
+        let greeting_account_v2 = GreetingAccountV2 {
+            version: 2,
+            counter: 7,
+            ..GreetingAccountV2::default()
+        };
+        let mut data = [0; GreetingAccountV2::LEN];
+        greeting_account_v2.store(&mut data).unwrap();
+        assert_eq!(GreetingAccountV2::load(&data).unwrap(), greeting_account_v2);
+        assert!(GreetingAccount::load(&data).is_err());
+        assert_eq!(
+            GreetingAccount::from(greeting_account_v2),
+            GreetingAccount {
+                version: GreetingAccount::VERSION,
+                counter: 7,
+                ..GreetingAccount::default()
+            }
+        );
+
+        let greeting_account_v3 = GreetingAccountV3 {
+            version: 3,
+            counter: 7,
+            bump_seed: 255,
+            ..GreetingAccountV3::default()
+        };
+        let mut data = [0; GreetingAccountV3::LEN];
+        greeting_account_v3.store(&mut data).unwrap();
+        assert_eq!(GreetingAccountV3::load(&data).unwrap(), greeting_account_v3);
+        assert!(GreetingAccount::load(&data).is_err());
+        assert_eq!(
+            GreetingAccount::from(greeting_account_v3),
+            GreetingAccount {
+                version: GreetingAccount::VERSION,
+                counter: 7,
+                bump_seed: 255,
+                ..GreetingAccount::default()
+            }
+        );
+
+        // Unknown versions are rejected
+        let mut data = [0; GreetingAccount::LEN];
+        GreetingAccount {
+            version: GreetingAccount::VERSION + 1,
+            ..GreetingAccount::default()
+        }
+        .store(&mut data)
+        .unwrap();
+        assert!(GreetingAccount::load(&data).is_err());
+    }
+
+    #[test]
+    fn test_greeting_account_pod() {
+        assert_eq!(
+            std::mem::size_of::<GreetingAccountPod>(),
+            GreetingAccount::LEN
+        );
+
+        let greeting_account = GreetingAccount {
+            version: GreetingAccount::VERSION,
+            counter: 7,
+            expires_at: -2,
+            has_mirror: true,
+            last_greeter: Pubkey::new_from_array([9; 32]),
+            last_slot: 42,
+            bump_seed: 255,
+            processing: false,
+        };
+        let mut data = [0; GreetingAccount::LEN];
+        greeting_account.store(&mut data).unwrap();
+
+        // Both views agree on the same bytes
+        let pod = GreetingAccountPod::from_account_data(&mut data).unwrap();
+        assert_eq!(GreetingAccount::from(&*pod), greeting_account);
+        pod.set_counter(8);
+        pod.set_last_slot(43);
+        pod.set_processing(true);
+        assert_eq!(
+            GreetingAccount::load(&data).unwrap(),
+            GreetingAccount {
+                counter: 8,
+                last_slot: 43,
+                processing: true,
+                ..greeting_account
+            }
+        );
+
+        // Fresh accounts are initialized, others checked like with Borsh
+        let mut data = [0; GreetingAccount::LEN];
+        GreetingAccountPod::from_account_data(&mut data).unwrap();
+        assert_eq!(
+            GreetingAccount::load(&data).unwrap(),
+            GreetingAccount {
+                version: GreetingAccount::VERSION,
+                ..GreetingAccount::default()
+            }
+        );
+        assert!(GreetingAccountPod::from_account_data(&mut [0; GreetingAccountV1::LEN]).is_err());
+        let mut data = [0; Config::LEN];
+        Config::default().store(&mut data).unwrap();
+        assert!(GreetingAccountPod::from_account_data(&mut data[..GreetingAccount::LEN]).is_err());
+    }
+
+    #[test]
+    fn test_registry() {
+        let mut registry = Registry {
+            is_initialized: true,
+            ..Registry::default()
+        };
+        let greeting_addresses: Vec<Pubkey> = (0..REGISTRY_RECENT_LEN + 2)
+            .map(|_| Pubkey::new_unique())
+            .collect();
+        for greeting_address in &greeting_addresses {
+            registry.record(*greeting_address).unwrap();
+        }
+        assert_eq!(registry.count, REGISTRY_RECENT_LEN as u64 + 2);
+        assert_eq!(registry.recent, greeting_addresses[2..]);
+
+        registry.count = u64::MAX;
+        assert_eq!(
+            registry.record(Pubkey::new_unique()),
+            Err(HelloWorldError::CounterOverflow)
+        );
+
+        // A full registry fits its account
+        let mut data = [0; Registry::LEN];
+        registry.store(&mut data).unwrap();
+        assert_eq!(Registry::load(&data).unwrap(), registry);
+    }
+
+    #[test]
+    fn test_history() {
+        let greeter = |slot| Pubkey::new_from_array([slot as u8; 32]);
+        let record = |history: &mut History, slot| {
+            history
+                .record(greeter(slot), slot, Hash::new_from_array([slot as u8; 32]))
+                .unwrap()
+        };
+        let slots = |history: &History| -> Vec<u64> {
+            history
+                .chronological()
+                .iter()
+                .map(|entry| entry.slot)
+                .collect()
+        };
+        let mut history = History {
+            is_initialized: true,
+            ..History::default()
+        };
+        assert!(history.chronological().is_empty());
+        assert_eq!(history.verify_chain(), Ok(()));
+
+        for slot in 0..3 {
+            record(&mut history, slot);
+        }
+        assert_eq!(slots(&history), [0, 1, 2]);
+        let first = history.chronological()[0];
+        assert_eq!(first.greeter, greeter(0));
+        assert_eq!(
+            first.chain_hash,
+            HistoryEntry::next_chain_hash(&Hash::default(), &greeter(0), 0)
+        );
+        assert_eq!(history.verify_chain(), Ok(()));
+
+        // Once full, the oldest greetings are overwritten, the chain going on
+        let total = HISTORY_LEN as u64 + 5;
+        for slot in 3..total {
+            record(&mut history, slot);
+        }
+        assert_eq!(history.count, total);
+        assert_eq!(slots(&history), (5..total).collect::<Vec<_>>());
+        assert_eq!(history.verify_chain(), Ok(()));
+
+        // Rewriting a greeting no longer matches its chain hash
+        let mut tampered = history.clone();
+        tampered.entries[(total % HISTORY_LEN as u64) as usize + 2].greeter = greeter(0);
+        assert_eq!(tampered.verify_chain(), Err(2));
+
+        let mut data = vec![0; History::LEN];
+        history.store(&mut data).unwrap();
+        assert_eq!(History::load(&data).unwrap(), history);
+    }
+
+    #[test]
+    fn test_channels() {
+        let mut channels = Channels::default();
+        assert_eq!(
+            channels.add(""),
+            Err(HelloWorldError::InvalidChannelName.into())
+        );
+        assert_eq!(
+            channels.add(&"a".repeat(MAX_CHANNEL_NAME_LEN + 1)),
+            Err(HelloWorldError::InvalidChannelName.into())
+        );
+        let name = |i: usize| format!("{:0>width$}", i, width = MAX_CHANNEL_NAME_LEN);
+        for i in 0..MAX_CHANNELS {
+            channels.add(&name(i)).unwrap();
+            assert_eq!(
+                channels.add(&name(i)),
+                Err(ProgramError::AccountAlreadyInitialized)
+            );
+        }
+        assert_eq!(
+            channels.add("full"),
+            Err(HelloWorldError::TooManyChannels.into())
+        );
+
+        // A full list of the longest names fits
+        let mut data = [0; Channels::LEN];
+        channels.store(&mut data).unwrap();
+        assert_eq!(Channels::load(&data).unwrap(), channels);
+    }
+
+    #[test]
+    fn test_denylist() {
+        let mut denylist = Denylist::default();
+        let greeter = Pubkey::new_from_array([1; 32]);
+        assert_eq!(
+            denylist.remove(&greeter),
+            Err(ProgramError::InvalidArgument)
+        );
+        for i in 0..MAX_DENIED as u8 {
+            denylist.add(Pubkey::new_from_array([i; 32])).unwrap();
+        }
+        assert_eq!(denylist.add(greeter), Err(ProgramError::InvalidArgument));
+        assert_eq!(
+            denylist.add(Pubkey::new_from_array([u8::MAX; 32])),
+            Err(HelloWorldError::DenylistFull.into())
+        );
+        assert!(denylist.is_denied(&greeter));
+
+        let mut data = [0; Denylist::LEN];
+        denylist.store(&mut data).unwrap();
+        assert_eq!(Denylist::load(&data).unwrap(), denylist);
+
+        denylist.remove(&greeter).unwrap();
+        assert!(!denylist.is_denied(&greeter));
+        denylist.add(greeter).unwrap();
+    }
+
+    #[test]
+    fn test_leaderboard() {
+        let greeters: Vec<Pubkey> = (0..LEADERBOARD_LEN as u8 + 2)
+            .map(|i| Pubkey::new_from_array([i; 32]))
+            .collect();
+        let ranking = |leaderboard: &Leaderboard| -> Vec<(Pubkey, u64)> {
+            leaderboard
+                .entries
+                .iter()
+                .map(|entry| (entry.greeter, entry.greetings))
+                .collect()
+        };
+        let mut leaderboard = Leaderboard {
+            is_initialized: true,
+            ..Leaderboard::default()
+        };
+
+        // Ties are ordered by pubkey, whatever the insertion order
+        leaderboard.update(greeters[2], 1);
+        leaderboard.update(greeters[0], 1);
+        leaderboard.update(greeters[1], 2);
+        assert_eq!(
+            ranking(&leaderboard),
+            [(greeters[1], 2), (greeters[0], 1), (greeters[2], 1)]
+        );
+
+        // Greeters move up instead of being listed twice
+        leaderboard.update(greeters[2], 3);
+        assert_eq!(
+            ranking(&leaderboard),
+            [(greeters[2], 3), (greeters[1], 2), (greeters[0], 1)]
+        );
+
+        // Once full, the last greeter is evicted by better ones only
+        let mut leaderboard = Leaderboard::default();
+        for greeter in &greeters[..LEADERBOARD_LEN] {
+            leaderboard.update(*greeter, 5);
+        }
+        let last = greeters[LEADERBOARD_LEN];
+        leaderboard.update(last, 4);
+        assert_eq!(leaderboard.entries.len(), LEADERBOARD_LEN);
+        assert!(!ranking(&leaderboard).contains(&(last, 4)));
+        leaderboard.update(last, 5);
+        assert!(!ranking(&leaderboard).contains(&(last, 5)));
+        leaderboard.update(last, 6);
+        assert_eq!(leaderboard.entries.len(), LEADERBOARD_LEN);
+        assert_eq!(leaderboard.entries[0].greeter, last);
+        assert_eq!(
+            leaderboard.entries[LEADERBOARD_LEN - 1].greeter,
+            greeters[LEADERBOARD_LEN - 2]
+        );
+
+        let mut data = [0; Leaderboard::LEN];
+        leaderboard.store(&mut data).unwrap();
+        assert_eq!(Leaderboard::load(&data).unwrap(), leaderboard);
+    }
+
+    #[test]
+    fn test_pack() {
+        assert_eq!(GreetingAccount::get_packed_len(), GreetingAccount::LEN);
+        assert_eq!(Config::get_packed_len(), Config::LEN);
+        assert_eq!(GuardianSet::get_packed_len(), GuardianSet::LEN);
+
+        // The largest proposal and timelock fit
+        let largest_update = ConfigUpdate {
+            admin: Some(Pubkey::new_unique()),
+            paused: Some(true),
+            fee_policy: Some(FeePolicy::TieredByCount {
+                tiers: vec![
+                    FeeTier {
+                        min_count: 0,
+                        lamports: 0,
+                    };
+                    MAX_FEE_TIERS
+                ],
+            }),
+            timelock_slots: Some(u64::MAX),
+            top_level_only: Some(true),
+            redact_logs: Some(true),
+            max_message_len: Some(u16::MAX),
+        };
+        let proposal = Proposal {
+            is_initialized: true,
+            action: MultisigAction::UpdateConfig(largest_update.clone()),
+            approvals: vec![Pubkey::new_unique(); MAX_MULTISIG_SIGNERS],
+            ..Proposal::default()
+        };
+        let mut data = [0; Proposal::LEN];
+        proposal.store(&mut data).unwrap();
+        assert_eq!(Proposal::load(&data).unwrap(), proposal);
+        let timelock = Timelock {
+            is_initialized: true,
+            queued: vec![
+                QueuedAction {
+                    id: u64::MAX,
+                    update: largest_update,
+                    executable_slot: u64::MAX,
+                };
+                MAX_QUEUED_ACTIONS
+            ],
+            ..Timelock::default()
+        };
+        let mut data = [0; Timelock::LEN];
+        timelock.store(&mut data).unwrap();
+        assert_eq!(Timelock::load(&data).unwrap(), timelock);
+
+        // Metadata fields are truncated to fit the account
+        let metadata = Metadata {
+            commit: "f".repeat(MAX_METADATA_FIELD_LEN),
+            ..Metadata::current(255)
+        };
+        assert!(metadata.name.len() <= MAX_METADATA_FIELD_LEN);
+        assert!(metadata.version.len() <= MAX_METADATA_FIELD_LEN);
+        let mut data = [0; Metadata::LEN];
+        Metadata::pack(metadata.clone(), &mut data).unwrap();
+        assert_eq!(Metadata::unpack(&data).unwrap(), metadata);
+
+        let config = Config {
+            is_initialized: true,
+            admin: Pubkey::new_from_array([9; 32]),
+            allowlist_root: Some([7; 32]),
+            pending_admin: Some(Pubkey::new_from_array([8; 32])),
+            ..Config::default()
+        };
+        let mut data = [0; Config::LEN];
+        Config::pack(config.clone(), &mut data).unwrap();
+        assert_eq!(Config::load(&data).unwrap(), config);
+        assert_eq!(Config::unpack(&data).unwrap(), config);
+
+        // Only exact sizes and initialized states unpack
+        assert_eq!(
+            Config::unpack(&data[..Config::LEN - 1]),
+            Err(ProgramError::InvalidAccountData)
+        );
+        assert_eq!(
+            Config::unpack(&[0; Config::LEN]),
+            Err(ProgramError::UninitializedAccount)
+        );
+        assert_eq!(
+            Config::unpack_unchecked(&[0; Config::LEN]).unwrap(),
+            Config::default()
+        );
+
+        // Fresh greeting accounts are ready to greet
+        assert_eq!(
+            GreetingAccount::unpack(&[0; GreetingAccount::LEN])
+                .unwrap()
+                .counter,
+            0
+        );
+    }
+
+    #[test]
+    fn test_discriminators() {
+        let mut data = [0; GuardianSet::LEN];
+        Config::default().store(&mut data).unwrap();
+        assert!(Config::load(&data).is_ok());
+        assert_eq!(
+            GreetingAccount::load(&data),
+            Err(ProgramError::InvalidAccountData)
+        );
+        assert_eq!(
+            GreeterAccount::load(&data),
+            Err(ProgramError::InvalidAccountData)
+        );
+        assert_eq!(
+            GuardianSet::load(&data),
+            Err(ProgramError::InvalidAccountData)
+        );
+    }
+
+    fn random_pubkey(rng: &mut impl Rng) -> Pubkey {
+        Pubkey::new_from_array(rng.gen())
+    }
+
+    fn random_pubkeys(rng: &mut impl Rng, max_len: usize) -> Vec<Pubkey> {
+        (0..rng.gen_range(0, max_len + 1))
+            .map(|_| random_pubkey(rng))
+            .collect()
+    }
+
+    fn random_string(rng: &mut impl Rng, max_len: usize) -> String {
+        let len = rng.gen_range(0, max_len + 1);
+        rng.sample_iter(Alphanumeric).take(len).collect()
+    }
+
+    /// Check that `state` loads back unchanged once stored, that truncated
+    /// data fails to load, and that garbage behind its discriminator does not
+    /// panic
+    fn check_round_trip<T: AccountState + Pack + Debug + PartialEq>(rng: &mut impl Rng, state: T) {
+        let mut data = vec![0; T::LEN];
+        state.store(&mut data).unwrap();
+        assert_eq!(T::load(&data).unwrap(), state);
+
+        let len = DISCRIMINATOR_LEN + state.try_to_vec().unwrap().len();
+        assert!(T::load(&data[..len - 1]).is_err());
+        assert!(T::load(&data[..rng.gen_range(0, len)]).is_err());
+
+        rng.fill(&mut data[DISCRIMINATOR_LEN..]);
+        let _ = T::load(&data);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..CASES {
+            let state = GreetingAccount {
+                version: GreetingAccount::VERSION,
+                counter: rng.gen(),
+                expires_at: rng.gen(),
+                has_mirror: rng.gen(),
+                last_greeter: random_pubkey(&mut rng),
+                last_slot: rng.gen(),
+                bump_seed: rng.gen(),
+                processing: rng.gen(),
+            };
+            check_round_trip(&mut rng, state);
+
+            let state = Registry {
+                is_initialized: rng.gen(),
+                count: rng.gen(),
+                recent: random_pubkeys(&mut rng, REGISTRY_RECENT_LEN),
+                bump_seed: rng.gen(),
+            };
+            check_round_trip(&mut rng, state);
+
+            let mut entries = [HistoryEntry::default(); HISTORY_LEN];
+            for entry in entries.iter_mut() {
+                *entry = HistoryEntry {
+                    greeter: random_pubkey(&mut rng),
+                    slot: rng.gen(),
+                    message_hash: Hash::new_from_array(rng.gen()),
+                    chain_hash: Hash::new_from_array(rng.gen()),
+                };
+            }
+            let state = History {
+                is_initialized: rng.gen(),
+                count: rng.gen(),
+                entries,
+                bump_seed: rng.gen(),
+            };
+            check_round_trip(&mut rng, state);
+
+            let state = Config {
+                is_initialized: rng.gen(),
+                admin: random_pubkey(&mut rng),
+                paused: rng.gen(),
+                allowlist_root: if rng.gen() { Some(rng.gen()) } else { None },
+                rate_limit_slots: rng.gen(),
+                greeting_ttl: rng.gen(),
+                fee_policy: FeePolicy::random(&mut rng),
+                timelock_slots: rng.gen(),
+                top_level_only: rng.gen(),
+                redact_logs: rng.gen(),
+                max_message_len: rng.gen(),
+                pending_admin: if rng.gen() {
+                    Some(random_pubkey(&mut rng))
+                } else {
+                    None
+                },
+                bump_seed: rng.gen(),
+            };
+            check_round_trip(&mut rng, state);
+
+            let state = GreeterAccount {
+                is_initialized: rng.gen(),
+                greetings: rng.gen(),
+                last_greeting_slot: rng.gen(),
+                bump_seed: rng.gen(),
+            };
+            check_round_trip(&mut rng, state);
+
+            let state = Leaderboard {
+                is_initialized: rng.gen(),
+                entries: (0..rng.gen_range(0, LEADERBOARD_LEN + 1))
+                    .map(|_| LeaderboardEntry {
+                        greeter: random_pubkey(&mut rng),
+                        greetings: rng.gen(),
+                    })
+                    .collect(),
+                bump_seed: rng.gen(),
+            };
+            check_round_trip(&mut rng, state);
+
+            let state = GuardianSet {
+                is_initialized: rng.gen(),
+                greeting: random_pubkey(&mut rng),
+                owner: random_pubkey(&mut rng),
+                threshold: rng.gen(),
+                recovery_delay: rng.gen(),
+                guardians: random_pubkeys(&mut rng, MAX_GUARDIANS),
+                pending_recovery: if rng.gen() {
+                    Some(PendingRecovery {
+                        new_owner: random_pubkey(&mut rng),
+                        recoverable_slot: rng.gen(),
+                    })
+                } else {
+                    None
+                },
+            };
+            check_round_trip(&mut rng, state);
+
+            let state = Metadata {
+                is_initialized: rng.gen(),
+                name: random_string(&mut rng, MAX_METADATA_FIELD_LEN),
+                version: random_string(&mut rng, MAX_METADATA_FIELD_LEN),
+                commit: random_string(&mut rng, MAX_METADATA_FIELD_LEN),
+                bump_seed: rng.gen(),
+            };
+            check_round_trip(&mut rng, state);
+
+            let state = Channels {
+                is_initialized: rng.gen(),
+                names: (0..rng.gen_range(0, MAX_CHANNELS + 1))
+                    .map(|_| random_string(&mut rng, MAX_CHANNEL_NAME_LEN))
+                    .collect(),
+                bump_seed: rng.gen(),
+            };
+            check_round_trip(&mut rng, state);
+
+            let state = Treasury {
+                is_initialized: rng.gen(),
+                total_collected: rng.gen(),
+                total_withdrawn: rng.gen(),
+                bump_seed: rng.gen(),
+            };
+            check_round_trip(&mut rng, state);
+
+            let state = Denylist {
+                is_initialized: rng.gen(),
+                denied: random_pubkeys(&mut rng, MAX_DENIED),
+                bump_seed: rng.gen(),
+            };
+            check_round_trip(&mut rng, state);
+
+            let state = Multisig {
+                is_initialized: rng.gen(),
+                threshold: rng.gen(),
+                signers: random_pubkeys(&mut rng, MAX_MULTISIG_SIGNERS),
+                proposal_count: rng.gen(),
+                bump_seed: rng.gen(),
+            };
+            check_round_trip(&mut rng, state);
+
+            let state = Proposal {
+                is_initialized: rng.gen(),
+                index: rng.gen(),
+                action: MultisigAction::random(&mut rng),
+                approvals: random_pubkeys(&mut rng, MAX_MULTISIG_SIGNERS),
+                executed: rng.gen(),
+                bump_seed: rng.gen(),
+            };
+            check_round_trip(&mut rng, state);
+
+            let state = Timelock {
+                is_initialized: rng.gen(),
+                next_id: rng.gen(),
+                queued: (0..rng.gen_range(0, MAX_QUEUED_ACTIONS + 1))
+                    .map(|_| QueuedAction {
+                        id: rng.gen(),
+                        update: ConfigUpdate::random(&mut rng),
+                        executable_slot: rng.gen(),
+                    })
+                    .collect(),
+                bump_seed: rng.gen(),
+            };
+            check_round_trip(&mut rng, state);
+        }
+    }
+}
@@ -0,0 +1,80 @@
+use helloworld::{
+    error::HelloWorldError,
+    instruction::{batch_greet, say_hello, withdraw},
+    process_instruction,
+    state::find_treasury_address,
+    GreetingAccount,
+};
+use solana_program_test::*;
+use solana_sdk::{
+    account::Account,
+    instruction::InstructionError,
+    pubkey::Pubkey,
+    rent::Rent,
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+};
+
+#[tokio::test]
+async fn test_duplicate_writable_accounts() {
+    let program_id = helloworld::id();
+    let greeted_pubkey = Pubkey::new_unique();
+    let admin = Keypair::new();
+
+    let mut program_test =
+        ProgramTest::new("helloworld", program_id, processor!(process_instruction));
+    program_test.add_account(
+        greeted_pubkey,
+        Account {
+            lamports: Rent::default().minimum_balance(GreetingAccount::LEN),
+            data: vec![0_u8; GreetingAccount::LEN],
+            owner: program_id,
+            ..Account::default()
+        },
+    );
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+    let greeter = payer.pubkey();
+
+    // The greeter paying its fee to itself
+    let mut self_paying = say_hello(&program_id, &greeted_pubkey, &greeter, vec![]);
+    self_paying.accounts[4].pubkey = greeter;
+    let treasury_address = find_treasury_address(&program_id).0;
+    let aliased = [
+        (self_paying, vec![&payer]),
+        (
+            batch_greet(
+                &program_id,
+                &[greeted_pubkey, greeted_pubkey],
+                &greeter,
+                vec![],
+            ),
+            vec![&payer],
+        ),
+        (
+            withdraw(&program_id, &treasury_address, &admin.pubkey(), 1),
+            vec![&payer, &admin],
+        ),
+    ];
+    for (ix, signers) in aliased {
+        let mut transaction = Transaction::new_with_payer(&[ix], Some(&greeter));
+        transaction.sign(&signers, recent_blockhash);
+        assert_eq!(
+            banks_client
+                .process_transaction(transaction)
+                .await
+                .unwrap_err()
+                .unwrap(),
+            TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(HelloWorldError::DuplicateAccount as u32)
+            )
+        );
+    }
+
+    let account = banks_client
+        .get_account(greeted_pubkey)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(account.data, vec![0_u8; GreetingAccount::LEN]);
+}
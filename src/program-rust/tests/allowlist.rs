@@ -0,0 +1,216 @@
+use helloworld::{
+    allowlist,
+    instruction::{
+        batch_greet, say_hello, say_hello_secp256k1, secp256k1_greeting_message, set_allowlist_root,
+    },
+    process_instruction,
+    state::{find_config_address, AccountState, Config},
+    GreetingAccount,
+};
+use solana_program_test::*;
+use solana_sdk::{
+    account::Account,
+    instruction::Instruction,
+    pubkey::Pubkey,
+    rent::Rent,
+    secp256k1_instruction::{construct_eth_pubkey, new_secp256k1_instruction},
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+};
+
+async fn process(
+    context: &mut ProgramTestContext,
+    instructions: &[Instruction],
+    signers: &[&Keypair],
+) -> Result<(), TransactionError> {
+    let recent_blockhash = context
+        .banks_client
+        .get_new_latest_blockhash(&context.last_blockhash)
+        .await
+        .unwrap();
+    context.last_blockhash = recent_blockhash;
+
+    let mut transaction = Transaction::new_with_payer(instructions, Some(&context.payer.pubkey()));
+    let mut all_signers = vec![&context.payer];
+    all_signers.extend_from_slice(signers);
+    transaction.sign(&all_signers, recent_blockhash);
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .map_err(|err| err.unwrap())
+}
+
+async fn greeting_count(context: &mut ProgramTestContext, pubkey: Pubkey) -> u32 {
+    let account = context
+        .banks_client
+        .get_account(pubkey)
+        .await
+        .expect("get_account")
+        .expect("greeted_account not found");
+    GreetingAccount::load(&account.data).unwrap().counter
+}
+
+fn config_account(admin: Pubkey) -> Account {
+    let config = Config {
+        is_initialized: true,
+        admin,
+        bump_seed: find_config_address(&program_id()).1,
+        ..Config::default()
+    };
+    let mut data = vec![0; Config::LEN];
+    config.store(&mut data).unwrap();
+    Account {
+        lamports: 5,
+        data,
+        owner: program_id(),
+        ..Account::default()
+    }
+}
+
+fn program_id() -> Pubkey {
+    helloworld::id()
+}
+
+#[tokio::test]
+async fn test_allowlist() {
+    let program_id = program_id();
+    let greeted_pubkey = Pubkey::new_unique();
+    let admin = Keypair::new();
+
+    let mut program_test =
+        ProgramTest::new("helloworld", program_id, processor!(process_instruction));
+    program_test.add_account(
+        greeted_pubkey,
+        Account {
+            lamports: Rent::default().minimum_balance(GreetingAccount::LEN),
+            data: vec![0_u8; GreetingAccount::LEN],
+            owner: program_id,
+            ..Account::default()
+        },
+    );
+    // Native CPI cannot allocate the config account, so start from an
+    // initialized one; `InitializeConfig` itself is covered under test-bpf
+    program_test.add_account(
+        find_config_address(&program_id).0,
+        config_account(admin.pubkey()),
+    );
+    let mut context = program_test.start_with_context().await;
+
+    let allowed = Keypair::new();
+    let denied = Keypair::new();
+    let secret_key = libsecp256k1::SecretKey::parse(&[7; 32]).unwrap();
+    let eth_address = construct_eth_pubkey(&libsecp256k1::PublicKey::from_secret_key(&secret_key));
+    let allowed_pubkey = allowed.pubkey();
+    let admin_pubkey = admin.pubkey();
+    let keys: Vec<&[u8]> = vec![allowed_pubkey.as_ref(), &eth_address, admin_pubkey.as_ref()];
+    let root = allowlist::root(&keys);
+
+    // Anyone can greet until an allowlist is set
+    let ix = say_hello(&program_id, &greeted_pubkey, &denied.pubkey(), vec![]);
+    process(&mut context, &[ix], &[&denied]).await.unwrap();
+
+    // Only the admin can set the allowlist
+    let ix = set_allowlist_root(&program_id, &denied.pubkey(), Some(root));
+    assert!(process(&mut context, &[ix], &[&denied]).await.is_err());
+    let ix = set_allowlist_root(&program_id, &admin_pubkey, Some(root));
+    process(&mut context, &[ix], &[&admin]).await.unwrap();
+
+    let config_account = context
+        .banks_client
+        .get_account(find_config_address(&program_id).0)
+        .await
+        .unwrap()
+        .unwrap();
+    let config = Config::load(&config_account.data).unwrap();
+    assert_eq!(config.admin, admin_pubkey);
+    assert_eq!(config.allowlist_root, Some(root));
+
+    // Allowlisted greeters need a valid proof
+    let ix = say_hello(&program_id, &greeted_pubkey, &allowed.pubkey(), vec![]);
+    assert!(process(&mut context, &[ix], &[&allowed]).await.is_err());
+    let ix = say_hello(
+        &program_id,
+        &greeted_pubkey,
+        &allowed.pubkey(),
+        allowlist::proof(&keys, 0),
+    );
+    process(&mut context, &[ix], &[&allowed]).await.unwrap();
+    assert_eq!(greeting_count(&mut context, greeted_pubkey).await, 2);
+
+    // Other greeters are rejected, even when borrowing someone else's proof
+    let ix = say_hello(
+        &program_id,
+        &greeted_pubkey,
+        &denied.pubkey(),
+        allowlist::proof(&keys, 0),
+    );
+    assert!(process(&mut context, &[ix], &[&denied]).await.is_err());
+    let ix = batch_greet(
+        &program_id,
+        &[greeted_pubkey],
+        &denied.pubkey(),
+        allowlist::proof(&keys, 0),
+    );
+    assert!(process(&mut context, &[ix], &[&denied]).await.is_err());
+
+    let ix = batch_greet(
+        &program_id,
+        &[greeted_pubkey],
+        &admin_pubkey,
+        allowlist::proof(&keys, 2),
+    );
+    process(&mut context, &[ix], &[&admin]).await.unwrap();
+    assert_eq!(greeting_count(&mut context, greeted_pubkey).await, 3);
+
+    // Ethereum keys are allowlisted by address
+    let instructions = [
+        new_secp256k1_instruction(&secret_key, &secp256k1_greeting_message(&greeted_pubkey, 3)),
+        say_hello_secp256k1(
+            &program_id,
+            &greeted_pubkey,
+            eth_address,
+            allowlist::proof(&keys, 1),
+            &context.payer.pubkey(),
+        ),
+    ];
+    process(&mut context, &instructions, &[]).await.unwrap();
+    assert_eq!(greeting_count(&mut context, greeted_pubkey).await, 4);
+
+    // Lifting the allowlist lets everyone greet again
+    let ix = set_allowlist_root(&program_id, &admin_pubkey, None);
+    process(&mut context, &[ix], &[&admin]).await.unwrap();
+    let ix = say_hello(&program_id, &greeted_pubkey, &denied.pubkey(), vec![]);
+    process(&mut context, &[ix], &[&denied]).await.unwrap();
+    assert_eq!(greeting_count(&mut context, greeted_pubkey).await, 5);
+}
+
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn test_initialize_config() {
+    use helloworld::instruction::initialize_config;
+
+    let program_id = program_id();
+    let program_test = ProgramTest::new("helloworld", program_id, None);
+    let mut context = program_test.start_with_context().await;
+    let admin = context.payer.pubkey();
+
+    let ix = initialize_config(&program_id, &admin);
+    process(&mut context, &[ix], &[]).await.unwrap();
+
+    let config_account = context
+        .banks_client
+        .get_account(find_config_address(&program_id).0)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(config_account.owner, program_id);
+    let config = Config::load(&config_account.data).unwrap();
+    assert!(config.is_initialized);
+    assert_eq!(config.admin, admin);
+    assert_eq!(config.allowlist_root, None);
+
+    // The config can only be initialized once
+    let ix = initialize_config(&program_id, &admin);
+    assert!(process(&mut context, &[ix], &[]).await.is_err());
+}
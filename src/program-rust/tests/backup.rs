@@ -0,0 +1,188 @@
+use helloworld::{
+    backup::GreetingDelta,
+    instruction::{apply_delta, export_delta, say_hello},
+    process_instruction,
+    state::{find_config_address, AccountState, Config},
+    GreetingAccount,
+};
+use solana_program_test::*;
+use solana_sdk::{
+    account::Account,
+    instruction::Instruction,
+    pubkey::Pubkey,
+    rent::Rent,
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+};
+
+async fn process(
+    context: &mut ProgramTestContext,
+    instruction: Instruction,
+    signers: &[&Keypair],
+) -> Result<(), TransactionError> {
+    let recent_blockhash = context
+        .banks_client
+        .get_new_latest_blockhash(&context.last_blockhash)
+        .await
+        .unwrap();
+    context.last_blockhash = recent_blockhash;
+
+    let mut transaction =
+        Transaction::new_with_payer(&[instruction], Some(&context.payer.pubkey()));
+    let mut all_signers = vec![&context.payer];
+    all_signers.extend_from_slice(signers);
+    transaction.sign(&all_signers, recent_blockhash);
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .map_err(|err| err.unwrap())
+}
+
+async fn load<T: AccountState>(context: &mut ProgramTestContext, pubkey: Pubkey) -> T {
+    let account = context
+        .banks_client
+        .get_account(pubkey)
+        .await
+        .unwrap()
+        .unwrap();
+    T::load(&account.data).unwrap()
+}
+
+/// Add a deployment of the program with an initialized config, and return
+/// its program id
+fn add_deployment(program_test: &mut ProgramTest, admin: &Pubkey, greeting_ttl: i64) -> Pubkey {
+    let program_id = helloworld::id();
+    program_test.add_program("helloworld", program_id, processor!(process_instruction));
+    // Native CPI cannot allocate the config account, so start from an
+    // initialized one
+    let mut config_data = vec![0; Config::LEN];
+    Config {
+        is_initialized: true,
+        admin: *admin,
+        greeting_ttl,
+        bump_seed: find_config_address(&program_id).1,
+        ..Config::default()
+    }
+    .store(&mut config_data)
+    .unwrap();
+    program_test.add_account(
+        find_config_address(&program_id).0,
+        Account {
+            lamports: 5,
+            data: config_data,
+            owner: program_id,
+            ..Account::default()
+        },
+    );
+    program_id
+}
+
+fn add_program_account(program_test: &mut ProgramTest, program_id: &Pubkey, len: usize) -> Pubkey {
+    let pubkey = Pubkey::new_unique();
+    program_test.add_account(
+        pubkey,
+        Account {
+            lamports: Rent::default().minimum_balance(len),
+            data: vec![0; len],
+            owner: *program_id,
+            ..Account::default()
+        },
+    );
+    pubkey
+}
+
+/// Fields of a greeting account restored from backups
+fn backed_up(greeting_account: GreetingAccount) -> GreetingAccount {
+    GreetingAccount {
+        has_mirror: false,
+        ..greeting_account
+    }
+}
+
+#[tokio::test]
+async fn test_backup_round_trip() {
+    let admin = Keypair::new();
+    let greeter = Keypair::new();
+
+    let mut program_test = ProgramTest::default();
+    let source_id = add_deployment(&mut program_test, &admin.pubkey(), 60);
+    let target_id = add_deployment(&mut program_test, &admin.pubkey(), 0);
+    let source = add_program_account(&mut program_test, &source_id, GreetingAccount::LEN);
+    let backup = add_program_account(&mut program_test, &source_id, GreetingDelta::LEN);
+    let target = add_program_account(&mut program_test, &target_id, GreetingAccount::LEN);
+    let mut context = program_test.start_with_context().await;
+    let payer = context.payer.pubkey();
+
+    let greet = |greeter: &Pubkey| say_hello(&source_id, &source, greeter, vec![]);
+    for _ in 0..2 {
+        process(&mut context, greet(&greeter.pubkey()), &[&greeter])
+            .await
+            .unwrap();
+    }
+
+    // Only the admin can export or restore
+    let ix = export_delta(&source_id, &source, &backup, &greeter.pubkey(), 0);
+    assert!(process(&mut context, ix, &[&greeter]).await.is_err());
+
+    // Full restore
+    let ix = export_delta(&source_id, &source, &backup, &admin.pubkey(), 0);
+    process(&mut context, ix, &[&admin]).await.unwrap();
+    let delta: GreetingDelta = load(&mut context, backup).await;
+    assert_eq!((delta.since_version, delta.version), (0, 2));
+    let ix = apply_delta(&target_id, &target, &greeter.pubkey(), delta.clone());
+    assert!(process(&mut context, ix, &[&greeter]).await.is_err());
+    let ix = apply_delta(&target_id, &target, &admin.pubkey(), delta.clone());
+    process(&mut context, ix, &[&admin]).await.unwrap();
+    assert_eq!(
+        load::<GreetingAccount>(&mut context, target).await,
+        backed_up(load(&mut context, source).await)
+    );
+
+    // Incremental restore, after more greetings
+    process(&mut context, greet(&payer), &[]).await.unwrap();
+    let ix = export_delta(&source_id, &source, &backup, &admin.pubkey(), 2);
+    process(&mut context, ix, &[&admin]).await.unwrap();
+    let incremental: GreetingDelta = load(&mut context, backup).await;
+    assert_eq!((incremental.since_version, incremental.version), (2, 3));
+    let ix = apply_delta(&target_id, &target, &admin.pubkey(), incremental.clone());
+    process(&mut context, ix, &[&admin]).await.unwrap();
+    assert_eq!(
+        load::<GreetingAccount>(&mut context, target).await,
+        backed_up(load(&mut context, source).await)
+    );
+
+    // Deltas do not apply twice or out of order
+    let ix = apply_delta(&target_id, &target, &admin.pubkey(), incremental);
+    assert!(process(&mut context, ix, &[&admin]).await.is_err());
+    let ix = apply_delta(&target_id, &target, &admin.pubkey(), delta);
+    assert!(process(&mut context, ix, &[&admin]).await.is_err());
+
+    // Nothing to export without new greetings, and no version from the future
+    let ix = export_delta(&source_id, &source, &backup, &admin.pubkey(), 3);
+    process(&mut context, ix, &[&admin]).await.unwrap();
+    let empty: GreetingDelta = load(&mut context, backup).await;
+    assert_eq!(
+        empty,
+        GreetingDelta {
+            since_version: 3,
+            version: 3,
+            ..GreetingDelta::default()
+        }
+    );
+    let ix = export_delta(&source_id, &source, &backup, &admin.pubkey(), 4);
+    assert!(process(&mut context, ix, &[&admin]).await.is_err());
+
+    // Client-side deltas between two snapshots carry only the changed fields
+    let snapshot: GreetingAccount = load(&mut context, source).await;
+    process(&mut context, greet(&payer), &[]).await.unwrap();
+    let current: GreetingAccount = load(&mut context, source).await;
+    let encoded = GreetingDelta::encode(&snapshot, &current).unwrap();
+    assert_eq!(encoded.last_greeter, None);
+    let ix = apply_delta(&target_id, &target, &admin.pubkey(), encoded);
+    process(&mut context, ix, &[&admin]).await.unwrap();
+    assert_eq!(
+        load::<GreetingAccount>(&mut context, target).await,
+        backed_up(current)
+    );
+}
@@ -0,0 +1,177 @@
+use borsh::BorshSerialize;
+use helloworld::{
+    error::HelloWorldError,
+    instruction::{create_channel, HelloInstruction},
+    process_instruction,
+    state::{find_channels_address, AccountState, Channels},
+};
+use solana_program_test::*;
+use solana_sdk::{
+    account::Account,
+    instruction::{AccountMeta, Instruction, InstructionError},
+    pubkey::Pubkey,
+    rent::Rent,
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+};
+
+async fn process(
+    context: &mut ProgramTestContext,
+    instruction: Instruction,
+    signers: &[&Keypair],
+) -> Result<(), TransactionError> {
+    let recent_blockhash = context
+        .banks_client
+        .get_new_latest_blockhash(&context.last_blockhash)
+        .await
+        .unwrap();
+    context.last_blockhash = recent_blockhash;
+
+    let mut transaction =
+        Transaction::new_with_payer(&[instruction], Some(&context.payer.pubkey()));
+    let mut all_signers = vec![&context.payer];
+    all_signers.extend_from_slice(signers);
+    transaction.sign(&all_signers, recent_blockhash);
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .map_err(|err| err.unwrap())
+}
+
+#[tokio::test]
+async fn test_channel_names() {
+    let program_id = helloworld::id();
+    let user = Keypair::new();
+
+    let mut program_test =
+        ProgramTest::new("helloworld", program_id, processor!(process_instruction));
+    let (channels_address, bump_seed) = find_channels_address(&program_id, &user.pubkey());
+    let mut channels_data = vec![0; Channels::LEN];
+    Channels {
+        is_initialized: true,
+        names: vec!["general".to_string()],
+        bump_seed,
+    }
+    .store(&mut channels_data)
+    .unwrap();
+    program_test.add_account(
+        channels_address,
+        Account {
+            lamports: Rent::default().minimum_balance(Channels::LEN),
+            data: channels_data,
+            owner: program_id,
+            ..Account::default()
+        },
+    );
+    let mut context = program_test.start_with_context().await;
+
+    // Names too long to be a seed have no channel address, so build the
+    // instruction by hand
+    let too_long = "a".repeat(33);
+    let mut ix = create_channel(&program_id, &user.pubkey(), "placeholder");
+    ix.data = HelloInstruction::CreateChannel { name: too_long }
+        .try_to_vec()
+        .unwrap();
+    ix.accounts[0] = AccountMeta::new(Pubkey::new_unique(), false);
+    for (ix, error) in [
+        (
+            create_channel(&program_id, &user.pubkey(), ""),
+            InstructionError::Custom(HelloWorldError::InvalidChannelName as u32),
+        ),
+        (
+            ix,
+            InstructionError::Custom(HelloWorldError::InvalidChannelName as u32),
+        ),
+        (
+            create_channel(&program_id, &user.pubkey(), "general"),
+            InstructionError::AccountAlreadyInitialized,
+        ),
+    ] {
+        assert_eq!(
+            process(&mut context, ix, &[&user]).await,
+            Err(TransactionError::InstructionError(0, error))
+        );
+    }
+
+    // The channel list must belong to the user
+    let other = Keypair::new();
+    let mut ix = create_channel(&program_id, &other.pubkey(), "news");
+    ix.accounts[2].pubkey = channels_address;
+    assert_eq!(
+        process(&mut context, ix, &[&other]).await,
+        Err(TransactionError::InstructionError(
+            0,
+            InstructionError::InvalidArgument
+        ))
+    );
+}
+
+// Native CPI cannot create accounts, so only run against the BPF build
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn test_create_channel() {
+    use helloworld::{
+        instruction::{initialize_registry, say_hello},
+        state::{find_channel_address, find_greeting_address, find_registry_address, Registry},
+        GreetingAccount,
+    };
+
+    let program_id = helloworld::id();
+    let program_test = ProgramTest::new("helloworld", program_id, processor!(process_instruction));
+    let mut context = program_test.start_with_context().await;
+    let user = context.payer.pubkey();
+
+    process(&mut context, initialize_registry(&program_id, &user), &[])
+        .await
+        .unwrap();
+    for name in ["general", "news"] {
+        process(&mut context, create_channel(&program_id, &user, name), &[])
+            .await
+            .unwrap();
+    }
+    let ix = say_hello(
+        &program_id,
+        &find_channel_address(&program_id, &user, "news").0,
+        &user,
+        vec![],
+    );
+    process(&mut context, ix, &[]).await.unwrap();
+
+    // Channels are listed and counted apart from the greeting address
+    let account = context
+        .banks_client
+        .get_account(find_channels_address(&program_id, &user).0)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        Channels::load(&account.data).unwrap().names,
+        ["general", "news"]
+    );
+    for (name, counter) in [("general", 0), ("news", 1)] {
+        let (address, bump_seed) = find_channel_address(&program_id, &user, name);
+        let account = context
+            .banks_client
+            .get_account(address)
+            .await
+            .unwrap()
+            .unwrap();
+        let greeting_account = GreetingAccount::load(&account.data).unwrap();
+        assert_eq!(greeting_account.counter, counter);
+        assert_eq!(greeting_account.bump_seed, bump_seed);
+    }
+    assert!(context
+        .banks_client
+        .get_account(find_greeting_address(&program_id, &user).0)
+        .await
+        .unwrap()
+        .is_none());
+    let account = context
+        .banks_client
+        .get_account(find_registry_address(&program_id).0)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(Registry::load(&account.data).unwrap().count, 2);
+}
@@ -0,0 +1,159 @@
+// Checks the compute units consumed by each instruction against the budgets in
+// `tests/compute_units.txt`, so that regressions show up in review. Native
+// processors are not metered, so this only runs against the BPF build.
+//
+// Record the budgets of new instructions, or accept a change in consumption,
+// with `HELLOWORLD_RECORD_COMPUTE_UNITS=1 cargo test-bpf --test compute_units`
+#![cfg(feature = "test-bpf")]
+
+use helloworld::{
+    instruction::{batch_greet, say_hello, say_hello_with_message},
+    process_instruction, GreetingAccount,
+};
+use solana_program_test::*;
+use solana_sdk::{
+    account::Account,
+    compute_budget::ComputeBudgetInstruction,
+    instruction::{Instruction, InstructionError},
+    pubkey::Pubkey,
+    rent::Rent,
+    signature::Signer,
+    transaction::{Transaction, TransactionError},
+};
+use std::{collections::BTreeMap, env, fs};
+
+const BUDGETS_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/compute_units.txt");
+const BUDGETS_HEADER: &str = "# Compute units each instruction may consume, checked by
+# `cargo test-bpf --test compute_units`
+";
+const MAX_COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+
+fn load_budgets() -> BTreeMap<String, u32> {
+    fs::read_to_string(BUDGETS_PATH)
+        .unwrap()
+        .lines()
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            let (name, units) = line.split_once(' ').unwrap();
+            (name.to_string(), units.parse().unwrap())
+        })
+        .collect()
+}
+
+fn store_budgets(budgets: &BTreeMap<String, u32>) {
+    let mut contents = BUDGETS_HEADER.to_string();
+    for (name, units) in budgets {
+        contents.push_str(&format!("{} {}\n", name, units));
+    }
+    fs::write(BUDGETS_PATH, contents).unwrap();
+}
+
+/// Whether `instruction` succeeds within `limit` compute units
+async fn fits(context: &mut ProgramTestContext, instruction: &Instruction, limit: u32) -> bool {
+    let recent_blockhash = context
+        .banks_client
+        .get_new_latest_blockhash(&context.last_blockhash)
+        .await
+        .unwrap();
+    context.last_blockhash = recent_blockhash;
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            ComputeBudgetInstruction::set_compute_unit_limit(limit),
+            instruction.clone(),
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        recent_blockhash,
+    );
+    match context.banks_client.process_transaction(transaction).await {
+        Ok(()) => true,
+        Err(BanksClientError::TransactionError(TransactionError::InstructionError(
+            _,
+            InstructionError::ComputationalBudgetExceeded,
+        ))) => false,
+        Err(err) => panic!("failed within {} compute units: {}", limit, err),
+    }
+}
+
+/// Fewest compute units `instruction` succeeds within
+async fn consumed_units(context: &mut ProgramTestContext, instruction: &Instruction) -> u32 {
+    let (mut low, mut high) = (0, MAX_COMPUTE_UNIT_LIMIT);
+    assert!(fits(context, instruction, high).await);
+    while high - low > 1 {
+        let middle = low + (high - low) / 2;
+        if fits(context, instruction, middle).await {
+            high = middle;
+        } else {
+            low = middle;
+        }
+    }
+    high
+}
+
+#[tokio::test]
+async fn test_compute_units() {
+    let program_id = helloworld::id();
+    let greeted: Vec<Pubkey> = (0..4).map(|_| Pubkey::new_unique()).collect();
+    let mut program_test =
+        ProgramTest::new("helloworld", program_id, processor!(process_instruction));
+    for pubkey in &greeted {
+        program_test.add_account(
+            *pubkey,
+            Account {
+                lamports: Rent::default().minimum_balance(GreetingAccount::LEN),
+                data: vec![0; GreetingAccount::LEN],
+                owner: program_id,
+                ..Account::default()
+            },
+        );
+    }
+    let mut context = program_test.start_with_context().await;
+    let payer = context.payer.pubkey();
+
+    let instructions = vec![
+        (
+            "say_hello",
+            say_hello(&program_id, &greeted[0], &payer, vec![]),
+        ),
+        (
+            "say_hello_with_message",
+            say_hello_with_message(
+                &program_id,
+                &greeted[0],
+                &payer,
+                vec![],
+                "Hello, world!".to_string(),
+            ),
+        ),
+        (
+            "batch_greet_4",
+            batch_greet(&program_id, &greeted, &payer, vec![]),
+        ),
+    ];
+
+    let mut budgets = load_budgets();
+    if env::var_os("HELLOWORLD_RECORD_COMPUTE_UNITS").is_some() {
+        for (name, instruction) in &instructions {
+            let units = consumed_units(&mut context, instruction).await;
+            budgets.insert(name.to_string(), units);
+        }
+        store_budgets(&budgets);
+        return;
+    }
+
+    for (name, instruction) in &instructions {
+        let budget = *budgets.get(*name).unwrap_or_else(|| {
+            panic!(
+                "{} has no budget in {}, record it with HELLOWORLD_RECORD_COMPUTE_UNITS=1",
+                name, BUDGETS_PATH
+            )
+        });
+        assert!(
+            fits(&mut context, instruction, budget).await,
+            "{} consumes more than its budget of {} compute units",
+            name,
+            budget
+        );
+    }
+}
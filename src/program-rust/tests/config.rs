@@ -0,0 +1,389 @@
+use helloworld::{
+    error::HelloWorldError,
+    fees::FeePolicy,
+    instruction::{
+        accept_admin, batch_greet, cancel_recovery, create_channel, create_mirror,
+        initiate_recovery, migrate, reap, recover_ownership, register_greeter, register_guardians,
+        say_hello, say_hello_secp256k1, secp256k1_greeting_message, set_rate_limit, update_config,
+        ConfigUpdate,
+    },
+    process_instruction,
+    state::{find_config_address, find_treasury_address, AccountState, Config},
+    GreetingAccount,
+};
+use solana_program_test::*;
+use solana_sdk::{
+    account::Account,
+    instruction::{Instruction, InstructionError},
+    pubkey::Pubkey,
+    rent::Rent,
+    secp256k1_instruction::{construct_eth_pubkey, new_secp256k1_instruction},
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+};
+
+async fn process(
+    context: &mut ProgramTestContext,
+    instructions: &[Instruction],
+    signers: &[&Keypair],
+) -> Result<(), TransactionError> {
+    let recent_blockhash = context
+        .banks_client
+        .get_new_latest_blockhash(&context.last_blockhash)
+        .await
+        .unwrap();
+    context.last_blockhash = recent_blockhash;
+
+    let mut transaction = Transaction::new_with_payer(instructions, Some(&context.payer.pubkey()));
+    let mut all_signers = vec![&context.payer];
+    all_signers.extend_from_slice(signers);
+    transaction.sign(&all_signers, recent_blockhash);
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .map_err(|err| err.unwrap())
+}
+
+async fn config(context: &mut ProgramTestContext, program_id: &Pubkey) -> Config {
+    let account = context
+        .banks_client
+        .get_account(find_config_address(program_id).0)
+        .await
+        .unwrap()
+        .unwrap();
+    Config::load(&account.data).unwrap()
+}
+
+#[tokio::test]
+async fn test_update_config() {
+    let program_id = helloworld::id();
+    let greeted_pubkey = Pubkey::new_unique();
+    let admin = Keypair::new();
+    let new_admin = Keypair::new();
+
+    let mut program_test =
+        ProgramTest::new("helloworld", program_id, processor!(process_instruction));
+    program_test.add_account(
+        greeted_pubkey,
+        Account {
+            lamports: Rent::default().minimum_balance(GreetingAccount::LEN),
+            data: vec![0_u8; GreetingAccount::LEN],
+            owner: program_id,
+            ..Account::default()
+        },
+    );
+    program_test.add_account(
+        find_treasury_address(&program_id).0,
+        Account {
+            lamports: 1_000_000_000,
+            ..Account::default()
+        },
+    );
+    // Native CPI cannot allocate the config account, so start from an
+    // initialized one
+    let mut config_data = vec![0; Config::LEN];
+    Config {
+        is_initialized: true,
+        admin: admin.pubkey(),
+        bump_seed: find_config_address(&program_id).1,
+        ..Config::default()
+    }
+    .store(&mut config_data)
+    .unwrap();
+    program_test.add_account(
+        find_config_address(&program_id).0,
+        Account {
+            lamports: 5,
+            data: config_data,
+            owner: program_id,
+            ..Account::default()
+        },
+    );
+    let mut context = program_test.start_with_context().await;
+    let greeter = context.payer.pubkey();
+
+    let pause = |admin: &Pubkey, paused| {
+        update_config(
+            &program_id,
+            admin,
+            ConfigUpdate {
+                paused: Some(paused),
+                ..ConfigUpdate::default()
+            },
+        )
+    };
+
+    // Only the admin can update the config
+    assert_eq!(
+        process(&mut context, &[pause(&greeter, true)], &[]).await,
+        Err(TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(HelloWorldError::Unauthorized as u32)
+        ))
+    );
+    process(&mut context, &[pause(&admin.pubkey(), true)], &[&admin])
+        .await
+        .unwrap();
+
+    // No greeting goes through while paused
+    let secret_key = libsecp256k1::SecretKey::parse(&[7; 32]).unwrap();
+    let eth_address = construct_eth_pubkey(&libsecp256k1::PublicKey::from_secret_key(&secret_key));
+    let signed_greeting = [
+        new_secp256k1_instruction(&secret_key, &secp256k1_greeting_message(&greeted_pubkey, 0)),
+        say_hello_secp256k1(&program_id, &greeted_pubkey, eth_address, vec![], &greeter),
+    ];
+    // The signed greeting comes first, it is only valid at count 0
+    let greetings = [
+        signed_greeting.to_vec(),
+        vec![say_hello(&program_id, &greeted_pubkey, &greeter, vec![])],
+        vec![batch_greet(
+            &program_id,
+            &[greeted_pubkey],
+            &greeter,
+            vec![],
+        )],
+    ];
+    for greeting in &greetings {
+        assert_eq!(
+            process(&mut context, greeting, &[]).await,
+            Err(TransactionError::InstructionError(
+                greeting.len() as u8 - 1,
+                InstructionError::Custom(HelloWorldError::ProgramPaused as u32)
+            ))
+        );
+    }
+
+    // Fields left out keep their value
+    let ix = update_config(
+        &program_id,
+        &admin.pubkey(),
+        ConfigUpdate {
+            admin: Some(new_admin.pubkey()),
+            fee_policy: Some(FeePolicy::Flat { lamports: 1000 }),
+            redact_logs: Some(true),
+            ..ConfigUpdate::default()
+        },
+    );
+    process(&mut context, &[ix], &[&admin]).await.unwrap();
+    let updated = config(&mut context, &program_id).await;
+    assert_eq!(updated.admin, admin.pubkey());
+    assert_eq!(updated.pending_admin, Some(new_admin.pubkey()));
+    assert!(updated.paused);
+    assert_eq!(updated.fee_policy, FeePolicy::Flat { lamports: 1000 });
+    assert!(updated.redact_logs);
+
+    // The new admin takes over once they accept, nobody else can
+    let unauthorized = Err(TransactionError::InstructionError(
+        0,
+        InstructionError::Custom(HelloWorldError::Unauthorized as u32),
+    ));
+    let intruder = Keypair::new();
+    assert_eq!(
+        process(
+            &mut context,
+            &[accept_admin(&program_id, &intruder.pubkey())],
+            &[&intruder]
+        )
+        .await,
+        unauthorized
+    );
+    // Proposing the current admin withdraws the proposal
+    let propose = |new_admin: &Pubkey| {
+        update_config(
+            &program_id,
+            &admin.pubkey(),
+            ConfigUpdate {
+                admin: Some(*new_admin),
+                ..ConfigUpdate::default()
+            },
+        )
+    };
+    process(&mut context, &[propose(&admin.pubkey())], &[&admin])
+        .await
+        .unwrap();
+    assert_eq!(config(&mut context, &program_id).await.pending_admin, None);
+    assert_eq!(
+        process(
+            &mut context,
+            &[accept_admin(&program_id, &new_admin.pubkey())],
+            &[&new_admin]
+        )
+        .await,
+        unauthorized
+    );
+    process(&mut context, &[propose(&new_admin.pubkey())], &[&admin])
+        .await
+        .unwrap();
+    process(
+        &mut context,
+        &[accept_admin(&program_id, &new_admin.pubkey())],
+        &[&new_admin],
+    )
+    .await
+    .unwrap();
+    let updated = config(&mut context, &program_id).await;
+    assert_eq!(updated.admin, new_admin.pubkey());
+    assert_eq!(updated.pending_admin, None);
+
+    // The previous admin lost control
+    assert!(
+        process(&mut context, &[pause(&admin.pubkey(), false)], &[&admin])
+            .await
+            .is_err()
+    );
+    process(
+        &mut context,
+        &[pause(&new_admin.pubkey(), false)],
+        &[&new_admin],
+    )
+    .await
+    .unwrap();
+    for greeting in &greetings {
+        process(&mut context, greeting, &[]).await.unwrap();
+    }
+}
+
+#[tokio::test]
+async fn test_config_stored_bump_seed() {
+    let program_id = helloworld::id();
+    let greeted_pubkey = Pubkey::new_unique();
+    let (config_address, bump_seed) = find_config_address(&program_id);
+
+    let mut program_test =
+        ProgramTest::new("helloworld", program_id, processor!(process_instruction));
+    program_test.add_account(
+        greeted_pubkey,
+        Account {
+            lamports: Rent::default().minimum_balance(GreetingAccount::LEN),
+            data: vec![0_u8; GreetingAccount::LEN],
+            owner: program_id,
+            ..Account::default()
+        },
+    );
+    // The config address is only checked against the bump seed stored in it
+    let mut config_data = vec![0; Config::LEN];
+    Config {
+        is_initialized: true,
+        bump_seed: bump_seed.wrapping_sub(1),
+        ..Config::default()
+    }
+    .store(&mut config_data)
+    .unwrap();
+    program_test.add_account(
+        config_address,
+        Account {
+            lamports: Rent::default().minimum_balance(Config::LEN),
+            data: config_data,
+            owner: program_id,
+            ..Account::default()
+        },
+    );
+    let mut context = program_test.start_with_context().await;
+    let greeter = context.payer.pubkey();
+
+    let ix = say_hello(&program_id, &greeted_pubkey, &greeter, vec![]);
+    assert_eq!(
+        process(&mut context, &[ix], &[]).await,
+        Err(TransactionError::InstructionError(
+            0,
+            InstructionError::InvalidArgument
+        ))
+    );
+}
+
+#[tokio::test]
+async fn test_pause_user_instructions() {
+    let program_id = helloworld::id();
+    let greeted_pubkey = Pubkey::new_unique();
+    let guardian_set_pubkey = Pubkey::new_unique();
+    let admin = Keypair::new();
+
+    let mut program_test =
+        ProgramTest::new("helloworld", program_id, processor!(process_instruction));
+    program_test.add_account(
+        greeted_pubkey,
+        Account {
+            lamports: Rent::default().minimum_balance(GreetingAccount::LEN),
+            data: vec![0_u8; GreetingAccount::LEN],
+            owner: program_id,
+            ..Account::default()
+        },
+    );
+    let mut config_data = vec![0; Config::LEN];
+    Config {
+        is_initialized: true,
+        admin: admin.pubkey(),
+        paused: true,
+        bump_seed: find_config_address(&program_id).1,
+        ..Config::default()
+    }
+    .store(&mut config_data)
+    .unwrap();
+    program_test.add_account(
+        find_config_address(&program_id).0,
+        Account {
+            lamports: Rent::default().minimum_balance(Config::LEN),
+            data: config_data,
+            owner: program_id,
+            ..Account::default()
+        },
+    );
+    let mut context = program_test.start_with_context().await;
+    let user = context.payer.pubkey();
+
+    let user_instructions = [
+        register_guardians(
+            &program_id,
+            &guardian_set_pubkey,
+            &greeted_pubkey,
+            &user,
+            vec![user],
+            1,
+            0,
+        ),
+        initiate_recovery(&program_id, &guardian_set_pubkey, &[], &user),
+        recover_ownership(&program_id, &guardian_set_pubkey),
+        cancel_recovery(&program_id, &guardian_set_pubkey, &user),
+        register_greeter(&program_id, &user),
+        reap(&program_id, &greeted_pubkey),
+        create_mirror(&program_id, &greeted_pubkey, &user),
+        migrate(&program_id, &greeted_pubkey, &user),
+        create_channel(&program_id, &user, "news"),
+    ];
+    for ix in user_instructions {
+        assert_eq!(
+            process(&mut context, &[ix], &[]).await,
+            Err(TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(HelloWorldError::ProgramPaused as u32)
+            ))
+        );
+    }
+
+    // The admin keeps control while paused
+    let ix = set_rate_limit(&program_id, &admin.pubkey(), 10);
+    process(&mut context, &[ix], &[&admin]).await.unwrap();
+    let ix = update_config(
+        &program_id,
+        &admin.pubkey(),
+        ConfigUpdate {
+            paused: Some(false),
+            ..ConfigUpdate::default()
+        },
+    );
+    process(&mut context, &[ix], &[&admin]).await.unwrap();
+    assert_eq!(config(&mut context, &program_id).await.rate_limit_slots, 10);
+
+    // Resumed instructions fail on their own checks, if at all
+    let ix = migrate(&program_id, &greeted_pubkey, &user);
+    process(&mut context, &[ix], &[]).await.unwrap();
+    let ix = recover_ownership(&program_id, &guardian_set_pubkey);
+    assert_ne!(
+        process(&mut context, &[ix], &[]).await,
+        Err(TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(HelloWorldError::ProgramPaused as u32)
+        ))
+    );
+}
@@ -0,0 +1,203 @@
+use helloworld::{
+    error::HelloWorldError,
+    instruction::{add_to_denylist, remove_from_denylist, say_hello},
+    process_instruction,
+    state::{find_config_address, find_denylist_address, AccountState, Config, Denylist},
+    GreetingAccount,
+};
+use solana_program_test::*;
+use solana_sdk::{
+    account::Account,
+    instruction::{Instruction, InstructionError},
+    pubkey::Pubkey,
+    rent::Rent,
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+};
+
+async fn process(
+    context: &mut ProgramTestContext,
+    instruction: Instruction,
+    signers: &[&Keypair],
+) -> Result<(), TransactionError> {
+    let recent_blockhash = context
+        .banks_client
+        .get_new_latest_blockhash(&context.last_blockhash)
+        .await
+        .unwrap();
+    context.last_blockhash = recent_blockhash;
+
+    let mut transaction =
+        Transaction::new_with_payer(&[instruction], Some(&context.payer.pubkey()));
+    let mut all_signers = vec![&context.payer];
+    all_signers.extend_from_slice(signers);
+    transaction.sign(&all_signers, recent_blockhash);
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .map_err(|err| err.unwrap())
+}
+
+async fn denylist(context: &mut ProgramTestContext, program_id: &Pubkey) -> Denylist {
+    let account = context
+        .banks_client
+        .get_account(find_denylist_address(program_id).0)
+        .await
+        .unwrap()
+        .unwrap();
+    Denylist::load(&account.data).unwrap()
+}
+
+fn add_state_account(
+    program_test: &mut ProgramTest,
+    program_id: &Pubkey,
+    pubkey: Pubkey,
+    state: &impl AccountState,
+    len: usize,
+) {
+    let mut data = vec![0; len];
+    state.store(&mut data).unwrap();
+    program_test.add_account(
+        pubkey,
+        Account {
+            lamports: Rent::default().minimum_balance(len),
+            data,
+            owner: *program_id,
+            ..Account::default()
+        },
+    );
+}
+
+fn add_config(program_test: &mut ProgramTest, program_id: &Pubkey, admin: &Pubkey) {
+    let (config_address, bump_seed) = find_config_address(program_id);
+    let config = Config {
+        is_initialized: true,
+        admin: *admin,
+        bump_seed,
+        ..Config::default()
+    };
+    add_state_account(
+        program_test,
+        program_id,
+        config_address,
+        &config,
+        Config::LEN,
+    );
+}
+
+#[tokio::test]
+async fn test_denylist() {
+    let program_id = helloworld::id();
+    let greeted_pubkey = Pubkey::new_unique();
+    let admin = Keypair::new();
+    let greeter = Keypair::new();
+
+    let mut program_test =
+        ProgramTest::new("helloworld", program_id, processor!(process_instruction));
+    program_test.add_account(
+        greeted_pubkey,
+        Account {
+            lamports: Rent::default().minimum_balance(GreetingAccount::LEN),
+            data: vec![0_u8; GreetingAccount::LEN],
+            owner: program_id,
+            ..Account::default()
+        },
+    );
+    add_config(&mut program_test, &program_id, &admin.pubkey());
+    // Native CPI cannot allocate the denylist, so start from an initialized
+    // one
+    let (denylist_address, bump_seed) = find_denylist_address(&program_id);
+    let initialized = Denylist {
+        is_initialized: true,
+        bump_seed,
+        ..Denylist::default()
+    };
+    add_state_account(
+        &mut program_test,
+        &program_id,
+        denylist_address,
+        &initialized,
+        Denylist::LEN,
+    );
+    let mut context = program_test.start_with_context().await;
+
+    // Only the admin can deny greeters
+    let ix = add_to_denylist(&program_id, &greeter.pubkey(), &greeter.pubkey());
+    assert_eq!(
+        process(&mut context, ix, &[&greeter]).await,
+        Err(TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(HelloWorldError::Unauthorized as u32)
+        ))
+    );
+
+    let ix = add_to_denylist(&program_id, &admin.pubkey(), &greeter.pubkey());
+    process(&mut context, ix.clone(), &[&admin]).await.unwrap();
+    assert!(denylist(&mut context, &program_id)
+        .await
+        .is_denied(&greeter.pubkey()));
+    assert_eq!(
+        process(&mut context, ix, &[&admin]).await,
+        Err(TransactionError::InstructionError(
+            0,
+            InstructionError::InvalidArgument
+        ))
+    );
+
+    let greet = say_hello(&program_id, &greeted_pubkey, &greeter.pubkey(), vec![]);
+    assert_eq!(
+        process(&mut context, greet.clone(), &[&greeter]).await,
+        Err(TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(HelloWorldError::GreeterDenied as u32)
+        ))
+    );
+    // Other greeters are unaffected
+    let ix = say_hello(&program_id, &greeted_pubkey, &admin.pubkey(), vec![]);
+    process(&mut context, ix, &[&admin]).await.unwrap();
+
+    let ix = remove_from_denylist(&program_id, &admin.pubkey(), &greeter.pubkey());
+    process(&mut context, ix.clone(), &[&admin]).await.unwrap();
+    assert_eq!(denylist(&mut context, &program_id).await, initialized);
+    assert_eq!(
+        process(&mut context, ix, &[&admin]).await,
+        Err(TransactionError::InstructionError(
+            0,
+            InstructionError::InvalidArgument
+        ))
+    );
+    process(&mut context, greet, &[&greeter]).await.unwrap();
+}
+
+// Native CPI cannot create accounts, so only run against the BPF build
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn test_create_denylist() {
+    let program_id = helloworld::id();
+    let admin = Keypair::new();
+    let greeter = Pubkey::new_unique();
+
+    let mut program_test =
+        ProgramTest::new("helloworld", program_id, processor!(process_instruction));
+    add_config(&mut program_test, &program_id, &admin.pubkey());
+    program_test.add_account(
+        admin.pubkey(),
+        Account {
+            lamports: 1_000_000_000,
+            ..Account::default()
+        },
+    );
+    let mut context = program_test.start_with_context().await;
+
+    let ix = add_to_denylist(&program_id, &admin.pubkey(), &greeter);
+    process(&mut context, ix, &[&admin]).await.unwrap();
+    assert_eq!(
+        denylist(&mut context, &program_id).await,
+        Denylist {
+            is_initialized: true,
+            denied: vec![greeter],
+            bump_seed: find_denylist_address(&program_id).1,
+        }
+    );
+}
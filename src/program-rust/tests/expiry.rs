@@ -0,0 +1,212 @@
+use helloworld::{
+    instruction::{reap, say_hello, set_greeting_expiry},
+    process_instruction,
+    state::{find_config_address, find_treasury_address, AccountState, Config},
+    GreetingAccount,
+};
+use solana_program_test::*;
+use solana_sdk::{
+    account::Account,
+    clock::Clock,
+    instruction::Instruction,
+    pubkey::Pubkey,
+    rent::Rent,
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+};
+
+const GREETING_TTL: i64 = 60;
+
+async fn process(
+    context: &mut ProgramTestContext,
+    instruction: Instruction,
+    signers: &[&Keypair],
+) -> Result<(), TransactionError> {
+    let recent_blockhash = context
+        .banks_client
+        .get_new_latest_blockhash(&context.last_blockhash)
+        .await
+        .unwrap();
+    context.last_blockhash = recent_blockhash;
+
+    let mut transaction =
+        Transaction::new_with_payer(&[instruction], Some(&context.payer.pubkey()));
+    let mut all_signers = vec![&context.payer];
+    all_signers.extend_from_slice(signers);
+    transaction.sign(&all_signers, recent_blockhash);
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .map_err(|err| err.unwrap())
+}
+
+async fn lamports(context: &mut ProgramTestContext, pubkey: Pubkey) -> u64 {
+    context
+        .banks_client
+        .get_balance(pubkey)
+        .await
+        .expect("get_balance")
+}
+
+#[tokio::test]
+async fn test_reap_expired_greeting() {
+    let program_id = helloworld::id();
+    let greeted_pubkey = Pubkey::new_unique();
+    let treasury = find_treasury_address(&program_id).0;
+    let admin = Keypair::new();
+    let greeter = Keypair::new();
+
+    let mut program_test =
+        ProgramTest::new("helloworld", program_id, processor!(process_instruction));
+    let greeted_lamports = Rent::default().minimum_balance(GreetingAccount::LEN);
+    program_test.add_account(
+        greeted_pubkey,
+        Account {
+            lamports: greeted_lamports,
+            data: vec![0_u8; GreetingAccount::LEN],
+            owner: program_id,
+            ..Account::default()
+        },
+    );
+    program_test.add_account(
+        treasury,
+        Account {
+            lamports: 1_000_000_000,
+            ..Account::default()
+        },
+    );
+    // Native CPI cannot allocate the config account, so start from an
+    // initialized one
+    let mut config_data = vec![0; Config::LEN];
+    Config {
+        is_initialized: true,
+        admin: admin.pubkey(),
+        bump_seed: find_config_address(&program_id).1,
+        ..Config::default()
+    }
+    .store(&mut config_data)
+    .unwrap();
+    program_test.add_account(
+        find_config_address(&program_id).0,
+        Account {
+            lamports: 5,
+            data: config_data,
+            owner: program_id,
+            ..Account::default()
+        },
+    );
+    let mut context = program_test.start_with_context().await;
+
+    // Greetings made before expiry is enabled never expire
+    let ix = say_hello(&program_id, &greeted_pubkey, &greeter.pubkey(), vec![]);
+    process(&mut context, ix, &[&greeter]).await.unwrap();
+    let ix = reap(&program_id, &greeted_pubkey);
+    assert!(process(&mut context, ix, &[]).await.is_err());
+
+    // Only the admin can make greetings expire
+    let ix = set_greeting_expiry(&program_id, &greeter.pubkey(), GREETING_TTL);
+    assert!(process(&mut context, ix, &[&greeter]).await.is_err());
+    let ix = set_greeting_expiry(&program_id, &admin.pubkey(), GREETING_TTL);
+    process(&mut context, ix, &[&admin]).await.unwrap();
+
+    let ix = say_hello(&program_id, &greeted_pubkey, &greeter.pubkey(), vec![]);
+    process(&mut context, ix, &[&greeter]).await.unwrap();
+    let greeted_account = context
+        .banks_client
+        .get_account(greeted_pubkey)
+        .await
+        .unwrap()
+        .unwrap();
+    let greeting = GreetingAccount::load(&greeted_account.data).unwrap();
+    let mut clock: Clock = context.banks_client.get_sysvar().await.unwrap();
+    assert_eq!(greeting.counter, 2);
+    assert_eq!(greeting.expires_at, clock.unix_timestamp + GREETING_TTL);
+
+    // Live greetings cannot be reaped
+    let ix = reap(&program_id, &greeted_pubkey);
+    assert!(process(&mut context, ix, &[]).await.is_err());
+
+    clock.unix_timestamp = greeting.expires_at;
+    context.set_sysvar(&clock);
+
+    // The lamports can only go to the treasury
+    let mut ix = reap(&program_id, &greeted_pubkey);
+    ix.accounts[1].pubkey = Pubkey::new_unique();
+    assert!(process(&mut context, ix, &[]).await.is_err());
+
+    let treasury_lamports = lamports(&mut context, treasury).await;
+    let ix = reap(&program_id, &greeted_pubkey);
+    process(&mut context, ix, &[]).await.unwrap();
+    assert_eq!(
+        lamports(&mut context, treasury).await,
+        treasury_lamports + greeted_lamports
+    );
+    assert!(context
+        .banks_client
+        .get_account(greeted_pubkey)
+        .await
+        .unwrap()
+        .is_none());
+}
+
+// Native programs cannot reassign accounts, so only run against the BPF build
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn test_reaped_account_stays_closed() {
+    use solana_sdk::instruction::InstructionError;
+
+    let program_id = helloworld::id();
+    let greeted_pubkey = Pubkey::new_unique();
+    let greeter = Keypair::new();
+
+    let mut program_test =
+        ProgramTest::new("helloworld", program_id, processor!(process_instruction));
+    let mut data = vec![0; GreetingAccount::LEN];
+    GreetingAccount {
+        version: GreetingAccount::VERSION,
+        counter: 1,
+        expires_at: 1,
+        ..GreetingAccount::default()
+    }
+    .store(&mut data)
+    .unwrap();
+    program_test.add_account(
+        greeted_pubkey,
+        Account {
+            lamports: Rent::default().minimum_balance(GreetingAccount::LEN),
+            data,
+            owner: program_id,
+            ..Account::default()
+        },
+    );
+    program_test.add_account(
+        find_treasury_address(&program_id).0,
+        Account {
+            lamports: 1_000_000_000,
+            ..Account::default()
+        },
+    );
+    let mut context = program_test.start_with_context().await;
+
+    // The reaped account belongs to the system program right away, so it
+    // cannot be greeted back to life later in the same transaction
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            reap(&program_id, &greeted_pubkey),
+            say_hello(&program_id, &greeted_pubkey, &greeter.pubkey(), vec![]),
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &greeter],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(1, InstructionError::IncorrectProgramId)
+    );
+}
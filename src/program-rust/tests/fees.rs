@@ -0,0 +1,242 @@
+use helloworld::{
+    fees::{FeePolicy, FeeTier},
+    instruction::{batch_greet, say_hello, say_hello_with_message, set_fee_policy},
+    process_instruction,
+    state::{find_config_address, find_treasury_address, AccountState, Config},
+    GreetingAccount,
+};
+use solana_program_test::*;
+use solana_sdk::{
+    account::Account,
+    instruction::Instruction,
+    pubkey::Pubkey,
+    rent::Rent,
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+};
+
+struct Setup {
+    context: ProgramTestContext,
+    program_id: Pubkey,
+    greeted_pubkeys: Vec<Pubkey>,
+    admin: Keypair,
+}
+
+async fn setup() -> Setup {
+    let program_id = helloworld::id();
+    let greeted_pubkeys: Vec<Pubkey> = (0..3).map(|_| Pubkey::new_unique()).collect();
+    let admin = Keypair::new();
+
+    let mut program_test =
+        ProgramTest::new("helloworld", program_id, processor!(process_instruction));
+    for greeted_pubkey in &greeted_pubkeys {
+        program_test.add_account(
+            *greeted_pubkey,
+            Account {
+                lamports: Rent::default().minimum_balance(GreetingAccount::LEN),
+                data: vec![0_u8; GreetingAccount::LEN],
+                owner: program_id,
+                ..Account::default()
+            },
+        );
+    }
+    // The treasury only accepts fees once it is rent exempt
+    program_test.add_account(
+        find_treasury_address(&program_id).0,
+        Account {
+            lamports: 1_000_000_000,
+            ..Account::default()
+        },
+    );
+    // Native CPI cannot allocate the config account, so start from an
+    // initialized one
+    let mut config_data = vec![0; Config::LEN];
+    Config {
+        is_initialized: true,
+        admin: admin.pubkey(),
+        bump_seed: find_config_address(&program_id).1,
+        ..Config::default()
+    }
+    .store(&mut config_data)
+    .unwrap();
+    program_test.add_account(
+        find_config_address(&program_id).0,
+        Account {
+            lamports: 5,
+            data: config_data,
+            owner: program_id,
+            ..Account::default()
+        },
+    );
+    let context = program_test.start_with_context().await;
+
+    Setup {
+        context,
+        program_id,
+        greeted_pubkeys,
+        admin,
+    }
+}
+
+async fn process(
+    context: &mut ProgramTestContext,
+    instruction: Instruction,
+    signers: &[&Keypair],
+) -> Result<(), TransactionError> {
+    let recent_blockhash = context
+        .banks_client
+        .get_new_latest_blockhash(&context.last_blockhash)
+        .await
+        .unwrap();
+    context.last_blockhash = recent_blockhash;
+
+    let mut transaction =
+        Transaction::new_with_payer(&[instruction], Some(&context.payer.pubkey()));
+    let mut all_signers = vec![&context.payer];
+    all_signers.extend_from_slice(signers);
+    transaction.sign(&all_signers, recent_blockhash);
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .map_err(|err| err.unwrap())
+}
+
+/// Process `instruction`, greeting as the fee payer, and return the fee it
+/// paid into the treasury
+async fn fee_paid(
+    context: &mut ProgramTestContext,
+    program_id: &Pubkey,
+    instruction: Instruction,
+) -> u64 {
+    let treasury = find_treasury_address(program_id).0;
+    let before = context.banks_client.get_balance(treasury).await.unwrap();
+    process(context, instruction, &[]).await.unwrap();
+    context.banks_client.get_balance(treasury).await.unwrap() - before
+}
+
+async fn set_policy(setup: &mut Setup, policy: FeePolicy) {
+    let ix = set_fee_policy(&setup.program_id, &setup.admin.pubkey(), policy);
+    process(&mut setup.context, ix, &[&setup.admin])
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_free() {
+    let mut setup = setup().await;
+    let program_id = setup.program_id;
+    let greeter = setup.context.payer.pubkey();
+
+    // Greetings are free until a policy is set
+    let ix = say_hello(&program_id, &setup.greeted_pubkeys[0], &greeter, vec![]);
+    assert_eq!(fee_paid(&mut setup.context, &program_id, ix).await, 0);
+
+    set_policy(&mut setup, FeePolicy::Flat { lamports: 1000 }).await;
+    set_policy(&mut setup, FeePolicy::Free).await;
+    let ix = batch_greet(&program_id, &setup.greeted_pubkeys, &greeter, vec![]);
+    assert_eq!(fee_paid(&mut setup.context, &program_id, ix).await, 0);
+}
+
+#[tokio::test]
+async fn test_flat() {
+    let mut setup = setup().await;
+    let program_id = setup.program_id;
+    let greeter = setup.context.payer.pubkey();
+
+    // Only the admin can set the policy
+    let ix = set_fee_policy(&program_id, &greeter, FeePolicy::Flat { lamports: 1000 });
+    assert!(process(&mut setup.context, ix, &[]).await.is_err());
+    set_policy(&mut setup, FeePolicy::Flat { lamports: 1000 }).await;
+
+    let ix = say_hello(&program_id, &setup.greeted_pubkeys[0], &greeter, vec![]);
+    assert_eq!(fee_paid(&mut setup.context, &program_id, ix).await, 1000);
+    let ix = batch_greet(&program_id, &setup.greeted_pubkeys, &greeter, vec![]);
+    assert_eq!(fee_paid(&mut setup.context, &program_id, ix).await, 3000);
+
+    // Fees cannot be diverted away from the treasury
+    let mut ix = say_hello(&program_id, &setup.greeted_pubkeys[0], &greeter, vec![]);
+    ix.accounts[4].pubkey = Pubkey::new_unique();
+    assert!(process(&mut setup.context, ix, &[]).await.is_err());
+}
+
+#[tokio::test]
+async fn test_per_message_byte() {
+    let mut setup = setup().await;
+    let program_id = setup.program_id;
+    let greeter = setup.context.payer.pubkey();
+    set_policy(
+        &mut setup,
+        FeePolicy::PerMessageByte {
+            base_lamports: 100,
+            lamports_per_byte: 10,
+        },
+    )
+    .await;
+
+    let ix = say_hello(&program_id, &setup.greeted_pubkeys[0], &greeter, vec![]);
+    assert_eq!(fee_paid(&mut setup.context, &program_id, ix).await, 100);
+    let ix = say_hello_with_message(
+        &program_id,
+        &setup.greeted_pubkeys[0],
+        &greeter,
+        vec![],
+        "hello".to_string(),
+    );
+    assert_eq!(fee_paid(&mut setup.context, &program_id, ix).await, 150);
+    // Batched greetings carry no message
+    let ix = batch_greet(&program_id, &setup.greeted_pubkeys, &greeter, vec![]);
+    assert_eq!(fee_paid(&mut setup.context, &program_id, ix).await, 300);
+}
+
+#[tokio::test]
+async fn test_tiered_by_count() {
+    let mut setup = setup().await;
+    let program_id = setup.program_id;
+    let greeter = setup.context.payer.pubkey();
+
+    let unsorted = FeePolicy::TieredByCount {
+        tiers: vec![
+            FeeTier {
+                min_count: 2,
+                lamports: 500,
+            },
+            FeeTier {
+                min_count: 1,
+                lamports: 100,
+            },
+        ],
+    };
+    let ix = set_fee_policy(&program_id, &setup.admin.pubkey(), unsorted);
+    assert!(process(&mut setup.context, ix, &[&setup.admin])
+        .await
+        .is_err());
+    set_policy(
+        &mut setup,
+        FeePolicy::TieredByCount {
+            tiers: vec![
+                FeeTier {
+                    min_count: 1,
+                    lamports: 100,
+                },
+                FeeTier {
+                    min_count: 2,
+                    lamports: 500,
+                },
+            ],
+        },
+    )
+    .await;
+
+    let greeted_pubkey = setup.greeted_pubkeys[0];
+    let ix = say_hello(&program_id, &greeted_pubkey, &greeter, vec![]);
+    assert_eq!(fee_paid(&mut setup.context, &program_id, ix).await, 0);
+    let ix = say_hello(&program_id, &greeted_pubkey, &greeter, vec![]);
+    assert_eq!(fee_paid(&mut setup.context, &program_id, ix).await, 100);
+    let ix = say_hello(&program_id, &greeted_pubkey, &greeter, vec![]);
+    assert_eq!(fee_paid(&mut setup.context, &program_id, ix).await, 500);
+
+    // Each account in a batch is priced by its own count
+    let ix = batch_greet(&program_id, &setup.greeted_pubkeys, &greeter, vec![]);
+    assert_eq!(fee_paid(&mut setup.context, &program_id, ix).await, 500);
+}
@@ -0,0 +1,260 @@
+use helloworld::{
+    instruction::{initialize_if_needed, say_hello},
+    process_instruction,
+    state::{find_greeting_address, AccountState, Config},
+    GreetingAccount,
+};
+use solana_program_test::*;
+use solana_sdk::{
+    account::Account,
+    instruction::{Instruction, InstructionError},
+    pubkey::Pubkey,
+    rent::Rent,
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+};
+
+async fn process(
+    context: &mut ProgramTestContext,
+    instruction: Instruction,
+    signers: &[&Keypair],
+) -> Result<(), TransactionError> {
+    let recent_blockhash = context
+        .banks_client
+        .get_new_latest_blockhash(&context.last_blockhash)
+        .await
+        .unwrap();
+    context.last_blockhash = recent_blockhash;
+
+    let mut transaction =
+        Transaction::new_with_payer(&[instruction], Some(&context.payer.pubkey()));
+    let mut all_signers = vec![&context.payer];
+    all_signers.extend_from_slice(signers);
+    transaction.sign(&all_signers, recent_blockhash);
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .map_err(|err| err.unwrap())
+}
+
+async fn greeting_account(context: &mut ProgramTestContext, pubkey: Pubkey) -> GreetingAccount {
+    let account = context
+        .banks_client
+        .get_account(pubkey)
+        .await
+        .unwrap()
+        .unwrap();
+    GreetingAccount::load(&account.data).unwrap()
+}
+
+#[tokio::test]
+async fn test_greeting_address() {
+    let program_id = helloworld::id();
+    let user = Keypair::new();
+    let (greeting_address, bump_seed) = find_greeting_address(&program_id, &user.pubkey());
+
+    let mut program_test =
+        ProgramTest::new("helloworld", program_id, processor!(process_instruction));
+    // Native CPI cannot allocate the greeting address, so start from a
+    // created one
+    let mut data = vec![0; GreetingAccount::LEN];
+    GreetingAccount {
+        version: GreetingAccount::VERSION,
+        bump_seed,
+        ..GreetingAccount::default()
+    }
+    .store(&mut data)
+    .unwrap();
+    program_test.add_account(
+        greeting_address,
+        Account {
+            lamports: Rent::default().minimum_balance(GreetingAccount::LEN),
+            data,
+            owner: program_id,
+            ..Account::default()
+        },
+    );
+    let mut context = program_test.start_with_context().await;
+
+    let ix = say_hello(&program_id, &greeting_address, &user.pubkey(), vec![]);
+    process(&mut context, ix, &[&user]).await.unwrap();
+    let greeting = greeting_account(&mut context, greeting_address).await;
+    assert_eq!(greeting.counter, 1);
+    assert_eq!(greeting.bump_seed, bump_seed);
+
+    // Others can greet the user's counter, but not create it for them
+    let other = Keypair::new();
+    let ix = say_hello(&program_id, &greeting_address, &other.pubkey(), vec![]);
+    process(&mut context, ix, &[&other]).await.unwrap();
+    assert_eq!(
+        greeting_account(&mut context, greeting_address)
+            .await
+            .counter,
+        2
+    );
+    let other_address = find_greeting_address(&program_id, &other.pubkey()).0;
+    let ix = say_hello(&program_id, &other_address, &user.pubkey(), vec![]);
+    assert!(process(&mut context, ix, &[&user]).await.is_err());
+}
+
+fn add_account(program_test: &mut ProgramTest, pubkey: Pubkey, owner: Pubkey, data: Vec<u8>) {
+    program_test.add_account(
+        pubkey,
+        Account {
+            lamports: Rent::default().minimum_balance(data.len()),
+            data,
+            owner,
+            ..Account::default()
+        },
+    );
+}
+
+fn greeting_data(bump_seed: u8) -> Vec<u8> {
+    let mut data = vec![0; GreetingAccount::LEN];
+    GreetingAccount {
+        version: GreetingAccount::VERSION,
+        counter: 7,
+        bump_seed,
+        ..GreetingAccount::default()
+    }
+    .store(&mut data)
+    .unwrap();
+    data
+}
+
+#[tokio::test]
+async fn test_initialize_if_needed() {
+    let program_id = helloworld::id();
+    let user = Keypair::new();
+    let wrong_state_user = Keypair::new();
+    let wrong_bump_user = Keypair::new();
+    let foreign_user = Keypair::new();
+    let (greeting_address, bump_seed) = find_greeting_address(&program_id, &user.pubkey());
+
+    let mut program_test =
+        ProgramTest::new("helloworld", program_id, processor!(process_instruction));
+    // Native CPI cannot allocate greeting addresses, so only check existing
+    // accounts
+    add_account(
+        &mut program_test,
+        greeting_address,
+        program_id,
+        greeting_data(bump_seed),
+    );
+    let mut config_data = vec![0; Config::LEN];
+    Config::default().store(&mut config_data).unwrap();
+    add_account(
+        &mut program_test,
+        find_greeting_address(&program_id, &wrong_state_user.pubkey()).0,
+        program_id,
+        config_data,
+    );
+    let (wrong_bump_address, wrong_bump_seed) =
+        find_greeting_address(&program_id, &wrong_bump_user.pubkey());
+    add_account(
+        &mut program_test,
+        wrong_bump_address,
+        program_id,
+        greeting_data(wrong_bump_seed.wrapping_sub(1)),
+    );
+    add_account(
+        &mut program_test,
+        find_greeting_address(&program_id, &foreign_user.pubkey()).0,
+        Pubkey::new_unique(),
+        greeting_data(bump_seed),
+    );
+    let mut context = program_test.start_with_context().await;
+
+    // Initializing an existing greeting account changes nothing
+    for _ in 0..2 {
+        let ix = initialize_if_needed(&program_id, &user.pubkey());
+        process(&mut context, ix, &[&user]).await.unwrap();
+        let greeting = greeting_account(&mut context, greeting_address).await;
+        assert_eq!(greeting.counter, 7);
+        assert_eq!(greeting.bump_seed, bump_seed);
+    }
+
+    // Mismatched accounts fail rather than pass for initialized
+    let ix = initialize_if_needed(&program_id, &wrong_state_user.pubkey());
+    assert_eq!(
+        process(&mut context, ix, &[&wrong_state_user]).await,
+        Err(TransactionError::InstructionError(
+            0,
+            InstructionError::InvalidAccountData
+        ))
+    );
+    let ix = initialize_if_needed(&program_id, &wrong_bump_user.pubkey());
+    assert_eq!(
+        process(&mut context, ix, &[&wrong_bump_user]).await,
+        Err(TransactionError::InstructionError(
+            0,
+            InstructionError::InvalidArgument
+        ))
+    );
+    let ix = initialize_if_needed(&program_id, &foreign_user.pubkey());
+    assert_eq!(
+        process(&mut context, ix, &[&foreign_user]).await,
+        Err(TransactionError::InstructionError(
+            0,
+            InstructionError::IncorrectProgramId
+        ))
+    );
+    // Another user's greeting account is not the signer's
+    let mut ix = initialize_if_needed(&program_id, &wrong_bump_user.pubkey());
+    ix.accounts[0].pubkey = greeting_address;
+    assert_eq!(
+        process(&mut context, ix, &[&wrong_bump_user]).await,
+        Err(TransactionError::InstructionError(
+            0,
+            InstructionError::InvalidArgument
+        ))
+    );
+}
+
+// Native CPI cannot create accounts, so only run against the BPF build
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn test_create_greeting_address() {
+    let program_id = helloworld::id();
+    let program_test = ProgramTest::new("helloworld", program_id, processor!(process_instruction));
+    let mut context = program_test.start_with_context().await;
+    let user = context.payer.pubkey();
+    let (greeting_address, bump_seed) = find_greeting_address(&program_id, &user);
+
+    // Greeting your own greeting address creates it
+    for counter in 1..=2 {
+        let ix = say_hello(&program_id, &greeting_address, &user, vec![]);
+        process(&mut context, ix, &[]).await.unwrap();
+        let greeting = greeting_account(&mut context, greeting_address).await;
+        assert_eq!(greeting.counter, counter);
+        assert_eq!(greeting.bump_seed, bump_seed);
+    }
+    let account = context
+        .banks_client
+        .get_account(greeting_address)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(account.owner, program_id);
+    assert!(Rent::default().is_exempt(account.lamports, account.data.len()));
+}
+
+// Native CPI cannot create accounts, so only run against the BPF build
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn test_initialize_if_needed_creates() {
+    let program_id = helloworld::id();
+    let program_test = ProgramTest::new("helloworld", program_id, processor!(process_instruction));
+    let mut context = program_test.start_with_context().await;
+    let user = context.payer.pubkey();
+    let (greeting_address, bump_seed) = find_greeting_address(&program_id, &user);
+
+    for _ in 0..2 {
+        let ix = initialize_if_needed(&program_id, &user);
+        process(&mut context, ix, &[]).await.unwrap();
+        let greeting = greeting_account(&mut context, greeting_address).await;
+        assert_eq!(greeting.counter, 0);
+        assert_eq!(greeting.bump_seed, bump_seed);
+    }
+}
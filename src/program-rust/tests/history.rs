@@ -0,0 +1,143 @@
+use helloworld::{
+    instruction::{initialize_history, say_hello_with_message},
+    process_instruction,
+    state::{find_history_address, AccountState, History},
+    GreetingAccount,
+};
+use solana_program_test::*;
+use solana_sdk::{
+    account::Account,
+    hash::hash,
+    instruction::{Instruction, InstructionError},
+    pubkey::Pubkey,
+    rent::Rent,
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+};
+
+async fn process(
+    context: &mut ProgramTestContext,
+    instruction: Instruction,
+    signers: &[&Keypair],
+) -> Result<(), TransactionError> {
+    let recent_blockhash = context
+        .banks_client
+        .get_new_latest_blockhash(&context.last_blockhash)
+        .await
+        .unwrap();
+    context.last_blockhash = recent_blockhash;
+
+    let mut transaction =
+        Transaction::new_with_payer(&[instruction], Some(&context.payer.pubkey()));
+    let mut all_signers = vec![&context.payer];
+    all_signers.extend_from_slice(signers);
+    transaction.sign(&all_signers, recent_blockhash);
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .map_err(|err| err.unwrap())
+}
+
+async fn history(context: &mut ProgramTestContext, program_id: &Pubkey) -> History {
+    let account = context
+        .banks_client
+        .get_account(find_history_address(program_id).0)
+        .await
+        .unwrap()
+        .unwrap();
+    History::load(&account.data).unwrap()
+}
+
+#[tokio::test]
+async fn test_history() {
+    let program_id = helloworld::id();
+    let greeted_pubkey = Pubkey::new_unique();
+
+    let mut program_test =
+        ProgramTest::new("helloworld", program_id, processor!(process_instruction));
+    program_test.add_account(
+        greeted_pubkey,
+        Account {
+            lamports: Rent::default().minimum_balance(GreetingAccount::LEN),
+            data: vec![0_u8; GreetingAccount::LEN],
+            owner: program_id,
+            ..Account::default()
+        },
+    );
+    // Native CPI cannot allocate the history account, so start from an
+    // initialized one
+    let mut history_data = vec![0; History::LEN];
+    History {
+        is_initialized: true,
+        bump_seed: find_history_address(&program_id).1,
+        ..History::default()
+    }
+    .store(&mut history_data)
+    .unwrap();
+    program_test.add_account(
+        find_history_address(&program_id).0,
+        Account {
+            lamports: Rent::default().minimum_balance(History::LEN),
+            data: history_data,
+            owner: program_id,
+            ..Account::default()
+        },
+    );
+    let mut context = program_test.start_with_context().await;
+    let payer = context.payer.pubkey();
+
+    assert_eq!(
+        process(&mut context, initialize_history(&program_id, &payer), &[]).await,
+        Err(TransactionError::InstructionError(
+            0,
+            InstructionError::AccountAlreadyInitialized
+        ))
+    );
+
+    let greeters = [Keypair::new(), Keypair::new(), Keypair::new()];
+    let messages = ["", "hi", "hello"];
+    for (greeter, message) in greeters.iter().zip(messages) {
+        let ix = say_hello_with_message(
+            &program_id,
+            &greeted_pubkey,
+            &greeter.pubkey(),
+            vec![],
+            message.to_string(),
+        );
+        process(&mut context, ix, &[greeter]).await.unwrap();
+    }
+
+    let history = history(&mut context, &program_id).await;
+    assert_eq!(history.count, 3);
+    let entries = history.chronological();
+    assert_eq!(entries.len(), 3);
+    for ((entry, greeter), message) in entries.iter().zip(&greeters).zip(messages) {
+        assert_eq!(entry.greeter, greeter.pubkey());
+        assert_eq!(entry.message_hash, hash(message.as_bytes()));
+    }
+    assert!(entries.windows(2).all(|pair| pair[0].slot <= pair[1].slot));
+    assert_eq!(history.verify_chain(), Ok(()));
+}
+
+// Native CPI cannot create accounts, so only run against the BPF build
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn test_initialize_history() {
+    let program_id = helloworld::id();
+    let program_test = ProgramTest::new("helloworld", program_id, processor!(process_instruction));
+    let mut context = program_test.start_with_context().await;
+    let payer = context.payer.pubkey();
+
+    process(&mut context, initialize_history(&program_id, &payer), &[])
+        .await
+        .unwrap();
+    assert_eq!(
+        history(&mut context, &program_id).await,
+        History {
+            is_initialized: true,
+            bump_seed: find_history_address(&program_id).1,
+            ..History::default()
+        }
+    );
+}
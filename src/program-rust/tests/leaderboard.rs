@@ -0,0 +1,181 @@
+use helloworld::{
+    instruction::{initialize_leaderboard, say_hello},
+    process_instruction,
+    state::{
+        find_greeter_address, find_leaderboard_address, AccountState, GreeterAccount, Leaderboard,
+        LeaderboardEntry,
+    },
+    GreetingAccount,
+};
+use solana_program_test::*;
+use solana_sdk::{
+    account::Account,
+    instruction::{Instruction, InstructionError},
+    pubkey::Pubkey,
+    rent::Rent,
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+};
+
+async fn process(
+    context: &mut ProgramTestContext,
+    instruction: Instruction,
+    signers: &[&Keypair],
+) -> Result<(), TransactionError> {
+    let recent_blockhash = context
+        .banks_client
+        .get_new_latest_blockhash(&context.last_blockhash)
+        .await
+        .unwrap();
+    context.last_blockhash = recent_blockhash;
+
+    let mut transaction =
+        Transaction::new_with_payer(&[instruction], Some(&context.payer.pubkey()));
+    let mut all_signers = vec![&context.payer];
+    all_signers.extend_from_slice(signers);
+    transaction.sign(&all_signers, recent_blockhash);
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .map_err(|err| err.unwrap())
+}
+
+async fn leaderboard(context: &mut ProgramTestContext, program_id: &Pubkey) -> Leaderboard {
+    let account = context
+        .banks_client
+        .get_account(find_leaderboard_address(program_id).0)
+        .await
+        .unwrap()
+        .unwrap();
+    Leaderboard::load(&account.data).unwrap()
+}
+
+fn add_state_account(
+    program_test: &mut ProgramTest,
+    program_id: &Pubkey,
+    pubkey: Pubkey,
+    state: &impl AccountState,
+    len: usize,
+) {
+    let mut data = vec![0; len];
+    state.store(&mut data).unwrap();
+    program_test.add_account(
+        pubkey,
+        Account {
+            lamports: Rent::default().minimum_balance(len),
+            data,
+            owner: *program_id,
+            ..Account::default()
+        },
+    );
+}
+
+#[tokio::test]
+async fn test_leaderboard() {
+    let program_id = helloworld::id();
+    let greeted_pubkey = Pubkey::new_unique();
+    let veteran = Keypair::new();
+    let newcomer = Keypair::new();
+    let unregistered = Keypair::new();
+
+    let mut program_test =
+        ProgramTest::new("helloworld", program_id, processor!(process_instruction));
+    program_test.add_account(
+        greeted_pubkey,
+        Account {
+            lamports: Rent::default().minimum_balance(GreetingAccount::LEN),
+            data: vec![0_u8; GreetingAccount::LEN],
+            owner: program_id,
+            ..Account::default()
+        },
+    );
+    // Native CPI cannot allocate accounts, so start from initialized ones
+    add_state_account(
+        &mut program_test,
+        &program_id,
+        find_leaderboard_address(&program_id).0,
+        &Leaderboard {
+            is_initialized: true,
+            bump_seed: find_leaderboard_address(&program_id).1,
+            ..Leaderboard::default()
+        },
+        Leaderboard::LEN,
+    );
+    for (greeter, greetings) in [(&veteran, 10), (&newcomer, 0)] {
+        let (greeter_address, bump_seed) = find_greeter_address(&program_id, &greeter.pubkey());
+        add_state_account(
+            &mut program_test,
+            &program_id,
+            greeter_address,
+            &GreeterAccount {
+                is_initialized: true,
+                greetings,
+                last_greeting_slot: 0,
+                bump_seed,
+            },
+            GreeterAccount::LEN,
+        );
+    }
+    let mut context = program_test.start_with_context().await;
+    let payer = context.payer.pubkey();
+
+    assert_eq!(
+        process(
+            &mut context,
+            initialize_leaderboard(&program_id, &payer),
+            &[]
+        )
+        .await,
+        Err(TransactionError::InstructionError(
+            0,
+            InstructionError::AccountAlreadyInitialized
+        ))
+    );
+
+    for greeter in [&newcomer, &veteran, &newcomer, &unregistered] {
+        let ix = say_hello(&program_id, &greeted_pubkey, &greeter.pubkey(), vec![]);
+        process(&mut context, ix, &[greeter]).await.unwrap();
+    }
+
+    // Only registered greeters are ranked, by their total greetings
+    assert_eq!(
+        leaderboard(&mut context, &program_id).await.entries,
+        [
+            LeaderboardEntry {
+                greeter: veteran.pubkey(),
+                greetings: 11,
+            },
+            LeaderboardEntry {
+                greeter: newcomer.pubkey(),
+                greetings: 2,
+            },
+        ]
+    );
+}
+
+// Native CPI cannot create accounts, so only run against the BPF build
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn test_initialize_leaderboard() {
+    let program_id = helloworld::id();
+    let program_test = ProgramTest::new("helloworld", program_id, processor!(process_instruction));
+    let mut context = program_test.start_with_context().await;
+    let payer = context.payer.pubkey();
+
+    process(
+        &mut context,
+        initialize_leaderboard(&program_id, &payer),
+        &[],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        leaderboard(&mut context, &program_id).await,
+        Leaderboard {
+            is_initialized: true,
+            bump_seed: find_leaderboard_address(&program_id).1,
+            ..Leaderboard::default()
+        }
+    );
+}
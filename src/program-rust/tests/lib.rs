@@ -1,18 +1,30 @@
-use borsh::BorshDeserialize;
-use helloworld::{process_instruction, GreetingAccount};
+use helloworld::{
+    cost::CostModel,
+    error::HelloWorldError,
+    instruction::{
+        batch_greet, create_mirror, say_hello, say_hello_secp256k1, secp256k1_greeting_message,
+    },
+    process_instruction,
+    state::{AccountState, Config},
+    GreetingAccount,
+};
 use solana_program_test::*;
 use solana_sdk::{
     account::Account,
-    instruction::{AccountMeta, Instruction},
+    instruction::{Instruction, InstructionError},
+    packet::PACKET_DATA_SIZE,
     pubkey::Pubkey,
-    signature::Signer,
-    transaction::Transaction,
+    rent::Rent,
+    secp256k1_instruction::{construct_eth_pubkey, new_secp256k1_instruction},
+    signature::{read_keypair_file, Signer},
+    sysvar,
+    transaction::{Transaction, TransactionError},
 };
-use std::mem;
+use std::time::Instant;
 
 #[tokio::test]
 async fn test_helloworld() {
-    let program_id = Pubkey::new_unique();
+    let program_id = helloworld::id();
     let greeted_pubkey = Pubkey::new_unique();
 
     let mut program_test = ProgramTest::new(
@@ -23,8 +35,8 @@ async fn test_helloworld() {
     program_test.add_account(
         greeted_pubkey,
         Account {
-            lamports: 5,
-            data: vec![0_u8; mem::size_of::<u32>()],
+            lamports: Rent::default().minimum_balance(GreetingAccount::LEN),
+            data: vec![0_u8; GreetingAccount::LEN],
             owner: program_id,
             ..Account::default()
         },
@@ -38,7 +50,7 @@ async fn test_helloworld() {
         .expect("get_account")
         .expect("greeted_account not found");
     assert_eq!(
-        GreetingAccount::try_from_slice(&greeted_account.data)
+        GreetingAccount::load(&greeted_account.data)
             .unwrap()
             .counter,
         0
@@ -46,10 +58,11 @@ async fn test_helloworld() {
 
     // Greet once
     let mut transaction = Transaction::new_with_payer(
-        &[Instruction::new_with_bincode(
-            program_id,
-            &[0], // ignored but makes the instruction unique in the slot
-            vec![AccountMeta::new(greeted_pubkey, false)],
+        &[say_hello(
+            &program_id,
+            &greeted_pubkey,
+            &payer.pubkey(),
+            vec![],
         )],
         Some(&payer.pubkey()),
     );
@@ -63,18 +76,23 @@ async fn test_helloworld() {
         .expect("get_account")
         .expect("greeted_account not found");
     assert_eq!(
-        GreetingAccount::try_from_slice(&greeted_account.data)
+        GreetingAccount::load(&greeted_account.data)
             .unwrap()
             .counter,
         1
     );
 
-    // Greet again
+    // Greet again, with a new blockhash so the transaction is unique
+    let recent_blockhash = banks_client
+        .get_new_latest_blockhash(&recent_blockhash)
+        .await
+        .unwrap();
     let mut transaction = Transaction::new_with_payer(
-        &[Instruction::new_with_bincode(
-            program_id,
-            &[1], // ignored but makes the instruction unique in the slot
-            vec![AccountMeta::new(greeted_pubkey, false)],
+        &[say_hello(
+            &program_id,
+            &greeted_pubkey,
+            &payer.pubkey(),
+            vec![],
         )],
         Some(&payer.pubkey()),
     );
@@ -88,9 +106,706 @@ async fn test_helloworld() {
         .expect("get_account")
         .expect("greeted_account not found");
     assert_eq!(
-        GreetingAccount::try_from_slice(&greeted_account.data)
+        GreetingAccount::load(&greeted_account.data)
             .unwrap()
             .counter,
         2
     );
 }
+
+#[tokio::test]
+async fn test_single_vs_batched_greetings() {
+    const GREETINGS: usize = 8;
+
+    let program_id = helloworld::id();
+    let greeted_pubkeys: Vec<Pubkey> = (0..GREETINGS).map(|_| Pubkey::new_unique()).collect();
+
+    let mut program_test =
+        ProgramTest::new("helloworld", program_id, processor!(process_instruction));
+    for greeted_pubkey in &greeted_pubkeys {
+        program_test.add_account(
+            *greeted_pubkey,
+            Account {
+                lamports: Rent::default().minimum_balance(GreetingAccount::LEN),
+                data: vec![0_u8; GreetingAccount::LEN],
+                owner: program_id,
+                ..Account::default()
+            },
+        );
+    }
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+    #[allow(deprecated)]
+    let (fee_calculator, _, _) = banks_client.get_fees().await.unwrap();
+    let cost_model = CostModel::new(fee_calculator.lamports_per_signature);
+
+    // One transaction per greeting
+    let balance = banks_client.get_balance(payer.pubkey()).await.unwrap();
+    let start = Instant::now();
+    for greeted_pubkey in &greeted_pubkeys {
+        let mut transaction = Transaction::new_with_payer(
+            &[say_hello(
+                &program_id,
+                greeted_pubkey,
+                &payer.pubkey(),
+                vec![],
+            )],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&payer], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+    }
+    let single_latency = start.elapsed();
+    let single_fee = balance - banks_client.get_balance(payer.pubkey()).await.unwrap();
+
+    // A single transaction greeting everyone
+    let balance = banks_client.get_balance(payer.pubkey()).await.unwrap();
+    let start = Instant::now();
+    let mut transaction = Transaction::new_with_payer(
+        &[batch_greet(
+            &program_id,
+            &greeted_pubkeys,
+            &payer.pubkey(),
+            vec![],
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+    let batched_latency = start.elapsed();
+    let batched_fee = balance - banks_client.get_balance(payer.pubkey()).await.unwrap();
+
+    println!(
+        "{} greetings: single {} lamports in {:?}, batched {} lamports in {:?}",
+        GREETINGS, single_fee, single_latency, batched_fee, batched_latency
+    );
+
+    // Verify every account was greeted once by each scenario
+    for greeted_pubkey in &greeted_pubkeys {
+        let greeted_account = banks_client
+            .get_account(*greeted_pubkey)
+            .await
+            .expect("get_account")
+            .expect("greeted_account not found");
+        assert_eq!(
+            GreetingAccount::load(&greeted_account.data)
+                .unwrap()
+                .counter,
+            2
+        );
+    }
+
+    // Verify the cost model matches what the bank charged
+    assert_eq!(single_fee, cost_model.single_fee(GREETINGS));
+    assert_eq!(batched_fee, cost_model.batched_fee(GREETINGS));
+    assert!(cost_model.should_batch(GREETINGS));
+}
+
+#[tokio::test]
+async fn test_counter_overflow() {
+    let program_id = helloworld::id();
+    let greeted_pubkey = Pubkey::new_unique();
+
+    let mut program_test =
+        ProgramTest::new("helloworld", program_id, processor!(process_instruction));
+    let mut data = vec![0_u8; GreetingAccount::LEN];
+    GreetingAccount {
+        version: GreetingAccount::VERSION,
+        counter: u32::MAX,
+        ..GreetingAccount::default()
+    }
+    .store(&mut data)
+    .unwrap();
+    program_test.add_account(
+        greeted_pubkey,
+        Account {
+            lamports: 5,
+            data,
+            owner: program_id,
+            ..Account::default()
+        },
+    );
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+    let mut transaction = Transaction::new_with_payer(
+        &[say_hello(
+            &program_id,
+            &greeted_pubkey,
+            &payer.pubkey(),
+            vec![],
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer], recent_blockhash);
+    assert_eq!(
+        banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(HelloWorldError::CounterOverflow as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn test_reentrant_greeting() {
+    let program_id = helloworld::id();
+    let greeted_pubkey = Pubkey::new_unique();
+    let processing_pubkey = Pubkey::new_unique();
+
+    let mut program_test =
+        ProgramTest::new("helloworld", program_id, processor!(process_instruction));
+    program_test.add_account(
+        greeted_pubkey,
+        Account {
+            lamports: Rent::default().minimum_balance(GreetingAccount::LEN),
+            data: vec![0_u8; GreetingAccount::LEN],
+            owner: program_id,
+            ..Account::default()
+        },
+    );
+    // A greeting cannot leave the flag set, so stage an account caught in
+    // the middle of one
+    let mut data = vec![0_u8; GreetingAccount::LEN];
+    GreetingAccount {
+        version: GreetingAccount::VERSION,
+        processing: true,
+        ..GreetingAccount::default()
+    }
+    .store(&mut data)
+    .unwrap();
+    program_test.add_account(
+        processing_pubkey,
+        Account {
+            lamports: Rent::default().minimum_balance(GreetingAccount::LEN),
+            data,
+            owner: program_id,
+            ..Account::default()
+        },
+    );
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+    // The flag is cleared once the greeting completes
+    let mut transaction = Transaction::new_with_payer(
+        &[say_hello(
+            &program_id,
+            &greeted_pubkey,
+            &payer.pubkey(),
+            vec![],
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+    let greeted_account = banks_client
+        .get_account(greeted_pubkey)
+        .await
+        .unwrap()
+        .unwrap();
+    let greeting_account = GreetingAccount::load(&greeted_account.data).unwrap();
+    assert_eq!(greeting_account.counter, 1);
+    assert!(!greeting_account.processing);
+
+    let mut transaction = Transaction::new_with_payer(
+        &[say_hello(
+            &program_id,
+            &processing_pubkey,
+            &payer.pubkey(),
+            vec![],
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer], recent_blockhash);
+    assert_eq!(
+        banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(HelloWorldError::ReentrantCall as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn test_greeting_account_not_rent_exempt() {
+    let program_id = helloworld::id();
+    let greeted_pubkey = Pubkey::new_unique();
+
+    let mut program_test =
+        ProgramTest::new("helloworld", program_id, processor!(process_instruction));
+    program_test.add_account(
+        greeted_pubkey,
+        Account {
+            lamports: Rent::default().minimum_balance(GreetingAccount::LEN) - 1,
+            data: vec![0_u8; GreetingAccount::LEN],
+            owner: program_id,
+            ..Account::default()
+        },
+    );
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+    // The first greeting initializes the account, which would not survive
+    let mut transaction = Transaction::new_with_payer(
+        &[say_hello(
+            &program_id,
+            &greeted_pubkey,
+            &payer.pubkey(),
+            vec![],
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer], recent_blockhash);
+    assert_eq!(
+        banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(HelloWorldError::NotRentExempt as u32)
+        )
+    );
+}
+
+#[test]
+fn test_cost_model_transaction_size() {
+    let payer = Pubkey::new_unique();
+    let program_id = helloworld::id();
+    for greetings in [1, 2, CostModel::max_batch_size()] {
+        let greeted_pubkeys: Vec<Pubkey> = (0..greetings).map(|_| Pubkey::new_unique()).collect();
+        let transaction = Transaction::new_with_payer(
+            &[batch_greet(&program_id, &greeted_pubkeys, &payer, vec![])],
+            Some(&payer),
+        );
+        let size = bincode::serialized_size(&transaction).unwrap() as usize;
+        assert_eq!(size, CostModel::transaction_size(greetings));
+        assert!(size <= PACKET_DATA_SIZE);
+    }
+}
+
+#[tokio::test]
+async fn test_secp256k1_greeting() {
+    let program_id = helloworld::id();
+    let greeted_pubkey = Pubkey::new_unique();
+
+    let mut program_test =
+        ProgramTest::new("helloworld", program_id, processor!(process_instruction));
+    program_test.add_account(
+        greeted_pubkey,
+        Account {
+            lamports: Rent::default().minimum_balance(GreetingAccount::LEN),
+            data: vec![0_u8; GreetingAccount::LEN],
+            owner: program_id,
+            ..Account::default()
+        },
+    );
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+    let secret_key = libsecp256k1::SecretKey::parse(&[7; 32]).unwrap();
+    let eth_address = construct_eth_pubkey(&libsecp256k1::PublicKey::from_secret_key(&secret_key));
+    let signed_greeting = [
+        new_secp256k1_instruction(&secret_key, &secp256k1_greeting_message(&greeted_pubkey, 0)),
+        say_hello_secp256k1(
+            &program_id,
+            &greeted_pubkey,
+            eth_address,
+            vec![],
+            &payer.pubkey(),
+        ),
+    ];
+
+    // Greet on behalf of the Ethereum key
+    let mut transaction = Transaction::new_with_payer(&signed_greeting, Some(&payer.pubkey()));
+    transaction.sign(&[&payer], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let greeted_account = banks_client
+        .get_account(greeted_pubkey)
+        .await
+        .expect("get_account")
+        .expect("greeted_account not found");
+    assert_eq!(
+        GreetingAccount::load(&greeted_account.data)
+            .unwrap()
+            .counter,
+        1
+    );
+
+    // Replaying the signature fails since it was made over the old count
+    let recent_blockhash = banks_client
+        .get_new_latest_blockhash(&recent_blockhash)
+        .await
+        .unwrap();
+    let mut transaction = Transaction::new_with_payer(&signed_greeting, Some(&payer.pubkey()));
+    transaction.sign(&[&payer], recent_blockhash);
+    assert!(banks_client.process_transaction(transaction).await.is_err());
+
+    // A signature by a different key fails
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            new_secp256k1_instruction(
+                &libsecp256k1::SecretKey::parse(&[8; 32]).unwrap(),
+                &secp256k1_greeting_message(&greeted_pubkey, 1),
+            ),
+            say_hello_secp256k1(
+                &program_id,
+                &greeted_pubkey,
+                eth_address,
+                vec![],
+                &payer.pubkey(),
+            ),
+        ],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer], recent_blockhash);
+    assert!(banks_client.process_transaction(transaction).await.is_err());
+
+    // Greeting without the signature verification fails
+    let mut transaction = Transaction::new_with_payer(
+        &[say_hello_secp256k1(
+            &program_id,
+            &greeted_pubkey,
+            eth_address,
+            vec![],
+            &payer.pubkey(),
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer], recent_blockhash);
+    assert!(banks_client.process_transaction(transaction).await.is_err());
+}
+
+#[tokio::test]
+async fn test_fake_instructions_sysvar() {
+    let program_id = helloworld::id();
+    let greeted_pubkey = Pubkey::new_unique();
+    let fake_sysvar = Pubkey::new_unique();
+
+    let mut program_test =
+        ProgramTest::new("helloworld", program_id, processor!(process_instruction));
+    program_test.add_account(
+        greeted_pubkey,
+        Account {
+            lamports: Rent::default().minimum_balance(GreetingAccount::LEN),
+            data: vec![0_u8; GreetingAccount::LEN],
+            owner: program_id,
+            ..Account::default()
+        },
+    );
+    // Owned by the sysvar program like the real one, at another address
+    program_test.add_account(
+        fake_sysvar,
+        Account {
+            lamports: 1,
+            data: vec![0; 64],
+            owner: sysvar::id(),
+            ..Account::default()
+        },
+    );
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+    let greeter = payer.pubkey();
+
+    let secret_key = libsecp256k1::SecretKey::parse(&[7; 32]).unwrap();
+    let eth_address = construct_eth_pubkey(&libsecp256k1::PublicKey::from_secret_key(&secret_key));
+    let mut signed_greeting =
+        say_hello_secp256k1(&program_id, &greeted_pubkey, eth_address, vec![], &greeter);
+    signed_greeting.accounts[1].pubkey = fake_sysvar;
+    let mut greeting = say_hello(&program_id, &greeted_pubkey, &greeter, vec![]);
+    greeting.accounts[10].pubkey = fake_sysvar;
+    let mut batch = batch_greet(&program_id, &[greeted_pubkey], &greeter, vec![]);
+    batch.accounts[5].pubkey = fake_sysvar;
+
+    for instructions in [
+        vec![
+            new_secp256k1_instruction(&secret_key, &secp256k1_greeting_message(&greeted_pubkey, 0)),
+            signed_greeting,
+        ],
+        vec![greeting],
+        vec![batch],
+    ] {
+        let mut transaction = Transaction::new_with_payer(&instructions, Some(&greeter));
+        transaction.sign(&[&payer], recent_blockhash);
+        assert_eq!(
+            banks_client
+                .process_transaction(transaction)
+                .await
+                .unwrap_err()
+                .unwrap(),
+            TransactionError::InstructionError(
+                instructions.len() as u8 - 1,
+                InstructionError::UnsupportedSysvar
+            )
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_spoofed_greeting_accounts() {
+    let program_id = helloworld::id();
+    let foreign_pubkey = Pubkey::new_unique();
+    let config_pubkey = Pubkey::new_unique();
+
+    let mut program_test =
+        ProgramTest::new("helloworld", program_id, processor!(process_instruction));
+    let mut data = vec![0; GreetingAccount::LEN];
+    GreetingAccount::default().store(&mut data).unwrap();
+    program_test.add_account(
+        foreign_pubkey,
+        Account {
+            lamports: Rent::default().minimum_balance(GreetingAccount::LEN),
+            data,
+            owner: Pubkey::new_unique(),
+            ..Account::default()
+        },
+    );
+    // A config account the size of a greeting account
+    let mut data = vec![0; Config::LEN];
+    Config::default().store(&mut data).unwrap();
+    data.truncate(GreetingAccount::LEN);
+    program_test.add_account(
+        config_pubkey,
+        Account {
+            lamports: Rent::default().minimum_balance(GreetingAccount::LEN),
+            data,
+            owner: program_id,
+            ..Account::default()
+        },
+    );
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+    // Greeting state is only trusted in program-owned accounts of the right
+    // type
+    for (pubkey, error) in [
+        (foreign_pubkey, InstructionError::IncorrectProgramId),
+        (config_pubkey, InstructionError::InvalidAccountData),
+    ] {
+        let mut transaction = Transaction::new_with_payer(
+            &[create_mirror(&program_id, &pubkey, &payer.pubkey())],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&payer], recent_blockhash);
+        assert_eq!(
+            banks_client
+                .process_transaction(transaction)
+                .await
+                .unwrap_err()
+                .unwrap(),
+            TransactionError::InstructionError(0, error)
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_missing_accounts() {
+    let program_id = helloworld::id();
+    let greeted_pubkey = Pubkey::new_unique();
+    let empty_pubkey = Pubkey::new_unique();
+
+    let mut program_test =
+        ProgramTest::new("helloworld", program_id, processor!(process_instruction));
+    program_test.add_account(
+        greeted_pubkey,
+        Account {
+            lamports: Rent::default().minimum_balance(GreetingAccount::LEN),
+            data: vec![0_u8; GreetingAccount::LEN],
+            owner: program_id,
+            ..Account::default()
+        },
+    );
+    program_test.add_account(
+        empty_pubkey,
+        Account {
+            lamports: Rent::default().minimum_balance(0),
+            owner: program_id,
+            ..Account::default()
+        },
+    );
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+    // Instructions missing accounts or data fail instead of panicking
+    let greeting = say_hello(&program_id, &greeted_pubkey, &payer.pubkey(), vec![]);
+    let mut no_accounts = greeting.clone();
+    no_accounts.accounts.clear();
+    let mut one_account = greeting.clone();
+    one_account.accounts.truncate(1);
+    for (instruction, error) in [
+        (no_accounts, InstructionError::NotEnoughAccountKeys),
+        (one_account, InstructionError::NotEnoughAccountKeys),
+        (
+            batch_greet(&program_id, &[empty_pubkey], &payer.pubkey(), vec![]),
+            InstructionError::AccountDataTooSmall,
+        ),
+    ] {
+        let mut transaction = Transaction::new_with_payer(&[instruction], Some(&payer.pubkey()));
+        transaction.sign(&[&payer], recent_blockhash);
+        assert_eq!(
+            banks_client
+                .process_transaction(transaction)
+                .await
+                .unwrap_err()
+                .unwrap(),
+            TransactionError::InstructionError(0, error)
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_invalid_instruction() {
+    let program_id = helloworld::id();
+    let program_test = ProgramTest::new("helloworld", program_id, processor!(process_instruction));
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+    let mut transaction = Transaction::new_with_payer(
+        &[Instruction::new_with_bytes(program_id, &[u8::MAX], vec![])],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer], recent_blockhash);
+    assert_eq!(
+        banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(HelloWorldError::InvalidInstruction as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn test_unexpected_program_id() {
+    // The deployed program must end up at the declared id
+    let keypair = read_keypair_file(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/helloworld-keypair.json"
+    ))
+    .unwrap();
+    assert_eq!(keypair.pubkey(), helloworld::id());
+
+    let program_id = Pubkey::new_unique();
+    let greeted_pubkey = Pubkey::new_unique();
+
+    let mut program_test =
+        ProgramTest::new("helloworld", program_id, processor!(process_instruction));
+    program_test.add_account(
+        greeted_pubkey,
+        Account {
+            lamports: Rent::default().minimum_balance(GreetingAccount::LEN),
+            data: vec![0_u8; GreetingAccount::LEN],
+            owner: program_id,
+            ..Account::default()
+        },
+    );
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+    let mut transaction = Transaction::new_with_payer(
+        &[say_hello(
+            &program_id,
+            &greeted_pubkey,
+            &payer.pubkey(),
+            vec![],
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer], recent_blockhash);
+    assert_eq!(
+        banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(0, InstructionError::IncorrectProgramId)
+    );
+}
+
+// Native programs are not metered, so only run against the BPF build. Run on
+// either side of a change to compare the compute cost of greetings.
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn test_greeting_compute_units() {
+    use solana_sdk::{compute_budget::ComputeBudgetInstruction, instruction::Instruction};
+
+    /// Smallest compute unit limit `instruction` succeeds with
+    async fn compute_units(
+        banks_client: &mut BanksClient,
+        payer: &solana_sdk::signature::Keypair,
+        instruction: Instruction,
+    ) -> u32 {
+        let (mut low, mut high) = (0, 200_000);
+        while low < high {
+            let units = low + (high - low) / 2;
+            let recent_blockhash = banks_client.get_latest_blockhash().await.unwrap();
+            let mut transaction = Transaction::new_with_payer(
+                &[
+                    ComputeBudgetInstruction::set_compute_unit_limit(units),
+                    instruction.clone(),
+                ],
+                Some(&payer.pubkey()),
+            );
+            transaction.sign(&[payer], recent_blockhash);
+            if banks_client.process_transaction(transaction).await.is_ok() {
+                high = units;
+            } else {
+                low = units + 1;
+            }
+        }
+        low
+    }
+
+    let program_id = helloworld::id();
+    let greeted_pubkeys: Vec<Pubkey> = (0..2).map(|_| Pubkey::new_unique()).collect();
+
+    let mut program_test =
+        ProgramTest::new("helloworld", program_id, processor!(process_instruction));
+    for greeted_pubkey in &greeted_pubkeys {
+        program_test.add_account(
+            *greeted_pubkey,
+            Account {
+                lamports: Rent::default().minimum_balance(GreetingAccount::LEN),
+                data: vec![0_u8; GreetingAccount::LEN],
+                owner: program_id,
+                ..Account::default()
+            },
+        );
+    }
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+    // Initialize the accounts so only steady state greetings are measured
+    let mut transaction = Transaction::new_with_payer(
+        &[batch_greet(
+            &program_id,
+            &greeted_pubkeys,
+            &payer.pubkey(),
+            vec![],
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let one = compute_units(
+        &mut banks_client,
+        &payer,
+        batch_greet(&program_id, &greeted_pubkeys[..1], &payer.pubkey(), vec![]),
+    )
+    .await;
+    let two = compute_units(
+        &mut banks_client,
+        &payer,
+        batch_greet(&program_id, &greeted_pubkeys, &payer.pubkey(), vec![]),
+    )
+    .await;
+    println!(
+        "BatchGreet: {} compute units for 1 greeting, {} per additional greeting",
+        one,
+        two - one
+    );
+    assert!(two > one);
+}
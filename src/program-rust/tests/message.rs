@@ -0,0 +1,162 @@
+use helloworld::{
+    error::HelloWorldError,
+    instruction::{say_hello_with_message, update_config, ConfigUpdate, MAX_MESSAGE_LEN},
+    process_instruction,
+    state::{find_config_address, AccountState, Config},
+    GreetingAccount,
+};
+use solana_program_test::*;
+use solana_sdk::{
+    account::Account,
+    instruction::{Instruction, InstructionError},
+    pubkey::Pubkey,
+    rent::Rent,
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+};
+
+async fn process(
+    context: &mut ProgramTestContext,
+    instruction: Instruction,
+    signers: &[&Keypair],
+) -> Result<(), TransactionError> {
+    let recent_blockhash = context
+        .banks_client
+        .get_new_latest_blockhash(&context.last_blockhash)
+        .await
+        .unwrap();
+    context.last_blockhash = recent_blockhash;
+
+    let mut transaction =
+        Transaction::new_with_payer(&[instruction], Some(&context.payer.pubkey()));
+    let mut all_signers = vec![&context.payer];
+    all_signers.extend_from_slice(signers);
+    transaction.sign(&all_signers, recent_blockhash);
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .map_err(|err| err.unwrap())
+}
+
+fn message_too_long() -> Result<(), TransactionError> {
+    Err(TransactionError::InstructionError(
+        0,
+        InstructionError::Custom(HelloWorldError::MessageTooLong as u32),
+    ))
+}
+
+#[tokio::test]
+async fn test_message() {
+    let program_id = helloworld::id();
+    let greeted_pubkey = Pubkey::new_unique();
+    let admin = Keypair::new();
+
+    let mut program_test =
+        ProgramTest::new("helloworld", program_id, processor!(process_instruction));
+    program_test.add_account(
+        greeted_pubkey,
+        Account {
+            lamports: Rent::default().minimum_balance(GreetingAccount::LEN),
+            data: vec![0_u8; GreetingAccount::LEN],
+            owner: program_id,
+            ..Account::default()
+        },
+    );
+    // Native CPI cannot allocate the config account, so start from an
+    // initialized one
+    let mut config_data = vec![0; Config::LEN];
+    Config {
+        is_initialized: true,
+        admin: admin.pubkey(),
+        bump_seed: find_config_address(&program_id).1,
+        ..Config::default()
+    }
+    .store(&mut config_data)
+    .unwrap();
+    program_test.add_account(
+        find_config_address(&program_id).0,
+        Account {
+            lamports: Rent::default().minimum_balance(Config::LEN),
+            data: config_data,
+            owner: program_id,
+            ..Account::default()
+        },
+    );
+    let mut context = program_test.start_with_context().await;
+
+    let greet = |message: String| {
+        say_hello_with_message(
+            &program_id,
+            &greeted_pubkey,
+            &admin.pubkey(),
+            vec![],
+            message,
+        )
+    };
+    process(&mut context, greet("a".repeat(MAX_MESSAGE_LEN)), &[&admin])
+        .await
+        .unwrap();
+    assert_eq!(
+        process(
+            &mut context,
+            greet("a".repeat(MAX_MESSAGE_LEN + 1)),
+            &[&admin]
+        )
+        .await,
+        message_too_long()
+    );
+
+    // Configs can only lower the limit
+    let cap = |max_message_len| {
+        update_config(
+            &program_id,
+            &admin.pubkey(),
+            ConfigUpdate {
+                max_message_len: Some(max_message_len),
+                ..ConfigUpdate::default()
+            },
+        )
+    };
+    assert_eq!(
+        process(&mut context, cap(MAX_MESSAGE_LEN as u16 + 1), &[&admin]).await,
+        Err(TransactionError::InstructionError(
+            0,
+            InstructionError::InvalidArgument
+        ))
+    );
+    process(&mut context, cap(5), &[&admin]).await.unwrap();
+    process(&mut context, greet("hello".to_string()), &[&admin])
+        .await
+        .unwrap();
+    assert_eq!(
+        process(&mut context, greet("hello!".to_string()), &[&admin]).await,
+        message_too_long()
+    );
+
+    // Lifting the cap restores the default limit
+    process(&mut context, cap(0), &[&admin]).await.unwrap();
+    process(&mut context, greet("hello!".to_string()), &[&admin])
+        .await
+        .unwrap();
+
+    // Messages must render safely
+    assert_eq!(
+        process(&mut context, greet("hello\r\n".to_string()), &[&admin]).await,
+        Err(TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(HelloWorldError::InvalidMessage as u32)
+        ))
+    );
+    let mut ix = greet("hello".to_string());
+    // No UTF-8 encoding holds the byte 0xff
+    let len = ix.data.len();
+    ix.data[len - 1] = 0xff;
+    assert_eq!(
+        process(&mut context, ix, &[&admin]).await,
+        Err(TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(HelloWorldError::InvalidInstruction as u32)
+        ))
+    );
+}
@@ -0,0 +1,111 @@
+use helloworld::{
+    instruction::write_metadata,
+    process_instruction,
+    state::{find_metadata_address, AccountState, Metadata},
+};
+use solana_program_test::*;
+use solana_sdk::{
+    account::Account,
+    instruction::Instruction,
+    pubkey::Pubkey,
+    rent::Rent,
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+};
+
+async fn process(
+    context: &mut ProgramTestContext,
+    instruction: Instruction,
+    signers: &[&Keypair],
+) -> Result<(), TransactionError> {
+    let recent_blockhash = context
+        .banks_client
+        .get_new_latest_blockhash(&context.last_blockhash)
+        .await
+        .unwrap();
+    context.last_blockhash = recent_blockhash;
+
+    let mut transaction =
+        Transaction::new_with_payer(&[instruction], Some(&context.payer.pubkey()));
+    let mut all_signers = vec![&context.payer];
+    all_signers.extend_from_slice(signers);
+    transaction.sign(&all_signers, recent_blockhash);
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .map_err(|err| err.unwrap())
+}
+
+async fn metadata(context: &mut ProgramTestContext, program_id: &Pubkey) -> Metadata {
+    let account = context
+        .banks_client
+        .get_account(find_metadata_address(program_id).0)
+        .await
+        .unwrap()
+        .unwrap();
+    Metadata::load(&account.data).unwrap()
+}
+
+#[tokio::test]
+async fn test_write_metadata() {
+    let program_id = helloworld::id();
+    let (metadata_address, bump_seed) = find_metadata_address(&program_id);
+
+    let mut program_test =
+        ProgramTest::new("helloworld", program_id, processor!(process_instruction));
+    // Native CPI cannot allocate the metadata account, so start from one
+    // written by an older build
+    let mut metadata_data = vec![0; Metadata::LEN];
+    Metadata {
+        is_initialized: true,
+        name: "helloworld".to_string(),
+        version: "0.0.0".to_string(),
+        commit: String::new(),
+        bump_seed,
+    }
+    .store(&mut metadata_data)
+    .unwrap();
+    program_test.add_account(
+        metadata_address,
+        Account {
+            lamports: Rent::default().minimum_balance(Metadata::LEN),
+            data: metadata_data,
+            owner: program_id,
+            ..Account::default()
+        },
+    );
+    let mut context = program_test.start_with_context().await;
+
+    // Anyone can refresh the metadata
+    let writer = Keypair::new();
+    process(
+        &mut context,
+        write_metadata(&program_id, &writer.pubkey()),
+        &[&writer],
+    )
+    .await
+    .unwrap();
+    let metadata = metadata(&mut context, &program_id).await;
+    assert_eq!(metadata, Metadata::current(bump_seed));
+    assert_eq!(metadata.name, env!("CARGO_PKG_NAME"));
+    assert_eq!(metadata.version, env!("CARGO_PKG_VERSION"));
+}
+
+// Native CPI cannot create accounts, so only run against the BPF build
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn test_create_metadata() {
+    let program_id = helloworld::id();
+    let program_test = ProgramTest::new("helloworld", program_id, processor!(process_instruction));
+    let mut context = program_test.start_with_context().await;
+    let payer = context.payer.pubkey();
+
+    process(&mut context, write_metadata(&program_id, &payer), &[])
+        .await
+        .unwrap();
+    assert_eq!(
+        metadata(&mut context, &program_id).await,
+        Metadata::current(find_metadata_address(&program_id).1)
+    );
+}
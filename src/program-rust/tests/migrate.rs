@@ -0,0 +1,211 @@
+use helloworld::{
+    instruction::{migrate, say_hello},
+    process_instruction,
+    state::{AccountState, GreetingAccountV1, GreetingAccountV2, GreetingAccountV3},
+    GreetingAccount,
+};
+use solana_program_test::*;
+use solana_sdk::{
+    account::Account,
+    instruction::Instruction,
+    pubkey::Pubkey,
+    rent::Rent,
+    signature::Signer,
+    transaction::{Transaction, TransactionError},
+};
+
+async fn process(
+    context: &mut ProgramTestContext,
+    instruction: Instruction,
+) -> Result<(), TransactionError> {
+    let recent_blockhash = context
+        .banks_client
+        .get_new_latest_blockhash(&context.last_blockhash)
+        .await
+        .unwrap();
+    context.last_blockhash = recent_blockhash;
+
+    let mut transaction =
+        Transaction::new_with_payer(&[instruction], Some(&context.payer.pubkey()));
+    transaction.sign(&[&context.payer], recent_blockhash);
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .map_err(|err| err.unwrap())
+}
+
+fn v1_account(program_id: &Pubkey) -> (GreetingAccountV1, Account) {
+    let greeting_account_v1 = GreetingAccountV1 {
+        counter: 7,
+        expires_at: 100,
+        last_greeter: Pubkey::new_unique(),
+        last_slot: 42,
+        ..GreetingAccountV1::default()
+    };
+    let mut data = vec![0; GreetingAccountV1::LEN];
+    greeting_account_v1.store(&mut data).unwrap();
+    let account = Account {
+        lamports: Rent::default().minimum_balance(GreetingAccountV1::LEN),
+        data,
+        owner: *program_id,
+        ..Account::default()
+    };
+    (greeting_account_v1, account)
+}
+
+fn v2_account(program_id: &Pubkey) -> (GreetingAccountV2, Account) {
+    let greeting_account_v2 = GreetingAccountV2 {
+        version: 2,
+        counter: 3,
+        ..GreetingAccountV2::default()
+    };
+    let mut data = vec![0; GreetingAccountV2::LEN];
+    greeting_account_v2.store(&mut data).unwrap();
+    let account = Account {
+        lamports: Rent::default().minimum_balance(GreetingAccountV2::LEN),
+        data,
+        owner: *program_id,
+        ..Account::default()
+    };
+    (greeting_account_v2, account)
+}
+
+fn v3_account(program_id: &Pubkey) -> (GreetingAccountV3, Account) {
+    let greeting_account_v3 = GreetingAccountV3 {
+        version: 3,
+        counter: 5,
+        bump_seed: 255,
+        ..GreetingAccountV3::default()
+    };
+    let mut data = vec![0; GreetingAccountV3::LEN];
+    greeting_account_v3.store(&mut data).unwrap();
+    let account = Account {
+        lamports: Rent::default().minimum_balance(GreetingAccountV3::LEN),
+        data,
+        owner: *program_id,
+        ..Account::default()
+    };
+    (greeting_account_v3, account)
+}
+
+#[tokio::test]
+async fn test_migrate_current_version() {
+    let program_id = helloworld::id();
+    let greeted_pubkey = Pubkey::new_unique();
+    let v1_pubkey = Pubkey::new_unique();
+    let v2_pubkey = Pubkey::new_unique();
+    let v3_pubkey = Pubkey::new_unique();
+    let unknown_pubkey = Pubkey::new_unique();
+
+    let mut program_test =
+        ProgramTest::new("helloworld", program_id, processor!(process_instruction));
+    program_test.add_account(
+        greeted_pubkey,
+        Account {
+            lamports: Rent::default().minimum_balance(GreetingAccount::LEN),
+            data: vec![0_u8; GreetingAccount::LEN],
+            owner: program_id,
+            ..Account::default()
+        },
+    );
+    program_test.add_account(v1_pubkey, v1_account(&program_id).1);
+    program_test.add_account(v2_pubkey, v2_account(&program_id).1);
+    program_test.add_account(v3_pubkey, v3_account(&program_id).1);
+    program_test.add_account(
+        unknown_pubkey,
+        Account {
+            lamports: 5,
+            data: vec![0_u8; GreetingAccount::LEN + 1],
+            owner: program_id,
+            ..Account::default()
+        },
+    );
+    let mut context = program_test.start_with_context().await;
+    let payer = context.payer.pubkey();
+
+    let ix = say_hello(&program_id, &greeted_pubkey, &payer, vec![]);
+    process(&mut context, ix).await.unwrap();
+    let before = context
+        .banks_client
+        .get_account(greeted_pubkey)
+        .await
+        .unwrap()
+        .unwrap();
+
+    // Migrating an already migrated account changes nothing
+    let ix = migrate(&program_id, &greeted_pubkey, &payer);
+    process(&mut context, ix).await.unwrap();
+    let after = context
+        .banks_client
+        .get_account(greeted_pubkey)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(before, after);
+    let greeting_account = GreetingAccount::load(&after.data).unwrap();
+    assert_eq!(greeting_account.version, GreetingAccount::VERSION);
+    assert_eq!(greeting_account.counter, 1);
+
+    // Old accounts must be migrated before they can be greeted
+    for pubkey in [v1_pubkey, v2_pubkey, v3_pubkey] {
+        let ix = say_hello(&program_id, &pubkey, &payer, vec![]);
+        assert!(process(&mut context, ix).await.is_err());
+    }
+
+    // Unknown layouts cannot be migrated
+    let ix = migrate(&program_id, &unknown_pubkey, &payer);
+    assert!(process(&mut context, ix).await.is_err());
+}
+
+// Native program-test cannot grow accounts, so only run against the BPF build
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn test_migrate_old_versions() {
+    let program_id = helloworld::id();
+    let v1_pubkey = Pubkey::new_unique();
+    let v2_pubkey = Pubkey::new_unique();
+    let v3_pubkey = Pubkey::new_unique();
+
+    let mut program_test =
+        ProgramTest::new("helloworld", program_id, processor!(process_instruction));
+    let (greeting_account_v1, account) = v1_account(&program_id);
+    program_test.add_account(v1_pubkey, account);
+    let (greeting_account_v2, account) = v2_account(&program_id);
+    program_test.add_account(v2_pubkey, account);
+    let (greeting_account_v3, account) = v3_account(&program_id);
+    program_test.add_account(v3_pubkey, account);
+    let mut context = program_test.start_with_context().await;
+    let payer = context.payer.pubkey();
+
+    for (pubkey, migrated) in [
+        (v1_pubkey, GreetingAccount::from(greeting_account_v1)),
+        (v2_pubkey, GreetingAccount::from(greeting_account_v2)),
+        (v3_pubkey, GreetingAccount::from(greeting_account_v3)),
+    ] {
+        let ix = migrate(&program_id, &pubkey, &payer);
+        process(&mut context, ix).await.unwrap();
+        let account = context
+            .banks_client
+            .get_account(pubkey)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(account.data.len(), GreetingAccount::LEN);
+        assert!(Rent::default().is_exempt(account.lamports, GreetingAccount::LEN));
+        assert_eq!(GreetingAccount::load(&account.data).unwrap(), migrated);
+    }
+
+    // Migrating again is a no-op, and the account can be greeted
+    let ix = migrate(&program_id, &v1_pubkey, &payer);
+    process(&mut context, ix).await.unwrap();
+    let ix = say_hello(&program_id, &v1_pubkey, &payer, vec![]);
+    process(&mut context, ix).await.unwrap();
+    let account = context
+        .banks_client
+        .get_account(v1_pubkey)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(GreetingAccount::load(&account.data).unwrap().counter, 8);
+}
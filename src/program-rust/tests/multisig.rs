@@ -0,0 +1,320 @@
+use helloworld::{
+    error::HelloWorldError,
+    instruction::{approve, execute, update_config, ConfigUpdate, MultisigAction},
+    process_instruction,
+    state::{
+        find_config_address, find_multisig_address, find_proposal_address, find_treasury_address,
+        AccountState, Config, Multisig, Proposal, Treasury,
+    },
+};
+use solana_program_test::*;
+use solana_sdk::{
+    account::Account,
+    instruction::{Instruction, InstructionError},
+    pubkey::Pubkey,
+    rent::Rent,
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+};
+
+async fn process(
+    context: &mut ProgramTestContext,
+    instruction: Instruction,
+    signers: &[&Keypair],
+) -> Result<(), TransactionError> {
+    let recent_blockhash = context
+        .banks_client
+        .get_new_latest_blockhash(&context.last_blockhash)
+        .await
+        .unwrap();
+    context.last_blockhash = recent_blockhash;
+
+    let mut transaction =
+        Transaction::new_with_payer(&[instruction], Some(&context.payer.pubkey()));
+    let mut all_signers = vec![&context.payer];
+    all_signers.extend_from_slice(signers);
+    transaction.sign(&all_signers, recent_blockhash);
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .map_err(|err| err.unwrap())
+}
+
+async fn state<T: AccountState>(context: &mut ProgramTestContext, address: Pubkey) -> T {
+    let account = context
+        .banks_client
+        .get_account(address)
+        .await
+        .unwrap()
+        .unwrap();
+    T::load(&account.data).unwrap()
+}
+
+fn add_state_account(
+    program_test: &mut ProgramTest,
+    program_id: &Pubkey,
+    pubkey: Pubkey,
+    state: &impl AccountState,
+    len: usize,
+) {
+    let mut data = vec![0; len];
+    state.store(&mut data).unwrap();
+    program_test.add_account(
+        pubkey,
+        Account {
+            lamports: Rent::default().minimum_balance(len),
+            data,
+            owner: *program_id,
+            ..Account::default()
+        },
+    );
+}
+
+fn add_config(program_test: &mut ProgramTest, program_id: &Pubkey, admin: &Pubkey) {
+    let (config_address, bump_seed) = find_config_address(program_id);
+    let config = Config {
+        is_initialized: true,
+        admin: *admin,
+        bump_seed,
+        ..Config::default()
+    };
+    add_state_account(
+        program_test,
+        program_id,
+        config_address,
+        &config,
+        Config::LEN,
+    );
+}
+
+fn add_proposal(
+    program_test: &mut ProgramTest,
+    program_id: &Pubkey,
+    index: u64,
+    action: MultisigAction,
+    approvals: Vec<Pubkey>,
+) {
+    let (proposal_address, bump_seed) = find_proposal_address(program_id, index);
+    let proposal = Proposal {
+        is_initialized: true,
+        index,
+        action,
+        approvals,
+        executed: false,
+        bump_seed,
+    };
+    add_state_account(
+        program_test,
+        program_id,
+        proposal_address,
+        &proposal,
+        Proposal::LEN,
+    );
+}
+
+#[tokio::test]
+async fn test_multisig() {
+    let program_id = helloworld::id();
+    let signers = [Keypair::new(), Keypair::new(), Keypair::new()];
+    let outsider = Keypair::new();
+    let destination = Pubkey::new_unique();
+    let (multisig_address, bump_seed) = find_multisig_address(&program_id);
+
+    let mut program_test =
+        ProgramTest::new("helloworld", program_id, processor!(process_instruction));
+    add_config(&mut program_test, &program_id, &multisig_address);
+    // Native CPI cannot allocate the multisig and its proposals, so start
+    // from initialized ones
+    add_state_account(
+        &mut program_test,
+        &program_id,
+        multisig_address,
+        &Multisig {
+            is_initialized: true,
+            threshold: 2,
+            signers: signers.iter().map(|signer| signer.pubkey()).collect(),
+            proposal_count: 2,
+            bump_seed,
+        },
+        Multisig::LEN,
+    );
+    let pause = MultisigAction::UpdateConfig(ConfigUpdate {
+        paused: Some(true),
+        ..ConfigUpdate::default()
+    });
+    add_proposal(
+        &mut program_test,
+        &program_id,
+        0,
+        pause.clone(),
+        vec![signers[0].pubkey()],
+    );
+    let withdrawal = MultisigAction::Withdraw {
+        destination,
+        amount: 1000,
+    };
+    add_proposal(
+        &mut program_test,
+        &program_id,
+        1,
+        withdrawal.clone(),
+        vec![signers[0].pubkey(), signers[2].pubkey()],
+    );
+    let (treasury_address, bump_seed) = find_treasury_address(&program_id);
+    let mut treasury_data = vec![0; Treasury::LEN];
+    Treasury {
+        is_initialized: true,
+        bump_seed,
+        ..Treasury::default()
+    }
+    .store(&mut treasury_data)
+    .unwrap();
+    program_test.add_account(
+        treasury_address,
+        Account {
+            lamports: Rent::default().minimum_balance(Treasury::LEN) + 1000,
+            data: treasury_data,
+            owner: program_id,
+            ..Account::default()
+        },
+    );
+    program_test.add_account(
+        destination,
+        Account {
+            lamports: 1_000_000_000,
+            ..Account::default()
+        },
+    );
+    let mut context = program_test.start_with_context().await;
+
+    // Signers cannot act as the admin on their own
+    let ix = update_config(
+        &program_id,
+        &signers[0].pubkey(),
+        ConfigUpdate {
+            paused: Some(true),
+            ..ConfigUpdate::default()
+        },
+    );
+    assert_eq!(
+        process(&mut context, ix, &[&signers[0]]).await,
+        Err(TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(HelloWorldError::Unauthorized as u32)
+        ))
+    );
+
+    let ix = execute(&program_id, 0, &pause);
+    assert_eq!(
+        process(&mut context, ix, &[]).await,
+        Err(TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(HelloWorldError::InsufficientApprovals as u32)
+        ))
+    );
+
+    let ix = approve(&program_id, &outsider.pubkey(), 0);
+    assert_eq!(
+        process(&mut context, ix, &[&outsider]).await,
+        Err(TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(HelloWorldError::Unauthorized as u32)
+        ))
+    );
+    let ix = approve(&program_id, &signers[0].pubkey(), 0);
+    assert_eq!(
+        process(&mut context, ix, &[&signers[0]]).await,
+        Err(TransactionError::InstructionError(
+            0,
+            InstructionError::InvalidArgument
+        ))
+    );
+    let ix = approve(&program_id, &signers[1].pubkey(), 0);
+    process(&mut context, ix, &[&signers[1]]).await.unwrap();
+
+    let ix = execute(&program_id, 0, &pause);
+    process(&mut context, ix.clone(), &[]).await.unwrap();
+    let config: Config = state(&mut context, find_config_address(&program_id).0).await;
+    assert!(config.paused);
+    // A proposal only executes once
+    assert_eq!(
+        process(&mut context, ix, &[]).await,
+        Err(TransactionError::InstructionError(
+            0,
+            InstructionError::InvalidArgument
+        ))
+    );
+
+    let ix = execute(&program_id, 1, &withdrawal);
+    process(&mut context, ix, &[]).await.unwrap();
+    let treasury: Treasury = state(&mut context, treasury_address).await;
+    assert_eq!(treasury.total_withdrawn, 1000);
+    let destination = context
+        .banks_client
+        .get_account(destination)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(destination.lamports, 1_000_001_000);
+}
+
+// Native CPI cannot create accounts, so only run against the BPF build
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn test_create_multisig() {
+    use helloworld::instruction::{create_multisig, propose};
+
+    let program_id = helloworld::id();
+    let admin = Keypair::new();
+    let signers = [Keypair::new(), Keypair::new()];
+    let (multisig_address, bump_seed) = find_multisig_address(&program_id);
+
+    let mut program_test =
+        ProgramTest::new("helloworld", program_id, processor!(process_instruction));
+    add_config(&mut program_test, &program_id, &admin.pubkey());
+    for payer in [admin.pubkey(), signers[0].pubkey()] {
+        program_test.add_account(
+            payer,
+            Account {
+                lamports: 1_000_000_000,
+                ..Account::default()
+            },
+        );
+    }
+    let mut context = program_test.start_with_context().await;
+
+    let signer_pubkeys: Vec<_> = signers.iter().map(|signer| signer.pubkey()).collect();
+    let ix = create_multisig(&program_id, &admin.pubkey(), signer_pubkeys.clone(), 3);
+    assert_eq!(
+        process(&mut context, ix, &[&admin]).await,
+        Err(TransactionError::InstructionError(
+            0,
+            InstructionError::InvalidArgument
+        ))
+    );
+    let ix = create_multisig(&program_id, &admin.pubkey(), signer_pubkeys.clone(), 2);
+    process(&mut context, ix, &[&admin]).await.unwrap();
+    let config: Config = state(&mut context, find_config_address(&program_id).0).await;
+    assert_eq!(config.admin, multisig_address);
+
+    let action = MultisigAction::UpdateConfig(ConfigUpdate {
+        paused: Some(true),
+        ..ConfigUpdate::default()
+    });
+    let ix = propose(&program_id, &signers[0].pubkey(), 0, action.clone());
+    process(&mut context, ix, &[&signers[0]]).await.unwrap();
+    assert_eq!(
+        state::<Multisig>(&mut context, multisig_address).await,
+        Multisig {
+            is_initialized: true,
+            threshold: 2,
+            signers: signer_pubkeys,
+            proposal_count: 1,
+            bump_seed,
+        }
+    );
+    let proposal: Proposal = state(&mut context, find_proposal_address(&program_id, 0).0).await;
+    assert_eq!(proposal.action, action);
+    assert_eq!(proposal.approvals, vec![signers[0].pubkey()]);
+}
@@ -0,0 +1,133 @@
+use helloworld::{
+    error::HelloWorldError,
+    fees::FeePolicy,
+    instruction::{initiate_recovery, register_greeter, set_fee_policy},
+    process_instruction,
+    state::{find_config_address, AccountState, Config, GuardianSet},
+};
+use solana_program_test::*;
+use solana_sdk::{
+    account::Account,
+    instruction::{Instruction, InstructionError},
+    pubkey::Pubkey,
+    rent::Rent,
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+};
+
+async fn process(
+    context: &mut ProgramTestContext,
+    instruction: Instruction,
+    signers: &[&Keypair],
+) -> Result<(), TransactionError> {
+    let recent_blockhash = context
+        .banks_client
+        .get_new_latest_blockhash(&context.last_blockhash)
+        .await
+        .unwrap();
+    context.last_blockhash = recent_blockhash;
+
+    let mut transaction =
+        Transaction::new_with_payer(&[instruction], Some(&context.payer.pubkey()));
+    let mut all_signers = vec![&context.payer];
+    all_signers.extend_from_slice(signers);
+    transaction.sign(&all_signers, recent_blockhash);
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .map_err(|err| err.unwrap())
+}
+
+/// Add an account holding `state` but owned by another program
+fn add_spoofed_account(
+    program_test: &mut ProgramTest,
+    pubkey: Pubkey,
+    state: &impl AccountState,
+    len: usize,
+) {
+    let mut data = vec![0; len];
+    state.store(&mut data).unwrap();
+    program_test.add_account(
+        pubkey,
+        Account {
+            lamports: Rent::default().minimum_balance(len),
+            data,
+            owner: Pubkey::new_unique(),
+            ..Account::default()
+        },
+    );
+}
+
+#[tokio::test]
+async fn test_spoofed_owners() {
+    let program_id = helloworld::id();
+    let attacker = Keypair::new();
+    let guardian_set_pubkey = Pubkey::new_unique();
+
+    let mut program_test =
+        ProgramTest::new("helloworld", program_id, processor!(process_instruction));
+    add_spoofed_account(
+        &mut program_test,
+        find_config_address(&program_id).0,
+        &Config {
+            is_initialized: true,
+            admin: attacker.pubkey(),
+            bump_seed: find_config_address(&program_id).1,
+            ..Config::default()
+        },
+        Config::LEN,
+    );
+    add_spoofed_account(
+        &mut program_test,
+        guardian_set_pubkey,
+        &GuardianSet {
+            is_initialized: true,
+            threshold: 1,
+            guardians: vec![attacker.pubkey()],
+            ..GuardianSet::default()
+        },
+        GuardianSet::LEN,
+    );
+    let mut context = program_test.start_with_context().await;
+
+    // A config the program does not own is as good as no config
+    let ix = set_fee_policy(
+        &program_id,
+        &attacker.pubkey(),
+        FeePolicy::Flat { lamports: 1 },
+    );
+    assert_eq!(
+        process(&mut context, ix, &[&attacker]).await,
+        Err(TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(HelloWorldError::AccountNotInitialized as u32)
+        ))
+    );
+
+    let ix = initiate_recovery(
+        &program_id,
+        &guardian_set_pubkey,
+        &[attacker.pubkey()],
+        &attacker.pubkey(),
+    );
+    assert_eq!(
+        process(&mut context, ix, &[&attacker]).await,
+        Err(TransactionError::InstructionError(
+            0,
+            InstructionError::IncorrectProgramId
+        ))
+    );
+
+    // Programs invoked by the program are checked as well
+    let payer = context.payer.pubkey();
+    let mut ix = register_greeter(&program_id, &payer);
+    ix.accounts[2].pubkey = Pubkey::new_unique();
+    assert_eq!(
+        process(&mut context, ix, &[]).await,
+        Err(TransactionError::InstructionError(
+            0,
+            InstructionError::IncorrectProgramId
+        ))
+    );
+}
@@ -0,0 +1,193 @@
+use helloworld::{
+    instruction::{batch_greet, say_hello, set_rate_limit},
+    process_instruction,
+    state::{find_config_address, find_greeter_address, AccountState, Config, GreeterAccount},
+    GreetingAccount,
+};
+use solana_program_test::*;
+use solana_sdk::{
+    account::Account,
+    clock::Clock,
+    instruction::Instruction,
+    pubkey::Pubkey,
+    rent::Rent,
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+};
+
+const RATE_LIMIT_SLOTS: u64 = 5;
+
+fn program_account<T: AccountState>(program_id: Pubkey, state: &T, len: usize) -> Account {
+    let mut data = vec![0; len];
+    state.store(&mut data).unwrap();
+    Account {
+        lamports: 5,
+        data,
+        owner: program_id,
+        ..Account::default()
+    }
+}
+
+async fn process(
+    context: &mut ProgramTestContext,
+    instruction: Instruction,
+    signers: &[&Keypair],
+) -> Result<(), TransactionError> {
+    let recent_blockhash = context
+        .banks_client
+        .get_new_latest_blockhash(&context.last_blockhash)
+        .await
+        .unwrap();
+    context.last_blockhash = recent_blockhash;
+
+    let mut transaction =
+        Transaction::new_with_payer(&[instruction], Some(&context.payer.pubkey()));
+    let mut all_signers = vec![&context.payer];
+    all_signers.extend_from_slice(signers);
+    transaction.sign(&all_signers, recent_blockhash);
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .map_err(|err| err.unwrap())
+}
+
+async fn greeter_state(
+    context: &mut ProgramTestContext,
+    program_id: &Pubkey,
+    greeter: &Pubkey,
+) -> GreeterAccount {
+    let account = context
+        .banks_client
+        .get_account(find_greeter_address(program_id, greeter).0)
+        .await
+        .expect("get_account")
+        .expect("greeter account not found");
+    GreeterAccount::load(&account.data).unwrap()
+}
+
+#[tokio::test]
+async fn test_rate_limit() {
+    let program_id = helloworld::id();
+    let greeted_pubkey = Pubkey::new_unique();
+    let admin = Keypair::new();
+    let greeter = Keypair::new();
+    let unregistered_greeter = Keypair::new();
+
+    let mut program_test =
+        ProgramTest::new("helloworld", program_id, processor!(process_instruction));
+    program_test.add_account(
+        greeted_pubkey,
+        Account {
+            lamports: Rent::default().minimum_balance(GreetingAccount::LEN),
+            data: vec![0_u8; GreetingAccount::LEN],
+            owner: program_id,
+            ..Account::default()
+        },
+    );
+    // Native CPI cannot allocate accounts, so start from an initialized config
+    // and a registered greeter
+    program_test.add_account(
+        find_config_address(&program_id).0,
+        program_account(
+            program_id,
+            &Config {
+                is_initialized: true,
+                admin: admin.pubkey(),
+                bump_seed: find_config_address(&program_id).1,
+                ..Config::default()
+            },
+            Config::LEN,
+        ),
+    );
+    let (greeter_address, bump_seed) = find_greeter_address(&program_id, &greeter.pubkey());
+    program_test.add_account(
+        greeter_address,
+        program_account(
+            program_id,
+            &GreeterAccount {
+                is_initialized: true,
+                bump_seed,
+                ..GreeterAccount::default()
+            },
+            GreeterAccount::LEN,
+        ),
+    );
+    let mut context = program_test.start_with_context().await;
+
+    // Only the admin can rate limit greeters
+    let ix = set_rate_limit(&program_id, &greeter.pubkey(), RATE_LIMIT_SLOTS);
+    assert!(process(&mut context, ix, &[&greeter]).await.is_err());
+    let ix = set_rate_limit(&program_id, &admin.pubkey(), RATE_LIMIT_SLOTS);
+    process(&mut context, ix, &[&admin]).await.unwrap();
+
+    // Unregistered greeters cannot greet while rate limited
+    let ix = say_hello(
+        &program_id,
+        &greeted_pubkey,
+        &unregistered_greeter.pubkey(),
+        vec![],
+    );
+    assert!(process(&mut context, ix, &[&unregistered_greeter])
+        .await
+        .is_err());
+
+    // The first greeting goes through
+    let ix = say_hello(&program_id, &greeted_pubkey, &greeter.pubkey(), vec![]);
+    process(&mut context, ix, &[&greeter]).await.unwrap();
+    let state = greeter_state(&mut context, &program_id, &greeter.pubkey()).await;
+    assert_eq!(state.greetings, 1);
+
+    // Greeting again within the window fails, whichever instruction is used
+    let ix = say_hello(&program_id, &greeted_pubkey, &greeter.pubkey(), vec![]);
+    assert!(process(&mut context, ix, &[&greeter]).await.is_err());
+    let ix = batch_greet(&program_id, &[greeted_pubkey], &greeter.pubkey(), vec![]);
+    assert!(process(&mut context, ix, &[&greeter]).await.is_err());
+
+    // Once the window has passed the greeter can greet again
+    context
+        .warp_to_slot(state.last_greeting_slot + RATE_LIMIT_SLOTS)
+        .unwrap();
+    let ix = batch_greet(&program_id, &[greeted_pubkey], &greeter.pubkey(), vec![]);
+    process(&mut context, ix, &[&greeter]).await.unwrap();
+    let state = greeter_state(&mut context, &program_id, &greeter.pubkey()).await;
+    assert_eq!(state.greetings, 2);
+    let clock: Clock = context.banks_client.get_sysvar().await.unwrap();
+    assert_eq!(state.last_greeting_slot, clock.slot);
+
+    // Lifting the rate limit allows back to back greetings from anyone
+    let ix = set_rate_limit(&program_id, &admin.pubkey(), 0);
+    process(&mut context, ix, &[&admin]).await.unwrap();
+    let ix = say_hello(&program_id, &greeted_pubkey, &greeter.pubkey(), vec![]);
+    process(&mut context, ix, &[&greeter]).await.unwrap();
+    let ix = say_hello(
+        &program_id,
+        &greeted_pubkey,
+        &unregistered_greeter.pubkey(),
+        vec![],
+    );
+    process(&mut context, ix, &[&unregistered_greeter])
+        .await
+        .unwrap();
+}
+
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn test_register_greeter() {
+    use helloworld::instruction::register_greeter;
+
+    let program_id = helloworld::id();
+    let program_test = ProgramTest::new("helloworld", program_id, None);
+    let mut context = program_test.start_with_context().await;
+    let greeter = context.payer.pubkey();
+
+    let ix = register_greeter(&program_id, &greeter);
+    process(&mut context, ix, &[]).await.unwrap();
+    let state = greeter_state(&mut context, &program_id, &greeter).await;
+    assert!(state.is_initialized);
+    assert_eq!(state.greetings, 0);
+
+    // Greeters can only register once
+    let ix = register_greeter(&program_id, &greeter);
+    assert!(process(&mut context, ix, &[]).await.is_err());
+}
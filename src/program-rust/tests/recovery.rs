@@ -0,0 +1,211 @@
+use helloworld::{
+    instruction::{cancel_recovery, initiate_recovery, recover_ownership, register_guardians},
+    process_instruction,
+    state::{AccountState, GuardianSet},
+    GreetingAccount,
+};
+use solana_program_test::*;
+use solana_sdk::{
+    account::Account,
+    clock::Clock,
+    instruction::Instruction,
+    pubkey::Pubkey,
+    rent::Rent,
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+};
+
+const RECOVERY_DELAY: u64 = 10;
+
+struct Setup {
+    context: ProgramTestContext,
+    program_id: Pubkey,
+    guardian_set_pubkey: Pubkey,
+    owner: Keypair,
+    guardians: Vec<Keypair>,
+}
+
+async fn setup() -> Setup {
+    let program_id = helloworld::id();
+    let greeting_pubkey = Pubkey::new_unique();
+    let guardian_set_pubkey = Pubkey::new_unique();
+
+    let mut program_test =
+        ProgramTest::new("helloworld", program_id, processor!(process_instruction));
+    program_test.add_account(
+        greeting_pubkey,
+        Account {
+            lamports: Rent::default().minimum_balance(GreetingAccount::LEN),
+            data: vec![0_u8; GreetingAccount::LEN],
+            owner: program_id,
+            ..Account::default()
+        },
+    );
+    program_test.add_account(
+        guardian_set_pubkey,
+        Account {
+            lamports: 5,
+            data: vec![0_u8; GuardianSet::LEN],
+            owner: program_id,
+            ..Account::default()
+        },
+    );
+    let mut context = program_test.start_with_context().await;
+
+    let owner = Keypair::new();
+    let guardians: Vec<Keypair> = (0..3).map(|_| Keypair::new()).collect();
+    let ix = register_guardians(
+        &program_id,
+        &guardian_set_pubkey,
+        &greeting_pubkey,
+        &owner.pubkey(),
+        guardians.iter().map(|guardian| guardian.pubkey()).collect(),
+        2,
+        RECOVERY_DELAY,
+    );
+    process(&mut context, ix, &[&owner]).await.unwrap();
+
+    Setup {
+        context,
+        program_id,
+        guardian_set_pubkey,
+        owner,
+        guardians,
+    }
+}
+
+async fn process(
+    context: &mut ProgramTestContext,
+    instruction: Instruction,
+    signers: &[&Keypair],
+) -> Result<(), TransactionError> {
+    let recent_blockhash = context
+        .banks_client
+        .get_new_latest_blockhash(&context.last_blockhash)
+        .await
+        .unwrap();
+    context.last_blockhash = recent_blockhash;
+
+    let mut transaction =
+        Transaction::new_with_payer(&[instruction], Some(&context.payer.pubkey()));
+    let mut all_signers = vec![&context.payer];
+    all_signers.extend_from_slice(signers);
+    transaction.sign(&all_signers, recent_blockhash);
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .map_err(|err| err.unwrap())
+}
+
+async fn get_guardian_set(context: &mut ProgramTestContext, pubkey: Pubkey) -> GuardianSet {
+    let account = context
+        .banks_client
+        .get_account(pubkey)
+        .await
+        .expect("get_account")
+        .expect("guardian set account not found");
+    GuardianSet::load(&account.data).unwrap()
+}
+
+async fn warp_past_recovery_delay(context: &mut ProgramTestContext) {
+    let clock: Clock = context.banks_client.get_sysvar().await.unwrap();
+    context
+        .warp_to_slot(clock.slot + RECOVERY_DELAY + 1)
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_recover_ownership() {
+    let Setup {
+        mut context,
+        program_id,
+        guardian_set_pubkey,
+        owner,
+        guardians,
+    } = setup().await;
+    let new_owner = Pubkey::new_unique();
+
+    let guardian_set = get_guardian_set(&mut context, guardian_set_pubkey).await;
+    assert_eq!(guardian_set.owner, owner.pubkey());
+    assert_eq!(guardian_set.guardians.len(), 3);
+
+    // A single guardian is not enough to start a recovery
+    let ix = initiate_recovery(
+        &program_id,
+        &guardian_set_pubkey,
+        &[guardians[0].pubkey()],
+        &new_owner,
+    );
+    assert!(process(&mut context, ix, &[&guardians[0]]).await.is_err());
+
+    // Neither is the same guardian passed twice
+    let ix = initiate_recovery(
+        &program_id,
+        &guardian_set_pubkey,
+        &[guardians[0].pubkey(), guardians[0].pubkey()],
+        &new_owner,
+    );
+    assert!(process(&mut context, ix, &[&guardians[0]]).await.is_err());
+
+    // Two guardians start the recovery
+    let ix = initiate_recovery(
+        &program_id,
+        &guardian_set_pubkey,
+        &[guardians[0].pubkey(), guardians[2].pubkey()],
+        &new_owner,
+    );
+    process(&mut context, ix, &[&guardians[0], &guardians[2]])
+        .await
+        .unwrap();
+
+    // The recovery cannot complete during the delay
+    let ix = recover_ownership(&program_id, &guardian_set_pubkey);
+    assert!(process(&mut context, ix, &[]).await.is_err());
+
+    warp_past_recovery_delay(&mut context).await;
+    let ix = recover_ownership(&program_id, &guardian_set_pubkey);
+    process(&mut context, ix, &[]).await.unwrap();
+
+    let guardian_set = get_guardian_set(&mut context, guardian_set_pubkey).await;
+    assert_eq!(guardian_set.owner, new_owner);
+    assert_eq!(guardian_set.pending_recovery, None);
+}
+
+#[tokio::test]
+async fn test_cancel_recovery() {
+    let Setup {
+        mut context,
+        program_id,
+        guardian_set_pubkey,
+        owner,
+        guardians,
+    } = setup().await;
+    let new_owner = Pubkey::new_unique();
+
+    let ix = initiate_recovery(
+        &program_id,
+        &guardian_set_pubkey,
+        &[guardians[0].pubkey(), guardians[1].pubkey()],
+        &new_owner,
+    );
+    process(&mut context, ix, &[&guardians[0], &guardians[1]])
+        .await
+        .unwrap();
+
+    // Guardians cannot cancel on the owner's behalf
+    let ix = cancel_recovery(&program_id, &guardian_set_pubkey, &guardians[0].pubkey());
+    assert!(process(&mut context, ix, &[&guardians[0]]).await.is_err());
+
+    // The original owner cancels during the delay
+    let ix = cancel_recovery(&program_id, &guardian_set_pubkey, &owner.pubkey());
+    process(&mut context, ix, &[&owner]).await.unwrap();
+
+    warp_past_recovery_delay(&mut context).await;
+    let ix = recover_ownership(&program_id, &guardian_set_pubkey);
+    assert!(process(&mut context, ix, &[]).await.is_err());
+
+    let guardian_set = get_guardian_set(&mut context, guardian_set_pubkey).await;
+    assert_eq!(guardian_set.owner, owner.pubkey());
+    assert_eq!(guardian_set.pending_recovery, None);
+}
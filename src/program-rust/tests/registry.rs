@@ -0,0 +1,155 @@
+use helloworld::{
+    instruction::{initialize_registry, say_hello},
+    process_instruction,
+    state::{find_registry_address, AccountState, Registry},
+    GreetingAccount,
+};
+use solana_program_test::*;
+use solana_sdk::{
+    account::Account,
+    instruction::{Instruction, InstructionError},
+    pubkey::Pubkey,
+    rent::Rent,
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+};
+
+async fn process(
+    context: &mut ProgramTestContext,
+    instruction: Instruction,
+    signers: &[&Keypair],
+) -> Result<(), TransactionError> {
+    let recent_blockhash = context
+        .banks_client
+        .get_new_latest_blockhash(&context.last_blockhash)
+        .await
+        .unwrap();
+    context.last_blockhash = recent_blockhash;
+
+    let mut transaction =
+        Transaction::new_with_payer(&[instruction], Some(&context.payer.pubkey()));
+    let mut all_signers = vec![&context.payer];
+    all_signers.extend_from_slice(signers);
+    transaction.sign(&all_signers, recent_blockhash);
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .map_err(|err| err.unwrap())
+}
+
+async fn registry(context: &mut ProgramTestContext, program_id: &Pubkey) -> Registry {
+    let account = context
+        .banks_client
+        .get_account(find_registry_address(program_id).0)
+        .await
+        .unwrap()
+        .unwrap();
+    Registry::load(&account.data).unwrap()
+}
+
+#[tokio::test]
+async fn test_registry_existing_accounts() {
+    let program_id = helloworld::id();
+    let greeted_pubkey = Pubkey::new_unique();
+
+    let mut program_test =
+        ProgramTest::new("helloworld", program_id, processor!(process_instruction));
+    program_test.add_account(
+        greeted_pubkey,
+        Account {
+            lamports: Rent::default().minimum_balance(GreetingAccount::LEN),
+            data: vec![0_u8; GreetingAccount::LEN],
+            owner: program_id,
+            ..Account::default()
+        },
+    );
+    // Native CPI cannot allocate the registry account, so start from an
+    // initialized one
+    let mut registry_data = vec![0; Registry::LEN];
+    Registry {
+        is_initialized: true,
+        bump_seed: find_registry_address(&program_id).1,
+        ..Registry::default()
+    }
+    .store(&mut registry_data)
+    .unwrap();
+    program_test.add_account(
+        find_registry_address(&program_id).0,
+        Account {
+            lamports: Rent::default().minimum_balance(Registry::LEN),
+            data: registry_data,
+            owner: program_id,
+            ..Account::default()
+        },
+    );
+    let mut context = program_test.start_with_context().await;
+    let greeter = context.payer.pubkey();
+
+    assert_eq!(
+        process(
+            &mut context,
+            initialize_registry(&program_id, &greeter),
+            &[]
+        )
+        .await,
+        Err(TransactionError::InstructionError(
+            0,
+            InstructionError::AccountAlreadyInitialized
+        ))
+    );
+
+    // Only created greeting addresses are recorded
+    let ix = say_hello(&program_id, &greeted_pubkey, &greeter, vec![]);
+    process(&mut context, ix, &[]).await.unwrap();
+    let registry = registry(&mut context, &program_id).await;
+    assert_eq!(registry.count, 0);
+    assert!(registry.recent.is_empty());
+}
+
+// Native CPI cannot create accounts, so only run against the BPF build
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn test_registry() {
+    use helloworld::state::find_greeting_address;
+
+    let program_id = helloworld::id();
+    let program_test = ProgramTest::new("helloworld", program_id, processor!(process_instruction));
+    let mut context = program_test.start_with_context().await;
+    let payer = context.payer.pubkey();
+
+    // Greeting addresses created before the registry are not recorded
+    let early_address = find_greeting_address(&program_id, &payer).0;
+    let ix = say_hello(&program_id, &early_address, &payer, vec![]);
+    process(&mut context, ix, &[]).await.unwrap();
+
+    process(&mut context, initialize_registry(&program_id, &payer), &[])
+        .await
+        .unwrap();
+    assert_eq!(
+        registry(&mut context, &program_id).await,
+        Registry {
+            is_initialized: true,
+            bump_seed: find_registry_address(&program_id).1,
+            ..Registry::default()
+        }
+    );
+
+    let users = [Keypair::new(), Keypair::new()];
+    let mut greeting_addresses = vec![];
+    for user in &users {
+        let ix = solana_sdk::system_instruction::transfer(&payer, &user.pubkey(), 1_000_000_000);
+        process(&mut context, ix, &[]).await.unwrap();
+        let greeting_address = find_greeting_address(&program_id, &user.pubkey()).0;
+        // Only the greeting that creates the address records it
+        for _ in 0..2 {
+            let ix = say_hello(&program_id, &greeting_address, &user.pubkey(), vec![]);
+            process(&mut context, ix, &[user]).await.unwrap();
+        }
+        greeting_addresses.push(greeting_address);
+    }
+
+    let registry = registry(&mut context, &program_id).await;
+    assert_eq!(registry.count, 2);
+    assert_eq!(registry.recent, greeting_addresses);
+}
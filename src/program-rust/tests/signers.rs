@@ -0,0 +1,195 @@
+use helloworld::{
+    error::HelloWorldError,
+    fees::FeePolicy,
+    instruction::{
+        cancel_recovery, create_channel, create_mirror, initialize_config, initialize_history,
+        initialize_leaderboard, initialize_registry, initialize_treasury, migrate,
+        register_greeter, register_guardians, say_hello, say_hello_secp256k1, set_fee_policy,
+        set_greeting_expiry, set_rate_limit, withdraw, write_metadata,
+    },
+    process_instruction,
+    state::{find_config_address, AccountState, Config, GuardianSet},
+    GreetingAccount,
+};
+use solana_program_test::*;
+use solana_sdk::{
+    account::Account,
+    instruction::InstructionError,
+    pubkey::Pubkey,
+    rent::Rent,
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+};
+
+fn add_state_account(
+    program_test: &mut ProgramTest,
+    program_id: &Pubkey,
+    pubkey: Pubkey,
+    state: &impl AccountState,
+    len: usize,
+) {
+    let mut data = vec![0; len];
+    state.store(&mut data).unwrap();
+    program_test.add_account(
+        pubkey,
+        Account {
+            lamports: Rent::default().minimum_balance(len),
+            data,
+            owner: *program_id,
+            ..Account::default()
+        },
+    );
+}
+
+#[tokio::test]
+async fn test_missing_signatures() {
+    let program_id = helloworld::id();
+    let greeted_pubkey = Pubkey::new_unique();
+    let guardian_set_pubkey = Pubkey::new_unique();
+    let authority = Keypair::new().pubkey();
+
+    let mut program_test =
+        ProgramTest::new("helloworld", program_id, processor!(process_instruction));
+    add_state_account(
+        &mut program_test,
+        &program_id,
+        greeted_pubkey,
+        &GreetingAccount::default(),
+        GreetingAccount::LEN,
+    );
+    add_state_account(
+        &mut program_test,
+        &program_id,
+        guardian_set_pubkey,
+        &GuardianSet {
+            is_initialized: true,
+            greeting: greeted_pubkey,
+            owner: authority,
+            ..GuardianSet::default()
+        },
+        GuardianSet::LEN,
+    );
+    add_state_account(
+        &mut program_test,
+        &program_id,
+        find_config_address(&program_id).0,
+        &Config {
+            is_initialized: true,
+            admin: authority,
+            bump_seed: find_config_address(&program_id).1,
+            ..Config::default()
+        },
+        Config::LEN,
+    );
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+    // Every authority or payer has to sign, whoever pays for the transaction
+    let instructions = [
+        say_hello(&program_id, &greeted_pubkey, &authority, vec![]),
+        say_hello_secp256k1(&program_id, &greeted_pubkey, [0; 20], vec![], &authority),
+        register_guardians(
+            &program_id,
+            &guardian_set_pubkey,
+            &greeted_pubkey,
+            &authority,
+            vec![],
+            1,
+            0,
+        ),
+        cancel_recovery(&program_id, &guardian_set_pubkey, &authority),
+        initialize_config(&program_id, &authority),
+        set_fee_policy(&program_id, &authority, FeePolicy::Free),
+        set_rate_limit(&program_id, &authority, 0),
+        set_greeting_expiry(&program_id, &authority, 0),
+        register_greeter(&program_id, &authority),
+        create_mirror(&program_id, &greeted_pubkey, &authority),
+        migrate(&program_id, &greeted_pubkey, &authority),
+        initialize_registry(&program_id, &authority),
+        initialize_history(&program_id, &authority),
+        initialize_leaderboard(&program_id, &authority),
+        write_metadata(&program_id, &authority),
+        create_channel(&program_id, &authority, "general"),
+        initialize_treasury(&program_id, &authority),
+        withdraw(&program_id, &payer.pubkey(), &authority, 0),
+    ];
+    for mut instruction in instructions {
+        for meta in &mut instruction.accounts {
+            meta.is_signer = false;
+        }
+        let data = instruction.data.clone();
+        let mut transaction = Transaction::new_with_payer(&[instruction], Some(&payer.pubkey()));
+        transaction.sign(&[&payer], recent_blockhash);
+        assert_eq!(
+            banks_client
+                .process_transaction(transaction)
+                .await
+                .unwrap_err()
+                .unwrap(),
+            TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature),
+            "instruction {:?}",
+            data
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_readonly_accounts() {
+    let program_id = helloworld::id();
+    let greeted_pubkey = Pubkey::new_unique();
+    let admin = Keypair::new();
+
+    let mut program_test =
+        ProgramTest::new("helloworld", program_id, processor!(process_instruction));
+    add_state_account(
+        &mut program_test,
+        &program_id,
+        greeted_pubkey,
+        &GreetingAccount::default(),
+        GreetingAccount::LEN,
+    );
+    add_state_account(
+        &mut program_test,
+        &program_id,
+        find_config_address(&program_id).0,
+        &Config {
+            is_initialized: true,
+            admin: admin.pubkey(),
+            bump_seed: find_config_address(&program_id).1,
+            ..Config::default()
+        },
+        Config::LEN,
+    );
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+    // Accounts the program updates are rejected up front when read-only
+    let instructions = [
+        (
+            say_hello(&program_id, &greeted_pubkey, &payer.pubkey(), vec![]),
+            vec![&payer],
+        ),
+        (
+            set_fee_policy(&program_id, &admin.pubkey(), FeePolicy::Free),
+            vec![&payer, &admin],
+        ),
+        (
+            create_mirror(&program_id, &greeted_pubkey, &payer.pubkey()),
+            vec![&payer],
+        ),
+    ];
+    for (mut instruction, signers) in instructions {
+        instruction.accounts[0].is_writable = false;
+        let mut transaction = Transaction::new_with_payer(&[instruction], Some(&payer.pubkey()));
+        transaction.sign(&signers, recent_blockhash);
+        assert_eq!(
+            banks_client
+                .process_transaction(transaction)
+                .await
+                .unwrap_err()
+                .unwrap(),
+            TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(HelloWorldError::AccountNotWritable as u32)
+            )
+        );
+    }
+}
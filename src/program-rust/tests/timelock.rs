@@ -0,0 +1,233 @@
+use helloworld::{
+    error::HelloWorldError,
+    fees::FeePolicy,
+    instruction::{
+        cancel_action, execute_action, queue_action, set_fee_policy, update_config, ConfigUpdate,
+    },
+    process_instruction,
+    state::{find_config_address, find_timelock_address, AccountState, Config, Timelock},
+};
+use solana_program_test::*;
+use solana_sdk::{
+    account::Account,
+    instruction::{Instruction, InstructionError},
+    pubkey::Pubkey,
+    rent::Rent,
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+};
+
+const TIMELOCK_SLOTS: u64 = 100;
+
+async fn process(
+    context: &mut ProgramTestContext,
+    instruction: Instruction,
+    signers: &[&Keypair],
+) -> Result<(), TransactionError> {
+    let recent_blockhash = context
+        .banks_client
+        .get_new_latest_blockhash(&context.last_blockhash)
+        .await
+        .unwrap();
+    context.last_blockhash = recent_blockhash;
+
+    let mut transaction =
+        Transaction::new_with_payer(&[instruction], Some(&context.payer.pubkey()));
+    let mut all_signers = vec![&context.payer];
+    all_signers.extend_from_slice(signers);
+    transaction.sign(&all_signers, recent_blockhash);
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .map_err(|err| err.unwrap())
+}
+
+async fn state<T: AccountState>(context: &mut ProgramTestContext, address: Pubkey) -> T {
+    let account = context
+        .banks_client
+        .get_account(address)
+        .await
+        .unwrap()
+        .unwrap();
+    T::load(&account.data).unwrap()
+}
+
+fn add_state_account(
+    program_test: &mut ProgramTest,
+    program_id: &Pubkey,
+    pubkey: Pubkey,
+    state: &impl AccountState,
+    len: usize,
+) {
+    let mut data = vec![0; len];
+    state.store(&mut data).unwrap();
+    program_test.add_account(
+        pubkey,
+        Account {
+            lamports: Rent::default().minimum_balance(len),
+            data,
+            owner: *program_id,
+            ..Account::default()
+        },
+    );
+}
+
+fn add_config(program_test: &mut ProgramTest, program_id: &Pubkey, admin: &Pubkey) {
+    let (config_address, bump_seed) = find_config_address(program_id);
+    let config = Config {
+        is_initialized: true,
+        admin: *admin,
+        timelock_slots: TIMELOCK_SLOTS,
+        bump_seed,
+        ..Config::default()
+    };
+    add_state_account(
+        program_test,
+        program_id,
+        config_address,
+        &config,
+        Config::LEN,
+    );
+}
+
+#[tokio::test]
+async fn test_timelock() {
+    let program_id = helloworld::id();
+    let admin = Keypair::new();
+    let new_admin = Pubkey::new_unique();
+    let config_address = find_config_address(&program_id).0;
+
+    let mut program_test =
+        ProgramTest::new("helloworld", program_id, processor!(process_instruction));
+    add_config(&mut program_test, &program_id, &admin.pubkey());
+    // Native CPI cannot allocate the timelock, so start from an initialized
+    // one
+    let (timelock_address, bump_seed) = find_timelock_address(&program_id);
+    add_state_account(
+        &mut program_test,
+        &program_id,
+        timelock_address,
+        &Timelock {
+            is_initialized: true,
+            bump_seed,
+            ..Timelock::default()
+        },
+        Timelock::LEN,
+    );
+    let mut context = program_test.start_with_context().await;
+
+    // Fee changes and authority transfers have to be queued
+    let transfer = ConfigUpdate {
+        admin: Some(new_admin),
+        ..ConfigUpdate::default()
+    };
+    let direct = [
+        update_config(&program_id, &admin.pubkey(), transfer.clone()),
+        set_fee_policy(&program_id, &admin.pubkey(), FeePolicy::Free),
+    ];
+    for ix in direct {
+        assert_eq!(
+            process(&mut context, ix, &[&admin]).await,
+            Err(TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(HelloWorldError::Timelocked as u32)
+            ))
+        );
+    }
+    // Pausing stays immediate
+    let ix = update_config(
+        &program_id,
+        &admin.pubkey(),
+        ConfigUpdate {
+            paused: Some(true),
+            ..ConfigUpdate::default()
+        },
+    );
+    process(&mut context, ix, &[&admin]).await.unwrap();
+
+    let ix = queue_action(&program_id, &admin.pubkey(), transfer);
+    process(&mut context, ix, &[&admin]).await.unwrap();
+    let ix = queue_action(
+        &program_id,
+        &admin.pubkey(),
+        ConfigUpdate {
+            timelock_slots: Some(0),
+            ..ConfigUpdate::default()
+        },
+    );
+    process(&mut context, ix, &[&admin]).await.unwrap();
+    let timelock: Timelock = state(&mut context, timelock_address).await;
+    assert_eq!(timelock.next_id, 2);
+    assert_eq!(timelock.queued.len(), 2);
+    let executable_slot = timelock.queued[0].executable_slot;
+
+    // Only the admin can cancel
+    let intruder = Keypair::new();
+    let ix = cancel_action(&program_id, &intruder.pubkey(), 1);
+    assert_eq!(
+        process(&mut context, ix, &[&intruder]).await,
+        Err(TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(HelloWorldError::Unauthorized as u32)
+        ))
+    );
+    let ix = cancel_action(&program_id, &admin.pubkey(), 1);
+    process(&mut context, ix, &[&admin]).await.unwrap();
+
+    assert_eq!(
+        process(&mut context, execute_action(&program_id, 0), &[]).await,
+        Err(TransactionError::InstructionError(
+            0,
+            InstructionError::InvalidArgument
+        ))
+    );
+    context.warp_to_slot(executable_slot).unwrap();
+    process(&mut context, execute_action(&program_id, 0), &[])
+        .await
+        .unwrap();
+    let config: Config = state(&mut context, config_address).await;
+    assert_eq!(config.pending_admin, Some(new_admin));
+    assert_eq!(config.timelock_slots, TIMELOCK_SLOTS);
+
+    // Executed and cancelled actions are gone
+    for id in [0, 1] {
+        assert_eq!(
+            process(&mut context, execute_action(&program_id, id), &[]).await,
+            Err(TransactionError::InstructionError(
+                0,
+                InstructionError::InvalidArgument
+            ))
+        );
+    }
+    let timelock: Timelock = state(&mut context, timelock_address).await;
+    assert!(timelock.queued.is_empty());
+}
+
+// Native CPI cannot create accounts, so only run against the BPF build
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn test_create_timelock() {
+    let program_id = helloworld::id();
+    let admin = Keypair::new();
+
+    let mut program_test =
+        ProgramTest::new("helloworld", program_id, processor!(process_instruction));
+    add_config(&mut program_test, &program_id, &admin.pubkey());
+    program_test.add_account(
+        admin.pubkey(),
+        Account {
+            lamports: 1_000_000_000,
+            ..Account::default()
+        },
+    );
+    let mut context = program_test.start_with_context().await;
+
+    let ix = queue_action(&program_id, &admin.pubkey(), ConfigUpdate::default());
+    process(&mut context, ix, &[&admin]).await.unwrap();
+    let (timelock_address, bump_seed) = find_timelock_address(&program_id);
+    let timelock: Timelock = state(&mut context, timelock_address).await;
+    assert!(timelock.is_initialized);
+    assert_eq!(timelock.bump_seed, bump_seed);
+    assert_eq!(timelock.next_id, 1);
+}
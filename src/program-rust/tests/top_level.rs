@@ -0,0 +1,148 @@
+use helloworld::{
+    error::HelloWorldError,
+    instruction::{say_hello, update_config, ConfigUpdate},
+    process_instruction,
+    state::{find_config_address, AccountState, Config},
+    GreetingAccount,
+};
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, instruction::AccountMeta, program::invoke,
+};
+use solana_program_test::*;
+use solana_sdk::{
+    account::Account,
+    instruction::{Instruction, InstructionError},
+    pubkey::Pubkey,
+    rent::Rent,
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+};
+
+/// Program forwarding its instruction to the program passed first
+fn process_caller(_program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    let (program, accounts) = accounts.split_first().unwrap();
+    let instruction = Instruction {
+        program_id: *program.key,
+        accounts: accounts
+            .iter()
+            .map(|account| AccountMeta {
+                pubkey: *account.key,
+                is_signer: account.is_signer,
+                is_writable: account.is_writable,
+            })
+            .collect(),
+        data: data.to_vec(),
+    };
+    invoke(&instruction, accounts)
+}
+
+/// Wrap `instruction` into an instruction of the caller program
+fn through_caller(caller_id: &Pubkey, instruction: Instruction) -> Instruction {
+    let mut accounts = vec![AccountMeta::new_readonly(instruction.program_id, false)];
+    accounts.extend(instruction.accounts);
+    Instruction {
+        program_id: *caller_id,
+        accounts,
+        data: instruction.data,
+    }
+}
+
+async fn process(
+    context: &mut ProgramTestContext,
+    instruction: Instruction,
+    signers: &[&Keypair],
+) -> Result<(), TransactionError> {
+    let recent_blockhash = context
+        .banks_client
+        .get_new_latest_blockhash(&context.last_blockhash)
+        .await
+        .unwrap();
+    context.last_blockhash = recent_blockhash;
+
+    let mut transaction =
+        Transaction::new_with_payer(&[instruction], Some(&context.payer.pubkey()));
+    let mut all_signers = vec![&context.payer];
+    all_signers.extend_from_slice(signers);
+    transaction.sign(&all_signers, recent_blockhash);
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .map_err(|err| err.unwrap())
+}
+
+#[tokio::test]
+async fn test_top_level_only() {
+    let program_id = helloworld::id();
+    let caller_id = Pubkey::new_unique();
+    let greeted_pubkey = Pubkey::new_unique();
+    let admin = Keypair::new();
+
+    let mut program_test =
+        ProgramTest::new("helloworld", program_id, processor!(process_instruction));
+    program_test.add_program("caller", caller_id, processor!(process_caller));
+    program_test.add_account(
+        greeted_pubkey,
+        Account {
+            lamports: Rent::default().minimum_balance(GreetingAccount::LEN),
+            data: vec![0_u8; GreetingAccount::LEN],
+            owner: program_id,
+            ..Account::default()
+        },
+    );
+    let mut config_data = vec![0; Config::LEN];
+    Config {
+        is_initialized: true,
+        admin: admin.pubkey(),
+        top_level_only: true,
+        bump_seed: find_config_address(&program_id).1,
+        ..Config::default()
+    }
+    .store(&mut config_data)
+    .unwrap();
+    program_test.add_account(
+        find_config_address(&program_id).0,
+        Account {
+            lamports: Rent::default().minimum_balance(Config::LEN),
+            data: config_data,
+            owner: program_id,
+            ..Account::default()
+        },
+    );
+    let mut context = program_test.start_with_context().await;
+    let greeter = context.payer.pubkey();
+    let greet = say_hello(&program_id, &greeted_pubkey, &greeter, vec![]);
+
+    process(&mut context, greet.clone(), &[]).await.unwrap();
+    assert_eq!(
+        process(&mut context, through_caller(&caller_id, greet.clone()), &[]).await,
+        Err(TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(HelloWorldError::CpiForbidden as u32)
+        ))
+    );
+
+    // The check relies on the real instructions sysvar
+    let mut spoofed = greet.clone();
+    spoofed.accounts[10].pubkey = Pubkey::new_unique();
+    assert_eq!(
+        process(&mut context, spoofed, &[]).await,
+        Err(TransactionError::InstructionError(
+            0,
+            InstructionError::UnsupportedSysvar
+        ))
+    );
+
+    let ix = update_config(
+        &program_id,
+        &admin.pubkey(),
+        ConfigUpdate {
+            top_level_only: Some(false),
+            ..ConfigUpdate::default()
+        },
+    );
+    process(&mut context, ix, &[&admin]).await.unwrap();
+    process(&mut context, through_caller(&caller_id, greet), &[])
+        .await
+        .unwrap();
+}
@@ -0,0 +1,201 @@
+use helloworld::{
+    fees::FeePolicy,
+    instruction::{say_hello, withdraw},
+    process_instruction,
+    state::{find_config_address, find_treasury_address, AccountState, Config, Treasury},
+    GreetingAccount,
+};
+use solana_program_test::*;
+use solana_sdk::{
+    account::Account,
+    instruction::{Instruction, InstructionError},
+    pubkey::Pubkey,
+    rent::Rent,
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+};
+
+// Large enough for withdrawals to fund a new rent-exempt account
+const FEE: u64 = 1_000_000;
+
+async fn process(
+    context: &mut ProgramTestContext,
+    instruction: Instruction,
+    signers: &[&Keypair],
+) -> Result<(), TransactionError> {
+    let recent_blockhash = context
+        .banks_client
+        .get_new_latest_blockhash(&context.last_blockhash)
+        .await
+        .unwrap();
+    context.last_blockhash = recent_blockhash;
+
+    let mut transaction =
+        Transaction::new_with_payer(&[instruction], Some(&context.payer.pubkey()));
+    let mut all_signers = vec![&context.payer];
+    all_signers.extend_from_slice(signers);
+    transaction.sign(&all_signers, recent_blockhash);
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .map_err(|err| err.unwrap())
+}
+
+async fn treasury(context: &mut ProgramTestContext, program_id: &Pubkey) -> (u64, Treasury) {
+    let account = context
+        .banks_client
+        .get_account(find_treasury_address(program_id).0)
+        .await
+        .unwrap()
+        .unwrap();
+    (account.lamports, Treasury::load(&account.data).unwrap())
+}
+
+fn add_state_account(
+    program_test: &mut ProgramTest,
+    program_id: &Pubkey,
+    pubkey: Pubkey,
+    state: &impl AccountState,
+    len: usize,
+) {
+    let mut data = vec![0; len];
+    state.store(&mut data).unwrap();
+    program_test.add_account(
+        pubkey,
+        Account {
+            lamports: Rent::default().minimum_balance(len),
+            data,
+            owner: *program_id,
+            ..Account::default()
+        },
+    );
+}
+
+#[tokio::test]
+async fn test_withdraw() {
+    let program_id = helloworld::id();
+    let greeted_pubkey = Pubkey::new_unique();
+    let admin = Keypair::new();
+    let destination = Pubkey::new_unique();
+
+    let mut program_test =
+        ProgramTest::new("helloworld", program_id, processor!(process_instruction));
+    program_test.add_account(
+        greeted_pubkey,
+        Account {
+            lamports: Rent::default().minimum_balance(GreetingAccount::LEN),
+            data: vec![0_u8; GreetingAccount::LEN],
+            owner: program_id,
+            ..Account::default()
+        },
+    );
+    // Native CPI cannot allocate accounts, so start from initialized ones
+    add_state_account(
+        &mut program_test,
+        &program_id,
+        find_config_address(&program_id).0,
+        &Config {
+            is_initialized: true,
+            admin: admin.pubkey(),
+            fee_policy: FeePolicy::Flat { lamports: FEE },
+            bump_seed: find_config_address(&program_id).1,
+            ..Config::default()
+        },
+        Config::LEN,
+    );
+    let (treasury_address, bump_seed) = find_treasury_address(&program_id);
+    add_state_account(
+        &mut program_test,
+        &program_id,
+        treasury_address,
+        &Treasury {
+            is_initialized: true,
+            bump_seed,
+            ..Treasury::default()
+        },
+        Treasury::LEN,
+    );
+    let mut context = program_test.start_with_context().await;
+    let greeter = context.payer.pubkey();
+
+    // Fees are accounted for as they come in
+    for _ in 0..3 {
+        let ix = say_hello(&program_id, &greeted_pubkey, &greeter, vec![]);
+        process(&mut context, ix, &[]).await.unwrap();
+    }
+    let (lamports, state) = treasury(&mut context, &program_id).await;
+    assert_eq!(state.total_collected, 3 * FEE);
+    assert_eq!(
+        lamports,
+        Rent::default().minimum_balance(Treasury::LEN) + 3 * FEE
+    );
+
+    // Only the admin can withdraw, and only above the rent-exempt minimum
+    let ix = withdraw(&program_id, &destination, &greeter, FEE);
+    assert!(process(&mut context, ix, &[]).await.is_err());
+    let ix = withdraw(&program_id, &destination, &admin.pubkey(), 3 * FEE + 1);
+    assert_eq!(
+        process(&mut context, ix, &[&admin]).await,
+        Err(TransactionError::InstructionError(
+            0,
+            InstructionError::InsufficientFunds
+        ))
+    );
+    let ix = withdraw(&program_id, &treasury_address, &admin.pubkey(), FEE);
+    assert!(process(&mut context, ix, &[&admin]).await.is_err());
+
+    let ix = withdraw(&program_id, &destination, &admin.pubkey(), 2 * FEE);
+    process(&mut context, ix, &[&admin]).await.unwrap();
+    let (lamports, state) = treasury(&mut context, &program_id).await;
+    assert_eq!(state.total_collected, 3 * FEE);
+    assert_eq!(state.total_withdrawn, 2 * FEE);
+    assert_eq!(
+        lamports,
+        Rent::default().minimum_balance(Treasury::LEN) + FEE
+    );
+    assert_eq!(
+        context.banks_client.get_balance(destination).await.unwrap(),
+        2 * FEE
+    );
+}
+
+// Native CPI cannot allocate accounts, so only run against the BPF build
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn test_initialize_treasury() {
+    use helloworld::instruction::initialize_treasury;
+    use solana_sdk::system_instruction;
+
+    let program_id = helloworld::id();
+    let program_test = ProgramTest::new("helloworld", program_id, processor!(process_instruction));
+    let mut context = program_test.start_with_context().await;
+    let payer = context.payer.pubkey();
+    let treasury_address = find_treasury_address(&program_id).0;
+
+    // Fees collected before initialization are kept
+    let ix = system_instruction::transfer(&payer, &treasury_address, FEE);
+    process(&mut context, ix, &[]).await.unwrap();
+    process(&mut context, initialize_treasury(&program_id, &payer), &[])
+        .await
+        .unwrap();
+    let (lamports, state) = treasury(&mut context, &program_id).await;
+    assert_eq!(lamports, Rent::default().minimum_balance(Treasury::LEN));
+    assert_eq!(
+        state,
+        Treasury {
+            is_initialized: true,
+            bump_seed: find_treasury_address(&program_id).1,
+            ..Treasury::default()
+        }
+    );
+
+    let ix = initialize_treasury(&program_id, &payer);
+    assert_eq!(
+        process(&mut context, ix, &[]).await,
+        Err(TransactionError::InstructionError(
+            0,
+            InstructionError::AccountAlreadyInitialized
+        ))
+    );
+}
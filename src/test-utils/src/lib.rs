@@ -0,0 +1,424 @@
+//! Deployment of BPF programs in `solana-program-test`, as the loaders
+//! deploy them on a cluster
+//!
+//! `ProgramTest::add_program` loads programs with the deprecated loader, or
+//! natively, which does not exercise upgrades nor the checks programs make
+//! of their own deployment. These helpers build the loader accounts instead:
+//!
+//! ```no_run
+//! # use solana_program_test::ProgramTest;
+//! # use solana_sdk::pubkey::Pubkey;
+//! # let (program_id, authority) = (Pubkey::new_unique(), Pubkey::new_unique());
+//! let elf = std::fs::read("dist/program/helloworld.so").unwrap();
+//! let mut program_test = ProgramTest::default();
+//! helloworld_test_utils::add_upgradeable_program(
+//!     &mut program_test,
+//!     program_id,
+//!     &elf,
+//!     Some(authority),
+//! );
+//! ```
+//!
+//! Transaction logs are queried through [`Logs`] rather than by position,
+//! tests branch from a common setup by restoring an [`AccountsSnapshot`], and
+//! move the bank forward with [`warp_slots`], [`warp_to_epoch`] and
+//! [`warp_seconds`].
+
+mod logs;
+mod snapshot;
+mod warp;
+
+pub use logs::{assert_log_contains, assert_log_matches, LogLine, Logs};
+pub use snapshot::AccountsSnapshot;
+use solana_program_test::{BanksClientError, ProgramTest, ProgramTestContext};
+use solana_sdk::{
+    account::{Account, AccountSharedData},
+    bpf_loader, bpf_loader_upgradeable,
+    bpf_loader_upgradeable::UpgradeableLoaderState,
+    instruction::Instruction,
+    loader_instruction,
+    pubkey::Pubkey,
+    rent::Rent,
+    signature::{Keypair, Signer},
+    transaction::Transaction,
+};
+pub use warp::{warp_seconds, warp_slots, warp_to_epoch};
+
+/// Bytes of the program written by each transaction filling a buffer
+const WRITE_CHUNK_LEN: usize = 900;
+
+/// Address of the program data account of the upgradeable program at
+/// `program_id`
+pub fn program_data_address(program_id: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[program_id.as_ref()], &bpf_loader_upgradeable::id()).0
+}
+
+/// Rent-exempt account owned by the upgradeable loader holding `state`
+/// followed by `elf`
+pub fn loader_account(state: &UpgradeableLoaderState, elf: &[u8], executable: bool) -> Account {
+    let mut data = bincode::serialize(state).unwrap();
+    data.extend_from_slice(elf);
+    Account {
+        lamports: Rent::default().minimum_balance(data.len()),
+        data,
+        owner: bpf_loader_upgradeable::id(),
+        executable,
+        ..Account::default()
+    }
+}
+
+/// Executable program account of the upgradeable loader, whose code is in
+/// the program data account at `programdata_address`
+pub fn upgradeable_program_account(programdata_address: Pubkey) -> Account {
+    loader_account(
+        &UpgradeableLoaderState::Program {
+            programdata_address,
+        },
+        &[],
+        true,
+    )
+}
+
+/// Program data account of the upgradeable loader holding `elf`, deployed at
+/// `slot` and upgradeable by `upgrade_authority`, if any
+pub fn program_data_account(elf: &[u8], slot: u64, upgrade_authority: Option<Pubkey>) -> Account {
+    loader_account(
+        &UpgradeableLoaderState::ProgramData {
+            slot,
+            upgrade_authority_address: upgrade_authority,
+        },
+        elf,
+        false,
+    )
+}
+
+/// Buffer account of the upgradeable loader holding `elf`, written by
+/// `authority`, to upgrade a program from
+pub fn buffer_account(elf: &[u8], authority: Pubkey) -> Account {
+    loader_account(
+        &UpgradeableLoaderState::Buffer {
+            authority_address: Some(authority),
+        },
+        elf,
+        false,
+    )
+}
+
+/// Deploy `elf` at `program_id` with the upgradeable loader, upgradeable by
+/// `upgrade_authority` if any, returning the address of its program data
+pub fn add_upgradeable_program(
+    program_test: &mut ProgramTest,
+    program_id: Pubkey,
+    elf: &[u8],
+    upgrade_authority: Option<Pubkey>,
+) -> Pubkey {
+    let programdata_address = program_data_address(&program_id);
+    program_test.add_account(program_id, upgradeable_program_account(programdata_address));
+    program_test.add_account(
+        programdata_address,
+        program_data_account(elf, 0, upgrade_authority),
+    );
+    programdata_address
+}
+
+/// Deploy `elf` with the upgradeable loader in a running bank, upgradeable by
+/// the payer, returning the address of the program
+///
+/// Unlike `add_upgradeable_program`, the program goes through the loader
+/// instructions a deployment sends on a cluster: a buffer is created and
+/// written, then deployed from with room for programs twice as large, so the
+/// loader verifies the program and fills in its accounts itself.
+pub async fn deploy_upgradeable(
+    context: &mut ProgramTestContext,
+    elf: &[u8],
+) -> Result<Pubkey, BanksClientError> {
+    let rent = context.banks_client.get_rent().await?;
+    let payer = Keypair::from_bytes(&context.payer.to_bytes()).unwrap();
+    let buffer = Keypair::new();
+    let program = Keypair::new();
+
+    let buffer_len = UpgradeableLoaderState::buffer_len(elf.len()).unwrap();
+    let instructions = bpf_loader_upgradeable::create_buffer(
+        &payer.pubkey(),
+        &buffer.pubkey(),
+        &payer.pubkey(),
+        rent.minimum_balance(buffer_len),
+        elf.len(),
+    )
+    .unwrap();
+    process_instructions(context, &instructions, &[&payer, &buffer]).await?;
+    for (index, chunk) in elf.chunks(WRITE_CHUNK_LEN).enumerate() {
+        let instruction = bpf_loader_upgradeable::write(
+            &buffer.pubkey(),
+            &payer.pubkey(),
+            (index * WRITE_CHUNK_LEN) as u32,
+            chunk.to_vec(),
+        );
+        process_instructions(context, &[instruction], &[&payer]).await?;
+    }
+
+    let program_len = UpgradeableLoaderState::program_len().unwrap();
+    let instructions = bpf_loader_upgradeable::deploy_with_max_program_len(
+        &payer.pubkey(),
+        &program.pubkey(),
+        &buffer.pubkey(),
+        &payer.pubkey(),
+        rent.minimum_balance(program_len),
+        elf.len().saturating_mul(2),
+    )
+    .unwrap();
+    process_instructions(context, &instructions, &[&payer, &program]).await?;
+    Ok(program.pubkey())
+}
+
+/// Deploy `elf` at `program_id` with the non-upgradeable loader, as programs
+/// that were made final are
+pub fn set_non_upgradeable_program_account(
+    program_test: &mut ProgramTest,
+    program_id: Pubkey,
+    elf: &[u8],
+) {
+    program_test.add_account(program_id, non_upgradeable_program_account(elf, true));
+}
+
+/// Replace the code of the non-upgradeable program at the address of
+/// `program` with `elf`, in a running bank
+///
+/// The bank caches the executable of each program it runs, and
+/// `ProgramTestContext::set_account` leaves that cache alone, so the program
+/// would keep running its former code. The account is set unfinalized
+/// instead, then finalized by the loader, which replaces the cached
+/// executable as on a cluster. This takes the keypair of the program address.
+pub async fn replace_non_upgradeable_program(
+    context: &mut ProgramTestContext,
+    program: &Keypair,
+    elf: &[u8],
+) -> Result<(), BanksClientError> {
+    context.set_account(
+        &program.pubkey(),
+        &AccountSharedData::from(non_upgradeable_program_account(elf, false)),
+    );
+    let payer = Keypair::from_bytes(&context.payer.to_bytes()).unwrap();
+    process_instructions(
+        context,
+        &[loader_instruction::finalize(
+            &program.pubkey(),
+            &bpf_loader::id(),
+        )],
+        &[&payer, program],
+    )
+    .await
+}
+
+/// Process `instructions` in a transaction paid by the first of `signers`
+async fn process_instructions(
+    context: &mut ProgramTestContext,
+    instructions: &[Instruction],
+    signers: &[&Keypair],
+) -> Result<(), BanksClientError> {
+    let transaction = Transaction::new_signed_with_payer(
+        instructions,
+        Some(&signers[0].pubkey()),
+        &signers.to_vec(),
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(transaction).await
+}
+
+/// Rent-exempt account of the non-upgradeable loader holding `elf`
+fn non_upgradeable_program_account(elf: &[u8], executable: bool) -> Account {
+    Account {
+        lamports: Rent::default().minimum_balance(elf.len()),
+        data: elf.to_vec(),
+        owner: bpf_loader::id(),
+        executable,
+        ..Account::default()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use solana_program_test::{programs::spl_programs, tokio};
+    use solana_sdk::{account::ReadableAccount, pubkey};
+
+    /// ELF of the SPL program bundled with `solana-program-test` at
+    /// `program_id`
+    fn spl_program_elf(program_id: Pubkey) -> Vec<u8> {
+        spl_programs(&Rent::default())
+            .into_iter()
+            .find(|(pubkey, _)| *pubkey == program_id)
+            .unwrap()
+            .1
+            .data()
+            .to_vec()
+    }
+
+    #[test]
+    fn test_loader_accounts() {
+        let program_id = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+        let elf = [1, 2, 3];
+        let programdata_address = program_data_address(&program_id);
+
+        let program = upgradeable_program_account(programdata_address);
+        assert!(program.executable);
+        assert_eq!(program.owner, bpf_loader_upgradeable::id());
+        assert_eq!(
+            program.data.len(),
+            UpgradeableLoaderState::program_len().unwrap()
+        );
+        assert_eq!(
+            bincode::deserialize::<UpgradeableLoaderState>(&program.data).unwrap(),
+            UpgradeableLoaderState::Program {
+                programdata_address
+            }
+        );
+
+        let program_data = program_data_account(&elf, 7, Some(authority));
+        assert!(!program_data.executable);
+        let metadata_len = UpgradeableLoaderState::programdata_data_offset().unwrap();
+        assert_eq!(&program_data.data[metadata_len..], &elf);
+        assert_eq!(
+            bincode::deserialize::<UpgradeableLoaderState>(&program_data.data).unwrap(),
+            UpgradeableLoaderState::ProgramData {
+                slot: 7,
+                upgrade_authority_address: Some(authority),
+            }
+        );
+        assert_eq!(
+            program_data.lamports,
+            Rent::default().minimum_balance(program_data.data.len())
+        );
+
+        let buffer = buffer_account(&elf, authority);
+        let metadata_len = UpgradeableLoaderState::buffer_data_offset().unwrap();
+        assert_eq!(&buffer.data[metadata_len..], &elf);
+    }
+
+    #[tokio::test]
+    async fn test_deployments() {
+        let (upgradeable_id, final_id) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let authority = Pubkey::new_unique();
+        let mut program_test = ProgramTest::default();
+        let programdata_address = add_upgradeable_program(
+            &mut program_test,
+            upgradeable_id,
+            &[1, 2, 3],
+            Some(authority),
+        );
+        set_non_upgradeable_program_account(&mut program_test, final_id, &[4, 5]);
+        let (mut banks_client, _, _) = program_test.start().await;
+
+        let program = banks_client
+            .get_account(upgradeable_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(program, upgradeable_program_account(programdata_address));
+        let program_data = banks_client
+            .get_account(programdata_address)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            program_data,
+            program_data_account(&[1, 2, 3], 0, Some(authority))
+        );
+
+        let program = banks_client.get_account(final_id).await.unwrap().unwrap();
+        assert!(program.executable);
+        assert_eq!(program.owner, bpf_loader::id());
+        assert_eq!(program.data, vec![4, 5]);
+    }
+
+    #[tokio::test]
+    async fn test_deploy_upgradeable() {
+        let elf = spl_program_elf(pubkey!("Memo1UhkJRfHyvLMcVucJwxXeuD728EqVDDwQDxFMNo"));
+        let mut context = ProgramTest::default().start_with_context().await;
+        let program_id = deploy_upgradeable(&mut context, &elf).await.unwrap();
+
+        let programdata_address = program_data_address(&program_id);
+        let program = context
+            .banks_client
+            .get_account(program_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(program, upgradeable_program_account(programdata_address));
+
+        let program_data = context
+            .banks_client
+            .get_account(programdata_address)
+            .await
+            .unwrap()
+            .unwrap();
+        let offset = UpgradeableLoaderState::programdata_data_offset().unwrap();
+        assert_eq!(
+            bincode::deserialize::<UpgradeableLoaderState>(&program_data.data).unwrap(),
+            UpgradeableLoaderState::ProgramData {
+                slot: context.banks_client.get_root_slot().await.unwrap(),
+                upgrade_authority_address: Some(context.payer.pubkey()),
+            }
+        );
+        assert_eq!(&program_data.data[offset..offset + elf.len()], &elf[..]);
+        assert_eq!(program_data.data.len(), offset + 2 * elf.len());
+    }
+
+    #[cfg(feature = "test-bpf")]
+    #[tokio::test]
+    async fn test_replace_non_upgradeable_program() {
+        use solana_sdk::instruction::{AccountMeta, Instruction};
+
+        // Version 3 of the memo program requires its accounts to sign, while
+        // version 1 ignores them
+        let memo_v1 = spl_program_elf(pubkey!("Memo1UhkJRfHyvLMcVucJwxXeuD728EqVDDwQDxFMNo"));
+        let memo_v3 = spl_program_elf(pubkey!("MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr"));
+
+        let program = Keypair::new();
+        let mut program_test = ProgramTest::default();
+        set_non_upgradeable_program_account(&mut program_test, program.pubkey(), &memo_v3);
+        let mut context = program_test.start_with_context().await;
+        let memo = |context: &mut ProgramTestContext, memo: &[u8]| {
+            Transaction::new_signed_with_payer(
+                &[Instruction::new_with_bytes(
+                    program.pubkey(),
+                    memo,
+                    vec![AccountMeta::new_readonly(Pubkey::new_unique(), false)],
+                )],
+                Some(&context.payer.pubkey()),
+                &[&context.payer],
+                context.last_blockhash,
+            )
+        };
+
+        let transaction = memo(&mut context, b"v3");
+        assert!(context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .is_err());
+
+        // Setting the account alone keeps running the cached version 3
+        context.set_account(
+            &program.pubkey(),
+            &AccountSharedData::from(non_upgradeable_program_account(&memo_v1, true)),
+        );
+        let transaction = memo(&mut context, b"set");
+        assert!(context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .is_err());
+
+        replace_non_upgradeable_program(&mut context, &program, &memo_v1)
+            .await
+            .unwrap();
+        let transaction = memo(&mut context, b"v1");
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap();
+    }
+}
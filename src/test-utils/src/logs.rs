@@ -0,0 +1,259 @@
+//! Assertions on transaction logs matching their content, not their position
+//!
+//! The runtime frames program output with invocation, compute and result
+//! lines that change between releases, so asserting a message is at a given
+//! index breaks on runtime upgrades. [`Logs`] parses the lines to query them
+//! instead.
+
+use regex::Regex;
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+
+/// A line of the logs of a transaction
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LogLine<'a> {
+    /// `Program <id> invoke [<depth>]`
+    Invoke { program_id: Pubkey, depth: usize },
+    /// `Program <id> success`
+    Success { program_id: Pubkey },
+    /// `Program <id> failed: <error>`
+    Failed { program_id: Pubkey, error: &'a str },
+    /// `Program <id> consumed <units> of <budget> compute units`
+    Consumed {
+        program_id: Pubkey,
+        units: u64,
+        budget: u64,
+    },
+    /// `Program log: <message>`
+    Log(&'a str),
+    /// `Program data: <fields>`, the base64 fields of `sol_log_data`
+    Data(&'a str),
+    /// `Program return: <id> <data>`, the base64 return data of a program
+    Return { program_id: Pubkey, data: &'a str },
+    /// Any other line, such as those truncating the logs
+    Other(&'a str),
+}
+
+impl<'a> LogLine<'a> {
+    /// Parse `line`, as `Other` unless the runtime framed it
+    pub fn parse(line: &'a str) -> Self {
+        Self::parse_framed(line).unwrap_or(LogLine::Other(line))
+    }
+
+    fn parse_framed(line: &'a str) -> Option<Self> {
+        if let Some(message) = line.strip_prefix("Program log: ") {
+            return Some(LogLine::Log(message));
+        }
+        if let Some(fields) = line.strip_prefix("Program data: ") {
+            return Some(LogLine::Data(fields));
+        }
+        if let Some(rest) = line.strip_prefix("Program return: ") {
+            let (program_id, data) = rest.split_once(' ')?;
+            return Some(LogLine::Return {
+                program_id: Pubkey::from_str(program_id).ok()?,
+                data,
+            });
+        }
+
+        let (program_id, rest) = line.strip_prefix("Program ")?.split_once(' ')?;
+        let program_id = Pubkey::from_str(program_id).ok()?;
+        if rest == "success" {
+            return Some(LogLine::Success { program_id });
+        }
+        if let Some(error) = rest.strip_prefix("failed: ") {
+            return Some(LogLine::Failed { program_id, error });
+        }
+        if let Some(depth) = rest
+            .strip_prefix("invoke [")
+            .and_then(|rest| rest.strip_suffix(']'))
+        {
+            return Some(LogLine::Invoke {
+                program_id,
+                depth: depth.parse().ok()?,
+            });
+        }
+        let (units, budget) = rest
+            .strip_prefix("consumed ")?
+            .strip_suffix(" compute units")?
+            .split_once(" of ")?;
+        Some(LogLine::Consumed {
+            program_id,
+            units: units.parse().ok()?,
+            budget: budget.parse().ok()?,
+        })
+    }
+}
+
+/// The parsed logs of a transaction, such as those of a simulation
+#[derive(Clone, Debug)]
+pub struct Logs<'a> {
+    lines: Vec<LogLine<'a>>,
+}
+
+impl<'a> Logs<'a> {
+    /// Parse the lines of `logs`
+    pub fn new(logs: &'a [String]) -> Self {
+        Self {
+            lines: logs.iter().map(|line| LogLine::parse(line)).collect(),
+        }
+    }
+
+    pub fn lines(&self) -> &[LogLine<'a>] {
+        &self.lines
+    }
+
+    /// Messages logged by any program
+    pub fn messages(&self) -> Vec<&'a str> {
+        self.lines
+            .iter()
+            .filter_map(|line| match line {
+                LogLine::Log(message) => Some(*message),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Messages logged by `program_id` itself, not by the programs it invokes
+    pub fn messages_of(&self, program_id: &Pubkey) -> Vec<&'a str> {
+        // Programs being invoked, the innermost last
+        let mut invocations = vec![];
+        let mut messages = vec![];
+        for line in &self.lines {
+            match line {
+                LogLine::Invoke { program_id, .. } => invocations.push(*program_id),
+                LogLine::Success { .. } | LogLine::Failed { .. } => {
+                    invocations.pop();
+                }
+                LogLine::Log(message) if invocations.last() == Some(program_id) => {
+                    messages.push(*message)
+                }
+                _ => {}
+            }
+        }
+        messages
+    }
+
+    /// Programs invoked, in order, with the depth of their invocation
+    pub fn invocations(&self) -> Vec<(Pubkey, usize)> {
+        self.lines
+            .iter()
+            .filter_map(|line| match line {
+                LogLine::Invoke { program_id, depth } => Some((*program_id, *depth)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Compute units consumed by the last invocation of `program_id`
+    pub fn consumed_units(&self, program_id: &Pubkey) -> Option<u64> {
+        self.lines.iter().rev().find_map(|line| match line {
+            LogLine::Consumed {
+                program_id: consumer,
+                units,
+                ..
+            } if consumer == program_id => Some(*units),
+            _ => None,
+        })
+    }
+
+    /// Program whose invocation failed first, with its error
+    pub fn failure(&self) -> Option<(Pubkey, &'a str)> {
+        self.lines.iter().find_map(|line| match line {
+            LogLine::Failed { program_id, error } => Some((*program_id, *error)),
+            _ => None,
+        })
+    }
+}
+
+/// Assert a line of `logs` contains `expected`
+#[track_caller]
+pub fn assert_log_contains(logs: &[String], expected: &str) {
+    assert!(
+        logs.iter().any(|line| line.contains(expected)),
+        "no log contains {:?} in:\n{}",
+        expected,
+        logs.join("\n")
+    );
+}
+
+/// Assert a line of `logs` matches the regular expression `pattern`
+#[track_caller]
+pub fn assert_log_matches(logs: &[String], pattern: &str) {
+    let regex = Regex::new(pattern).unwrap();
+    assert!(
+        logs.iter().any(|line| regex.is_match(line)),
+        "no log matches {:?} in:\n{}",
+        pattern,
+        logs.join("\n")
+    );
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn logs(program_id: &Pubkey, inner_id: &Pubkey) -> Vec<String> {
+        vec![
+            format!("Program {} invoke [1]", program_id),
+            "Program log: Greeted 1 time(s)!".to_string(),
+            format!("Program {} invoke [2]", inner_id),
+            "Program log: Memo (len 5): \"hello\"".to_string(),
+            format!("Program {} consumed 1000 of 190000 compute units", inner_id),
+            format!("Program {} success", inner_id),
+            "Program data: AQID".to_string(),
+            format!("Program return: {} AQ==", program_id),
+            format!(
+                "Program {} consumed 5000 of 200000 compute units",
+                program_id
+            ),
+            format!("Program {} failed: custom program error: 0x1", program_id),
+            "Log truncated".to_string(),
+        ]
+    }
+
+    #[test]
+    fn test_logs() {
+        let program_id = Pubkey::new_unique();
+        let inner_id = Pubkey::new_unique();
+        let lines = logs(&program_id, &inner_id);
+        let logs = Logs::new(&lines);
+
+        assert_eq!(logs.lines().len(), lines.len());
+        assert_eq!(
+            logs.lines()[7],
+            LogLine::Return {
+                program_id,
+                data: "AQ=="
+            }
+        );
+        assert_eq!(logs.lines()[6], LogLine::Data("AQID"));
+        assert_eq!(logs.lines()[10], LogLine::Other("Log truncated"));
+        assert_eq!(
+            logs.messages(),
+            vec!["Greeted 1 time(s)!", "Memo (len 5): \"hello\""]
+        );
+        assert_eq!(logs.messages_of(&program_id), vec!["Greeted 1 time(s)!"]);
+        assert_eq!(logs.messages_of(&inner_id), vec!["Memo (len 5): \"hello\""]);
+        assert_eq!(logs.invocations(), vec![(program_id, 1), (inner_id, 2)]);
+        assert_eq!(logs.consumed_units(&program_id), Some(5000));
+        assert_eq!(logs.consumed_units(&inner_id), Some(1000));
+        assert_eq!(logs.consumed_units(&Pubkey::new_unique()), None);
+        assert_eq!(
+            logs.failure(),
+            Some((program_id, "custom program error: 0x1"))
+        );
+
+        assert_log_contains(&lines, "Greeted 1 time(s)!");
+        assert_log_matches(
+            &lines,
+            r"^Program \w+ consumed \d+ of 200000 compute units$",
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "no log matches")]
+    fn test_assert_log_matches_fails() {
+        let lines = logs(&Pubkey::new_unique(), &Pubkey::new_unique());
+        assert_log_matches(&lines, r"Greeted \d+ times");
+    }
+}
@@ -0,0 +1,121 @@
+//! Snapshots of accounts in a running bank, for tests branching from a
+//! common setup without running it again
+
+use solana_program_test::{BanksClientError, ProgramTestContext};
+use solana_sdk::{
+    account::{Account, AccountSharedData},
+    pubkey::Pubkey,
+};
+
+/// State of a set of accounts at some point of a test
+///
+/// BanksClient cannot list the accounts of a program, so the accounts to
+/// capture are named, usually those the setup created. Restoring a program
+/// account does not replace the executable the bank cached for it, see
+/// `replace_non_upgradeable_program`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AccountsSnapshot {
+    accounts: Vec<(Pubkey, Option<Account>)>,
+}
+
+impl AccountsSnapshot {
+    /// Capture the accounts at `pubkeys`, including those that do not exist
+    pub async fn capture(
+        context: &mut ProgramTestContext,
+        pubkeys: &[Pubkey],
+    ) -> Result<Self, BanksClientError> {
+        let mut accounts = Vec::with_capacity(pubkeys.len());
+        for pubkey in pubkeys {
+            accounts.push((*pubkey, context.banks_client.get_account(*pubkey).await?));
+        }
+        Ok(Self { accounts })
+    }
+
+    /// Captured state of the account at `pubkey`, `None` if it did not exist
+    /// or was not captured
+    pub fn get(&self, pubkey: &Pubkey) -> Option<&Account> {
+        self.accounts
+            .iter()
+            .find(|(captured, _)| captured == pubkey)
+            .and_then(|(_, account)| account.as_ref())
+    }
+
+    /// Set the captured accounts back to their captured state, closing those
+    /// that did not exist
+    pub fn restore(&self, context: &mut ProgramTestContext) {
+        for (pubkey, account) in &self.accounts {
+            let account = account
+                .clone()
+                .map(AccountSharedData::from)
+                .unwrap_or_default();
+            context.set_account(pubkey, &account);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use solana_program_test::{tokio, ProgramTest};
+    use solana_sdk::{
+        signature::{Keypair, Signer},
+        system_instruction,
+        transaction::Transaction,
+    };
+
+    #[tokio::test]
+    async fn test_accounts_snapshot() {
+        let funded = Pubkey::new_unique();
+        let mut program_test = ProgramTest::default();
+        program_test.add_account(
+            funded,
+            Account {
+                lamports: 1_000_000_000,
+                ..Account::default()
+            },
+        );
+        let mut context = program_test.start_with_context().await;
+        let created = Keypair::new();
+        let pubkeys = [funded, created.pubkey()];
+        let snapshot = AccountsSnapshot::capture(&mut context, &pubkeys)
+            .await
+            .unwrap();
+        assert_eq!(snapshot.get(&funded).unwrap().lamports, 1_000_000_000);
+        assert_eq!(snapshot.get(&created.pubkey()), None);
+
+        let transaction = Transaction::new_signed_with_payer(
+            &[
+                system_instruction::transfer(&context.payer.pubkey(), &funded, 1_000_000),
+                system_instruction::create_account(
+                    &context.payer.pubkey(),
+                    &created.pubkey(),
+                    1_000_000_000,
+                    8,
+                    &Pubkey::new_unique(),
+                ),
+            ],
+            Some(&context.payer.pubkey()),
+            &[&context.payer, &created],
+            context.last_blockhash,
+        );
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap();
+        assert_ne!(
+            AccountsSnapshot::capture(&mut context, &pubkeys)
+                .await
+                .unwrap(),
+            snapshot
+        );
+
+        snapshot.restore(&mut context);
+        assert_eq!(
+            AccountsSnapshot::capture(&mut context, &pubkeys)
+                .await
+                .unwrap(),
+            snapshot
+        );
+    }
+}
@@ -0,0 +1,75 @@
+//! Moving a running bank forward in slots, epochs or time, relative to where
+//! it is rather than to absolute slots
+
+use solana_program_test::{ProgramTestContext, ProgramTestError};
+use solana_sdk::{
+    clock::{Clock, Epoch, Slot, UnixTimestamp},
+    epoch_schedule::EpochSchedule,
+};
+
+/// Warp `slots` slots past the slot of the clock, returning the new slot
+pub async fn warp_slots(
+    context: &mut ProgramTestContext,
+    slots: Slot,
+) -> Result<Slot, ProgramTestError> {
+    let clock: Clock = context.banks_client.get_sysvar().await.unwrap();
+    let slot = clock.slot.saturating_add(slots);
+    context.warp_to_slot(slot)?;
+    Ok(slot)
+}
+
+/// Warp to the first slot of `epoch`, returning it
+pub async fn warp_to_epoch(
+    context: &mut ProgramTestContext,
+    epoch: Epoch,
+) -> Result<Slot, ProgramTestError> {
+    let epoch_schedule: EpochSchedule = context.banks_client.get_sysvar().await.unwrap();
+    let slot = epoch_schedule.get_first_slot_in_epoch(epoch);
+    context.warp_to_slot(slot)?;
+    Ok(slot)
+}
+
+/// Move the time of the clock `seconds` forward, or backward if negative,
+/// returning the new time
+///
+/// Only the clock moves: the slot stays the same, as when the cluster runs
+/// slow. Expired accounts can be reaped right away this way.
+pub async fn warp_seconds(context: &mut ProgramTestContext, seconds: i64) -> UnixTimestamp {
+    let mut clock: Clock = context.banks_client.get_sysvar().await.unwrap();
+    clock.unix_timestamp = clock.unix_timestamp.saturating_add(seconds);
+    context.set_sysvar(&clock);
+    clock.unix_timestamp
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use solana_program_test::{tokio, ProgramTest};
+
+    async fn clock(context: &mut ProgramTestContext) -> Clock {
+        context.banks_client.get_sysvar().await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_warp() {
+        let mut context = ProgramTest::default().start_with_context().await;
+
+        let slot = warp_slots(&mut context, 10).await.unwrap();
+        assert_eq!(clock(&mut context).await.slot, slot);
+        assert!(matches!(
+            warp_slots(&mut context, 0).await,
+            Err(ProgramTestError::InvalidWarpSlot)
+        ));
+
+        let slot = warp_to_epoch(&mut context, 3).await.unwrap();
+        let clock_after_epoch = clock(&mut context).await;
+        assert_eq!(clock_after_epoch.slot, slot);
+        assert_eq!(clock_after_epoch.epoch, 3);
+
+        let unix_timestamp = warp_seconds(&mut context, 3600).await;
+        assert_eq!(unix_timestamp, clock_after_epoch.unix_timestamp + 3600);
+        let clock = clock(&mut context).await;
+        assert_eq!(clock.unix_timestamp, unix_timestamp);
+        assert_eq!(clock.slot, slot);
+    }
+}